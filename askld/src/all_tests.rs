@@ -4164,6 +4164,7 @@ fn canary_leak_in_each_relationship_field_detected() {
             from_object: -999999,
             from_offset_range: (Bound::Included(0), Bound::Excluded(1)),
             layer: CANARY_LAYER_ID,
+            kind: None,
         }
     }
 
@@ -6834,6 +6835,46 @@ fn sql_cache_cleared_by_delete_project() {
     });
 }
 
+#[test]
+fn query_result_cache_invalidated_by_delete_project() {
+    // The persistent result cache: an entry is served until a mutation
+    // commits, and a result keyed at a pre-mutation generation is refused.
+    use crate::query_store::QueryStore;
+    use crate::test_util::{create_isolated_fixture, store_and_index_with_shared_cache};
+    use diesel_async::pooled_connection::bb8::Pool;
+    use diesel_async::pooled_connection::AsyncDieselConnectionManager;
+    let fx = create_isolated_fixture(VERB_TEST);
+
+    let rt = tokio::runtime::Runtime::new().unwrap();
+    let local = tokio::task::LocalSet::new();
+    local.block_on(&rt, async {
+        let (store, _index) = store_and_index_with_shared_cache(fx.url()).await;
+        let config = AsyncDieselConnectionManager::<diesel_async::AsyncPgConnection>::new(fx.url());
+        let pool = Pool::builder().build(config).await.expect("query pool");
+        let queries = QueryStore::from_pool(pool, true);
+
+        let generation = queries.generation().await.unwrap();
+        assert!(queries.store(b"key", generation, "body").await.unwrap());
+        assert_eq!(
+            queries.lookup(b"key").await.unwrap().as_deref(),
+            Some("body")
+        );
+
+        assert!(store.delete_project(1).await.unwrap());
+
+        assert!(queries.generation().await.unwrap() > generation);
+        assert_eq!(
+            queries.lookup(b"key").await.unwrap(),
+            None,
+            "delete_project must drop cached results"
+        );
+        assert!(
+            !queries.store(b"key", generation, "stale").await.unwrap(),
+            "a result computed before the delete must not be stored"
+        );
+    });
+}
+
 #[test]
 fn sql_cache_concurrent_identical_loads() {
     // Concurrency smoke: two identical cold loads racing on one Index both
@@ -7126,3 +7167,134 @@ fn combined_fallback_consults_eph_rows() {
         );
     });
 }
+
+#[test]
+fn explain_reports_statement_graph_sql_and_cache_outcomes() {
+    use crate::explain::ExplainMode;
+    use crate::test_util::run_query_explained_on;
+    const QUERY: &str = r#""main" { "a" }"#;
+
+    let rt = tokio::runtime::Runtime::new().unwrap();
+    let local = tokio::task::LocalSet::new();
+    local.block_on(&rt, async {
+        let index = get_shared_index(TEST_INPUT_A).await;
+        let (res, first) = run_query_explained_on(index.clone(), QUERY, ExplainMode::Analyze)
+            .await
+            .unwrap();
+
+        assert_eq!(first.mode, "analyze");
+        let texts: Vec<&str> = first.statements.iter().map(|s| s.text.trim()).collect();
+        assert_eq!(texts, vec![r#""main""#, r#""a""#]);
+        let edges = |id: usize| -> Vec<(usize, &str)> {
+            first.statements[id]
+                .dependents
+                .iter()
+                .map(|d| (d.statement, d.role))
+                .collect()
+        };
+        assert_eq!(edges(0), vec![(1, "child")]);
+        assert_eq!(edges(1), vec![(0, "parent")]);
+        let rows: usize = first.statements.iter().filter_map(|s| s.rows).sum();
+        assert_eq!(rows, res.nodes.as_vec().len());
+        assert!(!first.worklist.is_empty());
+
+        assert!(!first.sql.is_empty());
+        assert!(first
+            .sql
+            .iter()
+            .all(|e| e.statement.is_none_or(|id| id < first.statements.len())));
+        assert!(
+            first.sql.iter().any(|e| e
+                .plan
+                .as_deref()
+                .is_some_and(|plan| plan.contains("actual time"))),
+            "analyze mode attaches Postgres plans"
+        );
+
+        // The same query again is answered from the SQL result cache.
+        let (_, second) = run_query_explained_on(index, QUERY, ExplainMode::Trace)
+            .await
+            .unwrap();
+        assert!(second.sql_cache.hits > 0);
+        assert!(second.sql.iter().all(|e| e.plan.is_none()));
+    });
+}
+
+#[test]
+fn worklist_reports_progress_once_per_statement() {
+    use crate::cfg::ControlFlowGraph;
+    use crate::execution_context::{ExecutionContext, StatementProgress};
+    use crate::parser::parse;
+    use std::cell::RefCell;
+    use std::rc::Rc;
+
+    let rt = tokio::runtime::Runtime::new().unwrap();
+    let local = tokio::task::LocalSet::new();
+    local.block_on(&rt, async {
+        let index = get_shared_index(TEST_INPUT_A).await;
+        let roots = index.load_root_layers().await.unwrap();
+        let cfg = ControlFlowGraph::from_symbols(index);
+        let ast = parse(r#""main" { "a" }"#).unwrap();
+
+        let seen: Rc<RefCell<Vec<StatementProgress>>> = Rc::default();
+        let mut ctx = ExecutionContext::new(roots);
+        let sink = seen.clone();
+        ctx.progress = Some(Box::new(move |step| sink.borrow_mut().push(step.clone())));
+        ast.execute(&mut ctx, &cfg).await.unwrap();
+
+        let seen = seen.borrow();
+        let mut ids: Vec<usize> = seen.iter().map(|s| s.statement).collect();
+        ids.sort_unstable();
+        assert_eq!(ids, vec![0, 1]);
+        let completed: Vec<usize> = seen.iter().map(|s| s.completed).collect();
+        assert_eq!(completed, vec![1, 2]);
+        assert!(seen.iter().all(|s| s.total == 2 && s.rows > 0));
+    });
+}
+
+#[test]
+fn labels_carry_over_to_a_later_execution() {
+    use crate::cfg::ControlFlowGraph;
+    use crate::execution_context::ExecutionContext;
+    use crate::parser::parse;
+
+    let rt = tokio::runtime::Runtime::new().unwrap();
+    let local = tokio::task::LocalSet::new();
+    local.block_on(&rt, async {
+        let index = get_shared_index(TEST_INPUT_B).await;
+        let roots = index.load_root_layers().await.unwrap();
+        let cfg = ControlFlowGraph::from_symbols(index);
+
+        let mut first = ExecutionContext::new(roots.clone());
+        parse(r#""e" { "M" { @found } }"#)
+            .unwrap()
+            .execute(&mut first, &cfg)
+            .await
+            .unwrap();
+        let found: Vec<i64> = first.labels["found"]
+            .nodes
+            .iter()
+            .map(|n| n.symbol_instance.id)
+            .collect();
+        assert_eq!(found, vec![210], "x, the child of M in e");
+
+        // Without the earlier labels, `#found` is unknown.
+        let mut fresh = ExecutionContext::new(roots.clone());
+        assert!(parse("#found")
+            .unwrap()
+            .execute(&mut fresh, &cfg)
+            .await
+            .is_err());
+
+        let mut second = ExecutionContext::new(roots);
+        second.labels = first.labels;
+        let res = parse("#found @again")
+            .unwrap()
+            .execute(&mut second, &cfg)
+            .await
+            .unwrap();
+        assert_eq!(res.nodes.as_vec(), vec![SymbolInstanceId::new(210)]);
+        assert!(second.labels.contains_key("found"));
+        assert_eq!(second.labels["again"].nodes.len(), 1);
+    });
+}
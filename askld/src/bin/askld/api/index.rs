@@ -1,7 +1,10 @@
+use std::collections::HashMap;
+
 use actix_web::{delete, get, http::header, web, HttpRequest, HttpResponse, Responder};
 use askld::auth::AuthIdentity;
 use askld::index_store::{
-    normalize_full_path, IndexStore, MultiTreeResult, StoreError, UploadError,
+    normalize_full_path, validate_upload, FilePosition, IndexCapabilities, IndexStore,
+    MultiTreeResult, PositionLookup, StoreError, UploadError,
 };
 use askld::proto::askl::index::{ContentBatch, Project};
 use log::{error, warn};
@@ -56,6 +59,10 @@ fn require_protobuf(req: &HttpRequest) -> Result<(), HttpResponse> {
 pub struct UploadIndexQuery {
     pub symbol_chunks: Option<i32>,
     pub object_chunks: Option<i32>,
+    /// Validate a complete `Project` (symbols and objects included) and
+    /// return the report without storing anything.
+    #[serde(default)]
+    pub dry_run: bool,
 }
 
 #[derive(Debug, Deserialize)]
@@ -94,6 +101,10 @@ pub async fn upload_index(
         }
     };
 
+    if query.dry_run {
+        return HttpResponse::Ok().json(validate_upload(upload, &HashMap::new()));
+    }
+
     match store
         .upload_index(upload, query.symbol_chunks, query.object_chunks)
         .await
@@ -151,10 +162,7 @@ pub async fn upload_symbol_chunk(
             .body("objects must be uploaded via POST /v1/index/projects/{id}/objects");
     }
     let seq = query.seq;
-    match store
-        .upload_symbol_chunk(*project_id, seq, upload.symbols)
-        .await
-    {
+    match store.upload_symbol_chunk(*project_id, seq, upload).await {
         Ok(()) => HttpResponse::Ok().json(serde_json::json!({ "seq": seq })),
         Err(UploadError::Invalid(msg)) => HttpResponse::BadRequest().body(msg),
         Err(UploadError::Storage(msg)) => {
@@ -281,6 +289,13 @@ pub async fn list_index_projects(store: web::Data<IndexStore>) -> impl Responder
     }
 }
 
+/// `GET /v1/index/capabilities` — the schema versions, enum values and
+/// limits uploads are checked against, for indexers to verify up front.
+#[get("/v1/index/capabilities")]
+pub async fn get_capabilities() -> impl Responder {
+    HttpResponse::Ok().json(IndexCapabilities::new(max_upload_bytes()))
+}
+
 #[get("/v1/index/projects/{project_id}")]
 pub async fn get_index_project(
     store: web::Data<IndexStore>,
@@ -472,6 +487,55 @@ pub async fn get_project_source(
     }
 }
 
+#[derive(Debug, Deserialize)]
+pub struct PositionQuery {
+    path: String,
+    /// 1-based line; with `col` (1-based byte column, default 1).
+    line: Option<usize>,
+    col: Option<usize>,
+    /// Byte offset, instead of `line`/`col`.
+    offset: Option<usize>,
+}
+
+/// `GET /v1/index/projects/{id}/at` — what is at a position of a file: the
+/// innermost symbol instance covering it and the symbol referenced there,
+/// for turning a click in an editor into a query.
+#[get("/v1/index/projects/{project_id}/at")]
+pub async fn get_symbol_at(
+    store: web::Data<IndexStore>,
+    project_id: web::Path<i32>,
+    query: web::Query<PositionQuery>,
+) -> impl Responder {
+    let path = query.path.trim();
+    if !path.starts_with('/') {
+        return HttpResponse::BadRequest().body("path must be an absolute path");
+    }
+    let position = match (query.offset, query.line, query.col) {
+        (Some(offset), None, None) => FilePosition::Offset(offset),
+        (None, Some(line), col) => FilePosition::LineCol {
+            line,
+            col: col.unwrap_or(1),
+        },
+        _ => {
+            return HttpResponse::BadRequest()
+                .body("give either line (and optionally col) or offset")
+        }
+    };
+
+    match store.symbol_at(*project_id, path, position).await {
+        Ok(PositionLookup::Found(found)) => HttpResponse::Ok().json(found),
+        Ok(PositionLookup::ProjectNotFound) => HttpResponse::NotFound().body("Project not found"),
+        Ok(PositionLookup::FileNotFound) => HttpResponse::NotFound().body("File not found"),
+        Ok(PositionLookup::OutOfRange) => {
+            HttpResponse::BadRequest().body("Position is outside the file")
+        }
+        Err(StoreError::Storage(message)) => {
+            error!("Failed to look up position in {}: {}", project_id, message);
+            HttpResponse::InternalServerError().body("Failed to look up position")
+        }
+    }
+}
+
 fn slice_content(
     content: Vec<u8>,
     start_offset: Option<i64>,
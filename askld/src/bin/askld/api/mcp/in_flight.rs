@@ -0,0 +1,162 @@
+//! Requests being answered, so a `notifications/cancelled` sent on another
+//! HTTP request can reach the one it names.
+//!
+//! Request ids are only unique per client, so requests are keyed by the
+//! caller's user and MCP session plus the JSON-RPC id exactly as sent: `1`
+//! and `"1"` are different requests. Anonymous callers without a session
+//! share one namespace, so their cancels are ignored rather than let one
+//! client stop another's request.
+
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+
+use serde_json::Value;
+use tokio_util::sync::CancellationToken;
+use uuid::Uuid;
+
+type RequestKey = (Option<Uuid>, Option<String>, String);
+
+#[derive(Default)]
+pub(super) struct InFlight {
+    requests: Mutex<HashMap<RequestKey, (u64, CancellationToken)>>,
+    next_serial: AtomicU64,
+}
+
+impl InFlight {
+    /// Track request `id` from `user_id` in `session` until the returned
+    /// guard drops. Its token is cancelled by [`InFlight::cancel`] or
+    /// together with `parent`. A client reusing an id while the first request
+    /// runs can only cancel the newer one.
+    pub(super) fn start(
+        &self,
+        user_id: Option<Uuid>,
+        session: Option<&str>,
+        id: &Value,
+        parent: &CancellationToken,
+    ) -> InFlightRequest<'_> {
+        let key = (user_id, session.map(str::to_string), id.to_string());
+        let serial = self.next_serial.fetch_add(1, Ordering::Relaxed);
+        let token = parent.child_token();
+        self.requests
+            .lock()
+            .unwrap()
+            .insert(key.clone(), (serial, token.clone()));
+        InFlightRequest {
+            in_flight: self,
+            key,
+            serial,
+            token,
+        }
+    }
+
+    /// Cancel `user_id`'s request `id` in `session`. Returns whether it was
+    /// still running; always false for an anonymous caller without a
+    /// session, which cannot tell its requests from anyone else's.
+    pub(super) fn cancel(&self, user_id: Option<Uuid>, session: Option<&str>, id: &Value) -> bool {
+        if user_id.is_none() && session.is_none() {
+            return false;
+        }
+        let key = (user_id, session.map(str::to_string), id.to_string());
+        match self.requests.lock().unwrap().get(&key) {
+            Some((_, token)) => {
+                token.cancel();
+                true
+            }
+            None => false,
+        }
+    }
+}
+
+/// A tracked request; see [`InFlight::start`].
+pub(super) struct InFlightRequest<'a> {
+    in_flight: &'a InFlight,
+    key: RequestKey,
+    serial: u64,
+    token: CancellationToken,
+}
+
+impl InFlightRequest<'_> {
+    pub(super) fn token(&self) -> &CancellationToken {
+        &self.token
+    }
+}
+
+impl Drop for InFlightRequest<'_> {
+    fn drop(&mut self) {
+        let mut requests = self.in_flight.requests.lock().unwrap();
+        if requests
+            .get(&self.key)
+            .is_some_and(|(serial, _)| *serial == self.serial)
+        {
+            requests.remove(&self.key);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn cancels_only_the_named_request_of_the_same_user() {
+        let in_flight = InFlight::default();
+        let parent = CancellationToken::new();
+        let user = Some(Uuid::new_v4());
+        let first = in_flight.start(user, None, &json!(1), &parent);
+        let other = in_flight.start(None, None, &json!(1), &parent);
+
+        assert!(!in_flight.cancel(user, None, &json!("1")));
+        assert!(in_flight.cancel(user, None, &json!(1)));
+        assert!(first.token().is_cancelled());
+        assert!(!other.token().is_cancelled());
+
+        drop(first);
+        assert!(!in_flight.cancel(user, None, &json!(1)));
+    }
+
+    #[test]
+    fn sessions_keep_their_ids_apart() {
+        let in_flight = InFlight::default();
+        let parent = CancellationToken::new();
+        let first = in_flight.start(None, Some("a"), &json!(1), &parent);
+        let second = in_flight.start(None, Some("b"), &json!(1), &parent);
+
+        assert!(!in_flight.cancel(None, None, &json!(1)));
+        assert!(in_flight.cancel(None, Some("b"), &json!(1)));
+        assert!(second.token().is_cancelled());
+        assert!(!first.token().is_cancelled());
+    }
+
+    #[test]
+    fn anonymous_callers_without_a_session_cannot_cancel() {
+        let in_flight = InFlight::default();
+        let parent = CancellationToken::new();
+        let request = in_flight.start(None, None, &json!(1), &parent);
+
+        assert!(!in_flight.cancel(None, None, &json!(1)));
+        assert!(!request.token().is_cancelled());
+    }
+
+    #[test]
+    fn a_reused_id_outlives_the_finished_request() {
+        let in_flight = InFlight::default();
+        let parent = CancellationToken::new();
+        let first = in_flight.start(None, Some("s"), &json!("a"), &parent);
+        let second = in_flight.start(None, Some("s"), &json!("a"), &parent);
+        drop(first);
+
+        assert!(in_flight.cancel(None, Some("s"), &json!("a")));
+        assert!(second.token().is_cancelled());
+    }
+
+    #[test]
+    fn parent_cancels_its_requests() {
+        let in_flight = InFlight::default();
+        let parent = CancellationToken::new();
+        let request = in_flight.start(None, None, &json!(7), &parent);
+        parent.cancel();
+        assert!(request.token().is_cancelled());
+    }
+}
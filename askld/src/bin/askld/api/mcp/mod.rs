@@ -1,60 +1,288 @@
 //! Model Context Protocol (MCP) server surface.
 //!
 //! Exposes askl to an AI agent as JSON-RPC 2.0 over HTTP (`POST /mcp`, Streamable
-//! HTTP: the reply is returned in the response body). The agent writes raw askl
+//! HTTP: the reply is returned in the response body, or streamed as SSE when the
+//! request asks for progress) and, through `askld mcp`, over stdio via
+//! [`handle_rpc_value`]. The agent writes raw askl
 //! query strings; every tool returns **markdown** (never JSON) rendered by the
 //! same code path as `/query?format=markdown`, so the MCP and the web UI share
 //! one source of truth.
 //!
 //! This module is the transport; the surface is split by concern:
 //! [`protocol`] (envelope + `initialize`), [`tools`], [`resources`], [`prompts`].
+//! [`in_flight`] tracks running requests for `notifications/cancelled`;
+//! [`sessions`] keeps what a client's queries leave for its next ones.
 
+mod in_flight;
 mod prompts;
 mod protocol;
 mod resources;
+mod sessions;
 mod tools;
 
-use actix_web::{web, HttpResponse, Responder};
+use actix_web::http::header;
+use actix_web::{web, HttpRequest, HttpResponse, Responder};
 use log::{debug, info, warn};
 use serde_json::{json, Value};
+use tokio::sync::mpsc;
+use tokio_util::sync::CancellationToken;
+use uuid::Uuid;
 
 use super::types::AsklData;
+use askld::auth::AuthIdentity;
 use askld::index_store::IndexStore;
-use protocol::{jsonrpc_error_value, jsonrpc_result_value, RpcError, MCP_JSONRPC_VERSION};
+use in_flight::InFlight;
+use protocol::{
+    jsonrpc_error_value, jsonrpc_result_value, parse_params, RpcError, MCP_JSONRPC_VERSION,
+};
+pub(crate) use sessions::McpSession;
+use sessions::Sessions;
+
+/// MCP state shared by every request, across workers.
+#[derive(Default)]
+pub struct McpState {
+    in_flight: InFlight,
+    sessions: Sessions,
+}
+
+impl McpState {
+    /// End the sessions idle for longer than `idle`; returns how many.
+    pub fn expire_sessions(&self, idle: std::time::Duration) -> usize {
+        self.sessions.expire(idle)
+    }
+}
+
+/// The header carrying the session id, as the Streamable HTTP transport
+/// names it (lowercase, as `HeaderName` stores it).
+const SESSION_HEADER_NAME: &str = "mcp-session-id";
+
+/// The transport side of one incoming message: where notifications about it
+/// go while its reply is streamed, a token the transport cancels once nobody
+/// is waiting for that reply, and the session it belongs to.
+#[derive(Default)]
+pub(crate) struct Outbound {
+    notify: Option<mpsc::UnboundedSender<Value>>,
+    closed: CancellationToken,
+    session: Option<McpSession>,
+}
+
+impl Outbound {
+    /// A message in `session` whose reply is sent in one piece.
+    pub(crate) fn in_session(session: McpSession) -> Self {
+        Self {
+            session: Some(session),
+            ..Self::default()
+        }
+    }
+}
+
+/// What a method handler may use besides its params.
+pub(super) struct RequestHooks<'a> {
+    /// Cancelled by `notifications/cancelled` or when the client goes away.
+    pub(super) cancel: &'a CancellationToken,
+    /// Where server-to-client notifications go; `None` when the transport
+    /// cannot deliver them before the reply.
+    pub(super) notify: Option<&'a mpsc::UnboundedSender<Value>>,
+    /// The session the request runs in, if the client opened one.
+    pub(super) session: Option<&'a McpSession>,
+}
 
 /// `POST /mcp` — a single JSON-RPC 2.0 message or a batch array. The reply is
-/// returned in the response body.
+/// returned in the response body, or streamed after its progress
+/// notifications when the request has a `progressToken` and the client
+/// accepts `text/event-stream`. An all-notification body yields
+/// `202 Accepted`. A valid API token attributes the session's
+/// queries to its user in the query history; none is required.
+///
+/// `initialize` opens a session, returned in the `Mcp-Session-Id` header;
+/// messages carrying it run in that session, and an unknown or expired one
+/// is answered `404 Not Found` so the client initializes again. Messages
+/// without the header run outside any session.
 pub async fn mcp_handler(
     askl_data: web::Data<AsklData>,
     index_store: web::Data<IndexStore>,
+    mcp: web::Data<McpState>,
+    identity: Option<AuthIdentity>,
+    req: HttpRequest,
     body: web::Bytes,
 ) -> impl Responder {
-    process_rpc_body(&askl_data, &index_store, &body).await
+    let user_id = identity.map(|identity| identity.user_id);
+    let value: Value = match serde_json::from_slice(&body) {
+        Ok(value) => value,
+        Err(err) => return HttpResponse::BadRequest().json(parse_error_reply(&err)),
+    };
+    let initialize = is_initialize(&value);
+    let session = if initialize {
+        Some(mcp.sessions.create(user_id))
+    } else {
+        match session_id(&req) {
+            None => None,
+            Some(id) => match mcp.sessions.get(id, user_id) {
+                Some(session) => Some(session),
+                None => {
+                    let id = value.get("id").cloned().unwrap_or(Value::Null);
+                    return HttpResponse::NotFound().json(jsonrpc_error_value(
+                        id,
+                        RpcError::invalid_request(
+                            "Unknown or expired session; send initialize to start a new one",
+                        ),
+                    ));
+                }
+            },
+        }
+    };
+    let opened = session
+        .as_ref()
+        .filter(|_| initialize)
+        .map(|session| session.id().to_string());
+
+    let mut response = if accepts_event_stream(&req) && wants_progress(&value) {
+        stream_reply(askl_data, index_store, mcp, user_id, value, session)
+    } else {
+        let outbound = Outbound {
+            session,
+            ..Outbound::default()
+        };
+        match handle_rpc_value(&askl_data, &index_store, &mcp, user_id, value, &outbound).await {
+            Ok(Some(reply)) => HttpResponse::Ok().json(reply),
+            Ok(None) => HttpResponse::Accepted().finish(),
+            Err(reply) => HttpResponse::BadRequest().json(reply),
+        }
+    };
+    if let Some(id) = opened {
+        info!("MCP session {} opened", id);
+        response.headers_mut().insert(
+            header::HeaderName::from_static(SESSION_HEADER_NAME),
+            header::HeaderValue::from_str(&id).expect("a UUID is a valid header value"),
+        );
+    }
+    response
 }
 
-/// Parse a JSON-RPC request body (single or batch), dispatch each message, and
-/// build the HTTP reply. Notifications (no `id`) produce no response; an
-/// all-notification batch yields `202 Accepted`.
-async fn process_rpc_body(
-    askl_data: &web::Data<AsklData>,
-    index_store: &web::Data<IndexStore>,
-    body: &[u8],
-) -> HttpResponse {
-    let value: Value = match serde_json::from_slice(body) {
-        Ok(value) => value,
-        Err(err) => {
-            return HttpResponse::BadRequest().json(jsonrpc_error_value(
-                Value::Null,
-                RpcError::parse_error(&err.to_string()),
-            ));
+/// `DELETE /mcp` — end the session named by the `Mcp-Session-Id` header.
+pub async fn mcp_delete_session(
+    mcp: web::Data<McpState>,
+    identity: Option<AuthIdentity>,
+    req: HttpRequest,
+) -> impl Responder {
+    let user_id = identity.map(|identity| identity.user_id);
+    match session_id(&req) {
+        None => HttpResponse::BadRequest().body("missing Mcp-Session-Id header"),
+        Some(id) if mcp.sessions.remove(id, user_id) => {
+            info!("MCP session {} ended by the client", id);
+            HttpResponse::NoContent().finish()
         }
+        Some(_) => HttpResponse::NotFound().body("Unknown or expired session"),
+    }
+}
+
+/// Whether `value` is an `initialize` request, which opens a session. The
+/// transport forbids batching it.
+fn is_initialize(value: &Value) -> bool {
+    value.get("id").is_some() && value.get("method").and_then(Value::as_str) == Some("initialize")
+}
+
+fn session_id(req: &HttpRequest) -> Option<&str> {
+    req.headers()
+        .get(SESSION_HEADER_NAME)
+        .and_then(|value| value.to_str().ok())
+}
+
+fn accepts_event_stream(req: &HttpRequest) -> bool {
+    req.headers()
+        .get_all(header::ACCEPT)
+        .filter_map(|value| value.to_str().ok())
+        .any(|value| value.contains("text/event-stream"))
+}
+
+/// Whether `value` is a single request carrying a `progressToken`; only then
+/// is there anything to stream before the reply.
+fn wants_progress(value: &Value) -> bool {
+    value.get("id").is_some() && value.pointer("/params/_meta/progressToken").is_some()
+}
+
+/// Answer one request as a `text/event-stream`: its notifications as they
+/// happen, then the reply, then the end of the stream. If the client
+/// disconnects first, the request is cancelled.
+fn stream_reply(
+    askl_data: web::Data<AsklData>,
+    index_store: web::Data<IndexStore>,
+    mcp: web::Data<McpState>,
+    user_id: Option<Uuid>,
+    value: Value,
+    session: Option<McpSession>,
+) -> HttpResponse {
+    let (tx, rx) = mpsc::unbounded_channel();
+    let outbound = Outbound {
+        notify: Some(tx.clone()),
+        closed: CancellationToken::new(),
+        session,
     };
+    // Query execution is not `Send`, so it runs on this worker's local set.
+    actix_web::rt::spawn(async move {
+        let mut handled = Box::pin(handle_rpc_value(
+            &askl_data,
+            &index_store,
+            &mcp,
+            user_id,
+            value,
+            &outbound,
+        ));
+        let reply = tokio::select! {
+            reply = &mut handled => reply,
+            _ = tx.closed() => {
+                debug!("MCP client closed its event stream; cancelling");
+                outbound.closed.cancel();
+                handled.await
+            }
+        };
+        if let Some(reply) = reply.unwrap_or_else(Some) {
+            let _ = tx.send(reply);
+        }
+    });
+    // Keep-alives also make a vanished client show up as a failed write.
+    let events = futures::stream::unfold(rx, |mut rx| async move {
+        let event = tokio::select! {
+            message = rx.recv() => sse_event(&message?),
+            _ = tokio::time::sleep(SSE_KEEPALIVE) => web::Bytes::from_static(b": keep-alive\n\n"),
+        };
+        Some((Ok::<_, actix_web::Error>(event), rx))
+    });
+    HttpResponse::Ok()
+        .content_type("text/event-stream")
+        .insert_header((header::CACHE_CONTROL, "no-cache"))
+        .streaming(events)
+}
+
+/// Idle time after which a streamed reply gets an SSE comment line.
+const SSE_KEEPALIVE: std::time::Duration = std::time::Duration::from_secs(5);
+
+/// One server-sent event carrying a JSON-RPC message.
+fn sse_event(message: &Value) -> web::Bytes {
+    web::Bytes::from(format!("event: message\ndata: {}\n\n", message))
+}
+
+/// The reply to a message that is not valid JSON.
+pub(crate) fn parse_error_reply(err: &serde_json::Error) -> Value {
+    jsonrpc_error_value(Value::Null, RpcError::parse_error(&err.to_string()))
+}
 
+/// Dispatch one parsed JSON-RPC message or batch, independent of transport.
+/// Notifications (no `id`) produce no response, so `Ok(None)` means there is
+/// nothing to send back; `Err` is the reply to a malformed (empty) batch.
+pub(crate) async fn handle_rpc_value(
+    askl_data: &web::Data<AsklData>,
+    index_store: &web::Data<IndexStore>,
+    mcp: &McpState,
+    user_id: Option<Uuid>,
+    value: Value,
+    outbound: &Outbound,
+) -> Result<Option<Value>, Value> {
     let is_batch = matches!(value, Value::Array(_));
     let messages = match value {
         Value::Array(messages) => {
             if messages.is_empty() {
-                return HttpResponse::BadRequest().json(jsonrpc_error_value(
+                return Err(jsonrpc_error_value(
                     Value::Null,
                     RpcError::invalid_request("Invalid Request: empty batch"),
                 ));
@@ -66,24 +294,40 @@ async fn process_rpc_body(
 
     let mut responses = Vec::new();
     for message in messages {
-        if let Some(response) = handle_message(askl_data, index_store, message).await {
+        if let Some(response) =
+            handle_message(askl_data, index_store, mcp, user_id, message, outbound).await
+        {
             responses.push(response);
         }
     }
 
     if responses.is_empty() {
-        return HttpResponse::Accepted().finish();
+        return Ok(None);
     }
-
-    let response_body = if is_batch {
+    Ok(Some(if is_batch {
         Value::Array(responses)
     } else {
         responses.into_iter().next().unwrap_or_else(|| {
             jsonrpc_error_value(Value::Null, RpcError::internal("Missing response"))
         })
-    };
+    }))
+}
 
-    HttpResponse::Ok().json(response_body)
+/// Internal-error replies for every request in `value` (a message or batch),
+/// for a transport that could not get an answer at all.
+pub(crate) fn transport_error_replies(value: &Value, message: &str) -> Option<Value> {
+    let error_for = |message_value: &Value| {
+        let id = message_value.get("id")?;
+        message_value.get("method")?;
+        Some(jsonrpc_error_value(id.clone(), RpcError::internal(message)))
+    };
+    match value {
+        Value::Array(messages) => {
+            let replies: Vec<Value> = messages.iter().filter_map(error_for).collect();
+            (!replies.is_empty()).then_some(Value::Array(replies))
+        }
+        other => error_for(other),
+    }
 }
 
 /// Validate one JSON-RPC envelope and route it. Returns `None` for
@@ -91,7 +335,10 @@ async fn process_rpc_body(
 async fn handle_message(
     askl_data: &web::Data<AsklData>,
     index_store: &web::Data<IndexStore>,
+    mcp: &McpState,
+    user_id: Option<Uuid>,
     message: Value,
+    outbound: &Outbound,
 ) -> Option<Value> {
     let obj = match message.as_object() {
         Some(obj) => obj,
@@ -121,6 +368,7 @@ async fn handle_message(
         }
     }
 
+    let session_id = outbound.session.as_ref().map(McpSession::id);
     if let Some(method_value) = obj.get("method") {
         let method = match method_value.as_str() {
             Some(method) => method,
@@ -135,12 +383,21 @@ async fn handle_message(
         let params = obj.get("params").cloned();
         let id = match obj.get("id").cloned() {
             None => {
-                handle_notification(method).await;
+                handle_notification(mcp, user_id, session_id, method, params);
                 return None;
             }
             Some(id) => id,
         };
-        return match dispatch_method(askl_data, index_store, method, params).await {
+        let request = mcp
+            .in_flight
+            .start(user_id, session_id, &id, &outbound.closed);
+        let hooks = RequestHooks {
+            cancel: request.token(),
+            notify: outbound.notify.as_ref(),
+            session: outbound.session.as_ref(),
+        };
+        let result = dispatch_method(askl_data, index_store, user_id, method, params, &hooks).await;
+        return match result {
             Ok(result) => Some(jsonrpc_result_value(id, result)),
             Err(err) => Some(jsonrpc_error_value(id, err)),
         };
@@ -158,32 +415,95 @@ async fn handle_message(
     ))
 }
 
-async fn handle_notification(method: &str) {
+fn handle_notification(
+    mcp: &McpState,
+    user_id: Option<Uuid>,
+    session_id: Option<&str>,
+    method: &str,
+    params: Option<Value>,
+) {
     debug!("MCP notification: {}", method);
-    if method != "notifications/initialized" {
-        warn!("MCP unknown notification: {}", method);
+    match method {
+        "notifications/initialized" => {}
+        "notifications/cancelled" => match parse_params::<CancelledParams>(params) {
+            Ok(params) => {
+                if mcp
+                    .in_flight
+                    .cancel(user_id, session_id, &params.request_id)
+                {
+                    info!(
+                        "MCP request {} cancelled: {}",
+                        params.request_id,
+                        params.reason.as_deref().unwrap_or("no reason given")
+                    );
+                } else {
+                    debug!("MCP cancel for finished request {}", params.request_id);
+                }
+            }
+            Err(err) => warn!("MCP malformed cancel notification: {:?}", err),
+        },
+        _ => warn!("MCP unknown notification: {}", method),
     }
 }
 
+#[derive(Debug, serde::Deserialize)]
+struct CancelledParams {
+    #[serde(rename = "requestId")]
+    request_id: Value,
+    #[serde(default)]
+    reason: Option<String>,
+}
+
 async fn dispatch_method(
     askl_data: &web::Data<AsklData>,
     index_store: &web::Data<IndexStore>,
+    user_id: Option<Uuid>,
     method: &str,
     params: Option<Value>,
+    hooks: &RequestHooks<'_>,
 ) -> Result<Value, RpcError> {
     info!("MCP request: {}", method);
     match method {
         "initialize" => protocol::initialize(params),
         "tools/list" => tools::list(),
-        "tools/call" => tools::call(askl_data, index_store, params).await,
+        "tools/call" => tools::call(askl_data, index_store, user_id, params, hooks).await,
         "resources/list" => resources::list(),
-        "resources/read" => resources::read(params),
-        // Fixed-URI resources only, no templates. Clients (Claude Code) probe
-        // this on connect; answer with an empty list so the call succeeds.
-        "resources/templates/list" => Ok(json!({ "resourceTemplates": [] })),
+        "resources/read" => resources::read(askl_data, index_store, params).await,
+        "resources/templates/list" => resources::templates(),
         "prompts/list" => prompts::list(),
         "prompts/get" => prompts::get(params),
         "ping" => Ok(json!({})),
         _ => Err(RpcError::method_not_found()),
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn streams_only_requests_that_ask_for_progress() {
+        let request = json!({
+            "jsonrpc": "2.0", "id": 3, "method": "tools/call",
+            "params": { "name": "askl_run", "_meta": { "progressToken": "p" } }
+        });
+        assert!(wants_progress(&request));
+
+        let mut notification = request.clone();
+        notification.as_object_mut().unwrap().remove("id");
+        assert!(!wants_progress(&notification));
+        assert!(!wants_progress(&json!([request])));
+        assert!(!wants_progress(
+            &json!({ "jsonrpc": "2.0", "id": 4, "method": "tools/call", "params": {} })
+        ));
+    }
+
+    #[test]
+    fn frames_one_message_per_event() {
+        let event = sse_event(&json!({ "jsonrpc": "2.0", "id": 1, "result": {} }));
+        assert_eq!(
+            &event[..],
+            b"event: message\ndata: {\"id\":1,\"jsonrpc\":\"2.0\",\"result\":{}}\n\n"
+        );
+    }
+}
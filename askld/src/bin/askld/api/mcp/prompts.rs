@@ -1,5 +1,7 @@
 //! MCP prompts — canned exploration starters that expand to an `askl_run`-first
-//! instruction for the model.
+//! instruction for the model. The review workflows (`impact_analysis`,
+//! `explain_file`, `similar_usages`) compose several queries and take an
+//! optional `project` to scope every one of them.
 
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
@@ -104,9 +106,62 @@ fn prompt_definitions() -> Vec<PromptDefinition> {
                 },
             ]),
         },
+        PromptDefinition {
+            name: "impact_analysis",
+            description: "Assess the impact of changing a function: everything that reaches it, \
+directly, transitively or through function pointers, grouped by directory.",
+            arguments: Some(vec![
+                PromptArgument {
+                    name: "symbol",
+                    description: "Function to be changed, e.g. vfs_read.",
+                    required: true,
+                },
+                PROJECT_ARGUMENT,
+            ]),
+        },
+        PromptDefinition {
+            name: "explain_file",
+            description: "Explain a source file: its functions, what they call outside the \
+file, and who calls into it.",
+            arguments: Some(vec![
+                PromptArgument {
+                    name: "file",
+                    description: "Exact project-prefixed path, e.g. /linux/fs/read_write.c.",
+                    required: true,
+                },
+                PROJECT_ARGUMENT,
+            ]),
+        },
+        PromptDefinition {
+            name: "similar_usages",
+            description: "Find how existing code uses an API, to model a new use on it.",
+            arguments: Some(vec![
+                PromptArgument {
+                    name: "symbol",
+                    description: "API function, type or macro, e.g. kmalloc.",
+                    required: true,
+                },
+                PROJECT_ARGUMENT,
+            ]),
+        },
     ]
 }
 
+const PROJECT_ARGUMENT: PromptArgument = PromptArgument {
+    name: "project",
+    description: "Project to search (see the askl_projects tool); all projects when omitted.",
+    required: false,
+};
+
+/// The `project("…") ` prefix that scopes a query to the `project`
+/// argument, or nothing when it is absent.
+fn project_scope(arguments: &HashMap<String, String>) -> String {
+    match arguments.get("project").map(|p| p.trim()) {
+        Some(project) if !project.is_empty() => format!("project(\"{}\") ", project),
+        _ => String::new(),
+    }
+}
+
 fn prompt_content(name: &str, arguments: &HashMap<String, String>) -> Option<PromptGetResult> {
     let arg = |key: &str| arguments.get(key).cloned().unwrap_or_default();
     let (description, text) = match name {
@@ -147,6 +202,60 @@ with `file:line`."
                 ),
             )
         }
+        "impact_analysis" => {
+            let symbol = arg("symbol");
+            let scope = project_scope(arguments);
+            (
+                format!("Assess the impact of changing {}", symbol),
+                format!(
+                    "Using the askl MCP, assess what is affected if `{symbol}` changes. \
+1. Run `askl_run` with `{scope}func {{ \"{symbol}\" }}` for the direct callers. \
+2. Run `{scope}func unnest {{ \"{symbol}\" }}` with `projection: \"names\"` for every function \
+that reaches it transitively. \
+3. Run `{scope}dispatch {{ func(\"{symbol}\") }}` for tables that store its address; their \
+users call it indirectly. \
+Group the affected functions by directory, with a count per directory, and mark the direct \
+callers. Call out entry points (functions nothing else calls) and any indirect use, since \
+those are easy to miss in review."
+                ),
+            )
+        }
+        "explain_file" => {
+            let file = arg("file");
+            let scope = project_scope(arguments);
+            (
+                format!("Explain {}", file),
+                format!(
+                    "Using the askl MCP, explain the file `{file}`. \
+1. Run `askl_run` with `{scope}file(\"{file}\") {{ func }}` with `projection: \"signature\"` to \
+list its functions. \
+2. Run `{scope}file(\"{file}\") {{ func {{ }} }}` for what they call; the callees whose path \
+is not `{file}` are its dependencies. \
+3. Run `{scope}file(\"{file}\") {{ func @f }}; func {{ #f }}` for who calls into it from \
+elsewhere. \
+If the path matches nothing, it must be the exact project-prefixed path: find it with the \
+`askl_tree` tool. Describe the file's purpose, its public entry points with their callers, and \
+its outside dependencies, citing `file:line`."
+                ),
+            )
+        }
+        "similar_usages" => {
+            let symbol = arg("symbol");
+            let scope = project_scope(arguments);
+            (
+                format!("Find usages of {}", symbol),
+                format!(
+                    "Using the askl MCP, find how existing code uses `{symbol}`. \
+1. Run `askl_run` with `{scope}func {{ \"{symbol}\" }}` for the functions that use it. \
+2. Re-run it with `projection: \"body\"`, narrowing with `file(...)` or `dir(...)` if there \
+are many, to read the call sites. \
+3. Run `{scope}search(\"{symbol}\", whole_word=\"true\")` for uses the call graph misses \
+(macros, function-pointer assignments). \
+Group the call sites by usage pattern (argument shapes, error handling, cleanup), give one \
+representative `file:line` per pattern, and note which pattern is most common."
+                ),
+            )
+        }
         _ => return None,
     };
     Some(PromptGetResult {
@@ -160,3 +269,43 @@ with `file:line`."
         }],
     })
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn every_listed_prompt_renders() {
+        for prompt in prompt_definitions() {
+            assert!(
+                prompt_content(prompt.name, &HashMap::new()).is_some(),
+                "{}",
+                prompt.name
+            );
+        }
+    }
+
+    #[test]
+    fn project_scopes_every_query() {
+        let arguments = HashMap::from([
+            ("symbol".to_string(), "kmalloc".to_string()),
+            ("project".to_string(), "linux".to_string()),
+        ]);
+        let text = &prompt_content("impact_analysis", &arguments)
+            .unwrap()
+            .messages[0]
+            .content
+            .text;
+        assert!(text.contains("`project(\"linux\") func { \"kmalloc\" }`"));
+        assert!(text.contains("`project(\"linux\") func unnest { \"kmalloc\" }`"));
+        assert!(text.contains("`project(\"linux\") dispatch { func(\"kmalloc\") }`"));
+
+        let unscoped = HashMap::from([("symbol".to_string(), "kmalloc".to_string())]);
+        let text = &prompt_content("impact_analysis", &unscoped)
+            .unwrap()
+            .messages[0]
+            .content
+            .text;
+        assert!(text.contains("`func { \"kmalloc\" }`"));
+    }
+}
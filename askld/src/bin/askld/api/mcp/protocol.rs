@@ -68,6 +68,10 @@ pub(super) fn jsonrpc_result_value(id: Value, result: Value) -> Value {
     json!({ "jsonrpc": MCP_JSONRPC_VERSION, "id": id, "result": result })
 }
 
+pub(super) fn jsonrpc_notification_value(method: &str, params: Value) -> Value {
+    json!({ "jsonrpc": MCP_JSONRPC_VERSION, "method": method, "params": params })
+}
+
 pub(super) fn jsonrpc_error_value(id: Value, error: RpcError) -> Value {
     json!({
         "jsonrpc": MCP_JSONRPC_VERSION,
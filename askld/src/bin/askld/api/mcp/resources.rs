@@ -1,11 +1,21 @@
-//! MCP resources — the askl documentation the agent should read before querying.
-//! Bodies live as markdown files under `resources/` and are embedded with
-//! `include_str!`, so the docs are editable as data, not string literals.
+//! MCP resources — the askl documentation the agent should read before querying,
+//! plus templated resources addressing indexed code: a source file by project
+//! and path, or a symbol card by symbol id. Doc bodies live as markdown files
+//! under `resources/` and are embedded with `include_str!`, so the docs are
+//! editable as data, not string literals.
 
+use std::collections::HashMap;
+
+use actix_web::web;
+use askld::index_store::{IndexStore, SymbolNeighbour};
+use percent_encoding::percent_decode_str;
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
 
 use super::protocol::{parse_params, to_value, RpcError};
+use super::tools::lang_for_path;
+use crate::api::symbols::{load_symbol_card, SymbolCard, SymbolLocation};
+use crate::api::types::AsklData;
 
 const SYNTAX_RESOURCE: &str = include_str!("resources/syntax.md");
 const WORKFLOW_RESOURCE: &str = include_str!("resources/workflow.md");
@@ -13,6 +23,13 @@ const COOKBOOK_RESOURCE: &str = include_str!("resources/cookbook.md");
 const LIMITATIONS_RESOURCE: &str = include_str!("resources/limitations.md");
 
 const MIME_MARKDOWN: &str = "text/markdown";
+const MIME_TEXT: &str = "text/plain";
+
+const FILE_TEMPLATE_PREFIX: &str = "askl://project/";
+const SYMBOL_TEMPLATE_PREFIX: &str = "askl://symbol/";
+/// A symbol card shows at most this many lines of the definition; the rest is
+/// an `askl_read` away.
+const CARD_SOURCE_LINES: usize = 60;
 
 /// `resources/list` — the catalog of available resources.
 pub(super) fn list() -> Result<Value, RpcError> {
@@ -21,16 +38,245 @@ pub(super) fn list() -> Result<Value, RpcError> {
     })
 }
 
-/// `resources/read` — the body of one resource by URI.
-pub(super) fn read(params: Option<Value>) -> Result<Value, RpcError> {
+/// `resources/templates/list` — the parameterised URIs `resources/read` accepts.
+pub(super) fn templates() -> Result<Value, RpcError> {
+    to_value(ResourceTemplatesListResult {
+        resource_templates: resource_template_definitions(),
+    })
+}
+
+/// `resources/read` — the body of one resource by URI: a static doc, or a
+/// file/symbol resolved against the index.
+pub(super) async fn read(
+    askl_data: &web::Data<AsklData>,
+    index_store: &web::Data<IndexStore>,
+    params: Option<Value>,
+) -> Result<Value, RpcError> {
     let params: ResourceReadParams = parse_params(params)?;
-    let content = resource_content(&params.uri)
-        .ok_or_else(|| RpcError::invalid_params(&format!("unknown resource: {}", params.uri)))?;
+    let content = match resource_content(&params.uri) {
+        Some(content) => content,
+        None => match parse_template_uri(&params.uri) {
+            Some(TemplateUri::File { project, path }) => {
+                read_file(askl_data, &params.uri, &project, &path).await?
+            }
+            Some(TemplateUri::Symbol(id)) => {
+                read_symbol(askl_data, index_store, &params.uri, id).await?
+            }
+            None => {
+                return Err(RpcError::invalid_params(&format!(
+                    "unknown resource: {}",
+                    params.uri
+                )))
+            }
+        },
+    };
     to_value(ResourceReadResult {
         contents: vec![content],
     })
 }
 
+/// A `resources/read` URI matching one of the templates.
+#[derive(Debug, PartialEq, Eq)]
+enum TemplateUri {
+    File { project: String, path: String },
+    Symbol(i64),
+}
+
+/// Match `uri` against the templates. Segments are percent-decoded; the file
+/// path is everything after `/file/`, slashes included.
+fn parse_template_uri(uri: &str) -> Option<TemplateUri> {
+    let decode = |s: &str| {
+        percent_decode_str(s)
+            .decode_utf8()
+            .ok()
+            .map(|s| s.into_owned())
+    };
+    if let Some(rest) = uri.strip_prefix(FILE_TEMPLATE_PREFIX) {
+        let (project, path) = rest.split_once("/file/")?;
+        let (project, path) = (decode(project)?, decode(path)?);
+        if project.is_empty() || path.is_empty() {
+            return None;
+        }
+        return Some(TemplateUri::File { project, path });
+    }
+    let id = uri.strip_prefix(SYMBOL_TEMPLATE_PREFIX)?;
+    id.parse().ok().map(TemplateUri::Symbol)
+}
+
+/// Source text of `path` in `project`. The path resolves like `askl_read`'s
+/// (exact, or a suffix on a segment boundary), but a resource is one file:
+/// an ambiguous suffix is an error listing the candidates.
+async fn read_file(
+    askl_data: &web::Data<AsklData>,
+    uri: &str,
+    project: &str,
+    path: &str,
+) -> Result<ResourceContent, RpcError> {
+    let index = &askl_data.cfg.index;
+    let matches = index
+        .resolve_source_file(path, Some(project))
+        .await
+        .map_err(|err| RpcError::internal(&format!("Failed to resolve '{}': {}", path, err)))?;
+    let chosen = match matches.as_slice() {
+        [] => {
+            return Err(RpcError::invalid_params(&format!(
+                "no file matching '{}' in project '{}'",
+                path, project
+            )))
+        }
+        [only] => only,
+        [first, ..] if first.filesystem_path == path => first,
+        several => {
+            let candidates: Vec<&str> =
+                several.iter().map(|m| m.filesystem_path.as_str()).collect();
+            return Err(RpcError::invalid_params(&format!(
+                "'{}' matches several files in project '{}': {}",
+                path,
+                project,
+                candidates.join(", ")
+            )));
+        }
+    };
+    let content = index
+        .get_file_contents_bytes(chosen.object_id)
+        .await
+        .map_err(|err| {
+            RpcError::internal(&format!(
+                "Failed to read '{}': {}",
+                chosen.filesystem_path, err
+            ))
+        })?;
+    Ok(ResourceContent {
+        uri: uri.to_string(),
+        mime_type: MIME_TEXT,
+        text: String::from_utf8_lossy(&content).into_owned(),
+    })
+}
+
+/// Markdown card for one symbol of the persistent index.
+async fn read_symbol(
+    askl_data: &web::Data<AsklData>,
+    index_store: &web::Data<IndexStore>,
+    uri: &str,
+    id: i64,
+) -> Result<ResourceContent, RpcError> {
+    let (card, contents) = load_symbol_card(askl_data, index_store, id)
+        .await
+        .map_err(|err| RpcError::internal(&format!("Failed to load symbol {}: {:?}", id, err)))?
+        .ok_or_else(|| RpcError::invalid_params(&format!("unknown symbol: {}", id)))?;
+    Ok(ResourceContent {
+        uri: uri.to_string(),
+        mime_type: MIME_MARKDOWN,
+        text: render_symbol_card(&card, &contents),
+    })
+}
+
+/// The card: identity and containers, caller/callee counts with the queries
+/// that list them and the most frequent of each, the doc comment, every
+/// definition and declaration as `file:line`, and the head of the first
+/// definition.
+pub(super) fn render_symbol_card(card: &SymbolCard, contents: &HashMap<i32, Vec<u8>>) -> String {
+    let mut md = format!(
+        "# `{}`\n\n{} in project `{}` (symbol id {})\n\n",
+        card.name, card.symbol_type, card.project_name, card.id
+    );
+    if !card.containers.is_empty() {
+        let containers: Vec<String> = card
+            .containers
+            .iter()
+            .map(|container| format!("{} `{}`", container.symbol_type, container.name))
+            .collect();
+        md.push_str(&format!("In {}.\n\n", containers.join(", ")));
+    }
+    md.push_str(&format!(
+        "- callers: {} — `{{ \"{}\" }}`\n",
+        card.caller_count, card.name
+    ));
+    push_neighbours(&mut md, &card.callers);
+    md.push_str(&format!(
+        "- callees: {} — `\"{}\" {{ }}`\n",
+        card.callee_count, card.name
+    ));
+    push_neighbours(&mut md, &card.callees);
+
+    if let Some(documentation) = &card.documentation {
+        md.push_str(&format!(
+            "\n## Documentation\n\n```\n{}\n```\n",
+            documentation
+        ));
+    }
+
+    let locations = card
+        .definitions
+        .iter()
+        .map(|location| ("definition", location))
+        .chain(
+            card.declarations
+                .iter()
+                .map(|location| ("declaration", location)),
+        );
+    let mut first = true;
+    for (kind, location) in locations {
+        if first {
+            md.push_str("\n## Instances\n\n");
+            first = false;
+        }
+        md.push_str(&format!("- {} `{}`\n", kind, location_str(location)));
+    }
+
+    let definition = card.definitions.iter().find_map(|location| {
+        let content = contents.get(&location.object_id)?;
+        Some((location, content, location.start_line?))
+    });
+    if let Some((location, content, start_line)) = definition {
+        let start = (location.start_offset as usize).min(content.len());
+        let end = (location.end_offset as usize).clamp(start, content.len());
+        let body = String::from_utf8_lossy(&content[start..end]);
+        let total = body.lines().count();
+        md.push_str(&format!(
+            "\n## Definition\n\n```{}\n",
+            lang_for_path(&location.path)
+        ));
+        for line in body.lines().take(CARD_SOURCE_LINES) {
+            md.push_str(line);
+            md.push('\n');
+        }
+        md.push_str("```\n");
+        if total > CARD_SOURCE_LINES {
+            md.push_str(&format!(
+                "\n_{} of {} lines shown; `askl_read` `{}` from line {} for the rest._\n",
+                CARD_SOURCE_LINES,
+                total,
+                location.path,
+                start_line + CARD_SOURCE_LINES
+            ));
+        }
+    }
+    md
+}
+
+/// The top callers or callees, indented under their count.
+fn push_neighbours(md: &mut String, neighbours: &[SymbolNeighbour]) {
+    for neighbour in neighbours {
+        md.push_str(&format!(
+            "  - `{}` #{} ({} ref{})\n",
+            neighbour.name,
+            neighbour.id,
+            neighbour.refs,
+            if neighbour.refs == 1 { "" } else { "s" }
+        ));
+    }
+}
+
+/// `path:line` or `path:start-end`; just the path without line numbers.
+fn location_str(location: &SymbolLocation) -> String {
+    match (location.start_line, location.end_line) {
+        (Some(start), Some(end)) if end > start => format!("{}:{}-{}", location.path, start, end),
+        (Some(start), _) => format!("{}:{}", location.path, start),
+        _ => location.path.clone(),
+    }
+}
+
 #[derive(Debug, Serialize)]
 struct ResourcesListResult {
     resources: Vec<ResourceDefinition>,
@@ -45,6 +291,22 @@ struct ResourceDefinition {
     mime_type: &'static str,
 }
 
+#[derive(Debug, Serialize)]
+struct ResourceTemplatesListResult {
+    #[serde(rename = "resourceTemplates")]
+    resource_templates: Vec<ResourceTemplateDefinition>,
+}
+
+#[derive(Debug, Serialize)]
+struct ResourceTemplateDefinition {
+    #[serde(rename = "uriTemplate")]
+    uri_template: &'static str,
+    name: &'static str,
+    description: &'static str,
+    #[serde(rename = "mimeType")]
+    mime_type: &'static str,
+}
+
 #[derive(Debug, Deserialize)]
 struct ResourceReadParams {
     uri: String,
@@ -95,6 +357,28 @@ static edges, read-only, result caps.",
     ]
 }
 
+fn resource_template_definitions() -> Vec<ResourceTemplateDefinition> {
+    vec![
+        ResourceTemplateDefinition {
+            uri_template: "askl://project/{name}/file/{path}",
+            name: "Source file",
+            description: "Full text of an indexed source file. `name` is the project name from \
+`askl_projects`; `path` is the file path or a suffix of it on a segment boundary \
+(`fs/read_write.c`) and must match a single file.",
+            mime_type: MIME_TEXT,
+        },
+        ResourceTemplateDefinition {
+            uri_template: "askl://symbol/{id}",
+            name: "Symbol card",
+            description: "Summary of one symbol by id (the `#id` after a symbol in `askl_run` \
+results): its definitions and declarations as `file:line`, its doc comment, the files and \
+directories containing it, caller and callee counts with the most frequent of each, and the head \
+of its definition.",
+            mime_type: MIME_MARKDOWN,
+        },
+    ]
+}
+
 fn resource_content(uri: &str) -> Option<ResourceContent> {
     let text = match uri {
         "askl://syntax" => SYNTAX_RESOURCE,
@@ -109,3 +393,103 @@ fn resource_content(uri: &str) -> Option<ResourceContent> {
         text: text.to_string(),
     })
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use askld::index_store::{SymbolContainer, SymbolInstanceSummary, SymbolSummary};
+
+    #[test]
+    fn parses_file_and_symbol_templates() {
+        assert_eq!(
+            parse_template_uri("askl://project/linux/file/fs/read%20write.c"),
+            Some(TemplateUri::File {
+                project: "linux".to_string(),
+                path: "fs/read write.c".to_string()
+            })
+        );
+        assert_eq!(
+            parse_template_uri("askl://project/my%2Fproj/file//src/a.c"),
+            Some(TemplateUri::File {
+                project: "my/proj".to_string(),
+                path: "/src/a.c".to_string()
+            })
+        );
+        assert_eq!(
+            parse_template_uri("askl://symbol/4294967308"),
+            Some(TemplateUri::Symbol(4294967308))
+        );
+        assert_eq!(parse_template_uri("askl://project/linux/file/"), None);
+        assert_eq!(parse_template_uri("askl://project/linux"), None);
+        assert_eq!(parse_template_uri("askl://symbol/main"), None);
+        assert_eq!(parse_template_uri("askl://syntax"), None);
+    }
+
+    fn instance(instance_type: &str, start: i32, end: i32) -> SymbolInstanceSummary {
+        SymbolInstanceSummary {
+            id: 1,
+            instance_type: instance_type.to_string(),
+            object_id: 7,
+            path: "/p/a.c".to_string(),
+            start_offset: start,
+            end_offset: end,
+        }
+    }
+
+    #[test]
+    fn symbol_card_lists_instances_and_definition() {
+        let source = b"int f(void);\n\nint f(void)\n{\n  return g();\n}\n".to_vec();
+        let summary = SymbolSummary {
+            id: 42,
+            name: "f".to_string(),
+            symbol_type: "function".to_string(),
+            project_id: 1,
+            project_name: "p".to_string(),
+            instances: vec![
+                instance("definition", 14, 44),
+                instance("declaration", 0, 11),
+            ],
+            caller_count: 0,
+            callee_count: 1,
+            callers: Vec::new(),
+            callees: vec![SymbolNeighbour {
+                id: 43,
+                name: "g".to_string(),
+                refs: 1,
+            }],
+            containers: vec![SymbolContainer {
+                id: 5,
+                name: "/p/a.c".to_string(),
+                symbol_type: "file".to_string(),
+            }],
+        };
+        let contents = HashMap::from([(7, source)]);
+        let card = render_symbol_card(&SymbolCard::new(summary, &contents), &contents);
+        assert!(card.starts_with("# `f`\n\nfunction in project `p` (symbol id 42)"));
+        assert!(card.contains("In file `/p/a.c`."));
+        assert!(card.contains("- callees: 1 — `\"f\" { }`\n  - `g` #43 (1 ref)\n"));
+        assert!(card.contains("- definition `/p/a.c:3-6`"));
+        assert!(card.contains("- declaration `/p/a.c:1`"));
+        assert!(card.contains("```c\nint f(void)\n{\n  return g();\n}\n```"));
+    }
+
+    #[test]
+    fn symbol_card_without_content_keeps_paths() {
+        let summary = SymbolSummary {
+            id: 42,
+            name: "f".to_string(),
+            symbol_type: "function".to_string(),
+            project_id: 1,
+            project_name: "p".to_string(),
+            instances: vec![instance("definition", 14, 44)],
+            caller_count: 2,
+            callee_count: 0,
+            callers: Vec::new(),
+            callees: Vec::new(),
+            containers: Vec::new(),
+        };
+        let card = render_symbol_card(&SymbolCard::new(summary, &HashMap::new()), &HashMap::new());
+        assert!(card.contains("- definition `/p/a.c`"));
+        assert!(!card.contains("## Definition"));
+    }
+}
@@ -0,0 +1,197 @@
+//! MCP sessions: what a client's `askl_run` calls leave behind for its next
+//! ones (see [`crate::api::query::QuerySession`]).
+//!
+//! `initialize` opens a session and returns its id in the `Mcp-Session-Id`
+//! response header; requests carrying that header run in it. A session
+//! belongs to the user that opened it (anonymous callers to anyone holding
+//! the id), and ends with `DELETE /mcp` or once it has been idle for as long
+//! as the eph-layer GC keeps unused layers. Sessions hold labelled selections
+//! in memory, so their number is capped per user (anonymous callers sharing
+//! one allowance) and in total; opening one past a cap ends the least
+//! recently used, whose client then gets `404` and initializes again.
+
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use uuid::Uuid;
+
+use crate::api::query::SharedSession;
+
+/// One open session, as the requests in it see it.
+#[derive(Clone)]
+pub(crate) struct McpSession {
+    id: String,
+    query: SharedSession,
+}
+
+impl McpSession {
+    /// A session no other client can reach, for a transport that serves a
+    /// single client (`askld mcp --database-url`).
+    pub(crate) fn private() -> Self {
+        Self {
+            id: Uuid::new_v4().to_string(),
+            query: SharedSession::default(),
+        }
+    }
+
+    pub(super) fn id(&self) -> &str {
+        &self.id
+    }
+
+    pub(super) fn query(&self) -> &SharedSession {
+        &self.query
+    }
+}
+
+/// Open sessions one user (or all anonymous callers together) may hold.
+const MAX_SESSIONS_PER_USER: usize = 16;
+/// Open sessions across all users.
+const MAX_SESSIONS: usize = 1024;
+
+struct Entry {
+    user_id: Option<Uuid>,
+    last_used: Instant,
+    session: McpSession,
+}
+
+#[derive(Default)]
+pub(super) struct Sessions {
+    sessions: Mutex<HashMap<String, Entry>>,
+}
+
+impl Sessions {
+    /// Open a session for `user_id`, first ending the least recently used
+    /// ones that would put it over a cap.
+    pub(super) fn create(&self, user_id: Option<Uuid>) -> McpSession {
+        let session = McpSession::private();
+        let mut sessions = self.sessions.lock().unwrap();
+        while sessions
+            .values()
+            .filter(|entry| entry.user_id == user_id)
+            .count()
+            >= MAX_SESSIONS_PER_USER
+        {
+            evict_least_recently_used(&mut sessions, |entry| entry.user_id == user_id);
+        }
+        while sessions.len() >= MAX_SESSIONS {
+            evict_least_recently_used(&mut sessions, |_| true);
+        }
+        sessions.insert(
+            session.id.clone(),
+            Entry {
+                user_id,
+                last_used: Instant::now(),
+                session: session.clone(),
+            },
+        );
+        session
+    }
+
+    /// `user_id`'s session `id`, marked used; `None` if it does not exist,
+    /// expired or belongs to someone else.
+    pub(super) fn get(&self, id: &str, user_id: Option<Uuid>) -> Option<McpSession> {
+        let mut sessions = self.sessions.lock().unwrap();
+        let entry = sessions
+            .get_mut(id)
+            .filter(|entry| entry.user_id == user_id)?;
+        entry.last_used = Instant::now();
+        Some(entry.session.clone())
+    }
+
+    /// End `user_id`'s session `id`. Returns whether there was one.
+    pub(super) fn remove(&self, id: &str, user_id: Option<Uuid>) -> bool {
+        let mut sessions = self.sessions.lock().unwrap();
+        if sessions
+            .get(id)
+            .is_some_and(|entry| entry.user_id == user_id)
+        {
+            sessions.remove(id);
+            return true;
+        }
+        false
+    }
+
+    /// End every session unused for longer than `idle`; returns how many.
+    /// A query still running in one keeps its session until it finishes.
+    pub(super) fn expire(&self, idle: Duration) -> usize {
+        let mut sessions = self.sessions.lock().unwrap();
+        let before = sessions.len();
+        sessions.retain(|_, entry| {
+            entry.last_used.elapsed() <= idle || entry.session.query.try_lock().is_err()
+        });
+        before - sessions.len()
+    }
+}
+
+fn evict_least_recently_used(
+    sessions: &mut HashMap<String, Entry>,
+    eligible: impl Fn(&Entry) -> bool,
+) {
+    let oldest = sessions
+        .iter()
+        .filter(|(_, entry)| eligible(entry))
+        .min_by_key(|(_, entry)| entry.last_used)
+        .map(|(id, _)| id.clone());
+    if let Some(id) = oldest {
+        sessions.remove(&id);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sessions_belong_to_their_user() {
+        let sessions = Sessions::default();
+        let user = Some(Uuid::new_v4());
+        let session = sessions.create(user);
+
+        assert!(sessions.get(session.id(), None).is_none());
+        let found = sessions.get(session.id(), user).unwrap();
+        assert!(std::sync::Arc::ptr_eq(found.query(), session.query()));
+
+        assert!(!sessions.remove(session.id(), None));
+        assert!(sessions.remove(session.id(), user));
+        assert!(sessions.get(session.id(), user).is_none());
+    }
+
+    #[test]
+    fn caps_sessions_per_user_evicting_the_least_recently_used() {
+        let sessions = Sessions::default();
+        let user = Some(Uuid::new_v4());
+        let first = sessions.create(user);
+        let second = sessions.create(user);
+        for _ in 2..MAX_SESSIONS_PER_USER {
+            sessions.create(user);
+        }
+        let other = sessions.create(None);
+        // Touching `first` leaves `second` the least recently used.
+        assert!(sessions.get(first.id(), user).is_some());
+
+        sessions.create(user);
+        assert!(sessions.get(second.id(), user).is_none());
+        assert!(sessions.get(first.id(), user).is_some());
+        assert!(sessions.get(other.id(), None).is_some());
+    }
+
+    #[test]
+    fn expires_only_idle_sessions() {
+        let sessions = Sessions::default();
+        let session = sessions.create(None);
+        assert_eq!(sessions.expire(Duration::from_secs(60)), 0);
+        assert_eq!(sessions.expire(Duration::ZERO), 1);
+        assert!(sessions.get(session.id(), None).is_none());
+    }
+
+    #[test]
+    fn expire_keeps_sessions_with_a_running_query() {
+        let sessions = Sessions::default();
+        let session = sessions.create(None);
+        let running = session.query().try_lock().unwrap();
+        assert_eq!(sessions.expire(Duration::ZERO), 0);
+        drop(running);
+        assert_eq!(sessions.expire(Duration::ZERO), 1);
+    }
+}
@@ -1,17 +1,29 @@
-//! The MCP tools (`askl_run`, `askl_projects`, `askl_read`) and their wire types.
+//! The MCP tools (`askl_run`, `askl_check`, `askl_projects`, `askl_read`,
+//! `askl_tree`, `askl_symbol`) and their wire types.
 //! Every tool returns **markdown** (never JSON); `askl_run` shares the exact
 //! `/query?format=markdown` render path.
 
 use actix_web::web;
-use askld::index_store::{IndexStore, UploadStatus};
+use std::collections::{HashMap, HashSet};
+
+use askld::check::{check, QueryCheck};
+use askld::execution_context::{ProgressFn, StatementProgress};
+use askld::index_store::{
+    normalize_full_path, IndexStore, MultiTreeResult, NodeType, ProjectTreeNode, UploadStatus,
+};
 use askld::line_index::{line_to_offset, LineIndex};
+use askld::parser::{parse_with_macros, QueryParams};
 use log::debug;
 use serde::{Deserialize, Serialize};
 use serde_json::{json, Value};
+use uuid::Uuid;
 
-use super::protocol::{parse_params, to_value, RpcError};
-use crate::api::query::{build_result_graph, render_graph_markdown};
+use super::protocol::{jsonrpc_notification_value, parse_params, to_value, RpcError};
+use super::resources::render_symbol_card;
+use super::RequestHooks;
+use crate::api::query::{run_query, QueryControl, QueryError, QueryOutput};
 use crate::api::render::Projection;
+use crate::api::symbols::load_symbol_card;
 use crate::api::types::AsklData;
 
 /// `tools/list` — the tool catalog.
@@ -25,15 +37,21 @@ pub(super) fn list() -> Result<Value, RpcError> {
 pub(super) async fn call(
     askl_data: &web::Data<AsklData>,
     index_store: &web::Data<IndexStore>,
+    user_id: Option<Uuid>,
     params: Option<Value>,
+    hooks: &RequestHooks<'_>,
 ) -> Result<Value, RpcError> {
     let call: ToolsCallParams = parse_params(params)?;
     debug!("MCP tools/call: {}", call.name);
     let arguments = call.arguments.unwrap_or_else(|| json!({}));
+    let progress_token = call.meta.and_then(|meta| meta.progress_token);
     let output = match call.name.as_str() {
-        "askl_run" => tool_askl_run(askl_data, arguments).await,
+        "askl_run" => tool_askl_run(askl_data, user_id, arguments, progress_token, hooks).await,
+        "askl_check" => tool_askl_check(askl_data, arguments, hooks).await,
         "askl_projects" => tool_askl_projects(index_store).await,
         "askl_read" => tool_askl_read(askl_data, arguments).await,
+        "askl_tree" => tool_askl_tree(index_store, arguments).await,
+        "askl_symbol" => tool_askl_symbol(askl_data, index_store, arguments).await,
         other => {
             return Err(RpcError::invalid_params(&format!(
                 "unknown tool: {}",
@@ -83,12 +101,24 @@ struct AsklRunArgs {
     projection: Option<String>,
     #[serde(default)]
     limit: Option<usize>,
+    #[serde(default)]
+    params: Option<Value>,
 }
 
 /// `askl_run` — execute a raw askl query and return the markdown report. Uses
-/// the exact `build_result_graph` + `render_graph_markdown` path as
-/// `/query?format=markdown`, so the output is identical.
-async fn tool_askl_run(data: &web::Data<AsklData>, arguments: Value) -> ToolOutput {
+/// the exact `run_query` path as `/query?format=markdown`, so the output (and
+/// the result cache) is shared. With a `progressToken` and a transport that
+/// can deliver them, each finished statement is reported as it completes;
+/// cancelling the request cancels the query. In an MCP session the query may
+/// use the labels of the session's earlier queries (and is never served from
+/// the result cache).
+async fn tool_askl_run(
+    data: &web::Data<AsklData>,
+    user_id: Option<Uuid>,
+    arguments: Value,
+    progress_token: Option<Value>,
+    hooks: &RequestHooks<'_>,
+) -> ToolOutput {
     let args: AsklRunArgs = match serde_json::from_value(arguments) {
         Ok(args) => args,
         Err(err) => return ToolOutput::error_md(format!("Invalid arguments: {}", err)),
@@ -106,14 +136,156 @@ async fn tool_askl_run(data: &web::Data<AsklData>, arguments: Value) -> ToolOutp
         },
     };
 
-    match build_result_graph(data, &args.query, args.limit).await {
-        Ok(graph) => {
-            ToolOutput::ok(render_graph_markdown(data, &args.query, &graph, projection).await)
-        }
+    let params = match args.params.as_ref().map(QueryParams::from_json).transpose() {
+        Ok(params) => params.unwrap_or_default(),
+        Err(err) => return ToolOutput::error_md(format!("Invalid params: {}", err)),
+    };
+
+    let progress = progress_token
+        .zip(hooks.notify.cloned())
+        .map(|(token, notify)| {
+            Box::new(move |step: &StatementProgress| {
+                let _ = notify.send(progress_notification(&token, step));
+            }) as ProgressFn
+        });
+    let control = QueryControl {
+        progress,
+        cancel: Some(hooks.cancel.clone()),
+        session: hooks.session.map(|session| session.query().clone()),
+    };
+    let output = QueryOutput::Markdown(projection);
+    match run_query(
+        data,
+        user_id,
+        &args.query,
+        params,
+        args.limit,
+        output,
+        None,
+        control,
+    )
+    .await
+    {
+        Ok(response) => ToolOutput::ok(response.body),
         Err(err) => ToolOutput::error(err.to_markdown()),
     }
 }
 
+#[derive(Debug, Deserialize)]
+struct AsklCheckArgs {
+    query: String,
+    #[serde(default)]
+    params: Option<Value>,
+}
+
+/// `askl_check` — parse a query the way `askl_run` would (same macros, same
+/// parameter binding, same session labels) and explain it without running
+/// it. Parse errors come back in the exact `askl_run` shape.
+async fn tool_askl_check(
+    data: &web::Data<AsklData>,
+    arguments: Value,
+    hooks: &RequestHooks<'_>,
+) -> ToolOutput {
+    let args: AsklCheckArgs = match serde_json::from_value(arguments) {
+        Ok(args) => args,
+        Err(err) => return ToolOutput::error_md(format!("Invalid arguments: {}", err)),
+    };
+    let params = match args.params.as_ref().map(QueryParams::from_json).transpose() {
+        Ok(params) => params.unwrap_or_default(),
+        Err(err) => return ToolOutput::error_md(format!("Invalid params: {}", err)),
+    };
+    let labels = match hooks.session {
+        Some(session) => session.query().lock().await.label_names(),
+        None => HashSet::new(),
+    };
+    let checked =
+        parse_with_macros(&args.query, params, &data.macros).and_then(|ast| check(&ast, &labels));
+    match checked {
+        Ok(checked) => ToolOutput::ok(render_check(&checked)),
+        Err(err) => ToolOutput::error(QueryError::Parse(err).to_markdown()),
+    }
+}
+
+/// The `askl_check` markdown: the normalized query, one bullet per statement
+/// (nested like the query) and the warnings.
+fn render_check(check: &QueryCheck) -> String {
+    let mut md = format!(
+        "# Query check\n\nThe query parses: {} statement{}, {} warning{}. Nothing was run.\n\n",
+        check.statements.len(),
+        if check.statements.len() == 1 { "" } else { "s" },
+        check.warnings.len(),
+        if check.warnings.len() == 1 { "" } else { "s" },
+    );
+    md.push_str(&format!(
+        "## Normalized\n\n```askl\n{}```\n\n",
+        check.normalized
+    ));
+
+    md.push_str("## Statements\n\n");
+    for statement in &check.statements {
+        let indent = "  ".repeat(statement.depth);
+        let mut line = format!("{}- **s{}** `{}`", indent, statement.id, statement.label());
+        match statement.parent {
+            None => line.push_str(" — top level"),
+            Some(parent) => line.push_str(&format!(
+                " — {}",
+                relation_phrase(&statement.relation, parent)
+            )),
+        }
+        if !statement.ref_kinds.is_empty() {
+            line.push_str(&format!(
+                "; refs limited to {}",
+                statement.ref_kinds.join(", ")
+            ));
+        }
+        if statement.unnest {
+            line.push_str("; unnested");
+        }
+        if statement.weak {
+            line.push_str("; weak (only scopes its neighbours)");
+        }
+        md.push_str(&line);
+        md.push('\n');
+        if !statement.inherited.is_empty() {
+            let inherited: Vec<String> = statement
+                .inherited
+                .iter()
+                .map(|verb| format!("`{}`", verb))
+                .collect();
+            md.push_str(&format!(
+                "{}  - inherited: {}\n",
+                indent,
+                inherited.join(", ")
+            ));
+        }
+        for implicit in &statement.implicit {
+            md.push_str(&format!("{}  - implicit: {}\n", indent, implicit));
+        }
+    }
+
+    if !check.warnings.is_empty() {
+        md.push_str("\n## Warnings\n\n");
+        for warning in &check.warnings {
+            md.push_str(&format!(
+                "- `{}`: {}\n",
+                warning.span.as_str().trim(),
+                warning.message
+            ));
+        }
+    }
+    md
+}
+
+/// How a statement's symbols relate to those of statement `parent`.
+fn relation_phrase(relation: &[&str], parent: usize) -> String {
+    match relation {
+        ["refs"] => format!("what s{} references (callees, uses)", parent),
+        ["has"] => format!("what s{} contains", parent),
+        ["refs", "has"] => format!("what s{} references or contains", parent),
+        other => format!("related to s{} via {}", parent, other.join(", ")),
+    }
+}
+
 /// `askl_projects` — list the indexed projects with their scope name, root path,
 /// status, and file/symbol counts. The **project name** is what `project("…")`
 /// takes; the counts tell the agent how big a scope it's about to query.
@@ -262,6 +434,202 @@ async fn tool_askl_read(data: &web::Data<AsklData>, arguments: Value) -> ToolOut
     ToolOutput::ok(md)
 }
 
+#[derive(Debug, Deserialize)]
+struct AsklTreeArgs {
+    project: String,
+    #[serde(default)]
+    path: Option<String>,
+    #[serde(default)]
+    expand: Vec<String>,
+    #[serde(default)]
+    compact: Option<bool>,
+    #[serde(default)]
+    counts: bool,
+}
+
+/// Most entries one `askl_tree` call renders; the rest are summarized so a
+/// huge flat directory can't flood the agent's context.
+const MAX_TREE_ENTRIES: usize = 500;
+
+/// `askl_tree` — list a project directory as a markdown outline: directories
+/// first, with `counts` each entry with the number of symbols defined in or
+/// below it (off by default: at the root of a large project that counts
+/// every definition in it). `expand` nests further directories in the same answer; `compact` (default
+/// on) folds single-child directory chains into one `a/b/c/` entry, exactly
+/// like `GET /v1/index/projects/{id}/tree`.
+async fn tool_askl_tree(store: &web::Data<IndexStore>, arguments: Value) -> ToolOutput {
+    let args: AsklTreeArgs = match serde_json::from_value(arguments) {
+        Ok(args) => args,
+        Err(err) => return ToolOutput::error_md(format!("Invalid arguments: {}", err)),
+    };
+    let raw_path = args.path.as_deref().unwrap_or("/");
+    if !raw_path.starts_with('/') || args.expand.iter().any(|p| !p.starts_with('/')) {
+        return ToolOutput::error_md("`path` and `expand` must be absolute paths.");
+    }
+    let path = normalize_full_path(raw_path);
+    let expand: Vec<String> = args.expand.iter().map(|p| normalize_full_path(p)).collect();
+    let compact = args.compact.unwrap_or(true);
+
+    let projects = match store.list_projects().await {
+        Ok(projects) => projects,
+        Err(err) => return ToolOutput::error_md(format!("Failed to list projects: {:?}", err)),
+    };
+    let Some(project) = projects.iter().find(|p| p.project_name == args.project) else {
+        return ToolOutput::error_md(format!(
+            "No project named '{}'; `askl_projects` lists the indexed ones.",
+            args.project
+        ));
+    };
+
+    let mut all_paths = vec![path.clone()];
+    all_paths.extend(expand.iter().cloned());
+    let listings = match store
+        .list_project_tree_multi(project.id, &all_paths, compact)
+        .await
+    {
+        Ok(MultiTreeResult::Nodes(map)) => map,
+        Ok(MultiTreeResult::ProjectNotFound) => {
+            return ToolOutput::error_md(format!("Project '{}' no longer exists.", args.project))
+        }
+        Ok(MultiTreeResult::NotReady) => {
+            return ToolOutput::error_md(format!(
+                "Project '{}' is still uploading; try again once it is complete.",
+                args.project
+            ))
+        }
+        Ok(MultiTreeResult::NotDirectory(p)) => {
+            return ToolOutput::error_md(format!(
+                "`{}` is not a directory of project '{}'; read files with `askl_read`.",
+                p, args.project
+            ))
+        }
+        Err(err) => return ToolOutput::error_md(format!("Failed to load tree: {:?}", err)),
+    };
+
+    let counts = if args.counts {
+        let mut count_paths = vec![path.clone()];
+        count_paths.extend(listings.values().flatten().map(|node| node.path.clone()));
+        count_paths.sort();
+        count_paths.dedup();
+        match store
+            .count_definitions_under(project.id, &count_paths)
+            .await
+        {
+            Ok(counts) => counts,
+            Err(err) => return ToolOutput::error_md(format!("Failed to count symbols: {:?}", err)),
+        }
+    } else {
+        HashMap::new()
+    };
+
+    ToolOutput::ok(render_tree(
+        &project.project_name,
+        &path,
+        &listings,
+        &counts,
+    ))
+}
+
+#[derive(Debug, Deserialize)]
+struct AsklSymbolArgs {
+    id: i64,
+}
+
+/// `askl_symbol` — the symbol card for one symbol id, the same markdown as
+/// the `askl://symbol/{id}` resource.
+async fn tool_askl_symbol(
+    askl_data: &web::Data<AsklData>,
+    store: &web::Data<IndexStore>,
+    arguments: Value,
+) -> ToolOutput {
+    let args: AsklSymbolArgs = match serde_json::from_value(arguments) {
+        Ok(args) => args,
+        Err(err) => return ToolOutput::error_md(format!("Invalid arguments: {}", err)),
+    };
+    match load_symbol_card(askl_data, store, args.id).await {
+        Ok(Some((card, contents))) => ToolOutput::ok(render_symbol_card(&card, &contents)),
+        Ok(None) => ToolOutput::error_md(format!(
+            "No symbol with id {}; ids are the `#id` after a symbol in `askl_run` results.",
+            args.id
+        )),
+        Err(err) => ToolOutput::error_md(format!("Failed to load symbol {}: {:?}", args.id, err)),
+    }
+}
+
+/// The `askl_tree` markdown: a heading for `base`, then one bullet per entry,
+/// with expanded directories' listings indented under them.
+fn render_tree(
+    project: &str,
+    base: &str,
+    listings: &HashMap<String, Vec<ProjectTreeNode>>,
+    counts: &HashMap<String, i64>,
+) -> String {
+    let mut md = format!(
+        "# `{}` in project {}{}\n\n",
+        base,
+        project,
+        symbol_count_suffix(counts.get(base))
+    );
+    let nodes = listings.get(base).map(Vec::as_slice).unwrap_or_default();
+    if nodes.is_empty() {
+        md.push_str("The directory is empty.\n");
+        return md;
+    }
+    let mut budget = MAX_TREE_ENTRIES;
+    render_tree_level(&mut md, nodes, listings, counts, 0, &mut budget);
+    if budget == 0 {
+        md.push_str(&format!(
+            "\n_Stopped after {} entries; list a subdirectory as `path` to see the rest._\n",
+            MAX_TREE_ENTRIES
+        ));
+    }
+    md.push_str(
+        "\n_Pass directories in `expand` to open them here, or one as `path` to descend; \
+read files with `askl_read`._\n",
+    );
+    md
+}
+
+fn render_tree_level(
+    md: &mut String,
+    nodes: &[ProjectTreeNode],
+    listings: &HashMap<String, Vec<ProjectTreeNode>>,
+    counts: &HashMap<String, i64>,
+    depth: usize,
+    budget: &mut usize,
+) {
+    for node in nodes {
+        if *budget == 0 {
+            return;
+        }
+        *budget -= 1;
+        let indent = "  ".repeat(depth);
+        let count = symbol_count_suffix(counts.get(&node.path));
+        if node.node_type == NodeType::File {
+            md.push_str(&format!("{}- `{}`{}\n", indent, node.name, count));
+            continue;
+        }
+        // A compacted chain is shown (and expanded) as its terminal directory.
+        let shown_path = node.compact_path.as_deref().unwrap_or(&node.path);
+        let label = format!("{}{}", node.name, &shown_path[node.path.len()..]);
+        md.push_str(&format!("{}- `{}/`{}\n", indent, label, count));
+        if let Some(children) = listings
+            .get(shown_path)
+            .or_else(|| listings.get(&node.path))
+        {
+            render_tree_level(md, children, listings, counts, depth + 1, budget);
+        }
+    }
+}
+
+fn symbol_count_suffix(count: Option<&i64>) -> String {
+    match count {
+        Some(1) => " — 1 symbol".to_string(),
+        Some(n) if *n > 0 => format!(" — {} symbols", n),
+        _ => String::new(),
+    }
+}
+
 fn upload_status_str(status: UploadStatus) -> &'static str {
     match status {
         UploadStatus::Uploading => "uploading",
@@ -272,7 +640,7 @@ fn upload_status_str(status: UploadStatus) -> &'static str {
 }
 
 /// Best-effort code-fence language hint from a file extension; `text` when unknown.
-fn lang_for_path(path: &str) -> &'static str {
+pub(super) fn lang_for_path(path: &str) -> &'static str {
     let ext = path.rsplit('.').next().unwrap_or("");
     match ext {
         "c" | "h" => "c",
@@ -301,10 +669,36 @@ struct ToolDefinition {
     input_schema: Value,
 }
 
+/// The `notifications/progress` for one finished statement of an `askl_run`.
+fn progress_notification(token: &Value, step: &StatementProgress) -> Value {
+    let label = if step.label.is_empty() {
+        "{…}"
+    } else {
+        &step.label
+    };
+    jsonrpc_notification_value(
+        "notifications/progress",
+        json!({
+            "progressToken": token,
+            "progress": step.completed,
+            "total": step.total,
+            "message": format!("s{} `{}`: {} rows", step.statement, label, step.rows),
+        }),
+    )
+}
+
 #[derive(Debug, Deserialize)]
 struct ToolsCallParams {
     name: String,
     arguments: Option<Value>,
+    #[serde(rename = "_meta", default)]
+    meta: Option<RequestMeta>,
+}
+
+#[derive(Debug, Deserialize)]
+struct RequestMeta {
+    #[serde(rename = "progressToken", default)]
+    progress_token: Option<Value>,
 }
 
 #[derive(Debug, Serialize)]
@@ -331,7 +725,8 @@ defined\", \"find text\". **Read the `askl://syntax` and `askl://cookbook` resou
 your query is well-formed. Core forms: `\"name\"` (definition), `\"x\" { }` (callees of x), \
 `{ \"x\" }` (callers of x), `search(\"literal\")` (full-text), `file(\"/path\")` (scope), plus \
 filters like `func`/`data` and `project(\"p\")`. Grow the query iteratively — the string is your \
-accumulated context.",
+accumulated context. In an MCP session, a `@label` from an earlier call stays usable: label a \
+result (`\"main\" @m`) and continue from it later (`#m { }`) instead of resending the query.",
             input_schema: json!({
                 "type": "object",
                 "properties": {
@@ -343,13 +738,41 @@ accumulated context.",
                         "type": "string",
                         "enum": ["names", "signature", "body"],
                         "description": "How much source to render per symbol: `names` (identifiers \
-            only), `signature` (default — first line of each definition), or `body` (full definition + doc \
+            only), `signature` (default — the indexed signature, else the first line of each definition), or `body` (full definition + doc \
             comment). Raise it only for the few symbols you're deepening into."
                     },
                     "limit": {
                         "type": "integer",
                         "description": "Max distinct symbols in the result (0 = unlimited). Defaults to \
             the server cap; the report says when results were truncated."
+                    },
+                    "params": {
+                        "type": "object",
+                        "description": "Values for `$name` placeholders in the query, e.g. \
+            `{\"fn\": \"vfs_read\"}` for `$fn { }`. Strings bind as plain names; use \
+            `{\"glob\": \"vfs_*\"}` for a glob."
+                    }
+                },
+                "required": ["query"]
+            }),
+        },
+        ToolDefinition {
+            name: "askl_check",
+            description: "Check an askl query without running it: parse it exactly as `askl_run` \
+would and return the normalized query, each statement's relationship to its parent scope \
+(callees, containment, …), the filters it inherits, and warnings such as an unconstrained \
+statement. Touches no index, so it is instant — use it when unsure a query means what you intend, \
+or to understand a parse error before retrying.",
+            input_schema: json!({
+                "type": "object",
+                "properties": {
+                    "query": {
+                        "type": "string",
+                        "description": "The askl query string to check."
+                    },
+                    "params": {
+                        "type": "object",
+                        "description": "Values for `$name` placeholders, as for `askl_run`."
                     }
                 },
                 "required": ["query"]
@@ -394,5 +817,163 @@ files still match it reads the best one and lists the rest.",
                 "required": ["file"]
             }),
         },
+        ToolDefinition {
+            name: "askl_tree",
+            description: "List a project directory as a markdown outline — subdirectories first, \
+then files — optionally with the number of symbols defined in or below each entry. Use it to \
+orient in an unfamiliar project before querying: pick the directories that matter, then scope \
+`askl_run` to them. Single-child directory chains are folded into one `a/b/c/` entry unless \
+`compact` is false.",
+            input_schema: json!({
+                "type": "object",
+                "properties": {
+                    "project": {
+                        "type": "string",
+                        "description": "Project name (see `askl_projects`)."
+                    },
+                    "path": {
+                        "type": "string",
+                        "description": "Absolute directory to list (default `/`, the project root)."
+                    },
+                    "expand": {
+                        "type": "array",
+                        "items": { "type": "string" },
+                        "description": "Absolute paths of directories below `path` to list \
+            inline, nested under their entries."
+                    },
+                    "compact": {
+                        "type": "boolean",
+                        "description": "Fold single-child directory chains (default true)."
+                    },
+                    "counts": {
+                        "type": "boolean",
+                        "description": "Show how many symbols each entry defines (default \
+            false; slow near the root of a large project, so prefer it on subdirectories)."
+                    }
+                },
+                "required": ["project"]
+            }),
+        },
+        ToolDefinition {
+            name: "askl_symbol",
+            description: "Everything about one symbol in a single call: its definitions and \
+declarations as `file:line`, doc comment, containing file and directory, project, caller and \
+callee counts with the most frequent of each, and the head of its definition. Takes the `#id` \
+shown after a symbol in `askl_run` results; use it before writing follow-up queries about a \
+symbol.",
+            input_schema: json!({
+                "type": "object",
+                "properties": {
+                    "id": {
+                        "type": "integer",
+                        "description": "Symbol id, the number after `#` in `askl_run` results."
+                    }
+                },
+                "required": ["id"]
+            }),
+        },
     ]
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn dir(path: &str, compact_path: Option<&str>) -> ProjectTreeNode {
+        ProjectTreeNode {
+            name: path.rsplit('/').next().unwrap().to_string(),
+            path: path.to_string(),
+            node_type: NodeType::Dir,
+            has_children: true,
+            file_id: None,
+            filetype: None,
+            compact_path: compact_path.map(str::to_string),
+        }
+    }
+
+    fn file(path: &str) -> ProjectTreeNode {
+        ProjectTreeNode {
+            node_type: NodeType::File,
+            has_children: false,
+            ..dir(path, None)
+        }
+    }
+
+    #[test]
+    fn tree_nests_expanded_dirs_and_folds_chains() {
+        let listings = HashMap::from([
+            (
+                "/".to_string(),
+                vec![
+                    dir("/drivers", Some("/drivers/net/phy")),
+                    dir("/fs", None),
+                    file("/Makefile"),
+                ],
+            ),
+            (
+                "/drivers/net/phy".to_string(),
+                vec![file("/drivers/net/phy/phy.c")],
+            ),
+        ]);
+        let counts = HashMap::from([
+            ("/".to_string(), 12),
+            ("/drivers".to_string(), 1),
+            ("/drivers/net/phy/phy.c".to_string(), 1),
+            ("/fs".to_string(), 11),
+        ]);
+
+        let md = render_tree("linux", "/", &listings, &counts);
+        let entries: Vec<&str> = md.lines().filter(|l| l.contains("- `")).collect();
+        assert!(md.starts_with("# `/` in project linux — 12 symbols\n"));
+        assert_eq!(
+            entries,
+            vec![
+                "- `drivers/net/phy/` — 1 symbol",
+                "  - `phy.c` — 1 symbol",
+                "- `fs/` — 11 symbols",
+                "- `Makefile`",
+            ]
+        );
+    }
+
+    #[test]
+    fn progress_counts_finished_statements() {
+        let step = StatementProgress {
+            statement: 2,
+            label: String::new(),
+            rows: 5,
+            completed: 1,
+            total: 3,
+        };
+        let notification = progress_notification(&json!("tok"), &step);
+        assert_eq!(notification["method"], "notifications/progress");
+        assert_eq!(
+            notification["params"],
+            json!({
+                "progressToken": "tok",
+                "progress": 1,
+                "total": 3,
+                "message": "s2 `{…}`: 5 rows",
+            })
+        );
+    }
+
+    #[test]
+    fn check_renders_statements_nested() {
+        let ast = askld::parser::parse("project(\"p\") \"x\" { func }").unwrap();
+        let md = render_check(&check(&ast, &HashSet::new()).unwrap());
+        assert!(md.contains("```askl\nproject(\"p\") \"x\" {\n  func\n}\n```"));
+        assert!(md.contains("- **s0** `project(\"p\") \"x\"` — top level\n"));
+        assert!(md.contains(
+            "  - **s1** `func` — what s0 references or contains; weak (only scopes its \
+             neighbours)\n    - inherited: `project(\"p\")`\n"
+        ));
+        assert!(!md.contains("## Warnings"));
+    }
+
+    #[test]
+    fn tree_reports_empty_directory() {
+        let md = render_tree("p", "/empty", &HashMap::new(), &HashMap::new());
+        assert_eq!(md, "# `/empty` in project p\n\nThe directory is empty.\n");
+    }
+}
@@ -6,7 +6,9 @@ pub mod index;
 pub mod mcp;
 pub mod query;
 pub mod render;
+pub mod symbols;
 pub mod types;
+pub mod ui;
 
 #[get("/version")]
 async fn version() -> impl Responder {
@@ -77,11 +79,24 @@ pub fn configure(cfg: &mut web::ServiceConfig) {
             web::resource("/v1/index/projects/{project_id}/finalize")
                 .route(web::post().to(index::finalize_project)),
         )
+        .service(index::get_capabilities)
         .service(index::get_index_project)
         .service(index::delete_index_project)
         .service(index::get_project_tree)
         .service(index::get_project_source)
+        .service(index::get_symbol_at)
+        .service(symbols::get_symbol)
         .service(query::query)
+        .service(query::history)
+        .service(query::highlight)
         .service(query::file)
-        .service(web::resource("/mcp").route(web::post().to(mcp::mcp_handler)));
+        .service(
+            web::resource("/mcp")
+                .route(web::post().to(mcp::mcp_handler))
+                .route(web::delete().to(mcp::mcp_delete_session)),
+        )
+        .service(ui::root)
+        .service(ui::ui)
+        .service(ui::index)
+        .service(ui::asset);
 }
@@ -1,18 +1,26 @@
 use actix_web::http::StatusCode;
-use actix_web::{get, post, web, HttpResponse, Responder};
-use askld::execution_context::ExecutionContext;
+use actix_web::{get, post, web, HttpRequest, HttpResponse, Responder};
+use askld::auth::AuthIdentity;
+use askld::execution_context::{ExecutionContext, ProgressFn};
+use askld::explain::{ExplainMode, ExplainTrace, QueryExplain};
+use askld::index_store::StoreError;
 use askld::offset_range::range_bounds_to_offsets;
-use askld::parser::parse;
+use askld::parser::{parse_with_macros, QueryParams};
+use askld::query_store::{CacheKeyInput, HistoryRecord};
+use index::db_diesel::{cancellable, CancelScope, EphContext, Selection};
 use index::symbols::{FileId, InstanceType, SymbolId, SymbolInstanceId, SymbolType};
-use log::{debug, info, warn};
+use log::{debug, error, info, warn};
 use serde::Deserialize;
 use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
 use tokio::time::timeout;
+use tokio_util::sync::CancellationToken;
+use uuid::Uuid;
 
-use super::render::{render_markdown, Projection, SourceMap};
+use super::render::{render_explain_markdown, render_markdown, Projection, SourceMap};
 use super::types::{
-    AsklData, Edge, ErrorResponse, Graph, GraphObjectEntry, HasEdge, Node, NodeSymbolInstance,
-    QueryStatement,
+    AsklData, Edge, ErrorResponse, Graph, GraphObjectEntry, HasEdge, HighlightResponse, Node,
+    NodeSymbolInstance, QueryHistoryResponse, QueryStatement, RelationEdge,
 };
 
 const MAX_RESPONSE_BYTES: usize = 1_024 * 1_024; // 1 MB
@@ -60,12 +68,18 @@ fn error_response(
 /// Error surfaced while turning a query string into a result graph. Carries the
 /// pest error (which renders its own `^---` caret) so the HTTP handler and the
 /// MCP tool present the same message. `Storage` is a plain string for infra
-/// failures that have no source span.
+/// failures that have no source span. A timed-out `explain=` run carries the
+/// report of what executed before the deadline. `Cancelled` means the caller
+/// abandoned the query through [`QueryControl::cancel`].
 pub enum QueryError {
     Parse(pest::error::Error<askld::parser::Rule>),
     Exec(pest::error::Error<askld::parser::Rule>),
-    Timeout(pest::error::Error<askld::parser::Rule>),
+    Timeout(
+        pest::error::Error<askld::parser::Rule>,
+        Option<Box<QueryExplain>>,
+    ),
     Storage(String),
+    Cancelled,
 }
 
 impl QueryError {
@@ -85,10 +99,30 @@ impl QueryError {
                 &err,
                 Some(SYNTAX_HINT),
             ),
-            QueryError::Timeout(err) => {
+            QueryError::Timeout(err, None) => {
                 error_response(want_markdown, StatusCode::GATEWAY_TIMEOUT, &err, None)
             }
+            QueryError::Timeout(err, Some(explain)) => {
+                let mut response = HttpResponse::build(StatusCode::GATEWAY_TIMEOUT);
+                if want_markdown {
+                    let md = format!(
+                        "# Error\n```text\n{}\n```\n{}",
+                        err,
+                        render_explain_markdown(&explain)
+                    );
+                    response
+                        .content_type("text/markdown; charset=utf-8")
+                        .body(md)
+                } else {
+                    let mut body = serde_json::to_value(ErrorResponse::from_pest(&err)).unwrap();
+                    body["explain"] = serde_json::to_value(&explain).unwrap();
+                    response
+                        .content_type("application/json")
+                        .body(body.to_string())
+                }
+            }
             QueryError::Storage(msg) => HttpResponse::InternalServerError().body(msg),
+            QueryError::Cancelled => HttpResponse::ServiceUnavailable().body("Query cancelled"),
         }
     }
 
@@ -100,20 +134,79 @@ impl QueryError {
             QueryError::Parse(err) | QueryError::Exec(err) => {
                 format!("# Error\n```text\n{}\n```\n\n{}\n", err, SYNTAX_HINT)
             }
-            QueryError::Timeout(err) => format!("# Error\n```text\n{}\n```\n", err),
+            QueryError::Timeout(err, explain) => {
+                let mut md = format!("# Error\n```text\n{}\n```\n", err);
+                if let Some(explain) = explain {
+                    md.push_str(&render_explain_markdown(explain));
+                }
+                md
+            }
             QueryError::Storage(msg) => format!("# Error\n{}\n", msg),
+            QueryError::Cancelled => "# Error\nQuery cancelled\n".to_string(),
+        }
+    }
+}
+
+/// JSON form of a `/query` request body, selected by
+/// `Content-Type: application/json`: the query text plus its parameters.
+#[derive(Debug, Deserialize)]
+struct QueryRequest {
+    query: String,
+    #[serde(default)]
+    params: Option<serde_json::Value>,
+}
+
+/// Collect `param.<name>=<value>` pairs from a query string. These always
+/// bind plain strings; typed values (globs) need the JSON body.
+fn params_from_query_string(query_string: &str) -> QueryParams {
+    let mut params = QueryParams::new();
+    for (key, value) in url::form_urlencoded::parse(query_string.as_bytes()) {
+        if let Some(name) = key.strip_prefix("param.") {
+            params.insert(name, askld::parser::Value::plain(value.into_owned()));
         }
     }
+    params
+}
+
+/// Split a `/query` body into the query text and its bound parameters. A JSON
+/// body carries both; a plain-text body is the query itself. Parameters from
+/// the URL override same-named ones from the body.
+fn query_and_params(req: &HttpRequest, body: String) -> Result<(String, QueryParams), String> {
+    let is_json = req
+        .headers()
+        .get(actix_web::http::header::CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .is_some_and(|ct| ct.starts_with("application/json"));
+    let (query_text, mut params) = if is_json {
+        let request: QueryRequest =
+            serde_json::from_str(&body).map_err(|e| format!("invalid JSON body: {}", e))?;
+        let params = match &request.params {
+            Some(json) => QueryParams::from_json(json).map_err(|e| e.to_string())?,
+            None => QueryParams::new(),
+        };
+        (request.query, params)
+    } else {
+        (body, QueryParams::new())
+    };
+    params.extend(params_from_query_string(req.query_string()));
+    Ok((query_text, params))
 }
 
 #[post("/query")]
 pub async fn query(
     data: web::Data<AsklData>,
+    identity: Option<AuthIdentity>,
     opts: web::Query<QueryOpts>,
+    req: HttpRequest,
     req_body: String,
 ) -> impl Responder {
     let _query = tracing::info_span!("query").entered();
 
+    let (req_body, params) = match query_and_params(&req, req_body) {
+        Ok(parts) => parts,
+        Err(msg) => return HttpResponse::BadRequest().body(msg),
+    };
+
     // Output-shape options are parsed up front so a bad value fails fast,
     // before we run the query. `format=json` (default) is unchanged.
     let want_markdown = match opts.format.as_deref() {
@@ -137,36 +230,389 @@ pub async fn query(
         },
     };
 
-    let result_graph = match build_result_graph(&data, &req_body, opts.limit).await {
-        Ok(graph) => graph,
+    let explain = match ExplainMode::from_name(opts.explain.as_deref().unwrap_or("")) {
+        Ok(explain) => explain,
+        Err(msg) => return HttpResponse::BadRequest().body(msg),
+    };
+    // A report carries the SQL run, and `analyze` runs every load twice:
+    // neither is for anonymous callers.
+    if explain.is_some() && identity.is_none() {
+        return HttpResponse::Unauthorized().body("explain requires an API token");
+    }
+
+    let output = if want_markdown {
+        QueryOutput::Markdown(projection)
+    } else {
+        QueryOutput::Json
+    };
+    let user_id = identity.map(|identity| identity.user_id);
+    let response = match run_query(
+        &data,
+        user_id,
+        &req_body,
+        params,
+        opts.limit,
+        output,
+        explain,
+        QueryControl::default(),
+    )
+    .await
+    {
+        Ok(response) => response,
         Err(err) => return err.into_http_response(want_markdown),
     };
 
     if want_markdown {
-        let md = render_graph_markdown(&data, &req_body, &result_graph, projection).await;
         return HttpResponse::Ok()
             .content_type("text/markdown; charset=utf-8")
-            .body(md);
+            .body(response.body);
     }
 
-    let json_graph = serde_json::to_string_pretty(&result_graph).unwrap();
-    if json_graph.len() > MAX_RESPONSE_BYTES {
+    if response.body.len() > MAX_RESPONSE_BYTES {
         return HttpResponse::PayloadTooLarge().body("Response too large");
     }
-    HttpResponse::Ok().body(json_graph)
+    HttpResponse::Ok().body(response.body)
+}
+
+/// What a query renders to. Part of the result cache key.
+#[derive(Clone, Copy, Debug)]
+pub enum QueryOutput {
+    Json,
+    Markdown(Projection),
+}
+
+impl QueryOutput {
+    fn name(self) -> String {
+        match self {
+            QueryOutput::Json => "json".to_string(),
+            QueryOutput::Markdown(projection) => format!("markdown:{}", projection.name()),
+        }
+    }
+}
+
+/// Hooks into one running query, for callers that report progress, may
+/// abandon it or build on earlier queries (the MCP `askl_run` tool). `/query`
+/// uses none.
+#[derive(Default)]
+pub struct QueryControl {
+    /// Called as the query finishes each statement.
+    pub progress: Option<ProgressFn>,
+    /// Cancelling it stops the query, cancelling its running SQL.
+    pub cancel: Option<CancellationToken>,
+    /// Run the query in this session, which it then updates.
+    pub session: Option<SharedSession>,
+}
+
+/// What a client's queries leave behind for its next ones: the ephemeral
+/// layers they built and the selections they labelled (see
+/// [`ExecutionContext::labels`]), so a later query can use `#label` without
+/// repeating the query that defined it. Only valid while the visible roots
+/// are unchanged; after an upload, or once the eph-layer GC collected one of
+/// its layers, the session starts over empty.
+#[derive(Default)]
+pub struct QuerySession {
+    eph: Option<EphContext>,
+    labels: HashMap<String, Selection>,
+}
+
+/// A session shared by the requests of one client. Queries in it run one at
+/// a time.
+pub type SharedSession = Arc<tokio::sync::Mutex<QuerySession>>;
+
+impl QuerySession {
+    /// Whether the session holds nothing a query could see: no labels and no
+    /// layers beyond the roots.
+    fn is_empty(&self) -> bool {
+        self.labels.is_empty() && self.eph.as_ref().is_none_or(|eph| !eph.has_chain())
+    }
+
+    /// The labels a query in this session may use without defining them.
+    pub fn label_names(&self) -> HashSet<String> {
+        self.labels.keys().cloned().collect()
+    }
+
+    /// Seed `ctx`, rooted in the current roots, with the session's layers
+    /// and labels, and mark those layers used so the GC keeps them.
+    async fn resume(&mut self, data: &AsklData, ctx: &mut ExecutionContext) {
+        let Some(eph) = &self.eph else {
+            return;
+        };
+        if eph.roots() != ctx.eph.roots() {
+            info!("Visible projects changed; starting the session over");
+            *self = Self::default();
+            return;
+        }
+        let mut layers: Vec<i64> = eph.visible_ids().into_iter().filter(|id| *id < 0).collect();
+        layers.sort_unstable();
+        layers.dedup();
+        match data
+            .cfg
+            .index
+            .touch_eph_layers_older_than(&layers, std::time::Duration::ZERO)
+            .await
+        {
+            Ok(touched) if touched == layers.len() => {
+                ctx.eph = eph.clone();
+                ctx.labels = self.labels.clone();
+            }
+            Ok(_) => {
+                info!("Session layers were garbage-collected; starting the session over");
+                *self = Self::default();
+            }
+            Err(err) => {
+                warn!("Failed to touch session layers: {:?}", err);
+                *self = Self::default();
+            }
+        }
+    }
+
+    /// Keep what the query run in `ctx` built for the next query.
+    fn record(&mut self, ctx: &mut ExecutionContext) {
+        self.eph = Some(ctx.eph.clone());
+        self.labels = std::mem::take(&mut ctx.labels);
+    }
+}
+
+/// A rendered query result.
+pub struct QueryResponse {
+    pub body: String,
+    pub cache_hit: bool,
+    /// Distinct symbols before capping; unknown for a cache hit.
+    pub total_symbols: Option<usize>,
+}
+
+/// Run `query_text` and render it as `output`, going through the persistent
+/// result cache when it is enabled, and record the run in `user_id`'s history
+/// (anonymous runs are not recorded). Shared by `/query` and the MCP
+/// `askl_run` tool. A cache that cannot be read or written degrades to
+/// running the query uncached. With `explain`, the query always executes and
+/// the rendered result includes the explain report.
+#[allow(clippy::too_many_arguments)]
+pub async fn run_query(
+    data: &AsklData,
+    user_id: Option<Uuid>,
+    query_text: &str,
+    params: QueryParams,
+    limit: Option<usize>,
+    output: QueryOutput,
+    explain: Option<ExplainMode>,
+    control: QueryControl,
+) -> Result<QueryResponse, QueryError> {
+    let started = std::time::Instant::now();
+    let params_json = (!params.is_empty()).then(|| params.to_json().to_string());
+
+    let result = run_query_cached(data, query_text, params, limit, output, explain, control).await;
+
+    let Some(user_id) = user_id else {
+        return result;
+    };
+    let (status, cache_hit, total_symbols) = match &result {
+        Ok(response) => ("ok", response.cache_hit, response.total_symbols),
+        Err(QueryError::Timeout(..)) => ("timeout", false, None),
+        Err(QueryError::Cancelled) => ("cancelled", false, None),
+        Err(_) => ("error", false, None),
+    };
+    let record = HistoryRecord {
+        user_id,
+        query: query_text.to_string(),
+        params: params_json,
+        output: output.name(),
+        status: status.to_string(),
+        cache_hit,
+        duration_ms: started.elapsed().as_millis() as i64,
+        total_symbols: total_symbols.map(|n| n as i64),
+    };
+    // Off the response path: a slow history insert must not delay the answer.
+    let queries = data.queries.clone();
+    tokio::spawn(async move {
+        if let Err(err) = queries.record(record).await {
+            warn!("Failed to record query history: {:?}", err);
+        }
+    });
+
+    result
+}
+
+async fn run_query_cached(
+    data: &AsklData,
+    query_text: &str,
+    params: QueryParams,
+    limit: Option<usize>,
+    output: QueryOutput,
+    explain: Option<ExplainMode>,
+    control: QueryControl,
+) -> Result<QueryResponse, QueryError> {
+    // A cached body has nothing to explain, and an explain report is not a
+    // result worth caching. A session query goes through the cache only while
+    // the session is empty (its labels and layers are not in the key), and
+    // its result is kept only if it left the session empty, so a hit never
+    // skips labels or layers the next query would use. A session busy with
+    // another query is not waited for here.
+    let session = control.session.clone();
+    let session_empty = || {
+        session
+            .as_ref()
+            .is_none_or(|session| session.try_lock().is_ok_and(|s| s.is_empty()))
+    };
+    let cache_key = if data.queries.cache_enabled() && explain.is_none() && session_empty() {
+        match result_cache_key(data, query_text, &params, limit, output, session.is_some()).await {
+            Ok(key) => Some(key),
+            Err(err) => {
+                warn!("Query cache unavailable: {:?}", err);
+                None
+            }
+        }
+    } else {
+        None
+    };
+
+    if let Some((key, _)) = &cache_key {
+        match data.queries.lookup(key).await {
+            Ok(Some(body)) => {
+                debug!("Query cache hit");
+                return Ok(QueryResponse {
+                    body,
+                    cache_hit: true,
+                    total_symbols: None,
+                });
+            }
+            Ok(None) => {}
+            Err(err) => warn!("Query cache lookup failed: {:?}", err),
+        }
+    }
+
+    let graph = build_result_graph(data, query_text, params, limit, explain, control).await?;
+    let body = match output {
+        QueryOutput::Json => serde_json::to_string_pretty(&graph).unwrap(),
+        QueryOutput::Markdown(projection) => {
+            let mut body = render_graph_markdown(data, query_text, &graph, projection).await;
+            if let Some(explain) = &graph.explain {
+                body.push_str(&render_explain_markdown(explain));
+            }
+            body
+        }
+    };
+
+    // Responses `/query` would refuse as too large are not worth keeping.
+    if let Some((key, generation)) = &cache_key {
+        if body.len() <= MAX_RESPONSE_BYTES && session_empty() {
+            if let Err(err) = data.queries.store(key, *generation, &body).await {
+                warn!("Query cache store failed: {:?}", err);
+            }
+        }
+    }
+
+    Ok(QueryResponse {
+        body,
+        cache_hit: false,
+        total_symbols: Some(graph.total_symbols),
+    })
+}
+
+/// The result cache key for a query plus the generation it was computed at.
+async fn result_cache_key(
+    data: &AsklData,
+    query_text: &str,
+    params: &QueryParams,
+    limit: Option<usize>,
+    output: QueryOutput,
+    session: bool,
+) -> Result<(Vec<u8>, i64), StoreError> {
+    // The generation is read first: everything after it, including the
+    // query itself, sees data at least as new.
+    let generation = data.queries.generation().await?;
+    let roots = data
+        .cfg
+        .index
+        .load_root_layers()
+        .await
+        .map_err(|err| StoreError::Storage(err.to_string()))?;
+    let key = CacheKeyInput {
+        generation,
+        roots: &roots,
+        query: query_text,
+        params,
+        limit: limit.unwrap_or(data.max_result_symbols),
+        output: &output.name(),
+        macros: &data.macros,
+        session,
+    }
+    .digest();
+    Ok((key, generation))
+}
+
+const DEFAULT_HISTORY_ENTRIES: i64 = 50;
+const MAX_HISTORY_ENTRIES: i64 = 500;
+
+#[derive(Debug, Deserialize)]
+pub struct HistoryOpts {
+    /// Entries to return, newest first (default 50, max 500).
+    limit: Option<i64>,
+}
+
+/// `GET /v1/query/history` — the caller's most recent queries with timing and
+/// whether each was served from the result cache.
+#[get("/v1/query/history")]
+pub async fn history(
+    identity: AuthIdentity,
+    data: web::Data<AsklData>,
+    opts: web::Query<HistoryOpts>,
+) -> impl Responder {
+    let limit = opts
+        .limit
+        .unwrap_or(DEFAULT_HISTORY_ENTRIES)
+        .clamp(1, MAX_HISTORY_ENTRIES);
+    match data.queries.history(identity.user_id, limit).await {
+        Ok(entries) => HttpResponse::Ok().json(QueryHistoryResponse { entries }),
+        Err(StoreError::Storage(message)) => {
+            error!("Failed to load query history: {}", message);
+            HttpResponse::InternalServerError().body("Failed to load query history")
+        }
+    }
+}
+
+/// `POST /v1/query/highlight` — the query's tokens classified by the askl
+/// grammar, for editors to colour; a query that does not parse comes back
+/// with its parse error instead.
+#[post("/v1/query/highlight")]
+pub async fn highlight(req_body: String) -> impl Responder {
+    let response = match askld::highlight::highlight(&req_body) {
+        Ok(tokens) => HighlightResponse {
+            tokens,
+            error: None,
+        },
+        Err(err) => HighlightResponse {
+            tokens: Vec::new(),
+            error: Some(ErrorResponse::from_pest(&err)),
+        },
+    };
+    HttpResponse::Ok().json(response)
 }
 
 /// Parse, execute, and assemble the capped result graph for `query_text`. Shared
 /// by the `/query` HTTP handler and the MCP `askl_run` tool so both produce the
-/// same graph (and thus the same markdown). `limit` overrides the server's
-/// default symbol cap (`None` → `data.max_result_symbols`; `0` → unlimited).
+/// same graph (and thus the same markdown). `params` binds the query's `$name`
+/// placeholders; the server's macro library is callable alongside the query's
+/// own `define`s. `limit` overrides the server's default symbol cap
+/// (`None` → `data.max_result_symbols`; `0` → unlimited). `explain` attaches
+/// an explain report to the graph (or to a timeout error).
+///
+/// Once `control.cancel` fires, the query's in-flight SQL is cancelled and
+/// execution is driven on until it fails, so no connection goes back to the
+/// pool with our cancel request still pending (see
+/// [`index::db_diesel::CancelScope`]). With `control.session`, the query
+/// waits for the session's previous query, starts from what it left and, if
+/// it succeeds, leaves its own layers and labels in the session.
 pub async fn build_result_graph(
     data: &AsklData,
     query_text: &str,
+    params: QueryParams,
     limit: Option<usize>,
+    explain: Option<ExplainMode>,
+    control: QueryControl,
 ) -> Result<Graph, QueryError> {
     debug!("Received query: {}", query_text);
-    let ast = parse(query_text).map_err(|err| {
+    let ast = parse_with_macros(query_text, params, &data.macros).map_err(|err| {
         info!("Parse error: {}", err);
         QueryError::Parse(err)
     })?;
@@ -178,16 +624,58 @@ pub async fn build_result_graph(
         warn!("Failed to resolve root layers: {}", err);
         QueryError::Storage("Failed to resolve root layers".to_string())
     })?;
+    let mut session = match &control.session {
+        Some(session) => {
+            let lock = session.lock();
+            Some(match &control.cancel {
+                Some(cancel) => tokio::select! {
+                    session = lock => session,
+                    _ = cancel.cancelled() => return Err(QueryError::Cancelled),
+                },
+                None => lock.await,
+            })
+        }
+        None => None,
+    };
     let mut ctx = ExecutionContext::new(roots);
+    if let Some(session) = &mut session {
+        session.resume(data, &mut ctx).await;
+    }
+    ctx.explain = explain.map(ExplainTrace::new);
+    ctx.progress = control.progress;
+    let scope = CancelScope::new();
+    let explain_report = |ctx: &ExecutionContext| {
+        ctx.explain
+            .as_ref()
+            .map(|trace| trace.report(&ctx.layer_activations))
+    };
 
     let res = {
         let _query_execute = tracing::info_span!("query_execute").entered();
-        let execute_future = ast.execute(&mut ctx, &data.cfg);
-        match timeout(data.query_timeout, execute_future).await {
+        // Boxed: the execution future is large, and nesting it in `run`
+        // unboxed overflows the worker stack in debug builds.
+        let mut execute_future =
+            Box::pin(cancellable(scope.clone(), ast.execute(&mut ctx, &data.cfg)));
+        let run = async {
+            if let Some(cancel) = &control.cancel {
+                tokio::select! {
+                    res = &mut execute_future => return res,
+                    _ = cancel.cancelled() => {}
+                }
+                info!("Query cancelled by the client");
+                scope.cancel().await;
+            }
+            execute_future.await
+        };
+        let res = timeout(data.query_timeout, run).await;
+        if scope.is_cancelled() {
+            return Err(QueryError::Cancelled);
+        }
+        match res {
             Ok(Err(err)) => {
                 if is_statement_timeout(&err) {
                     warn!("Query timed out (PG statement_timeout)");
-                    return Err(QueryError::Timeout(err));
+                    return Err(QueryError::Timeout(err, explain_report(&ctx).map(Box::new)));
                 }
                 // A usage error (unknown verb, bad params …).
                 return Err(QueryError::Exec(err));
@@ -211,17 +699,24 @@ pub async fn build_result_graph(
                     },
                     span.as_pest_span(),
                 );
-                return Err(QueryError::Timeout(err));
+                return Err(QueryError::Timeout(err, explain_report(&ctx).map(Box::new)));
             }
         }
     };
 
+    if let Some(session) = &mut session {
+        session.record(&mut ctx);
+    }
+    drop(session);
+
     info!("Symbols: {:#?}", res.nodes.as_vec().len());
     info!("Edges: {:#?}", res.edges.0.len());
     info!("Has edges: {:#?}", res.has_edges.0.len());
+    info!("Relation edges: {:#?}", res.relation_edges.0.len());
 
     let _build_response = tracing::debug_span!("build_response").entered();
     let mut result_graph = Graph::new();
+    result_graph.explain = explain_report(&ctx);
 
     // Pass 1: distinct symbols with a representative (path, offset) sort key,
     // plus the object→project map used for edge attribution.
@@ -256,7 +751,7 @@ pub async fn build_result_graph(
     let (kept, truncated) = select_kept(&sym_key, cap);
     result_graph.truncated = truncated;
 
-    for (from, to, loc) in res.edges.0 {
+    for (from, to, loc, kind) in res.edges.0 {
         if !(kept.contains(&from.symbol_id.0) && kept.contains(&to.symbol_id.0)) {
             continue;
         }
@@ -270,6 +765,7 @@ pub async fn build_result_graph(
             to.symbol_id,
             loc,
             from_project_id,
+            kind,
         ));
     }
 
@@ -285,6 +781,28 @@ pub async fn build_result_graph(
         ));
     }
 
+    for relation_edge in res.relation_edges.as_vec() {
+        if !(kept.contains(&relation_edge.from.0) && kept.contains(&relation_edge.to.0)) {
+            continue;
+        }
+        result_graph.add_relation_edge(RelationEdge::new(
+            relation_edge.from,
+            relation_edge.to,
+            relation_edge.kind,
+        ));
+    }
+
+    // Metadata is best-effort: a failed lookup degrades the signature
+    // projection to the source-slice fallback rather than failing the query.
+    let kept_ids: Vec<i64> = kept.iter().copied().collect();
+    let mut metadata = match data.cfg.index.load_symbol_metadata(&kept_ids).await {
+        Ok(metadata) => metadata,
+        Err(err) => {
+            debug!("query: no symbol metadata: {}", err);
+            HashMap::new()
+        }
+    };
+
     let mut result_objects = HashMap::new();
     for symbol in all_symbols {
         if !kept.contains(&symbol.id) {
@@ -329,12 +847,15 @@ pub async fn build_result_graph(
             });
         }
 
-        result_graph.add_node(Node::new(
-            SymbolId(symbol.id),
-            symbol.name.clone(),
-            symbol_instances,
-            query_stmts,
-        ));
+        result_graph.add_node(
+            Node::new(
+                SymbolId(symbol.id),
+                symbol.name.clone(),
+                symbol_instances,
+                query_stmts,
+            )
+            .with_metadata(metadata.remove(&symbol.id)),
+        );
     }
 
     result_graph.objects = result_objects.into_values().collect();
@@ -383,6 +904,10 @@ pub struct QueryOpts {
     /// Max distinct symbols in the result; `0` = unlimited. Defaults to the
     /// server's `max_result_symbols`.
     limit: Option<usize>,
+    /// `true` to attach an explain report (statement graph, worklist order,
+    /// SQL and cache outcomes), `analyze` to add Postgres `EXPLAIN ANALYZE`.
+    /// Authenticated callers only.
+    explain: Option<String>,
 }
 
 /// Choose which symbols survive the E1 cap, deterministically keeping the first
@@ -425,6 +950,14 @@ mod tests {
         assert_eq!(kept.len(), 2);
     }
 
+    #[test]
+    fn params_from_query_string_takes_prefixed_keys() {
+        let params = params_from_query_string("format=markdown&param.fn=vfs_read&param.q=a%22b");
+        assert_eq!(params.get("fn").unwrap().as_plain().unwrap(), "vfs_read");
+        assert_eq!(params.get("q").unwrap().as_plain().unwrap(), "a\"b");
+        assert!(params.get("format").is_none());
+    }
+
     #[test]
     fn select_kept_caps_by_path_offset_id() {
         let mut m = HashMap::new();
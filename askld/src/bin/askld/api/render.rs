@@ -10,8 +10,9 @@
 //! well-understood convention avoids the ambiguity of guessing a query's
 //! intent (caller vs. callee) from a flat graph.
 
+use askld::explain::QueryExplain;
 use askld::line_index::LineIndex;
-use index::symbols::{InstanceType, SymbolId, SymbolType};
+use index::symbols::{InstanceType, RelationKind, SymbolId, SymbolType};
 use std::collections::{HashMap, HashSet};
 
 use super::types::{ErrorResponse, Graph, LineColLocation, Node, NodeSymbolInstance};
@@ -21,7 +22,8 @@ use super::types::{ErrorResponse, Graph, LineColLocation, Node, NodeSymbolInstan
 pub enum Projection {
     /// `label (type) file:line` only — cheapest, for exploring wide.
     Names,
-    /// Adds the first line of the symbol (its signature). The default.
+    /// Adds the symbol's signature: the indexed one, else its first source
+    /// line. The default.
     #[default]
     Signature,
     /// Adds the full source body, fenced.
@@ -38,6 +40,15 @@ impl Projection {
             _ => None,
         }
     }
+
+    /// The agent-facing name, the inverse of [`Projection::from_name`].
+    pub fn name(self) -> &'static str {
+        match self {
+            Projection::Names => "names",
+            Projection::Signature => "signature",
+            Projection::Body => "body",
+        }
+    }
 }
 
 /// Raw file content for the objects referenced by a graph, keyed by the
@@ -50,6 +61,8 @@ enum EdgeKind {
     Ref,
     /// A containment relationship: rendered `▸`.
     Has,
+    /// A type-hierarchy relation: rendered `⇒` followed by its kind.
+    Relation(RelationKind),
 }
 
 impl EdgeKind {
@@ -57,6 +70,7 @@ impl EdgeKind {
         match self {
             EdgeKind::Ref => '→',
             EdgeKind::Has => '▸',
+            EdgeKind::Relation(_) => '⇒',
         }
     }
 }
@@ -130,6 +144,9 @@ pub fn render_markdown(
     for he in &graph.has_edges {
         record(he.parent(), he.child(), EdgeKind::Has);
     }
+    for re in &graph.relation_edges {
+        record(re.from(), re.to(), EdgeKind::Relation(re.kind()));
+    }
 
     // Top-level nodes: any source of an edge, plus any node that is not a
     // target of one (isolated selections, definitions, search hits). A node
@@ -231,6 +248,9 @@ impl<'a> RenderCtx<'a> {
         if !graph.has_edges.is_empty() {
             parts.push(format!("{} contains", graph.has_edges.len()));
         }
+        if !graph.relation_edges.is_empty() {
+            parts.push(format!("{} relations", graph.relation_edges.len()));
+        }
         format!("{}\n", parts.join(" · "))
     }
 
@@ -296,7 +316,13 @@ impl<'a> RenderCtx<'a> {
         content.get(start..end.max(start))
     }
 
-    fn signature(&self, inst: &NodeSymbolInstance) -> Option<String> {
+    /// The indexer-supplied signature when there is one; otherwise the first
+    /// source line of the primary instance, which is wrong for declarations
+    /// that span lines (K&R style, macro-wrapped) but better than nothing.
+    fn signature(&self, node: &Node, inst: &NodeSymbolInstance) -> Option<String> {
+        if let Some(sig) = node.metadata().and_then(|m| m.signature.clone()) {
+            return Some(sig);
+        }
         let bytes = self.slice(inst)?;
         let first = bytes.split(|b| *b == b'\n').next().unwrap_or(bytes);
         Some(String::from_utf8_lossy(first).trim_end().to_string())
@@ -329,14 +355,17 @@ impl<'a> RenderCtx<'a> {
         let Some(inst) = self.primary(node) else {
             return node.label().to_string();
         };
+        // `#id` addresses the symbol's card (the `askl://symbol/{id}` MCP
+        // resource).
         let mut line = format!(
-            "{}  ({})  {}",
+            "{}  ({})  {}  #{}",
             node.label(),
             type_abbr(inst.symbol_type),
             self.location(inst),
+            node.id(),
         );
         if matches!(projection, Projection::Signature | Projection::Body) {
-            if let Some(sig) = self.signature(inst) {
+            if let Some(sig) = self.signature(node, inst) {
                 line.push_str(&format!("\n    {sig}"));
             }
         }
@@ -391,17 +420,21 @@ impl<'a> RenderCtx<'a> {
     }
 
     fn child_line(&self, target: SymbolId, kind: EdgeKind) -> String {
+        let marker = match kind {
+            EdgeKind::Relation(relation) => format!("{} {}", kind.arrow(), relation.name()),
+            _ => kind.arrow().to_string(),
+        };
         match self.nodes_by_id.get(&target) {
             Some(node) => {
                 let (ty, loc) = match self.primary(node) {
                     Some(inst) => (type_abbr(inst.symbol_type), self.location(inst)),
                     None => ("?", String::new()),
                 };
-                format!("  {} {}  ({})  {}", kind.arrow(), node.label(), ty, loc)
+                format!("  {} {}  ({})  {}", marker, node.label(), ty, loc)
                     .trim_end()
                     .to_string()
             }
-            None => format!("  {} #{}", kind.arrow(), target.0),
+            None => format!("  {} #{}", marker, target.0),
         }
     }
 
@@ -428,6 +461,95 @@ impl<'a> RenderCtx<'a> {
     }
 }
 
+/// Render an explain report as a markdown section, appended after the results
+/// (or the error) of a `format=markdown` query run with `explain=`.
+pub fn render_explain_markdown(explain: &QueryExplain) -> String {
+    let mut out = format!(
+        "\n# Explain\nMode `{}`, {:.1} ms. SQL cache: {} hits, {} misses, {} uncached.\n",
+        explain.mode,
+        explain.elapsed_ms,
+        explain.sql_cache.hits,
+        explain.sql_cache.misses,
+        explain.sql_cache.uncached,
+    );
+
+    out.push_str("\n## Statements\n");
+    for stmt in &explain.statements {
+        let rows = stmt
+            .rows
+            .map_or_else(|| "no selection".to_string(), |n| format!("{n} rows"));
+        out.push_str(&format!(
+            "- #{} `{}` — {}{}",
+            stmt.id,
+            stmt.text.trim(),
+            rows,
+            if stmt.weak { ", weak" } else { "" },
+        ));
+        if !stmt.dependents.is_empty() {
+            let deps: Vec<String> = stmt
+                .dependents
+                .iter()
+                .map(|d| match &d.label {
+                    Some(label) => format!("#{} ({} @{})", d.statement, d.role, label),
+                    None => format!("#{} ({})", d.statement, d.role),
+                })
+                .collect();
+            out.push_str(&format!("; notifies {}", deps.join(", ")));
+        }
+        out.push('\n');
+    }
+
+    if !explain.worklist.is_empty() {
+        let steps: Vec<String> = explain
+            .worklist
+            .iter()
+            .map(|step| match step.rows {
+                Some(rows) => format!("#{} ({})", step.statement, rows),
+                None => format!("#{} (-)", step.statement),
+            })
+            .collect();
+        out.push_str(&format!("\n## Worklist\n{}\n", steps.join(" → ")));
+    }
+
+    if !explain.layers.is_empty() {
+        out.push_str("\n## Ephemeral layers\n");
+        for layer in &explain.layers {
+            out.push_str(&format!(
+                "- layer {} ({}, root {}): {}{}\n",
+                layer.layer_id,
+                layer.role,
+                layer.root_id,
+                if layer.created { "created" } else { "cached" },
+                if layer.truncated { ", truncated" } else { "" },
+            ));
+        }
+    }
+
+    out.push_str("\n## SQL\n");
+    if explain.sql.is_empty() {
+        out.push_str("_no SQL loads_\n");
+    }
+    for (i, sql) in explain.sql.iter().enumerate() {
+        let statement = sql
+            .statement
+            .map_or_else(|| "query".to_string(), |id| format!("#{id}"));
+        out.push_str(&format!(
+            "### {}. {} — {}, {} rows, {:.1} ms\n`{}`\n```sql\n{}\n```\n",
+            i + 1,
+            statement,
+            sql.cache,
+            sql.rows,
+            sql.elapsed_ms,
+            sql.row_type,
+            sql.sql.trim(),
+        ));
+        if let Some(plan) = &sql.plan {
+            out.push_str(&format!("```text\n{}\n```\n", plan.trim_end()));
+        }
+    }
+    out
+}
+
 /// One clean markdown bullet for a runtime warning: the semantic message, its
 /// query line, and any name suggestions inline — no pest caret art.
 fn warning_bullet(w: &ErrorResponse) -> String {
@@ -452,6 +574,7 @@ fn kind_rank(kind: EdgeKind) -> u8 {
     match kind {
         EdgeKind::Ref => 0,
         EdgeKind::Has => 1,
+        EdgeKind::Relation(_) => 2,
     }
 }
 
@@ -564,12 +687,48 @@ mod tests {
         // no warnings section when there are none
         assert!(!md.contains("# Warnings"), "{md}");
         // definition starts on line 2 of the file
-        assert!(md.contains("f  (func)  /linux/fs/read_write.c:2"), "{md}");
+        assert!(
+            md.contains("f  (func)  /linux/fs/read_write.c:2  #1\n"),
+            "{md}"
+        );
         assert!(md.contains("    ssize_t f(int x)"), "{md}");
         // signature projection does not fence the *body* (the query fence is ```askl)
         assert!(!md.contains("```c"), "{md}");
     }
 
+    #[test]
+    fn signature_projection_prefers_indexed_signature() {
+        // A K&R-style definition whose first line is only the return type.
+        let text = b"int\nadd(a, b)\n\tint a, b;\n{\n\treturn a + b;\n}\n".to_vec();
+        let end = text.len() as i32;
+        let mut src = SourceMap::new();
+        src.insert("10".into(), text);
+        let mut g = Graph::new();
+        obj(&mut g, "10", "/src/add.c");
+        let metadata = index::symbols::SymbolMetadata {
+            signature: Some("int add(int a, int b)".into()),
+            ..Default::default()
+        };
+        g.add_node(
+            node(
+                1,
+                "add",
+                vec![inst(
+                    "10",
+                    0,
+                    end,
+                    SymbolType::Function,
+                    InstanceType::Definition,
+                )],
+            )
+            .with_metadata(Some(metadata)),
+        );
+
+        let md = render_markdown("\"add\"", &g, &src, Projection::Signature);
+        assert!(md.contains("    int add(int a, int b)"), "{md}");
+        assert!(!md.contains("    int\n"), "{md}");
+    }
+
     #[test]
     fn names_projection_omits_signature() {
         let (src, start, end) = body_source();
@@ -650,6 +809,7 @@ mod tests {
             SymbolId(2),
             None,
             None,
+            None,
         ));
 
         let md = render_markdown("\"f\" { func }", &g, &src, Projection::Names);
@@ -662,6 +822,55 @@ mod tests {
         assert!(!top_level_g, "g should only appear as a child:\n{md}");
     }
 
+    #[test]
+    fn relation_edge_renders_kind_under_source() {
+        let mut src = SourceMap::new();
+        src.insert(
+            "10".into(),
+            b"struct Base {};\nstruct Derived : Base {};\n".to_vec(),
+        );
+
+        let mut g = Graph::new();
+        obj(&mut g, "10", "/a.cc");
+        g.add_node(node(
+            1,
+            "Base",
+            vec![inst(
+                "10",
+                0,
+                15,
+                SymbolType::Type,
+                InstanceType::Definition,
+            )],
+        ));
+        g.add_node(node(
+            2,
+            "Derived",
+            vec![inst(
+                "10",
+                16,
+                40,
+                SymbolType::Type,
+                InstanceType::Definition,
+            )],
+        ));
+        g.add_relation_edge(crate::api::types::RelationEdge::new(
+            SymbolId(2),
+            SymbolId(1),
+            RelationKind::Extends,
+        ));
+
+        let md = render_markdown(
+            "type(\"Derived\") { extends { type } }",
+            &g,
+            &src,
+            Projection::Names,
+        );
+        assert!(md.contains("1 relations"), "{md}");
+        assert!(md.contains("Derived  (type)  /a.cc:2"), "{md}");
+        assert!(md.contains("  ⇒ extends Base  (type)  /a.cc:1"), "{md}");
+    }
+
     #[test]
     fn truncation_surfaces_in_stats_and_warnings() {
         let (src, start, end) = body_source();
@@ -827,4 +1036,61 @@ mod tests {
         let bullet = warning_bullet(&w);
         assert_eq!(bullet, "something advisory (line 3)");
     }
+
+    #[test]
+    fn explain_section_lists_statements_worklist_and_sql() {
+        use askld::explain::{
+            ExplainEdge, ExplainSql, ExplainStatement, SqlCacheSummary, WorklistStep,
+        };
+        let statement = |id: usize, text: &str, rows, to: usize, role| ExplainStatement {
+            id,
+            text: text.to_string(),
+            start: 0,
+            end: text.len(),
+            selectors: vec![],
+            weak: false,
+            dependents: vec![ExplainEdge {
+                statement: to,
+                role,
+                label: None,
+            }],
+            rows,
+        };
+        let explain = QueryExplain {
+            mode: "analyze",
+            elapsed_ms: 12.5,
+            statements: vec![
+                statement(0, r#""main" { "a" }"#, Some(1), 1, "child"),
+                statement(1, r#""a""#, None, 0, "parent"),
+            ],
+            worklist: vec![WorklistStep {
+                statement: 0,
+                rows: Some(1),
+            }],
+            sql: vec![ExplainSql {
+                statement: Some(0),
+                row_type: "Row",
+                cache: "miss",
+                rows: 1,
+                elapsed_ms: 3.0,
+                sql: "SELECT 1".to_string(),
+                plan: Some("Result (actual time=0.001..0.001 rows=1 loops=1)".to_string()),
+            }],
+            sql_cache: SqlCacheSummary {
+                hits: 0,
+                misses: 1,
+                uncached: 0,
+            },
+            layers: vec![],
+        };
+
+        let md = render_explain_markdown(&explain);
+        assert!(md.contains("Mode `analyze`, 12.5 ms. SQL cache: 0 hits, 1 misses"));
+        assert!(md.contains("- #0 `\"main\" { \"a\" }` — 1 rows; notifies #1 (child)\n"));
+        assert!(md.contains("- #1 `\"a\"` — no selection; notifies #0 (parent)\n"));
+        assert!(md.contains("## Worklist\n#0 (1)\n"));
+        assert!(md.contains("### 1. #0 — miss, 1 rows, 3.0 ms\n`Row`\n```sql\nSELECT 1\n```\n"));
+        assert!(md.contains("```text\nResult (actual time"));
+        assert!(!md.contains("Ephemeral layers"));
+    }
 }
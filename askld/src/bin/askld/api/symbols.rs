@@ -0,0 +1,218 @@
+//! `GET /v1/symbols/{symbol_id}` — everything about one symbol in a single
+//! call: where it is defined and declared, its doc comment, its callers and
+//! callees, what contains it and which project it belongs to. The MCP
+//! `askl_symbol` tool and `askl://symbol/{id}` resource render the same card
+//! as markdown.
+
+use std::collections::HashMap;
+
+use actix_web::{get, web, HttpResponse, Responder};
+use askld::index_store::{
+    IndexStore, StoreError, SymbolContainer, SymbolInstanceSummary, SymbolNeighbour, SymbolSummary,
+};
+use askld::line_index::LineIndex;
+use index::symbols::FileId;
+use log::error;
+use serde::Serialize;
+
+use super::types::AsklData;
+
+/// A [`SymbolSummary`] with its instances resolved against the source:
+/// definitions and declarations with line numbers, and the doc comment text.
+#[derive(Debug, Serialize)]
+pub struct SymbolCard {
+    pub id: i64,
+    pub name: String,
+    pub symbol_type: String,
+    pub project_id: i32,
+    pub project_name: String,
+    pub definitions: Vec<SymbolLocation>,
+    pub declarations: Vec<SymbolLocation>,
+    /// The `documentation` instances' text in file order, when there are any.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub documentation: Option<String>,
+    pub caller_count: i64,
+    pub callers: Vec<SymbolNeighbour>,
+    pub callee_count: i64,
+    pub callees: Vec<SymbolNeighbour>,
+    pub containers: Vec<SymbolContainer>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct SymbolLocation {
+    pub object_id: i32,
+    pub path: String,
+    pub start_offset: i32,
+    pub end_offset: i32,
+    /// 1-based lines of the range; absent when the file could not be read.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub start_line: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub end_line: Option<usize>,
+}
+
+impl SymbolCard {
+    /// Build the card from `summary` and the contents of the files its
+    /// instances are in (keyed by object id); a missing file only costs its
+    /// instances their line numbers.
+    pub fn new(summary: SymbolSummary, contents: &HashMap<i32, Vec<u8>>) -> Self {
+        let mut definitions = Vec::new();
+        let mut declarations = Vec::new();
+        let mut documentation: Vec<&SymbolInstanceSummary> = Vec::new();
+        for instance in &summary.instances {
+            match instance.instance_type.as_str() {
+                "definition" => definitions.push(locate(instance, contents)),
+                "declaration" => declarations.push(locate(instance, contents)),
+                "documentation" => documentation.push(instance),
+                _ => {}
+            }
+        }
+        documentation.sort_by_key(|doc| (&doc.path, doc.start_offset));
+        let documentation: Vec<String> = documentation
+            .into_iter()
+            .filter_map(|doc| {
+                let content = contents.get(&doc.object_id)?;
+                let start = (doc.start_offset as usize).min(content.len());
+                let end = (doc.end_offset as usize).clamp(start, content.len());
+                Some(
+                    String::from_utf8_lossy(&content[start..end])
+                        .trim_end()
+                        .to_string(),
+                )
+            })
+            .collect();
+
+        Self {
+            id: summary.id,
+            name: summary.name,
+            symbol_type: summary.symbol_type,
+            project_id: summary.project_id,
+            project_name: summary.project_name,
+            definitions,
+            declarations,
+            documentation: (!documentation.is_empty()).then(|| documentation.join("\n")),
+            caller_count: summary.caller_count,
+            callers: summary.callers,
+            callee_count: summary.callee_count,
+            callees: summary.callees,
+            containers: summary.containers,
+        }
+    }
+}
+
+fn locate(instance: &SymbolInstanceSummary, contents: &HashMap<i32, Vec<u8>>) -> SymbolLocation {
+    let lines = contents.get(&instance.object_id).map(|content| {
+        let index = LineIndex::new(content);
+        let start = instance.start_offset as usize;
+        let end = (instance.end_offset as usize).saturating_sub(1).max(start);
+        (index.line_of(start), index.line_of(end))
+    });
+    SymbolLocation {
+        object_id: instance.object_id,
+        path: instance.path.clone(),
+        start_offset: instance.start_offset,
+        end_offset: instance.end_offset,
+        start_line: lines.map(|(start, _)| start),
+        end_line: lines.map(|(_, end)| end),
+    }
+}
+
+/// The card for `symbol_id` with the contents of the files it points into;
+/// `None` where [`IndexStore::get_symbol_summary`] has no summary.
+pub async fn load_symbol_card(
+    askl_data: &AsklData,
+    store: &IndexStore,
+    symbol_id: i64,
+) -> Result<Option<(SymbolCard, HashMap<i32, Vec<u8>>)>, StoreError> {
+    let Some(summary) = store.get_symbol_summary(symbol_id).await? else {
+        return Ok(None);
+    };
+    let mut contents = HashMap::new();
+    for instance in &summary.instances {
+        // Only the instances the card shows need their file; expansions and
+        // the like would cost a content fetch for nothing.
+        if !matches!(
+            instance.instance_type.as_str(),
+            "definition" | "declaration" | "documentation"
+        ) || contents.contains_key(&instance.object_id)
+        {
+            continue;
+        }
+        // A file that cannot be read only costs the card its line numbers.
+        if let Ok(content) = askl_data
+            .cfg
+            .index
+            .get_file_contents_bytes(FileId::new(instance.object_id))
+            .await
+        {
+            contents.insert(instance.object_id, content);
+        }
+    }
+    Ok(Some((SymbolCard::new(summary, &contents), contents)))
+}
+
+#[get("/v1/symbols/{symbol_id}")]
+pub async fn get_symbol(
+    askl_data: web::Data<AsklData>,
+    store: web::Data<IndexStore>,
+    symbol_id: web::Path<i64>,
+) -> impl Responder {
+    match load_symbol_card(&askl_data, &store, *symbol_id).await {
+        Ok(Some((card, _))) => HttpResponse::Ok().json(card),
+        Ok(None) => HttpResponse::NotFound().body("Symbol not found"),
+        Err(StoreError::Storage(message)) => {
+            error!("Failed to load symbol {}: {}", symbol_id, message);
+            HttpResponse::InternalServerError().body("Failed to load symbol")
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn instance(instance_type: &str, start: i32, end: i32) -> SymbolInstanceSummary {
+        SymbolInstanceSummary {
+            id: 1,
+            instance_type: instance_type.to_string(),
+            object_id: 7,
+            path: "/p/a.c".to_string(),
+            start_offset: start,
+            end_offset: end,
+        }
+    }
+
+    #[test]
+    fn card_splits_instances_and_reads_documentation() {
+        let source = b"/* Does f. */\nint f(void)\n{\n  return 0;\n}\nint f(void);\n".to_vec();
+        let summary = SymbolSummary {
+            id: 42,
+            name: "f".to_string(),
+            symbol_type: "function".to_string(),
+            project_id: 1,
+            project_name: "p".to_string(),
+            instances: vec![
+                instance("definition", 14, 41),
+                instance("declaration", 42, 54),
+                instance("documentation", 0, 13),
+                instance("expansion", 14, 17),
+            ],
+            caller_count: 0,
+            callee_count: 0,
+            callers: Vec::new(),
+            callees: Vec::new(),
+            containers: Vec::new(),
+        };
+        let card = SymbolCard::new(summary, &HashMap::from([(7, source)]));
+        assert_eq!(card.documentation.as_deref(), Some("/* Does f. */"));
+        assert_eq!(card.definitions.len(), 1);
+        assert_eq!(card.definitions[0].start_line, Some(2));
+        assert_eq!(card.definitions[0].end_line, Some(5));
+        assert_eq!(card.declarations.len(), 1);
+        assert_eq!(card.declarations[0].start_line, Some(6));
+
+        let json = serde_json::to_value(&card).unwrap();
+        assert_eq!(json["definitions"][0]["path"], "/p/a.c");
+        assert!(json.get("instances").is_none());
+    }
+}
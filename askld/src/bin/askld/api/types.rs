@@ -1,7 +1,13 @@
 use askld::cfg::ControlFlowGraph;
 use askld::diagnostic::Diagnostic;
+use askld::explain::QueryExplain;
+use askld::highlight::HighlightToken;
+use askld::macros::MacroLibrary;
 use askld::parser::Rule;
-use index::symbols::{FileId, InstanceType, SymbolId, SymbolInstanceId, SymbolType};
+use askld::query_store::{HistoryEntry, QueryStore};
+use index::symbols::{
+    FileId, InstanceType, SymbolId, SymbolInstanceId, SymbolMetadata, SymbolType,
+};
 use serde::{Deserialize, Serialize, Serializer};
 
 pub struct AsklData {
@@ -9,6 +15,10 @@ pub struct AsklData {
     pub query_timeout: std::time::Duration,
     /// Default cap on distinct symbols per result (0 = unlimited).
     pub max_result_symbols: usize,
+    /// Server-side macro library (`--macros`), callable from every query.
+    pub macros: MacroLibrary,
+    /// Persistent result cache (`--query-cache`) and per-user query history.
+    pub queries: QueryStore,
 }
 
 fn symbolid_as_string<S>(x: &SymbolId, s: S) -> Result<S::Ok, S::Error>
@@ -44,6 +54,9 @@ pub struct Node {
     label: String,
     symbol_instances: Vec<NodeSymbolInstance>,
     query_statements: Vec<QueryStatement>,
+    /// Declaration metadata, when the indexer provided any.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    metadata: Option<SymbolMetadata>,
 }
 
 impl Node {
@@ -58,9 +71,15 @@ impl Node {
             label,
             symbol_instances,
             query_statements,
+            metadata: None,
         }
     }
 
+    pub fn with_metadata(mut self, metadata: Option<SymbolMetadata>) -> Self {
+        self.metadata = metadata;
+        self
+    }
+
     pub fn id(&self) -> SymbolId {
         self.id
     }
@@ -72,6 +91,10 @@ impl Node {
     pub fn instances(&self) -> &[NodeSymbolInstance] {
         &self.symbol_instances
     }
+
+    pub fn metadata(&self) -> Option<&SymbolMetadata> {
+        self.metadata.as_ref()
+    }
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -85,6 +108,9 @@ pub struct Edge {
     from_project_id: Option<String>,
     from_offset_start: Option<i32>,
     from_offset_end: Option<i32>,
+    /// `call`, `read`, `write`, `address_taken` or `type_use`; null when
+    /// the indexer did not classify the reference.
+    kind: Option<index::symbols::RefKind>,
 }
 
 impl Edge {
@@ -93,6 +119,7 @@ impl Edge {
         to: SymbolId,
         occurrence: Option<index::symbols::Occurrence>,
         from_project_id: Option<String>,
+        kind: Option<index::symbols::RefKind>,
     ) -> Self {
         let range = occurrence.as_ref().map(|o| o.offset_range.clone());
         Self {
@@ -103,6 +130,7 @@ impl Edge {
             from_project_id,
             from_offset_start: range.map(|r| r.0),
             from_offset_end: range.map(|r| r.1),
+            kind,
         }
     }
 
@@ -154,6 +182,41 @@ impl HasEdge {
     }
 }
 
+/// Type-hierarchy edge: `from` extends, implements or embeds `to`.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct RelationEdge {
+    id: String,
+    #[serde(serialize_with = "symbolid_as_string")]
+    from: SymbolId,
+    #[serde(serialize_with = "symbolid_as_string")]
+    to: SymbolId,
+    /// `extends`, `implements` or `embeds`.
+    kind: index::symbols::RelationKind,
+}
+
+impl RelationEdge {
+    pub fn new(from: SymbolId, to: SymbolId, kind: index::symbols::RelationKind) -> Self {
+        Self {
+            id: format!("{}-{}-{}", kind.name(), from, to),
+            from,
+            to,
+            kind,
+        }
+    }
+
+    pub fn from(&self) -> SymbolId {
+        self.from
+    }
+
+    pub fn to(&self) -> SymbolId {
+        self.to
+    }
+
+    pub fn kind(&self) -> index::symbols::RelationKind {
+        self.kind
+    }
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct GraphObjectEntry {
     pub object_id: String,
@@ -166,12 +229,17 @@ pub struct Graph {
     pub nodes: Vec<Node>,
     pub edges: Vec<Edge>,
     pub has_edges: Vec<HasEdge>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub relation_edges: Vec<RelationEdge>,
     pub objects: Vec<GraphObjectEntry>,
     pub warnings: Vec<ErrorResponse>,
     /// True when the result was capped to `max_result_symbols`.
     pub truncated: bool,
     /// Distinct symbol count before capping (== `nodes.len()` when not truncated).
     pub total_symbols: usize,
+    /// How the query executed; present only for `explain=` requests.
+    #[serde(default, skip_deserializing, skip_serializing_if = "Option::is_none")]
+    pub explain: Option<QueryExplain>,
 }
 
 impl Graph {
@@ -180,10 +248,12 @@ impl Graph {
             nodes: vec![],
             edges: vec![],
             has_edges: vec![],
+            relation_edges: vec![],
             objects: vec![],
             warnings: vec![],
             truncated: false,
             total_symbols: 0,
+            explain: None,
         }
     }
 
@@ -199,6 +269,10 @@ impl Graph {
         self.has_edges.push(edge);
     }
 
+    pub fn add_relation_edge(&mut self, edge: RelationEdge) {
+        self.relation_edges.push(edge);
+    }
+
     pub fn add_warnings(&mut self, warnings: Vec<Diagnostic>) {
         for warning in &warnings {
             self.warnings.push(ErrorResponse::from_diagnostic(warning));
@@ -307,3 +381,15 @@ pub struct IndexDeleteResponse {
     pub project_id: i32,
     pub deleted: bool,
 }
+
+#[derive(Debug, Serialize)]
+pub struct QueryHistoryResponse {
+    pub entries: Vec<HistoryEntry>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct HighlightResponse {
+    /// Empty when the query does not parse.
+    pub tokens: Vec<HighlightToken>,
+    pub error: Option<ErrorResponse>,
+}
@@ -0,0 +1,45 @@
+//! The web UI: a query editor, call-graph view, source pane and project tree
+//! in one static page that talks to the JSON API (`/query`,
+//! `/v1/query/highlight`, `/source/{file_id}`, the project tree). The assets
+//! live under `ui/` and are embedded with `include_str!`, so the binary
+//! serves them with nothing deployed beside it.
+
+use actix_web::{get, http::header, web, HttpResponse, Responder};
+
+const INDEX_HTML: &str = include_str!("ui/index.html");
+const APP_JS: &str = include_str!("ui/app.js");
+const APP_CSS: &str = include_str!("ui/app.css");
+
+fn redirect_to_ui() -> HttpResponse {
+    HttpResponse::Found()
+        .insert_header((header::LOCATION, "/ui/"))
+        .finish()
+}
+
+#[get("/")]
+pub async fn root() -> impl Responder {
+    redirect_to_ui()
+}
+
+/// `GET /ui` — relative asset URLs need the trailing slash.
+#[get("/ui")]
+pub async fn ui() -> impl Responder {
+    redirect_to_ui()
+}
+
+#[get("/ui/")]
+pub async fn index() -> impl Responder {
+    HttpResponse::Ok()
+        .content_type("text/html; charset=utf-8")
+        .body(INDEX_HTML)
+}
+
+#[get("/ui/{asset}")]
+pub async fn asset(asset: web::Path<String>) -> impl Responder {
+    let (content_type, body) = match asset.as_str() {
+        "app.js" => ("text/javascript; charset=utf-8", APP_JS),
+        "app.css" => ("text/css; charset=utf-8", APP_CSS),
+        _ => return HttpResponse::NotFound().body("Not found"),
+    };
+    HttpResponse::Ok().content_type(content_type).body(body)
+}
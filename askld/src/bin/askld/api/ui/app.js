@@ -0,0 +1,532 @@
+// askl web UI: query editor, call graph, source pane and project tree.
+// Plain browser JavaScript against the askld JSON API; no build step.
+// Offsets from the API are byte offsets into UTF-8 text, so text is sliced
+// as bytes and decoded per piece.
+"use strict";
+
+const encoder = new TextEncoder();
+const decoder = new TextDecoder();
+
+const el = (id) => document.getElementById(id);
+const SVG_NS = "http://www.w3.org/2000/svg";
+
+function escapeHtml(text) {
+  return text.replace(/[&<>"]/g, (c) => ({ "&": "&amp;", "<": "&lt;", ">": "&gt;", '"': "&quot;" })[c]);
+}
+
+function setStatus(text, isError) {
+  const status = el("status");
+  status.textContent = text;
+  status.classList.toggle("error", !!isError);
+}
+
+async function fetchOk(url, options) {
+  const response = await fetch(url, options);
+  if (!response.ok) {
+    throw new Error(`${response.status}: ${await response.text()}`);
+  }
+  return response;
+}
+
+// ---------------------------------------------------------------- editor
+
+const query = el("query");
+const queryHighlight = el("query-highlight");
+let highlightSeq = 0;
+let highlightTimer = null;
+
+// Byte range of a pest error location (`{"Pos": n}` or `{"Span": [a, b]}`).
+function errorRange(location) {
+  if (location.Span) {
+    const [start, end] = location.Span;
+    return [start, Math.max(end, start + 1)];
+  }
+  return [location.Pos, location.Pos + 1];
+}
+
+function renderHighlight(text, tokens, error) {
+  const bytes = encoder.encode(text);
+  const spans = tokens.map((t) => [t.start, t.end, `tok-${t.kind}`]);
+  if (error) {
+    const [start, end] = errorRange(error.location);
+    spans.push([start, end, "tok-error"]);
+    spans.sort((a, b) => a[0] - b[0]);
+  }
+  let html = "";
+  let at = 0;
+  for (const [start, end, cls] of spans) {
+    if (start < at) {
+      continue;
+    }
+    html += escapeHtml(decoder.decode(bytes.slice(at, start)));
+    // An error past the end of the text marks a blank so it stays visible.
+    const piece = decoder.decode(bytes.slice(start, end)) || " ";
+    html += `<span class="${cls}">${escapeHtml(piece)}</span>`;
+    at = Math.min(end, bytes.length);
+  }
+  html += escapeHtml(decoder.decode(bytes.slice(at)));
+  // A trailing newline only takes up space with something after it.
+  queryHighlight.innerHTML = html + "\n";
+  queryHighlight.scrollTop = query.scrollTop;
+}
+
+async function refreshHighlight() {
+  const text = query.value;
+  const seq = ++highlightSeq;
+  try {
+    const response = await fetchOk("/v1/query/highlight", {
+      method: "POST",
+      headers: { "Content-Type": "text/plain" },
+      body: text,
+    });
+    const result = await response.json();
+    if (seq === highlightSeq) {
+      renderHighlight(text, result.tokens, text.trim() ? result.error : null);
+    }
+  } catch (err) {
+    if (seq === highlightSeq) {
+      renderHighlight(text, [], null);
+    }
+  }
+}
+
+query.addEventListener("input", () => {
+  // Show the plain text at once; the colours follow the server.
+  renderHighlight(query.value, [], null);
+  clearTimeout(highlightTimer);
+  highlightTimer = setTimeout(refreshHighlight, 150);
+});
+query.addEventListener("scroll", () => {
+  queryHighlight.scrollTop = query.scrollTop;
+});
+query.addEventListener("keydown", (event) => {
+  if (event.key === "Enter" && (event.ctrlKey || event.metaKey)) {
+    event.preventDefault();
+    runQuery();
+  }
+});
+el("run").addEventListener("click", () => runQuery());
+
+function setQuery(text) {
+  query.value = text;
+  refreshHighlight();
+}
+
+// ----------------------------------------------------------------- query
+
+let graph = null;
+
+function showMessages(messages) {
+  const list = el("messages");
+  list.innerHTML = "";
+  for (const { text, isError } of messages) {
+    const item = document.createElement("li");
+    item.textContent = text;
+    item.classList.toggle("error", isError);
+    list.appendChild(item);
+  }
+}
+
+async function runQuery() {
+  const text = query.value;
+  if (!text.trim()) {
+    return;
+  }
+  setStatus("Running…");
+  el("summary").textContent = "";
+  let response;
+  try {
+    response = await fetch("/query", {
+      method: "POST",
+      headers: { "Content-Type": "text/plain" },
+      body: text,
+    });
+  } catch (err) {
+    setStatus(`Query failed: ${err.message}`, true);
+    return;
+  }
+  const body = await response.text();
+  if (!response.ok) {
+    let message = body;
+    try {
+      message = JSON.parse(body).message;
+    } catch (_) {
+      // Not an ErrorResponse: show the plain body.
+    }
+    showMessages([{ text: message, isError: true }]);
+    setStatus(`Query failed (${response.status})`, true);
+    return;
+  }
+  graph = JSON.parse(body);
+  showMessages(graph.warnings.map((w) => ({ text: w.message, isError: false })));
+  const truncated = graph.truncated ? ` (truncated from ${graph.total_symbols})` : "";
+  el("summary").textContent = `${graph.nodes.length} symbols, ${graph.edges.length} references${truncated}`;
+  setStatus("");
+  drawGraph(graph);
+}
+
+// ----------------------------------------------------------------- graph
+
+const svg = el("graph");
+const viewport = el("viewport");
+const NODE_HEIGHT = 28;
+const LAYER_GAP = 90;
+const ROW_GAP = 18;
+let view = { x: 20, y: 20, scale: 1 };
+
+function applyView() {
+  viewport.setAttribute("transform", `translate(${view.x},${view.y}) scale(${view.scale})`);
+}
+
+function svgElement(tag, attrs) {
+  const node = document.createElementNS(SVG_NS, tag);
+  for (const [name, value] of Object.entries(attrs)) {
+    node.setAttribute(name, value);
+  }
+  return node;
+}
+
+// Layered layout: edges that close a cycle are set aside, every other edge
+// points one or more layers to the right, and each layer is ordered by the
+// mean position of its callers.
+function layout(nodes, edges) {
+  const ids = nodes.map((n) => n.id);
+  const out = new Map(ids.map((id) => [id, []]));
+  for (const edge of edges) {
+    if (out.has(edge.from) && out.has(edge.to) && edge.from !== edge.to) {
+      out.get(edge.from).push(edge.to);
+    }
+  }
+
+  const state = new Map();
+  const order = [];
+  const back = new Set();
+  for (const root of ids) {
+    if (state.has(root)) {
+      continue;
+    }
+    const stack = [[root, 0]];
+    state.set(root, "open");
+    while (stack.length) {
+      const top = stack[stack.length - 1];
+      const next = out.get(top[0])[top[1]++];
+      if (next === undefined) {
+        state.set(top[0], "done");
+        order.push(top[0]);
+        stack.pop();
+      } else if (state.get(next) === "open") {
+        back.add(`${top[0]}>${next}`);
+      } else if (!state.has(next)) {
+        state.set(next, "open");
+        stack.push([next, 0]);
+      }
+    }
+  }
+
+  const layer = new Map(ids.map((id) => [id, 0]));
+  for (const id of order.reverse()) {
+    for (const next of out.get(id)) {
+      if (!back.has(`${id}>${next}`)) {
+        layer.set(next, Math.max(layer.get(next), layer.get(id) + 1));
+      }
+    }
+  }
+
+  const layers = [];
+  for (const node of nodes) {
+    const l = layer.get(node.id);
+    (layers[l] = layers[l] || []).push(node);
+  }
+  const row = new Map();
+  const callers = new Map(ids.map((id) => [id, []]));
+  for (const [from, targets] of out) {
+    for (const to of targets) {
+      callers.get(to).push(from);
+    }
+  }
+  const positions = new Map();
+  let x = 0;
+  for (const members of layers) {
+    if (!members) {
+      continue;
+    }
+    const weight = (node) => {
+      const rows = callers.get(node.id).filter((c) => row.has(c)).map((c) => row.get(c));
+      return rows.length ? rows.reduce((a, b) => a + b, 0) / rows.length : Infinity;
+    };
+    members.sort((a, b) => weight(a) - weight(b) || a.label.localeCompare(b.label));
+    let width = 0;
+    members.forEach((node, i) => {
+      row.set(node.id, i);
+      const w = Math.min(320, 16 + node.label.length * 7.4);
+      width = Math.max(width, w);
+      positions.set(node.id, { x, y: i * (NODE_HEIGHT + ROW_GAP), w });
+    });
+    x += width + LAYER_GAP;
+  }
+  return positions;
+}
+
+function edgePath(from, to) {
+  const x1 = from.x + from.w;
+  const y1 = from.y + NODE_HEIGHT / 2;
+  const x2 = to.x;
+  const y2 = to.y + NODE_HEIGHT / 2;
+  if (x2 > x1) {
+    const dx = (x2 - x1) / 2;
+    return `M ${x1} ${y1} C ${x1 + dx} ${y1}, ${x2 - dx} ${y2}, ${x2} ${y2}`;
+  }
+  // Backwards or within a layer: loop out to the right and come back in.
+  const loop = 40 + Math.abs(y2 - y1) / 4;
+  return `M ${x1} ${y1} C ${x1 + loop} ${y1 - loop}, ${x2 - loop} ${y2 - loop}, ${x2} ${y2}`;
+}
+
+function selectElement(selected) {
+  for (const node of viewport.querySelectorAll(".selected")) {
+    node.classList.remove("selected");
+  }
+  if (selected) {
+    selected.classList.add("selected");
+  }
+}
+
+function drawGraph(result) {
+  viewport.innerHTML = "";
+  const positions = layout(result.nodes, result.edges);
+  const edgeLayer = svgElement("g", {});
+  const nodeLayer = svgElement("g", {});
+  viewport.append(edgeLayer, nodeLayer);
+
+  for (const edge of result.edges) {
+    const from = positions.get(edge.from);
+    const to = positions.get(edge.to);
+    if (!from || !to) {
+      continue;
+    }
+    const path = svgElement("path", {
+      d: edgePath(from, to),
+      class: `edge kind-${edge.kind || "call"}`,
+    });
+    const title = svgElement("title", {});
+    title.textContent = edge.kind || "reference";
+    path.appendChild(title);
+    path.addEventListener("click", (event) => {
+      event.stopPropagation();
+      selectElement(path);
+      if (edge.from_object) {
+        showSource(edge.from_object, edge.from_offset_start, edge.from_offset_end);
+      }
+    });
+    edgeLayer.appendChild(path);
+  }
+
+  for (const node of result.nodes) {
+    const pos = positions.get(node.id);
+    const group = svgElement("g", {
+      class: "node",
+      transform: `translate(${pos.x},${pos.y})`,
+    });
+    group.appendChild(svgElement("rect", { width: pos.w, height: NODE_HEIGHT }));
+    const label = svgElement("text", { x: 8, y: NODE_HEIGHT / 2 });
+    label.textContent = node.label;
+    group.appendChild(label);
+    const title = svgElement("title", {});
+    title.textContent = `${node.label} #${node.id}\nDouble-click to query its callees`;
+    group.appendChild(title);
+    group.addEventListener("click", (event) => {
+      event.stopPropagation();
+      selectElement(group);
+      const instances = node.symbol_instances;
+      const instance = instances.find((i) => i.instance_type === "definition") || instances[0];
+      if (instance) {
+        showSource(instance.object_id, instance.start_offset, instance.end_offset);
+      }
+    });
+    group.addEventListener("dblclick", (event) => {
+      event.stopPropagation();
+      setQuery(`"${node.label}" {}`);
+      runQuery();
+    });
+    nodeLayer.appendChild(group);
+  }
+
+  view = { x: 20, y: 20, scale: 1 };
+  applyView();
+}
+
+let pan = null;
+svg.addEventListener("pointerdown", (event) => {
+  // Nodes and edges keep their clicks; the background pans.
+  if (event.target.closest(".node, .edge")) {
+    return;
+  }
+  pan = { x: event.clientX - view.x, y: event.clientY - view.y };
+  svg.setPointerCapture(event.pointerId);
+  svg.classList.add("panning");
+});
+svg.addEventListener("pointermove", (event) => {
+  if (pan) {
+    view.x = event.clientX - pan.x;
+    view.y = event.clientY - pan.y;
+    applyView();
+  }
+});
+svg.addEventListener("pointerup", () => {
+  pan = null;
+  svg.classList.remove("panning");
+});
+svg.addEventListener(
+  "wheel",
+  (event) => {
+    event.preventDefault();
+    const rect = svg.getBoundingClientRect();
+    const px = event.clientX - rect.left;
+    const py = event.clientY - rect.top;
+    const factor = Math.exp(-event.deltaY * 0.001);
+    const scale = Math.min(4, Math.max(0.1, view.scale * factor));
+    view.x = px - ((px - view.x) * scale) / view.scale;
+    view.y = py - ((py - view.y) * scale) / view.scale;
+    view.scale = scale;
+    applyView();
+  },
+  { passive: false },
+);
+
+// ---------------------------------------------------------------- source
+
+const sourceCache = new Map();
+
+function objectPath(objectId) {
+  const entry = graph && graph.objects.find((o) => String(o.object_id) === String(objectId));
+  return entry ? entry.path : null;
+}
+
+async function showSource(fileId, start, end, path) {
+  const title = el("source-title");
+  const source = el("source");
+  title.textContent = path || objectPath(fileId) || `File ${fileId}`;
+  let bytes = sourceCache.get(String(fileId));
+  if (!bytes) {
+    try {
+      const response = await fetchOk(`/source/${fileId}`);
+      bytes = new Uint8Array(await response.arrayBuffer());
+    } catch (err) {
+      source.textContent = `Failed to load source: ${err.message}`;
+      return;
+    }
+    sourceCache.set(String(fileId), bytes);
+  }
+
+  const hasRange = start !== undefined && start !== null && end !== null && end !== undefined;
+  let html = "";
+  let lineStart = 0;
+  let lineNo = 1;
+  let firstMarked = null;
+  while (lineStart <= bytes.length) {
+    let lineEnd = bytes.indexOf(10, lineStart);
+    if (lineEnd < 0) {
+      lineEnd = bytes.length;
+    }
+    let text;
+    if (hasRange && start < lineEnd + 1 && end > lineStart) {
+      const from = Math.max(start, lineStart);
+      const to = Math.min(end, lineEnd);
+      text =
+        escapeHtml(decoder.decode(bytes.slice(lineStart, from))) +
+        `<mark>${escapeHtml(decoder.decode(bytes.slice(from, to)))}</mark>` +
+        escapeHtml(decoder.decode(bytes.slice(to, lineEnd)));
+      firstMarked = firstMarked || lineNo;
+    } else {
+      text = escapeHtml(decoder.decode(bytes.slice(lineStart, lineEnd)));
+    }
+    html += `<span class="line" data-line="${lineNo}">${text}</span>`;
+    if (lineEnd === bytes.length) {
+      break;
+    }
+    lineStart = lineEnd + 1;
+    lineNo += 1;
+  }
+  source.innerHTML = html;
+  const target = firstMarked && source.querySelector(`[data-line="${firstMarked}"]`);
+  if (target) {
+    target.scrollIntoView({ block: "center" });
+  } else {
+    el("source-pane").scrollTop = 0;
+  }
+}
+
+// ------------------------------------------------------------------ tree
+
+let projectId = null;
+
+async function loadProjects() {
+  let projects;
+  try {
+    projects = await (await fetchOk("/v1/index/projects")).json();
+  } catch (err) {
+    setStatus(`Failed to list projects: ${err.message}`, true);
+    return;
+  }
+  const select = el("project");
+  select.innerHTML = "";
+  for (const project of projects) {
+    const option = document.createElement("option");
+    option.value = project.id;
+    option.textContent = project.project_name;
+    select.appendChild(option);
+  }
+  if (projects.length) {
+    selectProject(projects[0].id);
+  }
+}
+
+el("project").addEventListener("change", (event) => selectProject(event.target.value));
+
+async function selectProject(id) {
+  projectId = id;
+  const tree = el("tree");
+  tree.innerHTML = "";
+  await expandDir(tree, "/");
+}
+
+async function expandDir(list, path) {
+  let result;
+  try {
+    const url = `/v1/index/projects/${projectId}/tree?path=${encodeURIComponent(path)}`;
+    result = await (await fetchOk(url)).json();
+  } catch (err) {
+    setStatus(`Failed to load ${path}: ${err.message}`, true);
+    return;
+  }
+  for (const node of result.nodes) {
+    const item = document.createElement("li");
+    const entry = document.createElement("span");
+    entry.className = "entry";
+    item.appendChild(entry);
+    if (node.node_type === "dir") {
+      // A compacted chain of single-child directories shows as one entry.
+      const target = node.compact_path || node.path;
+      entry.textContent = node.name + target.slice(node.path.length);
+      item.className = "dir";
+      const children = document.createElement("ul");
+      item.appendChild(children);
+      let loaded = false;
+      entry.addEventListener("click", async () => {
+        const open = item.classList.toggle("open");
+        children.hidden = !open;
+        if (open && !loaded) {
+          loaded = true;
+          await expandDir(children, target);
+        }
+      });
+    } else {
+      entry.textContent = node.name;
+      item.className = "file";
+      entry.addEventListener("click", () => showSource(node.file_id, null, null, node.path));
+    }
+    list.appendChild(item);
+  }
+}
+
+loadProjects();
+refreshHighlight();
@@ -1,4 +1,4 @@
-use clap::{Args as ClapArgs, Parser, Subcommand};
+use clap::{ArgGroup, Args as ClapArgs, Parser, Subcommand};
 
 /// Indexer for askl
 #[derive(Parser, Debug)]
@@ -13,6 +13,10 @@ pub enum Command {
     Serve(ServeArgs),
     Auth(AuthArgs),
     Index(IndexArgs),
+    /// Serve LSP over stdio, answering from an askld server
+    Lsp(LspArgs),
+    /// Serve MCP over stdio, from an askld server or straight from the database
+    Mcp(McpArgs),
 }
 
 #[derive(ClapArgs, Debug)]
@@ -44,6 +48,83 @@ pub struct ServeArgs {
     /// Max distinct symbols per query result (0 = unlimited); per-request `?limit=` overrides
     #[clap(long, default_value = "100", env = "ASKL_MAX_RESULT_SYMBOLS")]
     pub max_result_symbols: usize,
+
+    /// File of askl `define` declarations callable from every query
+    #[clap(long, env = "ASKL_MACROS")]
+    pub macros: Option<String>,
+
+    /// Cache query results in Postgres, shared across restarts and replicas
+    #[clap(long, env = "ASKL_QUERY_CACHE")]
+    pub query_cache: bool,
+
+    /// Read replica connection string for persistent-index queries; repeat
+    /// or comma-separate for several
+    #[clap(
+        long = "replica-url",
+        env = "ASKL_REPLICA_URLS",
+        use_value_delimiter = true,
+        multiple_occurrences = true
+    )]
+    pub replica_urls: Vec<String>,
+
+    /// Replay lag in seconds above which a replica stops serving reads
+    #[clap(long, default_value = "30", env = "ASKL_REPLICA_MAX_LAG")]
+    pub replica_max_lag: u64,
+}
+
+#[derive(ClapArgs, Debug)]
+pub struct LspArgs {
+    /// askld base URL
+    #[clap(long, default_value = "http://127.0.0.1:80")]
+    pub url: String,
+
+    /// Map a local checkout onto its indexed path, e.g. /home/me/linux=/linux (repeatable)
+    #[clap(long, multiple_occurrences = true)]
+    pub map: Vec<String>,
+
+    /// Restrict every query to this project (default: the project holding the document)
+    #[clap(long)]
+    pub project: Option<String>,
+
+    /// Max distinct symbols per query (0 = unlimited)
+    #[clap(long, default_value = "500")]
+    pub limit: usize,
+
+    /// Request timeout in seconds (0 disables timeout)
+    #[clap(long, default_value = "30")]
+    pub timeout: u64,
+}
+
+#[derive(ClapArgs, Debug)]
+#[clap(group(ArgGroup::new("source").required(true).args(&["url", "database-url"])))]
+pub struct McpArgs {
+    /// askld base URL to forward every message to
+    #[clap(long)]
+    pub url: Option<String>,
+
+    /// Bearer token for --url, attributing queries to its user (falls back to ASKL_TOKEN)
+    #[clap(long)]
+    pub token: Option<String>,
+
+    /// Request timeout in seconds for --url (0 disables timeout)
+    #[clap(long, default_value = "300")]
+    pub timeout: u64,
+
+    /// Postgres connection string to answer from directly, without a server
+    #[clap(long)]
+    pub database_url: Option<String>,
+
+    /// Query timeout in seconds with --database-url
+    #[clap(long, default_value = "5", env = "ASKL_QUERY_TIMEOUT")]
+    pub query_timeout: u64,
+
+    /// Max distinct symbols per query result with --database-url (0 = unlimited)
+    #[clap(long, default_value = "100", env = "ASKL_MAX_RESULT_SYMBOLS")]
+    pub max_result_symbols: usize,
+
+    /// File of askl `define` declarations for --database-url
+    #[clap(long, env = "ASKL_MACROS")]
+    pub macros: Option<String>,
 }
 
 #[derive(ClapArgs, Debug)]
@@ -116,6 +197,31 @@ pub enum IndexCommand {
         #[clap(long, action)]
         force: bool,
     },
+    /// Check an index offline and report every problem an upload would hit
+    Validate {
+        /// Path to index: a file (single Project .pb) or directory (multi-file output)
+        index: String,
+        /// Print the JSON report only
+        #[clap(long, action)]
+        json: bool,
+        /// Problems to list in the text report (0 lists all)
+        #[clap(long, default_value = "100")]
+        max_issues: usize,
+    },
+    /// Summarise an index offline: counts, largest files, most-referenced symbols
+    Inspect {
+        /// Path to index: a file (single Project .pb) or directory (multi-file output)
+        index: String,
+        /// Entries to list under largest files and top referenced symbols
+        #[clap(long, default_value = "10")]
+        top: usize,
+        /// Dump the instances of this symbol (name, leaf name or local id) with source
+        #[clap(long)]
+        symbol: Option<String>,
+        /// Print JSON only
+        #[clap(long, action)]
+        json: bool,
+    },
     ListProjects {
         /// askld base URL
         #[clap(long, default_value = "http://127.0.0.1:80")]
@@ -176,6 +282,8 @@ impl IndexCommand {
     pub fn error_context(&self) -> &'static str {
         match self {
             IndexCommand::Upload { .. } => "Failed to upload index",
+            IndexCommand::Validate { .. } => "Failed to validate index",
+            IndexCommand::Inspect { .. } => "Failed to inspect index",
             IndexCommand::ListProjects { .. } => "Failed to list projects",
             IndexCommand::GetProject { .. } => "Failed to get project",
             IndexCommand::DeleteProject { .. } => "Failed to delete project",
@@ -1,11 +1,11 @@
 use std::borrow::Cow;
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
 use std::sync::Arc;
 
 use crate::args::IndexCommand;
 use anyhow::{anyhow, Result};
-use askld::index_store::UploadStatus;
-use askld::proto::askl::index::{ContentBatch, Project};
+use askld::index_store::{validate_upload, UploadStatus, ValidationReport, SCHEMA_VERSION};
+use askld::proto::askl::index::{ContentBatch, ObjectContent, Project};
 use bytes::Bytes;
 use futures::stream::{FuturesUnordered, StreamExt};
 use indicatif::{ProgressBar, ProgressStyle};
@@ -68,6 +68,7 @@ const CONTENTS_UPLOAD_MAX_BYTES: usize = 128 * 1024 * 1024;
 const CONTENTS_HASH_BATCH_SIZE: usize = 10_000;
 const SYMBOLS_PER_CHUNK: usize = 1_000;
 const OBJECTS_PER_CHUNK: usize = 100;
+const RELATIONS_PER_CHUNK: usize = 10_000;
 const CHUNK_MAX_RETRIES: u32 = 3;
 
 /// Typed API endpoint builder — eliminates repeated raw-string URL construction.
@@ -108,7 +109,7 @@ fn set_progress_msg(progress: &Option<ProgressBar>, msg: impl Into<Cow<'static,
     }
 }
 
-fn normalize_base_url(url: &str) -> String {
+pub(super) fn normalize_base_url(url: &str) -> String {
     let mut base_url = url.trim().to_string();
     if !base_url.starts_with("http://") && !base_url.starts_with("https://") {
         base_url = format!("http://{}", base_url);
@@ -116,7 +117,7 @@ fn normalize_base_url(url: &str) -> String {
     base_url.trim_end_matches('/').to_string()
 }
 
-fn build_client(timeout: u64) -> reqwest::Client {
+pub(super) fn build_client(timeout: u64) -> reqwest::Client {
     let mut builder = reqwest::Client::builder();
     if timeout > 0 {
         builder = builder.timeout(Duration::from_secs(timeout));
@@ -212,7 +213,7 @@ async fn resolve_project_id(
     }
 }
 
-fn human_size(bytes: u64) -> String {
+pub(super) fn human_size(bytes: u64) -> String {
     if bytes >= 1024 * 1024 * 1024 {
         format!("{} GB", bytes / (1024 * 1024 * 1024))
     } else if bytes >= 1024 * 1024 {
@@ -388,6 +389,7 @@ async fn ensure_project(
     token: &str,
     project_name: &str,
     root_path: &str,
+    schema_version: u32,
     symbol_chunks: i32,
     object_chunks: i32,
     force: bool,
@@ -425,6 +427,7 @@ async fn ensure_project(
     let skeleton = Project {
         project_name: project_name.to_string(),
         root_path: root_path.to_string(),
+        schema_version,
         ..Default::default()
     };
     let mut buf = Vec::with_capacity(skeleton.encoded_len());
@@ -582,6 +585,28 @@ async fn upload_content_file_filtered(
     Ok(())
 }
 
+/// Reject an index written for a newer `index.proto` than this binary
+/// knows: decoding it here would silently drop the fields it cannot read.
+fn check_schema_version(schema_version: u32, path: &str) -> Result<()> {
+    if schema_version > SCHEMA_VERSION {
+        return Err(anyhow!(
+            "{} was written for index schema_version {}, but this askld only understands {}; \
+             upgrade askld",
+            path,
+            schema_version,
+            SCHEMA_VERSION
+        ));
+    }
+    Ok(())
+}
+
+/// `askld index upload` flags shared by the single-file and directory forms.
+struct UploadOptions {
+    json: bool,
+    window: usize,
+    force: bool,
+}
+
 /// Core upload logic: chunked symbol + object upload with content dedup.
 ///
 /// `content_file_paths` is a sorted list of `contents-*.pb` files to upload.
@@ -590,16 +615,20 @@ async fn upload_project_core(
     client: &reqwest::Client,
     base_url: &str,
     token: &str,
-    project_name: String,
-    root_path: String,
-    all_symbols: Vec<askld::proto::askl::index::Symbol>,
-    all_objects: Vec<askld::proto::askl::index::Object>,
+    upload: Project,
     content_file_paths: Vec<String>,
-    json: bool,
-    window: usize,
-    force: bool,
+    options: &UploadOptions,
 ) -> Result<i32> {
-    let show_progress = !json;
+    let Project {
+        project_name,
+        root_path,
+        schema_version,
+        symbols: all_symbols,
+        objects: all_objects,
+        relations: all_relations,
+        ..
+    } = upload;
+    let show_progress = !options.json;
 
     // Encode symbol chunks
     let symbol_chunks: Vec<(i32, Bytes)> = all_symbols
@@ -608,6 +637,7 @@ async fn upload_project_core(
         .map(|(seq, symbols)| {
             let msg = Project {
                 symbols: symbols.to_vec(),
+                schema_version,
                 ..Default::default()
             };
             let mut buf = Vec::with_capacity(msg.encoded_len());
@@ -617,15 +647,26 @@ async fn upload_project_core(
         })
         .collect::<Result<Vec<_>>>()?;
 
-    // Encode object chunks
-    let object_chunks: Vec<(i32, Bytes)> = all_objects
+    // Encode object chunks, then relation-only chunks: relations reference
+    // symbols only, so they ride the object phase once every symbol exists.
+    let object_msgs = all_objects
         .chunks(OBJECTS_PER_CHUNK)
+        .map(|objects| Project {
+            objects: objects.to_vec(),
+            schema_version,
+            ..Default::default()
+        });
+    let relation_msgs = all_relations
+        .chunks(RELATIONS_PER_CHUNK)
+        .map(|relations| Project {
+            relations: relations.to_vec(),
+            schema_version,
+            ..Default::default()
+        });
+    let object_chunks: Vec<(i32, Bytes)> = object_msgs
+        .chain(relation_msgs)
         .enumerate()
-        .map(|(seq, objects)| {
-            let msg = Project {
-                objects: objects.to_vec(),
-                ..Default::default()
-            };
+        .map(|(seq, msg)| {
             let mut buf = Vec::with_capacity(msg.encoded_len());
             msg.encode(&mut buf)
                 .map_err(|e| anyhow!("Failed to encode object chunk {}: {}", seq, e))?;
@@ -655,9 +696,10 @@ async fn upload_project_core(
         token,
         &project_name,
         &root_path,
+        schema_version,
         n_symbol_chunks,
         n_object_chunks,
-        force,
+        options.force,
         &progress,
     )
     .await
@@ -680,7 +722,7 @@ async fn upload_project_core(
         n_object_chunks,
         content_file_paths,
         &state,
-        window,
+        options.window,
         &progress,
     )
     .await;
@@ -823,9 +865,7 @@ async fn upload_single_file(
     token: &str,
     file_path: &str,
     project_name_override: Option<String>,
-    json: bool,
-    window: usize,
-    force: bool,
+    options: &UploadOptions,
 ) -> Result<()> {
     let data = tokio::fs::read(file_path)
         .await
@@ -837,27 +877,13 @@ async fn upload_single_file(
     if let Some(name) = project_name_override {
         upload.project_name = name;
     }
-    let project_name = upload.project_name.clone();
-    let root_path = upload.root_path.clone();
-    let all_symbols = std::mem::take(&mut upload.symbols);
-    let all_objects = std::mem::take(&mut upload.objects);
+    check_schema_version(upload.schema_version, file_path)?;
 
-    let project_id = upload_project_core(
-        client,
-        base_url,
-        token,
-        project_name,
-        root_path,
-        all_symbols,
-        all_objects,
-        Vec::new(), // no content files for single-file upload
-        json,
-        window,
-        force,
-    )
-    .await?;
+    // No content files for a single-file upload.
+    let project_id =
+        upload_project_core(client, base_url, token, upload, Vec::new(), options).await?;
 
-    if json {
+    if options.json {
         println!(
             "{}",
             serde_json::to_string_pretty(&serde_json::json!({ "project_id": project_id }))?
@@ -868,22 +894,8 @@ async fn upload_single_file(
     Ok(())
 }
 
-async fn upload_directory(
-    client: &reqwest::Client,
-    base_url: &str,
-    token: &str,
-    dir_path: &str,
-    project_name_override: Option<String>,
-    json: bool,
-    window: usize,
-    force: bool,
-) -> Result<()> {
-    let project_pb = format!("{}/project.pb", dir_path);
-    if !tokio::fs::try_exists(&project_pb).await.unwrap_or(false) {
-        return Err(anyhow!("project.pb not found in directory: {}", dir_path));
-    }
-
-    // Discover content batch files
+/// `contents-*.pb` batch files of a multi-file index directory, sorted.
+async fn discover_content_files(dir_path: &str) -> Result<Vec<String>> {
     let mut content_file_names: Vec<String> = Vec::new();
     let mut entries = tokio::fs::read_dir(dir_path)
         .await
@@ -899,10 +911,26 @@ async fn upload_directory(
         }
     }
     content_file_names.sort();
-    let content_file_paths: Vec<String> = content_file_names
+    Ok(content_file_names
         .iter()
         .map(|n| format!("{}/{}", dir_path, n))
-        .collect();
+        .collect())
+}
+
+async fn upload_directory(
+    client: &reqwest::Client,
+    base_url: &str,
+    token: &str,
+    dir_path: &str,
+    project_name_override: Option<String>,
+    options: &UploadOptions,
+) -> Result<()> {
+    let project_pb = format!("{}/project.pb", dir_path);
+    if !tokio::fs::try_exists(&project_pb).await.unwrap_or(false) {
+        return Err(anyhow!("project.pb not found in directory: {}", dir_path));
+    }
+
+    let content_file_paths = discover_content_files(dir_path).await?;
 
     // Parse project.pb
     let data = tokio::fs::read(&project_pb)
@@ -915,27 +943,12 @@ async fn upload_directory(
     if let Some(name) = project_name_override {
         upload.project_name = name;
     }
-    let project_name = upload.project_name.clone();
-    let root_path = upload.root_path.clone();
-    let all_symbols = std::mem::take(&mut upload.symbols);
-    let all_objects = std::mem::take(&mut upload.objects);
+    check_schema_version(upload.schema_version, &project_pb)?;
 
-    let project_id = upload_project_core(
-        client,
-        base_url,
-        token,
-        project_name,
-        root_path,
-        all_symbols,
-        all_objects,
-        content_file_paths,
-        json,
-        window,
-        force,
-    )
-    .await?;
+    let project_id =
+        upload_project_core(client, base_url, token, upload, content_file_paths, options).await?;
 
-    if json {
+    if options.json {
         println!(
             "{}",
             serde_json::to_string_pretty(&serde_json::json!({ "project_id": project_id }))?
@@ -946,6 +959,119 @@ async fn upload_directory(
     Ok(())
 }
 
+/// Read a single `Project` file or the `project.pb` of a multi-file index
+/// directory, along with the directory's content batch files (none for a
+/// single file). Nothing is uploaded.
+pub(super) async fn read_index_offline(index: &str) -> Result<(Project, Vec<String>)> {
+    let path = std::path::Path::new(index);
+    let (project_pb, content_files) = if path.is_dir() {
+        let project_pb = format!("{}/project.pb", index);
+        if !tokio::fs::try_exists(&project_pb).await.unwrap_or(false) {
+            return Err(anyhow!("project.pb not found in directory: {}", index));
+        }
+        (project_pb, discover_content_files(index).await?)
+    } else if path.is_file() {
+        (index.to_string(), Vec::new())
+    } else {
+        return Err(anyhow!("Index path does not exist: {}", index));
+    };
+
+    let data = tokio::fs::read(&project_pb)
+        .await
+        .map_err(|e| anyhow!("Failed to read {}: {}", project_pb, e))?;
+    let project = Project::decode(data.as_slice())
+        .map_err(|e| anyhow!("Failed to decode protobuf: {}", e))?;
+    Ok((project, content_files))
+}
+
+/// Decode `content_files` one batch at a time, handing every entry to `f`.
+pub(super) async fn for_each_content(
+    content_files: &[String],
+    mut f: impl FnMut(ObjectContent),
+) -> Result<()> {
+    for file in content_files {
+        let data = tokio::fs::read(file)
+            .await
+            .map_err(|e| anyhow!("Failed to read {}: {}", file, e))?;
+        let batch = ContentBatch::decode(data.as_slice())
+            .map_err(|e| anyhow!("Failed to decode {}: {}", file, e))?;
+        batch.contents.into_iter().for_each(&mut f);
+    }
+    Ok(())
+}
+
+fn print_validation_report(report: &ValidationReport, max_issues: usize) {
+    let stats = &report.stats;
+    println!("Schema version: {}", report.schema_version);
+    println!("Symbols: {}", stats.symbols);
+    for (ty, count) in &stats.symbols_by_type {
+        println!("  {}: {}", ty, count);
+    }
+    println!(
+        "Objects: {} ({} without content, {})",
+        stats.objects,
+        stats.objects_without_content,
+        human_size(stats.content_bytes)
+    );
+    println!("Instances: {}", stats.instances);
+    println!("Refs: {}", stats.refs);
+    for (kind, count) in &stats.refs_by_kind {
+        println!("  {}: {}", kind, count);
+    }
+    println!("Relations: {}", stats.relations);
+
+    if report.valid {
+        println!("No problems found.");
+        return;
+    }
+    println!();
+    println!("Problems: {}", report.issues.len());
+    for (kind, count) in &report.issue_counts {
+        println!("  {}: {}", kind, count);
+    }
+    let shown = if max_issues == 0 {
+        report.issues.len()
+    } else {
+        max_issues.min(report.issues.len())
+    };
+    for issue in &report.issues[..shown] {
+        match &issue.object {
+            Some(object) => println!("{}: {}", object, issue.message),
+            None => println!("{}", issue.message),
+        }
+    }
+    if shown < report.issues.len() {
+        println!(
+            "... {} more (use --max-issues 0 to list all)",
+            report.issues.len() - shown
+        );
+    }
+}
+
+async fn validate_index(index: &str, json: bool, max_issues: usize) -> Result<()> {
+    let (project, content_files) = read_index_offline(index).await?;
+    let mut content_lengths = HashMap::new();
+    for_each_content(&content_files, |entry| {
+        content_lengths.insert(entry.content_hash, entry.content.len());
+    })
+    .await?;
+    let report = validate_upload(project, &content_lengths);
+    if json {
+        println!("{}", serde_json::to_string_pretty(&report)?);
+    } else {
+        print_validation_report(&report, max_issues);
+    }
+    if report.valid {
+        Ok(())
+    } else {
+        Err(anyhow!(
+            "{} problem(s) found in {}",
+            report.issues.len(),
+            index
+        ))
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -1079,6 +1205,22 @@ mod tests {
         assert!(!ep.projects().contains("//v1"));
         assert!(!ep.project(1).contains("//v1"));
     }
+
+    // --- check_schema_version ---
+
+    #[test]
+    fn check_schema_version_accepts_known_and_unversioned() {
+        assert!(check_schema_version(0, "project.pb").is_ok());
+        assert!(check_schema_version(SCHEMA_VERSION, "project.pb").is_ok());
+    }
+
+    #[test]
+    fn check_schema_version_rejects_newer_naming_the_file() {
+        let err = check_schema_version(SCHEMA_VERSION + 1, "out/project.pb").unwrap_err();
+        let msg = err.to_string();
+        assert!(msg.contains("out/project.pb"), "{}", msg);
+        assert!(msg.contains(&(SCHEMA_VERSION + 1).to_string()), "{}", msg);
+    }
 }
 
 pub async fn run_index_command(command: IndexCommand) -> Result<()> {
@@ -1096,22 +1238,32 @@ pub async fn run_index_command(command: IndexCommand) -> Result<()> {
             let token = resolve_token(token)?;
             let base_url = normalize_base_url(&url);
             let client = build_client(timeout);
+            let options = UploadOptions {
+                json,
+                window,
+                force,
+            };
 
             let path = std::path::Path::new(&index);
             if path.is_dir() {
-                upload_directory(
-                    &client, &base_url, &token, &index, project, json, window, force,
-                )
-                .await?;
+                upload_directory(&client, &base_url, &token, &index, project, &options).await?;
             } else if path.is_file() {
-                upload_single_file(
-                    &client, &base_url, &token, &index, project, json, window, force,
-                )
-                .await?;
+                upload_single_file(&client, &base_url, &token, &index, project, &options).await?;
             } else {
                 return Err(anyhow!("Index path does not exist: {}", index));
             }
         }
+        IndexCommand::Validate {
+            index,
+            json,
+            max_issues,
+        } => validate_index(&index, json, max_issues).await?,
+        IndexCommand::Inspect {
+            index,
+            top,
+            symbol,
+            json,
+        } => super::inspect::inspect_index(&index, top, symbol.as_deref(), json).await?,
         IndexCommand::ListProjects {
             url,
             token,
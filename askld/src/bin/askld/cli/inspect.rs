@@ -0,0 +1,469 @@
+//! `askld index inspect`: summarise an index file or directory offline, and
+//! dump one symbol's instances with the source they cover.
+
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+use anyhow::{anyhow, Result};
+use askld::index_store::enum_name;
+use askld::proto::askl::index::{InstanceType, Project, RefKind, RelationKind, SymbolType};
+use serde::Serialize;
+
+use super::index::{for_each_content, human_size, read_index_offline};
+
+/// Lines of source shown per instance before the snippet is cut short.
+const SNIPPET_MAX_LINES: usize = 12;
+
+#[derive(Debug, Serialize)]
+struct FileSummary {
+    path: String,
+    bytes: Option<usize>,
+    instances: usize,
+    refs: usize,
+}
+
+#[derive(Debug, Serialize)]
+struct ReferencedSymbol {
+    local_id: i64,
+    name: String,
+    r#type: String,
+    refs: usize,
+}
+
+#[derive(Debug, Serialize)]
+struct InstanceDump {
+    path: String,
+    instance_type: String,
+    start_offset: i32,
+    end_offset: i32,
+    /// 1-based line of `start_offset`, when the content is available.
+    line: Option<usize>,
+    snippet: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+struct SymbolDump {
+    local_id: i64,
+    name: String,
+    r#type: String,
+    instances: Vec<InstanceDump>,
+}
+
+#[derive(Debug, Serialize)]
+struct InspectReport {
+    project_name: String,
+    root_path: String,
+    schema_version: u32,
+    symbols_by_type: BTreeMap<String, usize>,
+    objects: usize,
+    content_bytes: u64,
+    instances_by_type: BTreeMap<String, usize>,
+    refs_by_kind: BTreeMap<String, usize>,
+    relations_by_kind: BTreeMap<String, usize>,
+    largest_files: Vec<FileSummary>,
+    top_referenced: Vec<ReferencedSymbol>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    symbols: Vec<SymbolDump>,
+}
+
+fn symbol_type_name(value: i32) -> String {
+    enum_name(value, |t: SymbolType| t.as_str_name())
+}
+
+/// Symbols whose full name or leaf (the part after the last `.`, `/` or
+/// `:`) equals `query`, or whose local id it spells.
+fn matches_symbol(query: &str, local_id: i64, name: &str) -> bool {
+    if query.parse::<i64>() == Ok(local_id) {
+        return true;
+    }
+    let leaf = name.rsplit(['.', '/', ':']).next().unwrap_or(name);
+    name == query || leaf == query
+}
+
+/// 1-based line of `start` and the lines covering `start..end`, at most
+/// `max_lines` of them. `None` when the range does not fit `content`.
+fn snippet(content: &[u8], start: usize, end: usize, max_lines: usize) -> Option<(usize, String)> {
+    if start > end || end > content.len() {
+        return None;
+    }
+    let line_start = content[..start]
+        .iter()
+        .rposition(|&b| b == b'\n')
+        .map_or(0, |i| i + 1);
+    let line_end = content[end..]
+        .iter()
+        .position(|&b| b == b'\n')
+        .map_or(content.len(), |i| end + i);
+    let line = content[..start].iter().filter(|&&b| b == b'\n').count() + 1;
+    let text = String::from_utf8_lossy(&content[line_start..line_end]);
+    let mut lines: Vec<&str> = text.lines().collect();
+    if lines.len() > max_lines {
+        let more = lines.len() - max_lines;
+        lines.truncate(max_lines);
+        return Some((
+            line,
+            format!("{}\n... {} more lines", lines.join("\n"), more),
+        ));
+    }
+    Some((line, lines.join("\n")))
+}
+
+async fn build_report(
+    project: Project,
+    content_files: &[String],
+    top: usize,
+    symbol_query: Option<&str>,
+) -> Result<InspectReport> {
+    let mut symbols_by_type = BTreeMap::new();
+    for symbol in &project.symbols {
+        *symbols_by_type
+            .entry(symbol_type_name(symbol.r#type))
+            .or_default() += 1;
+    }
+
+    let wanted: HashSet<i64> = symbol_query
+        .map(|query| {
+            project
+                .symbols
+                .iter()
+                .filter(|s| matches_symbol(query, s.local_id, &s.name))
+                .map(|s| s.local_id)
+                .collect()
+        })
+        .unwrap_or_default();
+    if let Some(query) = symbol_query {
+        if wanted.is_empty() {
+            return Err(anyhow!("No symbol matches '{}'", query));
+        }
+    }
+
+    // Content batches are only read for sizes and for the objects that hold
+    // the requested symbol, so large indexes are never held in memory whole.
+    let mut needed_hashes = HashSet::new();
+    for object in &project.objects {
+        if object.content.is_empty()
+            && object
+                .symbol_instances
+                .iter()
+                .any(|i| wanted.contains(&i.symbol_local_id))
+        {
+            needed_hashes.insert(object.content_hash.clone());
+        }
+    }
+    let mut content_lengths = HashMap::new();
+    let mut contents = HashMap::new();
+    for_each_content(content_files, |entry| {
+        content_lengths.insert(entry.content_hash.clone(), entry.content.len());
+        if needed_hashes.contains(&entry.content_hash) {
+            contents.insert(entry.content_hash, entry.content);
+        }
+    })
+    .await?;
+
+    let mut instances_by_type = BTreeMap::new();
+    let mut refs_by_kind = BTreeMap::new();
+    let mut ref_counts: HashMap<i64, usize> = HashMap::new();
+    let mut files = Vec::new();
+    let mut content_bytes = 0u64;
+    let mut dumps: BTreeMap<i64, Vec<InstanceDump>> = BTreeMap::new();
+    for object in &project.objects {
+        let content: Option<&[u8]> = if !object.content.is_empty() {
+            Some(&object.content)
+        } else {
+            contents.get(&object.content_hash).map(Vec::as_slice)
+        };
+        let bytes = if !object.content.is_empty() {
+            Some(object.content.len())
+        } else {
+            content_lengths.get(&object.content_hash).copied()
+        };
+        content_bytes += bytes.unwrap_or(0) as u64;
+
+        for instance in &object.symbol_instances {
+            let instance_type =
+                enum_name(instance.instance_type, |t: InstanceType| t.as_str_name());
+            *instances_by_type.entry(instance_type.clone()).or_default() += 1;
+            if wanted.contains(&instance.symbol_local_id) {
+                let found = content.and_then(|content| {
+                    snippet(
+                        content,
+                        usize::try_from(instance.start_offset).ok()?,
+                        usize::try_from(instance.end_offset).ok()?,
+                        SNIPPET_MAX_LINES,
+                    )
+                });
+                dumps
+                    .entry(instance.symbol_local_id)
+                    .or_default()
+                    .push(InstanceDump {
+                        path: object.filesystem_path.clone(),
+                        instance_type,
+                        start_offset: instance.start_offset,
+                        end_offset: instance.end_offset,
+                        line: found.as_ref().map(|(line, _)| *line),
+                        snippet: found.map(|(_, text)| text),
+                    });
+            }
+        }
+        for reference in &object.refs {
+            *refs_by_kind
+                .entry(enum_name(reference.kind, |k: RefKind| k.as_str_name()))
+                .or_default() += 1;
+            *ref_counts.entry(reference.to_symbol_local_id).or_default() += 1;
+        }
+        files.push(FileSummary {
+            path: object.filesystem_path.clone(),
+            bytes,
+            instances: object.symbol_instances.len(),
+            refs: object.refs.len(),
+        });
+    }
+
+    let mut relations_by_kind = BTreeMap::new();
+    for relation in &project.relations {
+        *relations_by_kind
+            .entry(enum_name(relation.kind, |k: RelationKind| k.as_str_name()))
+            .or_default() += 1;
+    }
+
+    let objects = files.len();
+    files.sort_by(|a, b| b.bytes.cmp(&a.bytes).then_with(|| a.path.cmp(&b.path)));
+    files.truncate(top);
+
+    let by_id: HashMap<i64, _> = project.symbols.iter().map(|s| (s.local_id, s)).collect();
+    let mut top_referenced: Vec<ReferencedSymbol> = ref_counts
+        .into_iter()
+        .map(|(local_id, refs)| {
+            let symbol = by_id.get(&local_id);
+            ReferencedSymbol {
+                local_id,
+                name: symbol.map_or_else(|| "<undefined>".to_string(), |s| s.name.clone()),
+                r#type: symbol.map_or_else(String::new, |s| symbol_type_name(s.r#type)),
+                refs,
+            }
+        })
+        .collect();
+    top_referenced.sort_by(|a, b| b.refs.cmp(&a.refs).then(a.local_id.cmp(&b.local_id)));
+    top_referenced.truncate(top);
+
+    let symbols = dumps
+        .into_iter()
+        .map(|(local_id, instances)| {
+            let symbol = by_id[&local_id];
+            SymbolDump {
+                local_id,
+                name: symbol.name.clone(),
+                r#type: symbol_type_name(symbol.r#type),
+                instances,
+            }
+        })
+        .collect();
+
+    Ok(InspectReport {
+        project_name: project.project_name,
+        root_path: project.root_path,
+        schema_version: project.schema_version,
+        symbols_by_type,
+        objects,
+        content_bytes,
+        instances_by_type,
+        refs_by_kind,
+        relations_by_kind,
+        largest_files: files,
+        top_referenced,
+        symbols,
+    })
+}
+
+fn print_counts(label: &str, counts: &BTreeMap<String, usize>) {
+    println!("{}: {}", label, counts.values().sum::<usize>());
+    for (name, count) in counts {
+        println!("  {}: {}", name, count);
+    }
+}
+
+fn print_report(report: &InspectReport) {
+    println!("Project: {} ({})", report.project_name, report.root_path);
+    println!("Schema version: {}", report.schema_version);
+    print_counts("Symbols", &report.symbols_by_type);
+    println!(
+        "Objects: {} ({})",
+        report.objects,
+        human_size(report.content_bytes)
+    );
+    print_counts("Instances", &report.instances_by_type);
+    print_counts("Refs", &report.refs_by_kind);
+    print_counts("Relations", &report.relations_by_kind);
+
+    if !report.largest_files.is_empty() {
+        println!();
+        println!("Largest files:");
+        for file in &report.largest_files {
+            let size = file
+                .bytes
+                .map_or_else(|| "?".to_string(), |b| human_size(b as u64));
+            println!(
+                "  {:>10}  {} ({} instances, {} refs)",
+                size, file.path, file.instances, file.refs
+            );
+        }
+    }
+    if !report.top_referenced.is_empty() {
+        println!();
+        println!("Top referenced symbols:");
+        for symbol in &report.top_referenced {
+            println!(
+                "  {:>8}  {} [{}] #{}",
+                symbol.refs, symbol.name, symbol.r#type, symbol.local_id
+            );
+        }
+    }
+    for symbol in &report.symbols {
+        println!();
+        println!(
+            "{} [{}] #{}: {} instances",
+            symbol.name,
+            symbol.r#type,
+            symbol.local_id,
+            symbol.instances.len()
+        );
+        for instance in &symbol.instances {
+            match instance.line {
+                Some(line) => println!("  {}:{} {}", instance.path, line, instance.instance_type),
+                None => println!(
+                    "  {} [{}..{}] {} (content not available)",
+                    instance.path,
+                    instance.start_offset,
+                    instance.end_offset,
+                    instance.instance_type
+                ),
+            }
+            if let Some(snippet) = &instance.snippet {
+                for line in snippet.lines() {
+                    println!("    | {}", line);
+                }
+            }
+        }
+    }
+}
+
+pub(super) async fn inspect_index(
+    index: &str,
+    top: usize,
+    symbol: Option<&str>,
+    json: bool,
+) -> Result<()> {
+    let (project, content_files) = read_index_offline(index).await?;
+    let report = build_report(project, &content_files, top, symbol).await?;
+    if json {
+        println!("{}", serde_json::to_string_pretty(&report)?);
+    } else {
+        print_report(&report);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use askld::proto::askl::index::{Object, Project, Symbol, SymbolInstance, SymbolRef};
+
+    use super::*;
+
+    const SOURCE: &[u8] = b"int helper(void);\n\nint main(void) {\n  return helper();\n}\n";
+
+    fn project() -> Project {
+        Project {
+            project_name: "p".to_string(),
+            root_path: "/p".to_string(),
+            symbols: vec![
+                Symbol {
+                    local_id: 1,
+                    name: "p.main".to_string(),
+                    r#type: 1,
+                    ..Default::default()
+                },
+                Symbol {
+                    local_id: 2,
+                    name: "p.helper".to_string(),
+                    r#type: 1,
+                    ..Default::default()
+                },
+            ],
+            objects: vec![
+                Object {
+                    local_id: 1,
+                    filesystem_path: "/p/main.c".to_string(),
+                    content: SOURCE.to_vec(),
+                    symbol_instances: vec![SymbolInstance {
+                        symbol_local_id: 1,
+                        instance_type: 1,
+                        start_offset: 19,
+                        end_offset: 56,
+                    }],
+                    refs: vec![SymbolRef {
+                        to_symbol_local_id: 2,
+                        from_offset_start: 45,
+                        from_offset_end: 51,
+                        kind: 1,
+                    }],
+                    ..Default::default()
+                },
+                Object {
+                    local_id: 2,
+                    filesystem_path: "/p/empty.c".to_string(),
+                    content: b"\n".to_vec(),
+                    ..Default::default()
+                },
+            ],
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn snippet_covers_whole_lines() {
+        let (line, text) = snippet(SOURCE, 23, 27, 10).unwrap();
+        assert_eq!(line, 3);
+        assert_eq!(text, "int main(void) {");
+        assert!(snippet(SOURCE, 0, 1000, 10).is_none());
+    }
+
+    #[test]
+    fn snippet_is_cut_at_max_lines() {
+        let (_, text) = snippet(SOURCE, 19, 56, 1).unwrap();
+        assert_eq!(text, "int main(void) {\n... 2 more lines");
+    }
+
+    #[test]
+    fn symbol_matches_leaf_full_name_or_id() {
+        assert!(matches_symbol("main", 1, "p.main"));
+        assert!(matches_symbol("p.main", 1, "p.main"));
+        assert!(matches_symbol("1", 1, "p.main"));
+        assert!(!matches_symbol("mai", 1, "p.main"));
+    }
+
+    #[tokio::test]
+    async fn report_counts_and_dumps_symbol() {
+        let report = build_report(project(), &[], 1, Some("main")).await.unwrap();
+        assert_eq!(report.symbols_by_type["FUNCTION"], 2);
+        assert_eq!(report.objects, 2);
+        assert_eq!(report.largest_files.len(), 1);
+        assert_eq!(report.largest_files[0].path, "/p/main.c");
+        assert_eq!(report.top_referenced[0].name, "p.helper");
+        assert_eq!(report.refs_by_kind["CALL"], 1);
+
+        let dump = &report.symbols[0];
+        assert_eq!(dump.local_id, 1);
+        assert_eq!(dump.instances[0].line, Some(3));
+        assert!(dump.instances[0]
+            .snippet
+            .as_deref()
+            .unwrap()
+            .contains("return helper();"));
+    }
+
+    #[tokio::test]
+    async fn unknown_symbol_is_an_error() {
+        assert!(build_report(project(), &[], 10, Some("nope"))
+            .await
+            .is_err());
+    }
+}
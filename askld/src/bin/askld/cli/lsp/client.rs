@@ -0,0 +1,195 @@
+//! The askld side of the bridge: runs askl queries over HTTP and decodes the
+//! `/query` JSON graph.
+
+use anyhow::{anyhow, Result};
+use index::symbols::{symbol_path_and_leaf, InstanceType, SymbolType};
+use serde::Deserialize;
+use serde_json::{json, Map, Value};
+
+use crate::api::types::{GraphObjectEntry, NodeSymbolInstance};
+
+/// The subset of the `/query` response the bridge needs. Ids stay strings,
+/// as they are on the wire.
+#[derive(Debug, Deserialize)]
+pub(super) struct WireGraph {
+    pub nodes: Vec<WireNode>,
+    pub edges: Vec<WireEdge>,
+    pub objects: Vec<GraphObjectEntry>,
+}
+
+#[derive(Debug, Deserialize)]
+pub(super) struct WireNode {
+    pub id: String,
+    pub label: String,
+    pub symbol_instances: Vec<NodeSymbolInstance>,
+}
+
+#[derive(Debug, Deserialize)]
+pub(super) struct WireEdge {
+    pub from: String,
+    pub to: String,
+    pub from_object: Option<String>,
+    pub from_offset_start: Option<i32>,
+    pub from_offset_end: Option<i32>,
+}
+
+impl WireGraph {
+    pub(super) fn object_path(&self, object_id: &str) -> Option<&str> {
+        self.objects
+            .iter()
+            .find(|o| o.object_id == object_id)
+            .map(|o| o.path.as_str())
+    }
+
+    pub(super) fn node(&self, id: &str) -> Option<&WireNode> {
+        self.nodes.iter().find(|n| n.id == id)
+    }
+}
+
+impl WireNode {
+    pub(super) fn symbol_type(&self) -> Option<SymbolType> {
+        self.symbol_instances.first().map(|i| i.symbol_type)
+    }
+
+    /// The last component of the symbol's path, i.e. what a user types.
+    pub(super) fn leaf(&self) -> String {
+        let symbol_type = self
+            .symbol_type()
+            .map_or(SymbolType::Function as i32, |t| t as i32);
+        symbol_path_and_leaf(&self.label, symbol_type).1
+    }
+
+    /// Definitions, or declarations when the index has no definition.
+    pub(super) fn definitions(&self) -> Vec<&NodeSymbolInstance> {
+        for wanted in [InstanceType::Definition, InstanceType::Declaration] {
+            let found: Vec<_> = self
+                .symbol_instances
+                .iter()
+                .filter(|i| i.instance_type == wanted)
+                .collect();
+            if !found.is_empty() {
+                return found;
+            }
+        }
+        self.symbol_instances.iter().take(1).collect()
+    }
+}
+
+/// An indexed project, as `GET /v1/index/projects` lists it.
+#[derive(Debug, Clone, Deserialize)]
+pub(super) struct WireProject {
+    pub project_name: String,
+    pub root_path: String,
+}
+
+pub(super) struct AsklClient {
+    http: reqwest::Client,
+    base_url: String,
+    limit: usize,
+}
+
+impl AsklClient {
+    pub(super) fn new(http: reqwest::Client, base_url: String, limit: usize) -> Self {
+        Self {
+            http,
+            base_url,
+            limit,
+        }
+    }
+
+    /// Run `query` with `params` bound to its `$name` placeholders, within
+    /// `project` when given. Names from the editor always travel as
+    /// parameters, never spliced into the text.
+    pub(super) async fn query(
+        &self,
+        query: &str,
+        mut params: Map<String, Value>,
+        project: Option<&str>,
+    ) -> Result<WireGraph> {
+        let query = match project {
+            Some(project) => {
+                params.insert("project".to_string(), json!(project));
+                format!("project($project) {}", query)
+            }
+            None => query.to_string(),
+        };
+        let response = self
+            .http
+            .post(format!("{}/query?limit={}", self.base_url, self.limit))
+            .json(&json!({ "query": query, "params": params }))
+            .send()
+            .await
+            .map_err(|e| anyhow!("Request failed: {}", e))?;
+        if !response.status().is_success() {
+            let status = response.status();
+            let body = response.text().await.unwrap_or_default();
+            return Err(anyhow!("Query failed ({}): {}", status, body));
+        }
+        response
+            .json()
+            .await
+            .map_err(|e| anyhow!("Invalid query response: {}", e))
+    }
+
+    /// The indexed projects and their root paths.
+    pub(super) async fn projects(&self) -> Result<Vec<WireProject>> {
+        let response = self
+            .http
+            .get(format!("{}/v1/index/projects", self.base_url))
+            .send()
+            .await
+            .map_err(|e| anyhow!("Request failed: {}", e))?;
+        if !response.status().is_success() {
+            let status = response.status();
+            let body = response.text().await.unwrap_or_default();
+            return Err(anyhow!("Listing projects failed ({}): {}", status, body));
+        }
+        response
+            .json()
+            .await
+            .map_err(|e| anyhow!("Invalid projects response: {}", e))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn decodes_query_response() {
+        let graph: WireGraph = serde_json::from_value(json!({
+            "nodes": [{
+                "id": "7",
+                "label": "fs.read_write.vfs_read",
+                "symbol_instances": [{
+                    "id": "1", "symbol": "7", "object_id": "3", "project_id": "1",
+                    "symbol_type": "Function", "instance_type": "declaration",
+                    "start_offset": 0, "end_offset": 4
+                }, {
+                    "id": "2", "symbol": "7", "object_id": "4", "project_id": "1",
+                    "symbol_type": "Function", "instance_type": "definition",
+                    "start_offset": 10, "end_offset": 40
+                }],
+                "query_statements": []
+            }],
+            "edges": [{
+                "id": "7-8", "from": "7", "to": "8", "from_object": "4",
+                "from_project_id": "1", "from_offset_start": 20, "from_offset_end": 24
+            }],
+            "has_edges": [],
+            "objects": [{ "object_id": "4", "path": "/linux/fs/read_write.c", "project_id": "1" }],
+            "warnings": [],
+            "truncated": false,
+            "total_symbols": 1
+        }))
+        .unwrap();
+
+        let node = graph.node("7").unwrap();
+        assert_eq!(node.leaf(), "vfs_read");
+        let defs = node.definitions();
+        assert_eq!(defs.len(), 1);
+        assert_eq!(defs[0].object_id, "4");
+        assert_eq!(graph.object_path("4"), Some("/linux/fs/read_write.c"));
+        assert_eq!(graph.edges[0].from_offset_start, Some(20));
+    }
+}
@@ -0,0 +1,577 @@
+//! `askld lsp` — a language server over stdio backed by a remote askld.
+//!
+//! Each LSP request is answered by an askl query against the configured
+//! server: the identifier under the cursor becomes a `$name` parameter of a
+//! fixed query (`$name` for definitions, `{ $name }` for references, …),
+//! scoped to the project holding the document (its local path mapped onto
+//! the index with [`PathMap`]), and the result's byte offsets are mapped back
+//! onto the editor's checkout. A symbol defined in the document itself, like
+//! a `static` helper, shadows same-named ones elsewhere in the project.
+//! Results in files outside every mapping are dropped — the editor could not
+//! open them anyway.
+
+mod client;
+mod paths;
+mod text;
+mod transport;
+
+use std::collections::{BTreeMap, HashMap};
+
+use anyhow::{anyhow, Result};
+use lsp_types::{
+    CallHierarchyIncomingCall, CallHierarchyIncomingCallsParams, CallHierarchyItem,
+    CallHierarchyOutgoingCall, CallHierarchyOutgoingCallsParams, CallHierarchyPrepareParams,
+    CallHierarchyServerCapability, DidChangeTextDocumentParams, DidCloseTextDocumentParams,
+    DidOpenTextDocumentParams, GotoDefinitionParams, GotoDefinitionResponse, InitializeResult,
+    Location, OneOf, Range, ReferenceParams, ServerCapabilities, ServerInfo, SymbolInformation,
+    SymbolKind, TextDocumentPositionParams, TextDocumentSyncCapability, TextDocumentSyncKind, Url,
+    WorkspaceSymbolParams,
+};
+use serde::de::DeserializeOwned;
+use serde_json::{json, Map, Value};
+
+use self::client::{AsklClient, WireGraph, WireNode, WireProject};
+use self::paths::{is_under, PathMap};
+use self::text::{position_of, word_at};
+use self::transport::{read_message, write_message};
+use super::index::{build_client, normalize_base_url};
+use crate::args::LspArgs;
+use index::symbols::SymbolType;
+
+const METHOD_NOT_FOUND: i64 = -32601;
+const INVALID_PARAMS: i64 = -32602;
+/// LSP `RequestFailed`: the request was valid but the askld query failed.
+const REQUEST_FAILED: i64 = -32803;
+
+struct RpcFailure {
+    code: i64,
+    message: String,
+}
+
+impl From<anyhow::Error> for RpcFailure {
+    fn from(err: anyhow::Error) -> Self {
+        Self {
+            code: REQUEST_FAILED,
+            message: err.to_string(),
+        }
+    }
+}
+
+fn parse<T: DeserializeOwned>(params: Value) -> Result<T, RpcFailure> {
+    serde_json::from_value(params).map_err(|e| RpcFailure {
+        code: INVALID_PARAMS,
+        message: e.to_string(),
+    })
+}
+
+fn to_result<T: serde::Serialize>(value: T) -> Result<Value, RpcFailure> {
+    Ok(serde_json::to_value(value).expect("LSP types serialize"))
+}
+
+fn name_param(name: &str) -> Map<String, Value> {
+    let mut params = Map::new();
+    params.insert("name".to_string(), json!(name));
+    params
+}
+
+fn symbol_kind(symbol_type: Option<SymbolType>) -> SymbolKind {
+    match symbol_type {
+        Some(SymbolType::Function) => SymbolKind::FUNCTION,
+        Some(SymbolType::File) => SymbolKind::FILE,
+        Some(SymbolType::Module) => SymbolKind::MODULE,
+        Some(SymbolType::Directory) => SymbolKind::PACKAGE,
+        Some(SymbolType::Type) => SymbolKind::STRUCT,
+        Some(SymbolType::Data) => SymbolKind::VARIABLE,
+        Some(SymbolType::Macro) => SymbolKind::CONSTANT,
+        Some(SymbolType::Field) => SymbolKind::FIELD,
+        Some(SymbolType::Content) | None => SymbolKind::NULL,
+    }
+}
+
+/// Resolves index paths and byte offsets to editor locations, reading each
+/// local file at most once per request.
+struct Locator<'a> {
+    paths: &'a PathMap,
+    documents: &'a HashMap<Url, String>,
+    files: HashMap<String, Option<(Url, Vec<u8>)>>,
+}
+
+impl<'a> Locator<'a> {
+    fn new(paths: &'a PathMap, documents: &'a HashMap<Url, String>) -> Self {
+        Self {
+            paths,
+            documents,
+            files: HashMap::new(),
+        }
+    }
+
+    async fn load(&mut self, index_path: &str) -> Option<&(Url, Vec<u8>)> {
+        if !self.files.contains_key(index_path) {
+            let mut loaded = None;
+            if let Some(local) = self.paths.to_local(index_path) {
+                if let Ok(uri) = Url::from_file_path(&local) {
+                    // Prefer the editor's buffer: it may hold unsaved edits.
+                    let content = match self.documents.get(&uri) {
+                        Some(text) => Some(text.clone().into_bytes()),
+                        None => tokio::fs::read(&local).await.ok(),
+                    };
+                    loaded = content.map(|content| (uri, content));
+                }
+            }
+            self.files.insert(index_path.to_string(), loaded);
+        }
+        self.files.get(index_path).and_then(Option::as_ref)
+    }
+
+    async fn location(&mut self, index_path: &str, start: i32, end: i32) -> Option<Location> {
+        let (uri, content) = self.load(index_path).await?;
+        let range = Range::new(
+            position_of(content, start.max(0) as usize),
+            position_of(content, end.max(0) as usize),
+        );
+        Some(Location::new(uri.clone(), range))
+    }
+
+    /// Locations of a node's definitions.
+    async fn definitions(&mut self, graph: &WireGraph, node: &WireNode) -> Vec<Location> {
+        let mut locations = Vec::new();
+        for instance in node.definitions() {
+            let Some(path) = graph.object_path(&instance.object_id) else {
+                continue;
+            };
+            if let Some(location) = self
+                .location(path, instance.start_offset, instance.end_offset)
+                .await
+            {
+                locations.push(location);
+            }
+        }
+        locations
+    }
+
+    /// A call hierarchy item for `node`, anchored at its first definition;
+    /// follow-up calls on it stay in `project`.
+    async fn call_item(
+        &mut self,
+        graph: &WireGraph,
+        node: &WireNode,
+        project: Option<&str>,
+    ) -> Option<CallHierarchyItem> {
+        let location = self.definitions(graph, node).await.into_iter().next()?;
+        let name = node.leaf();
+        Some(CallHierarchyItem {
+            name: name.clone(),
+            kind: symbol_kind(node.symbol_type()),
+            tags: None,
+            detail: Some(node.label.clone()),
+            uri: location.uri,
+            range: location.range,
+            selection_range: location.range,
+            data: Some(json!({ "id": node.id, "name": name, "project": project })),
+        })
+    }
+}
+
+/// The askld symbol id, name and project stashed in a call hierarchy item's
+/// `data`.
+fn item_symbol(item: &CallHierarchyItem) -> Result<(String, String, Option<String>), RpcFailure> {
+    let data = item.data.as_ref();
+    let field = |key: &str| {
+        data.and_then(|d| d.get(key))
+            .and_then(Value::as_str)
+            .map(str::to_string)
+    };
+    match (field("id"), field("name")) {
+        (Some(id), Some(name)) => Ok((id, name, field("project"))),
+        _ => Err(RpcFailure {
+            code: INVALID_PARAMS,
+            message: "call hierarchy item was not produced by this server".to_string(),
+        }),
+    }
+}
+
+/// The project whose root holds `index_path`, the deepest one if roots nest.
+fn project_for(projects: &[WireProject], index_path: &str) -> Option<String> {
+    projects
+        .iter()
+        .filter(|p| is_under(index_path, &p.root_path))
+        .max_by_key(|p| p.root_path.len())
+        .map(|p| p.project_name.clone())
+}
+
+/// Keep only the items located in `document` when there are any: a symbol
+/// the document defines itself shadows same-named ones elsewhere.
+fn prefer_document<T>(items: &mut Vec<T>, document: &Url, uri: impl Fn(&T) -> &Url) {
+    if items.iter().any(|item| uri(item) == document) {
+        items.retain(|item| uri(item) == document);
+    }
+}
+
+struct LspServer {
+    client: AsklClient,
+    paths: PathMap,
+    /// `--project`: scope every query to it instead of the document's.
+    project: Option<String>,
+    /// Last project listing, refreshed when a document matches none of it.
+    projects: Vec<WireProject>,
+    /// Text of the documents the editor has open, by URI.
+    documents: HashMap<Url, String>,
+}
+
+impl LspServer {
+    async fn handle_request(&mut self, method: &str, params: Value) -> Result<Value, RpcFailure> {
+        match method {
+            "initialize" => to_result(InitializeResult {
+                capabilities: ServerCapabilities {
+                    text_document_sync: Some(TextDocumentSyncCapability::Kind(
+                        TextDocumentSyncKind::FULL,
+                    )),
+                    definition_provider: Some(OneOf::Left(true)),
+                    references_provider: Some(OneOf::Left(true)),
+                    call_hierarchy_provider: Some(CallHierarchyServerCapability::Simple(true)),
+                    workspace_symbol_provider: Some(OneOf::Left(true)),
+                    ..ServerCapabilities::default()
+                },
+                server_info: Some(ServerInfo {
+                    name: "askld".to_string(),
+                    version: Some(env!("CARGO_PKG_VERSION").to_string()),
+                }),
+            }),
+            "shutdown" => Ok(Value::Null),
+            "textDocument/definition" => self.definition(parse(params)?).await,
+            "textDocument/references" => self.references(parse(params)?).await,
+            "textDocument/prepareCallHierarchy" => {
+                self.prepare_call_hierarchy(parse(params)?).await
+            }
+            "callHierarchy/incomingCalls" => self.incoming_calls(parse(params)?).await,
+            "callHierarchy/outgoingCalls" => self.outgoing_calls(parse(params)?).await,
+            "workspace/symbol" => self.workspace_symbol(parse(params)?).await,
+            other => Err(RpcFailure {
+                code: METHOD_NOT_FOUND,
+                message: format!("unsupported method: {}", other),
+            }),
+        }
+    }
+
+    fn handle_notification(&mut self, method: &str, params: Value) {
+        match method {
+            "textDocument/didOpen" => {
+                if let Ok(p) = serde_json::from_value::<DidOpenTextDocumentParams>(params) {
+                    self.documents
+                        .insert(p.text_document.uri, p.text_document.text);
+                }
+            }
+            "textDocument/didChange" => {
+                if let Ok(p) = serde_json::from_value::<DidChangeTextDocumentParams>(params) {
+                    // Full sync: the last change carries the whole document.
+                    if let Some(change) = p.content_changes.into_iter().last() {
+                        self.documents.insert(p.text_document.uri, change.text);
+                    }
+                }
+            }
+            "textDocument/didClose" => {
+                if let Ok(p) = serde_json::from_value::<DidCloseTextDocumentParams>(params) {
+                    self.documents.remove(&p.text_document.uri);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// The identifier at a document position, from the open buffer or disk.
+    async fn word_at(&self, position: &TextDocumentPositionParams) -> Option<String> {
+        let uri = &position.text_document.uri;
+        let text = match self.documents.get(uri) {
+            Some(text) => text.clone(),
+            None => tokio::fs::read_to_string(uri.to_file_path().ok()?)
+                .await
+                .ok()?,
+        };
+        word_at(&text, position.position)
+    }
+
+    /// The project to scope a request from `document` to: `--project` when
+    /// given, else the indexed project whose root holds the document's index
+    /// path. `None` — query every project — when the document maps to none.
+    async fn document_project(&mut self, document: &Url) -> Option<String> {
+        if let Some(project) = &self.project {
+            return Some(project.clone());
+        }
+        let local = document.to_file_path().ok()?;
+        let index_path = self.paths.to_index(local.to_str()?)?;
+        if let Some(project) = project_for(&self.projects, &index_path) {
+            return Some(project);
+        }
+        // Maybe uploaded since the last listing.
+        let projects = self.client.projects().await.ok()?;
+        let project = project_for(&projects, &index_path);
+        self.projects = projects;
+        project
+    }
+
+    async fn definition(&mut self, params: GotoDefinitionParams) -> Result<Value, RpcFailure> {
+        let Some(name) = self.word_at(&params.text_document_position_params).await else {
+            return Ok(Value::Null);
+        };
+        let document = &params.text_document_position_params.text_document.uri;
+        let project = self.document_project(document).await;
+        let graph = self
+            .client
+            .query("$name", name_param(&name), project.as_deref())
+            .await?;
+        let mut locator = Locator::new(&self.paths, &self.documents);
+        let mut locations = Vec::new();
+        for node in graph.nodes.iter().filter(|n| n.leaf() == name) {
+            locations.extend(locator.definitions(&graph, node).await);
+        }
+        prefer_document(&mut locations, document, |l| &l.uri);
+        to_result(GotoDefinitionResponse::Array(locations))
+    }
+
+    async fn references(&mut self, params: ReferenceParams) -> Result<Value, RpcFailure> {
+        let Some(name) = self.word_at(&params.text_document_position).await else {
+            return Ok(Value::Null);
+        };
+        let document = &params.text_document_position.text_document.uri;
+        let project = self.document_project(document).await;
+        let graph = self
+            .client
+            .query("{ $name }", name_param(&name), project.as_deref())
+            .await?;
+        let mut locator = Locator::new(&self.paths, &self.documents);
+        let mut targets: Vec<(&WireNode, Vec<Location>)> = Vec::new();
+        for node in graph.nodes.iter().filter(|n| n.leaf() == name) {
+            targets.push((node, locator.definitions(&graph, node).await));
+        }
+        // A target the document defines shadows same-named ones elsewhere.
+        if targets
+            .iter()
+            .any(|(_, defs)| defs.iter().any(|l| &l.uri == document))
+        {
+            targets.retain(|(_, defs)| defs.iter().any(|l| &l.uri == document));
+        }
+
+        let mut locations = Vec::new();
+        for edge in &graph.edges {
+            if !targets.iter().any(|(t, _)| t.id == edge.to) {
+                continue;
+            }
+            let (Some(object), Some(start), Some(end)) = (
+                edge.from_object.as_deref(),
+                edge.from_offset_start,
+                edge.from_offset_end,
+            ) else {
+                continue;
+            };
+            let Some(path) = graph.object_path(object) else {
+                continue;
+            };
+            if let Some(location) = locator.location(path, start, end).await {
+                locations.push(location);
+            }
+        }
+        if params.context.include_declaration {
+            for (_, definitions) in targets {
+                locations.extend(definitions);
+            }
+        }
+        to_result(locations)
+    }
+
+    async fn prepare_call_hierarchy(
+        &mut self,
+        params: CallHierarchyPrepareParams,
+    ) -> Result<Value, RpcFailure> {
+        let Some(name) = self.word_at(&params.text_document_position_params).await else {
+            return Ok(Value::Null);
+        };
+        let document = &params.text_document_position_params.text_document.uri;
+        let project = self.document_project(document).await;
+        let graph = self
+            .client
+            .query("func($name)", name_param(&name), project.as_deref())
+            .await?;
+        let mut locator = Locator::new(&self.paths, &self.documents);
+        let mut items = Vec::new();
+        for node in graph.nodes.iter().filter(|n| n.leaf() == name) {
+            items.extend(locator.call_item(&graph, node, project.as_deref()).await);
+        }
+        prefer_document(&mut items, document, |item| &item.uri);
+        to_result(items)
+    }
+
+    async fn incoming_calls(
+        &self,
+        params: CallHierarchyIncomingCallsParams,
+    ) -> Result<Value, RpcFailure> {
+        let (id, name, project) = item_symbol(&params.item)?;
+        let graph = self
+            .client
+            .query("{ func($name) }", name_param(&name), project.as_deref())
+            .await?;
+
+        // Call sites grouped by caller; each site is in the caller's file.
+        let mut sites: BTreeMap<&str, Vec<(&str, i32, i32)>> = BTreeMap::new();
+        for edge in graph.edges.iter().filter(|e| e.to == id) {
+            if let (Some(object), Some(start), Some(end)) = (
+                edge.from_object.as_deref(),
+                edge.from_offset_start,
+                edge.from_offset_end,
+            ) {
+                sites
+                    .entry(&edge.from)
+                    .or_default()
+                    .push((object, start, end));
+            }
+        }
+
+        let mut locator = Locator::new(&self.paths, &self.documents);
+        let mut calls = Vec::new();
+        for (caller, sites) in sites {
+            let Some(node) = graph.node(caller) else {
+                continue;
+            };
+            let Some(from) = locator.call_item(&graph, node, project.as_deref()).await else {
+                continue;
+            };
+            let mut from_ranges = Vec::new();
+            for (object, start, end) in sites {
+                if let Some(path) = graph.object_path(object) {
+                    if let Some(location) = locator.location(path, start, end).await {
+                        from_ranges.push(location.range);
+                    }
+                }
+            }
+            calls.push(CallHierarchyIncomingCall { from, from_ranges });
+        }
+        to_result(calls)
+    }
+
+    async fn outgoing_calls(
+        &self,
+        params: CallHierarchyOutgoingCallsParams,
+    ) -> Result<Value, RpcFailure> {
+        let (id, name, project) = item_symbol(&params.item)?;
+        let graph = self
+            .client
+            .query(
+                "func($name) { func }",
+                name_param(&name),
+                project.as_deref(),
+            )
+            .await?;
+
+        let mut sites: BTreeMap<&str, Vec<(&str, i32, i32)>> = BTreeMap::new();
+        for edge in graph.edges.iter().filter(|e| e.from == id) {
+            if let (Some(object), Some(start), Some(end)) = (
+                edge.from_object.as_deref(),
+                edge.from_offset_start,
+                edge.from_offset_end,
+            ) {
+                sites
+                    .entry(&edge.to)
+                    .or_default()
+                    .push((object, start, end));
+            }
+        }
+
+        let mut locator = Locator::new(&self.paths, &self.documents);
+        let mut calls = Vec::new();
+        for (callee, sites) in sites {
+            let Some(node) = graph.node(callee) else {
+                continue;
+            };
+            let Some(to) = locator.call_item(&graph, node, project.as_deref()).await else {
+                continue;
+            };
+            let mut from_ranges = Vec::new();
+            for (object, start, end) in sites {
+                if let Some(path) = graph.object_path(object) {
+                    if let Some(location) = locator.location(path, start, end).await {
+                        from_ranges.push(location.range);
+                    }
+                }
+            }
+            calls.push(CallHierarchyOutgoingCall { to, from_ranges });
+        }
+        to_result(calls)
+    }
+
+    async fn workspace_symbol(&self, params: WorkspaceSymbolParams) -> Result<Value, RpcFailure> {
+        let query = params.query.trim();
+        if query.is_empty() {
+            return to_result(Vec::<SymbolInformation>::new());
+        }
+        // Globs need a few literal characters to use the index; shorter
+        // queries look the name up exactly.
+        let pattern = if query.chars().count() >= 3 {
+            json!({ "glob": format!("*{}*", query) })
+        } else {
+            json!(query)
+        };
+        let mut bind = Map::new();
+        bind.insert("name".to_string(), pattern);
+        let graph = self
+            .client
+            .query("$name", bind, self.project.as_deref())
+            .await?;
+
+        let mut locator = Locator::new(&self.paths, &self.documents);
+        let mut symbols = Vec::new();
+        for node in &graph.nodes {
+            for location in locator.definitions(&graph, node).await {
+                #[allow(deprecated)]
+                symbols.push(SymbolInformation {
+                    name: node.leaf(),
+                    kind: symbol_kind(node.symbol_type()),
+                    tags: None,
+                    deprecated: None,
+                    location,
+                    container_name: Some(node.label.clone()),
+                });
+            }
+        }
+        to_result(symbols)
+    }
+}
+
+pub async fn run_lsp(args: LspArgs) -> Result<()> {
+    let paths = PathMap::parse(&args.map)?;
+    let client = AsklClient::new(
+        build_client(args.timeout),
+        normalize_base_url(&args.url),
+        args.limit,
+    );
+    let mut server = LspServer {
+        client,
+        paths,
+        project: args.project,
+        projects: Vec::new(),
+        documents: HashMap::new(),
+    };
+
+    let mut reader = tokio::io::BufReader::new(tokio::io::stdin());
+    let mut writer = tokio::io::stdout();
+    while let Some(message) = read_message(&mut reader).await? {
+        let method = message.get("method").and_then(Value::as_str).unwrap_or("");
+        let params = message.get("params").cloned().unwrap_or(Value::Null);
+        match message.get("id") {
+            // A request (responses to our own requests are never expected:
+            // the server sends none).
+            Some(id) if !method.is_empty() => {
+                let response = match server.handle_request(method, params).await {
+                    Ok(result) => json!({ "jsonrpc": "2.0", "id": id, "result": result }),
+                    Err(failure) => json!({
+                        "jsonrpc": "2.0",
+                        "id": id,
+                        "error": { "code": failure.code, "message": failure.message },
+                    }),
+                };
+                write_message(&mut writer, &response).await?;
+            }
+            _ if method == "exit" => return Ok(()),
+            _ => server.handle_notification(method, params),
+        }
+    }
+    Err(anyhow!("client closed the connection without sending exit"))
+}
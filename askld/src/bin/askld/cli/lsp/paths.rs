@@ -0,0 +1,145 @@
+//! Mapping between the editor's local checkout paths and the project-prefixed
+//! paths stored in the index (`/home/me/src/linux/fs/x.c` ↔ `/linux/fs/x.c`),
+//! in both directions: results travel index → local, the document a request
+//! is made from local → index.
+
+use anyhow::{anyhow, Result};
+
+#[derive(Debug, Default)]
+pub(super) struct PathMap {
+    /// `(local prefix, index prefix)` pairs, longest index prefix first.
+    entries: Vec<(String, String)>,
+}
+
+impl PathMap {
+    /// Parse `--map LOCAL=INDEX` arguments.
+    pub(super) fn parse(specs: &[String]) -> Result<Self> {
+        let mut entries = Vec::new();
+        for spec in specs {
+            let (local, remote) = spec
+                .split_once('=')
+                .ok_or_else(|| anyhow!("invalid --map '{}': expected LOCAL=INDEX", spec))?;
+            if local.is_empty() || remote.is_empty() {
+                return Err(anyhow!("invalid --map '{}': empty path", spec));
+            }
+            entries.push((
+                local.trim_end_matches('/').to_string(),
+                remote.trim_end_matches('/').to_string(),
+            ));
+        }
+        entries.sort_by_key(|(_, index)| std::cmp::Reverse(index.len()));
+        Ok(Self { entries })
+    }
+
+    /// The local path for an index file, or `None` if it lies outside every
+    /// mapped project.
+    pub(super) fn to_local(&self, index_path: &str) -> Option<String> {
+        if self.entries.is_empty() {
+            return Some(index_path.to_string());
+        }
+        self.entries
+            .iter()
+            .find_map(|(l, r)| replace_prefix(index_path, r, l))
+    }
+
+    /// The index path for a local file, or `None` if it lies outside every
+    /// mapped checkout. The longest matching local prefix wins.
+    pub(super) fn to_index(&self, local_path: &str) -> Option<String> {
+        if self.entries.is_empty() {
+            return Some(local_path.to_string());
+        }
+        self.entries
+            .iter()
+            .filter_map(|(l, r)| Some((l.len(), replace_prefix(local_path, l, r)?)))
+            .max_by_key(|(len, _)| *len)
+            .map(|(_, path)| path)
+    }
+}
+
+/// Whether `path` is `dir` itself or lies below it.
+pub(super) fn is_under(path: &str, dir: &str) -> bool {
+    let dir = dir.trim_end_matches('/');
+    replace_prefix(path, dir, "").is_some()
+}
+
+/// Swap `from` for `to` when `path` is `from` itself or lies below it.
+fn replace_prefix(path: &str, from: &str, to: &str) -> Option<String> {
+    let rest = path.strip_prefix(from)?;
+    if rest.is_empty() || rest.starts_with('/') {
+        Some(format!("{}{}", to, rest))
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn map(specs: &[&str]) -> PathMap {
+        PathMap::parse(&specs.iter().map(|s| s.to_string()).collect::<Vec<_>>()).unwrap()
+    }
+
+    #[test]
+    fn maps_index_paths_to_local() {
+        let m = map(&["/home/me/src/linux=/linux"]);
+        assert_eq!(
+            m.to_local("/linux/fs/read_write.c").as_deref(),
+            Some("/home/me/src/linux/fs/read_write.c")
+        );
+        assert_eq!(m.to_local("/other/x.c"), None);
+    }
+
+    #[test]
+    fn prefix_must_end_at_a_component() {
+        let m = map(&["/src/linux/=/linux"]);
+        assert_eq!(m.to_local("/linuxx/x.c"), None);
+        assert_eq!(m.to_local("/linux").as_deref(), Some("/src/linux"));
+    }
+
+    #[test]
+    fn longest_prefix_wins() {
+        let m = map(&["/src/linux=/linux", "/src/drivers=/linux/drivers"]);
+        assert_eq!(m.to_local("/linux/a.c").as_deref(), Some("/src/linux/a.c"));
+        assert_eq!(
+            m.to_local("/linux/drivers/b.c").as_deref(),
+            Some("/src/drivers/b.c")
+        );
+    }
+
+    #[test]
+    fn maps_local_paths_to_index() {
+        let m = map(&["/src/linux=/linux", "/src/linux/drivers=/drivers"]);
+        assert_eq!(
+            m.to_index("/src/linux/fs/a.c").as_deref(),
+            Some("/linux/fs/a.c")
+        );
+        assert_eq!(
+            m.to_index("/src/linux/drivers/b.c").as_deref(),
+            Some("/drivers/b.c")
+        );
+        assert_eq!(m.to_index("/src/linuxx/a.c"), None);
+    }
+
+    #[test]
+    fn empty_map_is_identity() {
+        let m = PathMap::default();
+        assert_eq!(m.to_local("/linux/a.c").as_deref(), Some("/linux/a.c"));
+        assert_eq!(m.to_index("/linux/a.c").as_deref(), Some("/linux/a.c"));
+    }
+
+    #[test]
+    fn under_a_directory() {
+        assert!(is_under("/linux/fs/a.c", "/linux"));
+        assert!(is_under("/linux/fs/a.c", "/linux/"));
+        assert!(is_under("/linux", "/linux"));
+        assert!(!is_under("/linuxx/a.c", "/linux"));
+        assert!(is_under("/linux/a.c", "/"));
+    }
+
+    #[test]
+    fn rejects_malformed_specs() {
+        assert!(PathMap::parse(&["/src".to_string()]).is_err());
+        assert!(PathMap::parse(&["=/linux".to_string()]).is_err());
+    }
+}
@@ -0,0 +1,92 @@
+//! Position arithmetic over file contents. LSP positions count UTF-16 code
+//! units within a line; the index stores byte offsets.
+
+use askld::line_index::line_to_offset;
+use lsp_types::Position;
+
+/// Convert a byte offset into an LSP position.
+pub(super) fn position_of(content: &[u8], offset: usize) -> Position {
+    let offset = offset.min(content.len());
+    let before = &content[..offset];
+    let line = before.iter().filter(|b| **b == b'\n').count();
+    let line_start = before
+        .iter()
+        .rposition(|b| *b == b'\n')
+        .map_or(0, |i| i + 1);
+    let character = String::from_utf8_lossy(&content[line_start..offset])
+        .encode_utf16()
+        .count();
+    Position::new(line as u32, character as u32)
+}
+
+/// Convert an LSP position into a byte offset, clamped to the line's end.
+fn offset_of(content: &str, position: Position) -> Option<usize> {
+    let line_start = line_to_offset(content.as_bytes(), position.line as usize + 1)? as usize;
+    let line = content[line_start..].split('\n').next().unwrap_or("");
+    let mut units = 0;
+    for (i, ch) in line.char_indices() {
+        if units >= position.character as usize {
+            return Some(line_start + i);
+        }
+        units += ch.len_utf16();
+    }
+    Some(line_start + line.len())
+}
+
+fn is_ident_char(ch: char) -> bool {
+    ch == '_' || ch.is_alphanumeric()
+}
+
+/// The identifier under (or immediately before) the cursor.
+pub(super) fn word_at(content: &str, position: Position) -> Option<String> {
+    let offset = offset_of(content, position)?;
+    let start = content[..offset]
+        .char_indices()
+        .rev()
+        .take_while(|(_, ch)| is_ident_char(*ch))
+        .last()
+        .map(|(i, _)| i)
+        .unwrap_or(offset);
+    let end = content[offset..]
+        .char_indices()
+        .find(|(_, ch)| !is_ident_char(*ch))
+        .map(|(i, _)| offset + i)
+        .unwrap_or(content.len());
+    let word = &content[start..end];
+    if word.is_empty() || word.starts_with(|c: char| c.is_ascii_digit()) {
+        None
+    } else {
+        Some(word.to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn positions_count_utf16_units() {
+        let text = "a\nxé𝄞y\n";
+        assert_eq!(position_of(text.as_bytes(), 0), Position::new(0, 0));
+        assert_eq!(position_of(text.as_bytes(), 2), Position::new(1, 0));
+        // 'y' follows x (1 byte), é (2 bytes), 𝄞 (4 bytes, 2 UTF-16 units).
+        let y = text.find('y').unwrap();
+        assert_eq!(position_of(text.as_bytes(), y), Position::new(1, 4));
+    }
+
+    #[test]
+    fn word_under_cursor() {
+        let text = "int x = vfs_read(f);\n";
+        assert_eq!(
+            word_at(text, Position::new(0, 10)).as_deref(),
+            Some("vfs_read")
+        );
+        // Cursor right after the identifier still selects it.
+        assert_eq!(
+            word_at(text, Position::new(0, 16)).as_deref(),
+            Some("vfs_read")
+        );
+        assert_eq!(word_at(text, Position::new(0, 7)), None);
+        assert_eq!(word_at(text, Position::new(5, 0)), None);
+    }
+}
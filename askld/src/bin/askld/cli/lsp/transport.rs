@@ -0,0 +1,80 @@
+//! LSP base protocol: JSON-RPC messages framed by a `Content-Length` header.
+
+use anyhow::{anyhow, Result};
+use serde_json::Value;
+use tokio::io::{AsyncBufRead, AsyncBufReadExt, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+
+/// Read one message. Returns `None` on a clean EOF between messages.
+pub(super) async fn read_message<R: AsyncBufRead + Unpin>(reader: &mut R) -> Result<Option<Value>> {
+    let mut content_length: Option<usize> = None;
+    let mut line = String::new();
+    loop {
+        line.clear();
+        if reader.read_line(&mut line).await? == 0 {
+            return match content_length {
+                None => Ok(None),
+                Some(_) => Err(anyhow!("unexpected EOF inside message header")),
+            };
+        }
+        let header = line.trim_end_matches(['\r', '\n']);
+        if header.is_empty() {
+            break;
+        }
+        if let Some((name, value)) = header.split_once(':') {
+            if name.eq_ignore_ascii_case("Content-Length") {
+                content_length = Some(
+                    value
+                        .trim()
+                        .parse()
+                        .map_err(|_| anyhow!("invalid Content-Length: {}", value.trim()))?,
+                );
+            }
+        }
+    }
+
+    let length = content_length.ok_or_else(|| anyhow!("message without Content-Length"))?;
+    let mut body = vec![0u8; length];
+    reader.read_exact(&mut body).await?;
+    Ok(Some(serde_json::from_slice(&body)?))
+}
+
+pub(super) async fn write_message<W: AsyncWrite + Unpin>(
+    writer: &mut W,
+    message: &Value,
+) -> Result<()> {
+    let body = serde_json::to_vec(message)?;
+    writer
+        .write_all(format!("Content-Length: {}\r\n\r\n", body.len()).as_bytes())
+        .await?;
+    writer.write_all(&body).await?;
+    writer.flush().await?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[tokio::test]
+    async fn round_trip() {
+        let mut buf = Vec::new();
+        write_message(&mut buf, &json!({ "id": 1, "method": "ä" }))
+            .await
+            .unwrap();
+        write_message(&mut buf, &json!({ "id": 2 })).await.unwrap();
+
+        let mut reader = tokio::io::BufReader::new(buf.as_slice());
+        let first = read_message(&mut reader).await.unwrap().unwrap();
+        assert_eq!(first["method"], "ä");
+        let second = read_message(&mut reader).await.unwrap().unwrap();
+        assert_eq!(second["id"], 2);
+        assert!(read_message(&mut reader).await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn missing_content_length_is_an_error() {
+        let mut reader = tokio::io::BufReader::new(&b"Content-Type: x\r\n\r\n{}"[..]);
+        assert!(read_message(&mut reader).await.is_err());
+    }
+}
@@ -0,0 +1,330 @@
+//! `askld mcp` — the MCP server over stdio, for agent hosts that launch MCP
+//! servers as subprocesses.
+//!
+//! Messages are newline-delimited JSON-RPC, as the MCP stdio transport
+//! specifies; stdout carries nothing else (logs go to stderr). With `--url`
+//! every message is forwarded to a running server's `POST /mcp`; with
+//! `--database-url` the server's own dispatch ([`handle_rpc_value`]) answers
+//! in-process, so both modes expose exactly the tools, resources and prompts
+//! of the HTTP endpoint. Messages are answered one at a time, so progress
+//! notifications and `notifications/cancelled` only take effect over HTTP.
+//!
+//! The process is one MCP session: in-process, every message runs in a
+//! private session; proxied, the session the server opened for `initialize`
+//! is carried on later messages, and reopened by replaying that `initialize`
+//! should it expire.
+
+use std::future::Future;
+use std::sync::Mutex;
+
+use actix_web::web;
+use anyhow::{anyhow, Context, Result};
+use askld::cfg::ControlFlowGraph;
+use askld::index_store::IndexStore;
+use askld::macros::MacroLibrary;
+use askld::query_store::QueryStore;
+use diesel_async::pooled_connection::bb8::Pool as AsyncPool;
+use diesel_async::pooled_connection::AsyncDieselConnectionManager;
+use index::db_diesel::{Index, SqlResultCache};
+use serde_json::Value;
+use tokio::io::{AsyncBufRead, AsyncBufReadExt, AsyncWrite, AsyncWriteExt};
+
+use super::index::{build_client, normalize_base_url};
+use crate::api::mcp::{
+    handle_rpc_value, parse_error_reply, transport_error_replies, McpSession, McpState, Outbound,
+};
+use crate::api::types::AsklData;
+use crate::args::McpArgs;
+use crate::server::build_index_pool;
+
+/// Where messages are answered.
+enum Backend {
+    Proxy {
+        http: reqwest::Client,
+        endpoint: String,
+        token: Option<String>,
+        session: Mutex<ProxySession>,
+    },
+    Direct {
+        askl_data: web::Data<AsklData>,
+        index_store: web::Data<IndexStore>,
+        mcp: McpState,
+        session: McpSession,
+    },
+}
+
+/// The server-side session a proxy carries for its client.
+#[derive(Default)]
+struct ProxySession {
+    /// The client's `initialize`, replayed to reopen an expired session.
+    initialize: Option<Value>,
+    id: Option<String>,
+}
+
+impl Backend {
+    /// The reply to one message or batch, if it needs one.
+    async fn handle(&self, message: Value) -> Option<Value> {
+        match self {
+            Backend::Direct {
+                askl_data,
+                index_store,
+                mcp,
+                session,
+            } => handle_rpc_value(
+                askl_data,
+                index_store,
+                mcp,
+                None,
+                message,
+                &Outbound::in_session(session.clone()),
+            )
+            .await
+            .unwrap_or_else(Some),
+            Backend::Proxy {
+                http,
+                endpoint,
+                token,
+                session,
+            } => {
+                match forward_in_session(http, endpoint, token.as_deref(), session, &message).await
+                {
+                    Ok(reply) => reply,
+                    Err(err) => transport_error_replies(&message, &format!("{:#}", err)),
+                }
+            }
+        }
+    }
+}
+
+/// [`forward`] `message` in the proxy's session. An `initialize` opens a new
+/// one; a message the server answers with an expired session reopens it and
+/// is sent once more.
+async fn forward_in_session(
+    http: &reqwest::Client,
+    endpoint: &str,
+    token: Option<&str>,
+    session: &Mutex<ProxySession>,
+    message: &Value,
+) -> Result<Option<Value>> {
+    if message.get("method").and_then(Value::as_str) == Some("initialize") {
+        let (reply, id) = forward(http, endpoint, token, None, message).await?;
+        *session.lock().unwrap() = ProxySession {
+            initialize: Some(message.clone()),
+            id,
+        };
+        return Ok(reply);
+    }
+
+    let id = session.lock().unwrap().id.clone();
+    match forward(http, endpoint, token, id.as_deref(), message).await {
+        Err(err) if id.is_some() && err.is::<SessionExpired>() => {}
+        result => return result.map(|(reply, _)| reply),
+    }
+    let initialize = session.lock().unwrap().initialize.clone();
+    let reopened = match initialize {
+        Some(initialize) => forward(http, endpoint, token, None, &initialize).await?.1,
+        None => None,
+    };
+    log::info!("MCP session expired; continuing in a new one");
+    session.lock().unwrap().id = reopened.clone();
+    Ok(forward(http, endpoint, token, reopened.as_deref(), message)
+        .await?
+        .0)
+}
+
+/// The server no longer knows the session a message was sent in.
+#[derive(Debug)]
+struct SessionExpired;
+
+impl std::fmt::Display for SessionExpired {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("MCP session expired")
+    }
+}
+
+impl std::error::Error for SessionExpired {}
+
+/// POST `message` to the server's MCP endpoint in session `session_id`,
+/// returning the reply and the session id the server sent back. `202
+/// Accepted` (all notifications) has no body to relay; a 400 still carries a
+/// JSON-RPC error; a 404 in a session fails with [`SessionExpired`].
+async fn forward(
+    http: &reqwest::Client,
+    endpoint: &str,
+    token: Option<&str>,
+    session_id: Option<&str>,
+    message: &Value,
+) -> Result<(Option<Value>, Option<String>)> {
+    let mut request = http.post(endpoint).json(message);
+    if let Some(token) = token {
+        request = request.bearer_auth(token);
+    }
+    if let Some(session_id) = session_id {
+        request = request.header("Mcp-Session-Id", session_id);
+    }
+    let response = request
+        .send()
+        .await
+        .with_context(|| format!("Failed to reach {}", endpoint))?;
+    let status = response.status();
+    let opened = response
+        .headers()
+        .get("Mcp-Session-Id")
+        .and_then(|value| value.to_str().ok())
+        .map(str::to_string);
+    if status == reqwest::StatusCode::ACCEPTED {
+        return Ok((None, opened));
+    }
+    if status == reqwest::StatusCode::NOT_FOUND && session_id.is_some() {
+        return Err(SessionExpired.into());
+    }
+    if !status.is_success() && status != reqwest::StatusCode::BAD_REQUEST {
+        let body = response.text().await.unwrap_or_default();
+        return Err(anyhow!("{} answered {}: {}", endpoint, status, body.trim()));
+    }
+    let reply = response
+        .json()
+        .await
+        .with_context(|| format!("Invalid reply from {}", endpoint))?;
+    Ok((Some(reply), opened))
+}
+
+fn load_macros(path: Option<&str>) -> Result<MacroLibrary> {
+    let Some(path) = path else {
+        return Ok(MacroLibrary::new());
+    };
+    let source = std::fs::read_to_string(path)
+        .with_context(|| format!("Failed to read macro library {}", path))?;
+    MacroLibrary::parse(&source)
+        .map_err(|err| anyhow!("Failed to parse macro library {}:\n{}", path, err))
+}
+
+/// The query-side state `askld serve` builds, minus auth and background GC.
+/// The SQL result cache stays disabled: uploads go through some server whose
+/// mutations could not clear this process's cache.
+async fn connect_direct(args: &McpArgs, database_url: &str) -> Result<Backend> {
+    let macros = load_macros(args.macros.as_deref())?;
+    let store_config = AsyncDieselConnectionManager::new_with_config(
+        database_url,
+        index::db_diesel::eph_pool_manager_config(),
+    );
+    let store_pool = AsyncPool::builder()
+        .test_on_check_out(false)
+        .build(store_config)
+        .await
+        .context("Failed to build database pool")?;
+    let index_pool = build_index_pool(database_url, args.query_timeout)
+        .await
+        .context("Failed to build index database pool")?;
+
+    let sql_cache = SqlResultCache::new(0);
+    let index_query = Index::from_pool_with_cache(index_pool, sql_cache.clone());
+    index_query
+        .validate_canary()
+        .await
+        .context("Database is not an askl index (run `askld serve` once to migrate it)")?;
+    Ok(Backend::Direct {
+        askl_data: web::Data::new(AsklData {
+            cfg: ControlFlowGraph::from_symbols(index_query),
+            query_timeout: std::time::Duration::from_secs(args.query_timeout),
+            max_result_symbols: args.max_result_symbols,
+            macros,
+            queries: QueryStore::from_pool(store_pool.clone(), false),
+        }),
+        index_store: web::Data::new(IndexStore::from_pool_with_cache(store_pool, sql_cache)),
+        mcp: McpState::default(),
+        session: McpSession::private(),
+    })
+}
+
+/// Answer newline-delimited messages from `reader` until EOF, one reply line
+/// per message that needs one. Blank lines are ignored.
+async fn serve_lines<R, W, F, Fut>(reader: R, writer: &mut W, handle: F) -> Result<()>
+where
+    R: AsyncBufRead + Unpin,
+    W: AsyncWrite + Unpin,
+    F: Fn(Value) -> Fut,
+    Fut: Future<Output = Option<Value>>,
+{
+    let mut lines = reader.lines();
+    while let Some(line) = lines.next_line().await? {
+        if line.trim().is_empty() {
+            continue;
+        }
+        let reply = match serde_json::from_str(&line) {
+            Ok(message) => handle(message).await,
+            Err(err) => Some(parse_error_reply(&err)),
+        };
+        if let Some(reply) = reply {
+            let mut encoded = serde_json::to_vec(&reply)?;
+            encoded.push(b'\n');
+            writer.write_all(&encoded).await?;
+            writer.flush().await?;
+        }
+    }
+    Ok(())
+}
+
+pub async fn run_mcp(args: McpArgs) -> Result<()> {
+    let backend = match (&args.url, &args.database_url) {
+        (Some(url), _) => Backend::Proxy {
+            http: build_client(args.timeout),
+            endpoint: format!("{}/mcp", normalize_base_url(url)),
+            token: args
+                .token
+                .clone()
+                .or_else(|| std::env::var("ASKL_TOKEN").ok())
+                .filter(|token| !token.trim().is_empty()),
+            session: Mutex::default(),
+        },
+        (None, Some(database_url)) => connect_direct(&args, database_url).await?,
+        (None, None) => return Err(anyhow!("pass --url or --database-url")),
+    };
+
+    let reader = tokio::io::BufReader::new(tokio::io::stdin());
+    let mut writer = tokio::io::stdout();
+    serve_lines(reader, &mut writer, |message| backend.handle(message)).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[tokio::test]
+    async fn replies_line_per_message_and_skips_notifications() {
+        let input = b"{\"id\":1,\"method\":\"ping\"}\n\n{\"method\":\"note\"}\nnot json\n";
+        let mut output = Vec::new();
+        serve_lines(&input[..], &mut output, |message| async move {
+            message
+                .get("id")
+                .map(|id| json!({ "id": id, "result": {} }))
+        })
+        .await
+        .unwrap();
+
+        let lines: Vec<Value> = output
+            .split(|b| *b == b'\n')
+            .filter(|line| !line.is_empty())
+            .map(|line| serde_json::from_slice(line).unwrap())
+            .collect();
+        assert_eq!(lines.len(), 2);
+        assert_eq!(lines[0], json!({ "id": 1, "result": {} }));
+        assert_eq!(lines[1]["error"]["code"], -32700);
+    }
+
+    #[test]
+    fn transport_errors_answer_only_requests() {
+        let batch = json!([
+            { "jsonrpc": "2.0", "id": 1, "method": "ping" },
+            { "jsonrpc": "2.0", "method": "notifications/initialized" },
+        ]);
+        let replies = transport_error_replies(&batch, "down").unwrap();
+        assert_eq!(replies.as_array().unwrap().len(), 1);
+        assert_eq!(replies[0]["id"], 1);
+        assert_eq!(replies[0]["error"]["message"], "down");
+
+        let notification = json!({ "jsonrpc": "2.0", "method": "notifications/initialized" });
+        assert!(transport_error_replies(&notification, "down").is_none());
+    }
+}
@@ -1,5 +1,10 @@
 pub mod auth;
 pub mod index;
+mod inspect;
+pub mod lsp;
+mod mcp;
 
 pub use auth::run_auth_command;
 pub use index::run_index_command;
+pub use lsp::run_lsp;
+pub use mcp::run_mcp;
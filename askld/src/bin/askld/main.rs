@@ -36,6 +36,21 @@ async fn main() -> std::io::Result<()> {
             }
             Ok(())
         }
+        Command::Lsp(lsp_args) => {
+            if let Err(err) = cli::run_lsp(lsp_args).await {
+                print_error_chain("LSP server failed", &err);
+                std::process::exit(1);
+            }
+            Ok(())
+        }
+        Command::Mcp(mcp_args) => {
+            env_logger::init();
+            if let Err(err) = cli::run_mcp(mcp_args).await {
+                print_error_chain("MCP server failed", &err);
+                std::process::exit(1);
+            }
+            Ok(())
+        }
         Command::Serve(serve_args) => server::run(serve_args).await,
     }
 }
@@ -3,6 +3,8 @@ use actix_web::{dev::Service, web, App, HttpServer};
 use askld::auth::{self, AuthStore};
 use askld::cfg::ControlFlowGraph;
 use askld::index_store::IndexStore;
+use askld::macros::MacroLibrary;
+use askld::query_store::QueryStore;
 use diesel::pg::PgConnection;
 use diesel_async::pooled_connection::bb8::Pool as AsyncPool;
 use diesel_async::pooled_connection::AsyncDieselConnectionManager;
@@ -10,7 +12,7 @@ use diesel_async::{AsyncConnection, AsyncPgConnection};
 use diesel_migrations::MigrationHarness;
 use futures::FutureExt;
 
-use index::db_diesel::Index;
+use index::db_diesel::{Index, ReplicaSet};
 use log::{info, warn};
 use tracing_chrome::ChromeLayerBuilder;
 use tracing_subscriber::layer::SubscriberExt;
@@ -47,6 +49,46 @@ fn build_cors() -> Cors {
     }
 }
 
+/// Async pool for Index queries (with statement_timeout).  Starts from the
+/// shared eph config but overrides custom_setup to ALSO set the per-query
+/// statement_timeout; the idle-in-transaction timeout must be re-applied here
+/// because a replaced custom_setup does not compose.
+pub(crate) async fn build_index_pool(
+    database_url: &str,
+    query_timeout_secs: u64,
+) -> Result<AsyncPool<AsyncPgConnection>, diesel_async::pooled_connection::PoolError> {
+    let query_timeout_ms = query_timeout_secs * 1000;
+    let mut index_pool_config = index::db_diesel::eph_pool_manager_config();
+    index_pool_config.custom_setup = Box::new(move |url| {
+        async move {
+            let mut conn = AsyncPgConnection::establish(url).await?;
+            diesel_async::RunQueryDsl::<AsyncPgConnection>::execute(
+                diesel::sql_query(&format!("SET statement_timeout = {}", query_timeout_ms)),
+                &mut conn,
+            )
+            .await
+            .map_err(diesel::ConnectionError::CouldntSetupConfiguration)?;
+            diesel_async::RunQueryDsl::<AsyncPgConnection>::execute(
+                diesel::sql_query(&format!(
+                    "SET idle_in_transaction_session_timeout = '{}'",
+                    index::db_diesel::EPH_POOL_IDLE_IN_TXN_TIMEOUT
+                )),
+                &mut conn,
+            )
+            .await
+            .map_err(diesel::ConnectionError::CouldntSetupConfiguration)?;
+            Ok(conn)
+        }
+        .boxed()
+    });
+    let index_config =
+        AsyncDieselConnectionManager::new_with_config(database_url, index_pool_config);
+    AsyncPool::builder()
+        .test_on_check_out(false)
+        .build(index_config)
+        .await
+}
+
 pub async fn run(serve_args: ServeArgs) -> std::io::Result<()> {
     let _guard = if let Some(trace_dir) = &serve_args.trace {
         use chrono::prelude::*;
@@ -84,6 +126,18 @@ pub async fn run(serve_args: ServeArgs) -> std::io::Result<()> {
         None
     };
 
+    // Load the macro library before touching the DB so a typo fails fast.
+    let macros = match &serve_args.macros {
+        Some(path) => {
+            let source = std::fs::read_to_string(path).expect("Failed to read macro library");
+            let macros = MacroLibrary::parse(&source)
+                .unwrap_or_else(|e| panic!("Failed to parse macro library {}:\n{}", path, e));
+            info!("Loaded {} macros from {}", macros.len(), path);
+            macros
+        }
+        None => MacroLibrary::new(),
+    };
+
     // Run migrations with a sync connection
     {
         use diesel::Connection;
@@ -108,40 +162,8 @@ pub async fn run(serve_args: ServeArgs) -> std::io::Result<()> {
         .await
         .expect("Failed to build async database pool");
 
-    // Async pool for Index queries (with statement_timeout).  Starts from
-    // the shared eph config but overrides custom_setup to ALSO set the
-    // per-query statement_timeout; the idle-in-transaction timeout must be
-    // re-applied here because a replaced custom_setup does not compose.
     let query_timeout_secs = serve_args.query_timeout;
-    let query_timeout_ms = query_timeout_secs * 1000;
-    let mut index_pool_config = index::db_diesel::eph_pool_manager_config();
-    index_pool_config.custom_setup = Box::new(move |url| {
-        async move {
-            let mut conn = AsyncPgConnection::establish(url).await?;
-            diesel_async::RunQueryDsl::<AsyncPgConnection>::execute(
-                diesel::sql_query(&format!("SET statement_timeout = {}", query_timeout_ms)),
-                &mut conn,
-            )
-            .await
-            .map_err(diesel::ConnectionError::CouldntSetupConfiguration)?;
-            diesel_async::RunQueryDsl::<AsyncPgConnection>::execute(
-                diesel::sql_query(&format!(
-                    "SET idle_in_transaction_session_timeout = '{}'",
-                    index::db_diesel::EPH_POOL_IDLE_IN_TXN_TIMEOUT
-                )),
-                &mut conn,
-            )
-            .await
-            .map_err(diesel::ConnectionError::CouldntSetupConfiguration)?;
-            Ok(conn)
-        }
-        .boxed()
-    });
-    let index_config =
-        AsyncDieselConnectionManager::new_with_config(&serve_args.database_url, index_pool_config);
-    let index_pool: AsyncPool<AsyncPgConnection> = AsyncPool::builder()
-        .test_on_check_out(false)
-        .build(index_config)
+    let index_pool = build_index_pool(&serve_args.database_url, query_timeout_secs)
         .await
         .expect("Failed to build index database pool");
 
@@ -155,7 +177,28 @@ pub async fn run(serve_args: ServeArgs) -> std::io::Result<()> {
     let index_store = IndexStore::from_pool_with_cache(async_pool.clone(), sql_cache.clone());
     let index_store = web::Data::new(index_store);
 
-    let index_query = Index::from_pool_with_cache(index_pool, sql_cache.clone());
+    let mut index_query = Index::from_pool_with_cache(index_pool, sql_cache.clone());
+    let replicas = if serve_args.replica_urls.is_empty() {
+        None
+    } else {
+        let mut pools = Vec::with_capacity(serve_args.replica_urls.len());
+        for url in &serve_args.replica_urls {
+            // Connections are opened lazily, so an unreachable replica only
+            // shows up as unhealthy rather than failing startup.
+            let pool = build_index_pool(url, query_timeout_secs)
+                .await
+                .expect("Failed to build replica database pool");
+            pools.push((url.clone(), pool));
+        }
+        let replicas = std::sync::Arc::new(ReplicaSet::new(
+            pools,
+            std::time::Duration::from_secs(serve_args.replica_max_lag),
+        ));
+        let healthy = replicas.check_health().await;
+        info!("{} of {} read replicas healthy", healthy, replicas.len());
+        index_query = index_query.with_replicas(replicas.clone());
+        Some(replicas)
+    };
     index_query
         .validate_canary()
         .await
@@ -164,8 +207,14 @@ pub async fn run(serve_args: ServeArgs) -> std::io::Result<()> {
         cfg: ControlFlowGraph::from_symbols(index_query),
         query_timeout: std::time::Duration::from_secs(query_timeout_secs),
         max_result_symbols: serve_args.max_result_symbols,
+        macros,
+        queries: QueryStore::from_pool(async_pool.clone(), serve_args.query_cache),
     });
 
+    // One for all workers: a cancel may arrive on another worker than the
+    // request it names, and a session's requests on any worker.
+    let mcp_state = web::Data::new(api::mcp::McpState::default());
+
     // Background GC: periodically purge ephemeral layers idle past the TTL.
     //
     // Interval tuned so the table is checked frequently enough to bound peak
@@ -174,7 +223,14 @@ pub async fn run(serve_args: ServeArgs) -> std::io::Result<()> {
     // without the layer being evicted between requests.
     const EPH_GC_INTERVAL_SECS: u64 = 600; // 10 min: how often we scan
     const EPH_GC_TTL_SECS: u64 = 3600; // 1 h: minimum idle age to delete
+
+    // Query results outlive deploys (that is their point) but not a week
+    // without a hit; history is kept for a month.
+    const QUERY_CACHE_TTL_SECS: u64 = 7 * 24 * 3600;
+    const QUERY_HISTORY_TTL_SECS: u64 = 30 * 24 * 3600;
     let gc_index = askl_data.cfg.index.clone();
+    let gc_queries = askl_data.queries.clone();
+    let gc_mcp = mcp_state.clone();
     tokio::spawn(async move {
         let interval = std::time::Duration::from_secs(EPH_GC_INTERVAL_SECS);
         let ttl = std::time::Duration::from_secs(EPH_GC_TTL_SECS);
@@ -191,6 +247,12 @@ pub async fn run(serve_args: ServeArgs) -> std::io::Result<()> {
                 }
                 _ = tokio::time::sleep(interval) => {}
             }
+            // Sessions first: a session idle past the TTL no longer keeps
+            // its layers fresh, so it must not outlive them.
+            let expired = gc_mcp.expire_sessions(ttl);
+            if expired > 0 {
+                info!("GC: expired {} idle MCP sessions", expired);
+            }
             match gc_index.purge_old_eph_layers(ttl).await {
                 Ok(n) => {
                     consecutive_failures = 0;
@@ -213,9 +275,37 @@ pub async fn run(serve_args: ServeArgs) -> std::io::Result<()> {
                     }
                 }
             }
+            match gc_queries
+                .prune(
+                    std::time::Duration::from_secs(QUERY_CACHE_TTL_SECS),
+                    std::time::Duration::from_secs(QUERY_HISTORY_TTL_SECS),
+                )
+                .await
+            {
+                Ok((results, history)) => info!(
+                    "GC: pruned {} cached query results and {} history entries",
+                    results, history
+                ),
+                Err(e) => warn!("GC: query cache/history prune failed: {:?}", e),
+            }
         }
     });
 
+    // Replica health: a replica that falls behind or goes away stops getting
+    // reads until it catches up again.
+    const REPLICA_HEALTH_INTERVAL_SECS: u64 = 10;
+    if let Some(replicas) = replicas {
+        tokio::spawn(async move {
+            let mut interval =
+                tokio::time::interval(std::time::Duration::from_secs(REPLICA_HEALTH_INTERVAL_SECS));
+            interval.tick().await;
+            loop {
+                interval.tick().await;
+                replicas.check_health().await;
+            }
+        });
+    }
+
     info!(
         "Starting server on {}:{}...",
         serve_args.host, serve_args.port
@@ -233,6 +323,7 @@ pub async fn run(serve_args: ServeArgs) -> std::io::Result<()> {
             .app_data(askl_data.clone())
             .app_data(auth_store.clone())
             .app_data(index_store.clone())
+            .app_data(mcp_state.clone())
             .configure(api::configure)
     })
     .bind((serve_args.host, serve_args.port))?
@@ -2,7 +2,7 @@ use std::{collections::HashSet, iter::Iterator};
 
 use index::db_diesel::{Index, SelectionNode};
 use index::symbols::Occurrence;
-use index::symbols::{SymbolId, SymbolInstanceId};
+use index::symbols::{RefKind, RelationKind, SymbolId, SymbolInstanceId};
 
 pub struct ControlFlowGraph {
     pub index: Index,
@@ -37,8 +37,16 @@ pub struct SymbolNodeId {
     pub instance_id: SymbolInstanceId,
 }
 
+/// (from, to, where the reference is, what kind of reference it is)
+pub type RefEdge = (
+    SymbolNodeId,
+    SymbolNodeId,
+    Option<Occurrence>,
+    Option<RefKind>,
+);
+
 #[derive(Debug, Clone)]
-pub struct EdgeList(pub HashSet<(SymbolNodeId, SymbolNodeId, Option<Occurrence>)>);
+pub struct EdgeList(pub HashSet<RefEdge>);
 
 impl EdgeList {
     pub fn new() -> Self {
@@ -50,13 +58,14 @@ impl EdgeList {
         from: SymbolNodeId,
         to: SymbolNodeId,
         occurrence: Option<Occurrence>,
+        kind: Option<RefKind>,
     ) {
-        self.0.insert((from, to, occurrence));
+        self.0.insert((from, to, occurrence, kind));
     }
 
-    pub fn as_vec(&self) -> Vec<(SymbolNodeId, SymbolNodeId, Option<Occurrence>)> {
+    pub fn as_vec(&self) -> Vec<RefEdge> {
         let mut res: Vec<_> = self.0.clone().into_iter().collect();
-        res.sort_by(|(from_a, to_a, _), (from_b, to_b, _)| {
+        res.sort_by(|(from_a, to_a, _, _), (from_b, to_b, _, _)| {
             from_a
                 .instance_id
                 .cmp(&from_b.instance_id)
@@ -100,6 +109,39 @@ impl HasEdgeList {
     }
 }
 
+/// Type-hierarchy edge between two selected symbols: `from` extends,
+/// implements or embeds `to`.  Symbol-level, like the relation itself.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct RelationEdge {
+    pub from: SymbolId,
+    pub to: SymbolId,
+    pub kind: RelationKind,
+}
+
+#[derive(Default)]
+pub struct RelationEdgeList(pub HashSet<RelationEdge>);
+
+impl RelationEdgeList {
+    pub fn new() -> Self {
+        Self(HashSet::new())
+    }
+
+    pub fn add(&mut self, edge: RelationEdge) {
+        self.0.insert(edge);
+    }
+
+    pub fn as_vec(&self) -> Vec<RelationEdge> {
+        let mut res: Vec<_> = self.0.iter().cloned().collect();
+        res.sort_by(|a, b| {
+            a.from
+                .cmp(&b.from)
+                .then_with(|| a.to.cmp(&b.to))
+                .then_with(|| a.kind.cmp(&b.kind))
+        });
+        res
+    }
+}
+
 impl ControlFlowGraph {
     pub fn from_symbols(index_diesel: Index) -> Self {
         Self {
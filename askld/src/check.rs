@@ -0,0 +1,342 @@
+//! Static query checks: what a parsed query means, without running it.
+//!
+//! [`check`] walks the statement tree the parser built and reports, for each
+//! statement, the verbs written in it, the ones it inherited from enclosing
+//! scopes and the ones the parser added, plus how its symbols relate to its
+//! parent's.  It also renders the query in a normalized layout and collects
+//! the warnings that need no index.  Nothing here touches the database, so a
+//! check is cheap enough to run before every query.
+
+use std::collections::HashSet;
+use std::rc::Rc;
+
+use index::symbols::RefKind;
+
+use crate::diagnostic::Diagnostic;
+use crate::hierarchy::Hierarchy;
+use crate::parser::Rule;
+use crate::statement::Statement;
+
+/// The result of checking one query.
+#[derive(Debug)]
+pub struct QueryCheck {
+    /// The query re-indented one statement per line, macro calls expanded.
+    pub normalized: String,
+    /// Statements in pre-order; a statement's id is its position here (the
+    /// same ids `explain` uses).
+    pub statements: Vec<CheckedStatement>,
+    pub warnings: Vec<Diagnostic>,
+}
+
+#[derive(Debug)]
+pub struct CheckedStatement {
+    pub id: usize,
+    /// `None` for top-level statements.
+    pub parent: Option<usize>,
+    pub depth: usize,
+    /// The statement's own verbs as written; empty for a bare scope.
+    pub text: String,
+    /// How this statement's symbols relate to its parent's (`refs`, `has`,
+    /// …); empty for top-level statements.
+    pub relation: Vec<&'static str>,
+    /// Reference kinds `refs` is restricted to; empty when any qualifies.
+    pub ref_kinds: Vec<&'static str>,
+    pub unnest: bool,
+    /// Weak statements only scope their neighbours (see `explain`).
+    pub weak: bool,
+    /// Verbs carried over from the enclosing statement, as written there.
+    pub inherited: Vec<String>,
+    /// What the verbs the parser added do.
+    pub implicit: Vec<String>,
+}
+
+impl CheckedStatement {
+    /// The statement's text for one-line summaries: its own verbs, or `{…}`
+    /// for a bare scope.
+    pub fn label(&self) -> &str {
+        if self.text.is_empty() {
+            "{…}"
+        } else {
+            &self.text
+        }
+    }
+}
+
+/// Check the query `root` (as returned by [`crate::parser::parse`]).  Fails
+/// where execution would fail before touching the index: a `#label` that no
+/// statement defines and that is not among `imported_labels`, the labels an
+/// earlier execution left behind (see
+/// [`crate::execution_context::ExecutionContext::labels`]).
+pub fn check(
+    root: &Rc<Statement>,
+    imported_labels: &HashSet<String>,
+) -> Result<QueryCheck, pest::error::Error<Rule>> {
+    let statements = root.plan(imported_labels)?;
+    let id_of =
+        |statement: &Rc<Statement>| statements.iter().position(|s| Rc::ptr_eq(s, statement));
+
+    let mut checked: Vec<CheckedStatement> = Vec::with_capacity(statements.len());
+    let mut warnings = Vec::new();
+    for (id, statement) in statements.iter().enumerate() {
+        let parent = statement.parent().and_then(|p| p.upgrade());
+        let parent_id = parent.as_ref().and_then(id_of);
+        let depth = parent_id.map(|p| checked[p].depth + 1).unwrap_or(0);
+
+        let command = statement.command();
+        let parent_spans: Vec<pest::Span<'_>> = parent
+            .as_ref()
+            .map(|p| p.command().verbs().iter().map(|v| v.span()).collect())
+            .unwrap_or_default();
+
+        let mut inherited = Vec::new();
+        let mut implicit = Vec::new();
+        for verb in command.verbs() {
+            let span = verb.span();
+            if let Some(text) = verb.implicit_text() {
+                implicit.push(text);
+            } else if parent_spans.contains(&span) {
+                inherited.push(squash(span.as_str()));
+            }
+        }
+
+        let (relation, ref_kinds) = if parent_id.is_some() {
+            let rel = statement.get_relationship_type();
+            let kinds = rel
+                .ref_kinds()
+                .unwrap_or_default()
+                .into_iter()
+                .map(|k| RefKind::from(k).name())
+                .collect();
+            (rel.names(), kinds)
+        } else {
+            (Vec::new(), Vec::new())
+        };
+
+        warnings.extend(command.name_constraint_warnings());
+        checked.push(CheckedStatement {
+            id,
+            parent: parent_id,
+            depth,
+            text: own_text(statement),
+            relation,
+            ref_kinds,
+            unnest: statement.is_unnest(),
+            weak: statement.get_state().weak,
+            inherited,
+            implicit,
+        });
+    }
+
+    for statement in &statements {
+        if statement
+            .parent()
+            .and_then(|p| p.upgrade())
+            .and_then(|p| id_of(&p))
+            .is_some()
+        {
+            continue;
+        }
+        let span = statement.command().span().clone();
+        if is_unconstrained(statement) {
+            warnings.push(Diagnostic::note(
+                span,
+                "Nothing in this statement names a symbol, so it matches every symbol its \
+                 filters allow. Add a name or `search(…)`, or nest it under one.",
+            ));
+        } else if checked[id_of(statement).unwrap()].text.is_empty()
+            && statement.children().next().is_some()
+        {
+            warnings.push(Diagnostic::note(
+                span,
+                "A bare `{ … }` keeps callers and containers of every type; `func { … }` \
+                 keeps only functions.",
+            ));
+        }
+    }
+    warnings.sort_by_key(|d| d.start());
+
+    let mut normalized = String::new();
+    for statement in root.children() {
+        render_statement(&statement, &statements, &checked, &mut normalized);
+    }
+
+    Ok(QueryCheck {
+        normalized,
+        statements: checked,
+        warnings,
+    })
+}
+
+/// Whether neither `statement` nor anything nested in it constrains the
+/// selection.
+fn is_unconstrained(statement: &Rc<Statement>) -> bool {
+    statement.command().is_non_constraining() && statement.children().all(|c| is_unconstrained(&c))
+}
+
+fn render_statement(
+    statement: &Rc<Statement>,
+    statements: &[Rc<Statement>],
+    checked: &[CheckedStatement],
+    out: &mut String,
+) {
+    let Some(id) = statements.iter().position(|s| Rc::ptr_eq(s, statement)) else {
+        return;
+    };
+    let indent = "  ".repeat(checked[id].depth);
+    let text = &checked[id].text;
+    let children: Vec<Rc<Statement>> = statement.children().collect();
+    let mut line = format!("{}{}", indent, text);
+
+    if children.is_empty() {
+        line.push('\n');
+        out.push_str(&line);
+        return;
+    }
+    if !text.is_empty() {
+        line.push(' ');
+    }
+    // An empty scope (`"x" { }`) parses to a single statement with no verbs.
+    let empty_scope = children.iter().all(|child| {
+        child.children().next().is_none()
+            && statements
+                .iter()
+                .position(|s| Rc::ptr_eq(s, child))
+                .is_some_and(|c| checked[c].text.is_empty())
+    });
+    if empty_scope {
+        line.push_str("{ }\n");
+        out.push_str(&line);
+        return;
+    }
+    line.push_str("{\n");
+    out.push_str(&line);
+    for child in &children {
+        render_statement(child, statements, checked, out);
+    }
+    out.push_str(&format!("{}}}\n", indent));
+}
+
+/// The verbs of `statement` as written: its source up to its scope.
+/// Modifiers such as `has` only change the parser context, so the text is
+/// the only place they survive.
+pub(crate) fn own_text(statement: &Rc<Statement>) -> String {
+    let text = statement.command().span().as_str();
+    if statement.children().next().is_none() {
+        // The placeholder statement of an empty scope spans that scope.
+        return if text.starts_with('{') {
+            String::new()
+        } else {
+            squash(text)
+        };
+    }
+    let squashed = squash(text);
+    let mut in_string = false;
+    for (i, c) in squashed.char_indices() {
+        match c {
+            '"' => in_string = !in_string,
+            '{' if !in_string => return squashed[..i].trim_end().to_string(),
+            _ => {}
+        }
+    }
+    squashed
+}
+
+/// Drop comments and collapse runs of whitespace outside string literals to
+/// one space.  Strings have no escapes, so a `"` always toggles.
+fn squash(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut in_string = false;
+    let mut pending_space = false;
+    let mut rest = text;
+    while let Some(c) = rest.chars().next() {
+        if !in_string {
+            if rest.starts_with("//") {
+                rest = rest.find('\n').map_or("", |end| &rest[end..]);
+                continue;
+            }
+            if rest.starts_with("/*") {
+                rest = rest[2..].find("*/").map_or("", |end| &rest[end + 4..]);
+                pending_space = true;
+                continue;
+            }
+            if c.is_whitespace() {
+                pending_space = true;
+                rest = &rest[c.len_utf8()..];
+                continue;
+            }
+        }
+        if pending_space && !out.is_empty() {
+            out.push(' ');
+        }
+        pending_space = false;
+        if c == '"' {
+            in_string = !in_string;
+        }
+        out.push(c);
+        rest = &rest[c.len_utf8()..];
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::parse;
+
+    fn check_query(query: &str) -> QueryCheck {
+        check(&parse(query).unwrap(), &HashSet::new()).unwrap()
+    }
+
+    #[test]
+    fn normalizes_layout_and_reports_inherited_filters() {
+        let check =
+            check_query("project(\"linux\") func {\n  \"vfs_read\"   /* entry */ { has { } }\n}");
+        assert_eq!(
+            check.normalized,
+            "project(\"linux\") func {\n  \"vfs_read\" {\n    has { }\n  }\n}\n"
+        );
+        let read = &check.statements[1];
+        assert_eq!(read.text, "\"vfs_read\"");
+        assert_eq!(read.parent, Some(0));
+        assert_eq!(read.inherited, vec!["project(\"linux\")", "func"]);
+        assert_eq!(check.statements[2].label(), "has");
+        assert_eq!(check.statements[3].relation, vec!["has"]);
+    }
+
+    #[test]
+    fn reports_scope_relations() {
+        let check = check_query("\"x\" refs(kind=\"call\") { \"y\" }; \"z\" { }");
+        assert_eq!(
+            check.normalized,
+            "\"x\" refs(kind=\"call\") {\n  \"y\"\n}\n\"z\" { }\n"
+        );
+        assert!(check.statements[0].relation.is_empty());
+        assert_eq!(check.statements[1].relation, vec!["refs"]);
+        assert_eq!(check.statements[1].ref_kinds, vec!["call"]);
+        assert_eq!(check.statements[3].relation, vec!["refs", "has"]);
+        assert!(check.statements[3].weak);
+        assert!(check.warnings.is_empty());
+    }
+
+    #[test]
+    fn warns_about_unconstrained_and_untyped_scopes() {
+        let check = check_query("func\n{ \"vfs_read\" }");
+        let warnings: Vec<&str> = check.warnings.iter().map(|w| w.span.as_str()).collect();
+        assert_eq!(warnings, vec!["func", "{ \"vfs_read\" }"]);
+        assert!(check.warnings[0].message.contains("matches every symbol"));
+        assert!(check.warnings[1].message.contains("`func { … }`"));
+    }
+
+    #[test]
+    fn unknown_label_is_an_error() {
+        let err = check(&parse("\"x\" { #missing }").unwrap(), &HashSet::new()).unwrap_err();
+        assert!(err.to_string().contains("Label 'missing' not found"));
+    }
+
+    #[test]
+    fn imported_label_is_not_an_error() {
+        let imported = HashSet::from(["earlier".to_string()]);
+        let check = check(&parse("\"x\" { #earlier }").unwrap(), &imported).unwrap();
+        assert_eq!(check.statements[1].text, "#earlier");
+    }
+}
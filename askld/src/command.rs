@@ -6,9 +6,9 @@ use crate::parser::Rule;
 use crate::span::Span;
 use crate::statement::Statement;
 use crate::verb::{
-    add_verb, find_symbol_by_instance_id, ConstraintAction, DeriveMethod, Filter, LabelResolutions,
-    Labeler, LayerPopulate, LayerSpec, NotificationContext, PerLayerPopulate, Selector, SelectorId,
-    SupplementPopulate, Verb, VerbTag,
+    add_verb, find_symbol_by_instance_id, with_related_instance_ids, with_relations,
+    ConstraintAction, DeriveMethod, Filter, LabelResolutions, Labeler, LayerPopulate, LayerSpec,
+    NotificationContext, PerLayerPopulate, Selector, SelectorId, SupplementPopulate, Verb, VerbTag,
 };
 use anyhow::Result;
 use core::fmt::Debug;
@@ -18,7 +18,8 @@ use index::db_diesel::{
 };
 use index::symbols::SymbolInstanceId;
 use sha2::{Digest, Sha256};
-use std::collections::HashMap;
+use std::borrow::Cow;
+use std::collections::{HashMap, HashSet};
 use std::rc::Rc;
 use std::sync::Arc;
 
@@ -144,6 +145,29 @@ impl Command {
         self.verbs = add_verb(verbs, other);
     }
 
+    /// Every verb of the command: its own, inherited and implicit ones.
+    pub fn verbs(&self) -> &[Arc<dyn Verb>] {
+        &self.verbs
+    }
+
+    /// A note for each selector that requires a name constraint the command
+    /// lacks (any filter verb on the command counts).
+    pub fn name_constraint_warnings(&self) -> Vec<Diagnostic> {
+        let has_name = self.verbs.iter().any(|v| v.has_name_constraint());
+        if has_name {
+            return Vec::new();
+        }
+        self.selectors()
+            .filter(|selector| selector.requires_name_constraint())
+            .map(|selector| {
+                Diagnostic::note(
+                    Span::from_pest(selector.span(), self.span().input()),
+                    "select requires at least one name filter (filter(\"compound_name\", ...) or filter(\"exact_name\", ...))",
+                )
+            })
+            .collect()
+    }
+
     pub(crate) fn filters<'a>(&'a self) -> Box<dyn Iterator<Item = &'a dyn Filter> + 'a> {
         Box::new(self.verbs.iter().filter_map(|verb| verb.as_filter().ok()))
     }
@@ -381,6 +405,25 @@ impl Command {
         }
     }
 
+    /// [`with_relations`], reporting a failed load against this statement.
+    async fn with_relations<'a>(
+        &self,
+        index: &Index,
+        dependency: &'a Selection,
+        rel_type: RelationshipType,
+    ) -> Result<Cow<'a, Selection>, pest::error::Error<Rule>> {
+        with_relations(index, dependency, rel_type)
+            .await
+            .map_err(|e| {
+                pest::error::Error::new_from_span(
+                    pest::error::ErrorVariant::CustomError {
+                        message: e.to_string(),
+                    },
+                    self.span().as_pest_span(),
+                )
+            })
+    }
+
     /// Notify all selectors using a pre-built merged selection (constraint + derivation).
     /// Used when a parent is notified by the union of all its children's selections.
     pub async fn notify_from_selection(
@@ -394,6 +437,7 @@ impl Command {
         parent_scope: ScopeContext,
         children_scope: ScopeContext,
     ) -> Result<NotificationResult, pest::error::Error<Rule>> {
+        let dependency = &*self.with_relations(index, dependency, rel_type).await?;
         let mut changed = false;
         let mut warnings = vec![];
         let selector_filters: Vec<&dyn Filter> = self.filters().collect();
@@ -422,25 +466,32 @@ impl Command {
                 if !unnest {
                     find_parts.push(CompositeFilter::leaf(InnermostOnlyMixin::new()));
                 }
+                find_parts.extend(rel_type.ref_kind_filter());
                 let find_filter = CompositeFilter::and(find_parts);
+                let to_error = |e: anyhow::Error| {
+                    pest::error::Error::new_from_span(
+                        pest::error::ErrorVariant::CustomError {
+                            message: format!("Failed to find parent instance IDs: {}", e),
+                        },
+                        selector.span(),
+                    )
+                };
+                let parent_ids = index
+                    .find_parent_instance_ids(
+                        &child_ids,
+                        rel_type.contains(RelationshipType::REFS),
+                        rel_type.contains(RelationshipType::HAS),
+                        &find_filter,
+                        &ctx.eph,
+                    )
+                    .await
+                    .map_err(to_error)?;
                 derivation_ids = Some(
-                    index
-                        .find_parent_instance_ids(
-                            &child_ids,
-                            rel_type.contains(RelationshipType::REFS),
-                            rel_type.contains(RelationshipType::HAS),
-                            &find_filter,
-                            &ctx.eph,
-                        )
-                        .await
-                        .map_err(|e| {
-                            pest::error::Error::new_from_span(
-                                pest::error::ErrorVariant::CustomError {
-                                    message: format!("Failed to find parent instance IDs: {}", e),
-                                },
-                                selector.span(),
-                            )
-                        })?,
+                    with_related_instance_ids(
+                        index, parent_ids, dependency, rel_type, unnest, false, &ctx.eph,
+                    )
+                    .await
+                    .map_err(to_error)?,
                 );
             }
             let decl_ids = derivation_ids.as_ref().unwrap();
@@ -492,6 +543,9 @@ impl Command {
             Some(selection) => selection,
             None => return Ok(NotificationResult::new(false, vec![])),
         };
+        let dependency = self
+            .with_relations(index, &dependency, notif_ctx.rel_type)
+            .await?;
 
         let mut changed = false;
         let mut warnings = vec![];
@@ -616,25 +670,10 @@ impl Command {
             });
         }
 
-        let mut warnings = vec![];
+        let mut warnings = self.name_constraint_warnings();
         let mut selections = vec![];
         let mut layer_activations = vec![];
 
-        // Validate: each selector that requires a name constraint must have one
-        // command-wide (any filter verb on the command counts).
-        for selector in selectors.iter() {
-            if !selector.requires_name_constraint() {
-                continue;
-            }
-            let has_name = self.verbs.iter().any(|v| v.has_name_constraint());
-            if !has_name {
-                warnings.push(Diagnostic::note(
-                    Span::from_pest(selector.span(), self.span().input()),
-                    "select requires at least one name filter (filter(\"compound_name\", ...) or filter(\"exact_name\", ...))",
-                ));
-            }
-        }
-
         let to_pest = |e: anyhow::Error| {
             pest::error::Error::new_from_span(
                 pest::error::ErrorVariant::CustomError {
@@ -854,18 +893,28 @@ impl Command {
     }
 }
 
-pub struct LabeledStatements(HashMap<String, Vec<Rc<Statement>>>);
+pub struct LabeledStatements {
+    statements: HashMap<String, Vec<Rc<Statement>>>,
+    /// Labels defined by an earlier execution (see
+    /// [`crate::execution_context::ExecutionContext::labels`]).  A `#label`
+    /// naming one of these is not an error even when no statement of this
+    /// query defines it.
+    imported: HashSet<String>,
+}
 
 impl LabeledStatements {
-    pub fn new() -> Self {
-        Self(HashMap::new())
+    pub fn new(imported: HashSet<String>) -> Self {
+        Self {
+            statements: HashMap::new(),
+            imported,
+        }
     }
 
     pub fn remember(&mut self, statement: Rc<Statement>) -> usize {
         let marks = statement.command().get_labels();
         let marks_len = marks.len();
         for mark in marks {
-            self.0
+            self.statements
                 .entry(mark)
                 .or_insert_with(Vec::new)
                 .push(statement.clone());
@@ -875,7 +924,13 @@ impl LabeledStatements {
     }
 
     pub fn get_statements(&self, label: &str) -> Option<&Vec<Rc<Statement>>> {
-        self.0.get(label)
+        self.statements.get(label)
+    }
+
+    /// True if `label` comes from an earlier execution rather than a
+    /// statement of this query.
+    pub fn is_imported(&self, label: &str) -> bool {
+        !self.statements.contains_key(label) && self.imported.contains(label)
     }
 }
 
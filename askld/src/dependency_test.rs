@@ -1,4 +1,4 @@
-use crate::test_util::{format_edges, run_query, run_query_err, TEST_INPUT_A};
+use crate::test_util::{format_edges, run_query, run_query_err, TEST_INPUT_A, VERB_TEST};
 use index::symbols::SymbolInstanceId;
 
 #[test]
@@ -214,3 +214,39 @@ fn forced_label_use_loop_returns_empty() {
     let edges = format_edges(res.edges);
     assert!(edges.is_empty());
 }
+
+#[test]
+fn macro_labels_do_not_leak_to_caller() {
+    // The label inside the macro body is renamed per expansion, so the
+    // caller's `use("foo")` cannot see it.
+    const QUERY: &str = r#"define lab(x) = "b" $x {label("foo")}
+lab("a"); use("foo")"#;
+    let res = run_query_err(TEST_INPUT_A, QUERY);
+    assert!(res.is_err());
+    if let Err(e) = res {
+        assert!(e.to_string().contains("Label 'foo' not found"));
+    }
+}
+
+#[test]
+fn macro_labels_reach_ephemeral_verb_arguments() {
+    // `symbol_id="@target"` in the body names the expansion's own
+    // `@target`, which is renamed along with it.
+    const QUERY: &str = concat!(
+        r#"define eph(x) = @target $x { layer { ephemeral_instance(symbol_id="@target", "#,
+        r#"object_id="1", start="50000", end="50100", instance_type="1") } }; "#,
+        r#"eph("foo")"#,
+    );
+    let res = run_query_err(VERB_TEST, QUERY);
+    assert!(res.is_ok(), "{:?}", res.err());
+}
+
+#[test]
+fn macro_expansion_matches_inline_query() {
+    const MACRO: &str = r#"define callees(x, y) = $x $y {}
+callees("b", "a")"#;
+    let expanded = run_query(TEST_INPUT_A, MACRO);
+    let inline = run_query(TEST_INPUT_A, r#""b" "a" {}"#);
+    assert_eq!(expanded.nodes.as_vec(), inline.nodes.as_vec());
+    assert_eq!(format_edges(expanded.edges), format_edges(inline.edges));
+}
@@ -0,0 +1,118 @@
+use crate::test_util::{format_edges, run_query, run_query_err, TEST_INPUT_DISPATCH};
+use index::symbols::SymbolInstanceId;
+
+// Test fixture (test_input_dispatch.sql), all in ops.c:
+//
+// my_read   inst=103  func
+// my_write  inst=104  func
+// my_fops   inst=105  data   = { .read = my_read, .write = my_write }
+// register  inst=106  func   calls my_read, takes &my_write, reads my_fops
+// tables    inst=107  data   = { { .write = my_write }, &counter }
+//   file_operations.write inst=108  field, nested in tables, holds my_write
+// counter   inst=109  data
+
+#[test]
+fn dispatch_finds_functions_held_by_data() {
+    const QUERY: &str = r#"data("my_fops") dispatch { func }"#;
+    let res = run_query(TEST_INPUT_DISPATCH, QUERY);
+
+    let nodes = res.nodes.as_vec();
+    assert_eq!(
+        nodes,
+        vec![
+            SymbolInstanceId::new(103),
+            SymbolInstanceId::new(104),
+            SymbolInstanceId::new(105),
+        ]
+    );
+}
+
+#[test]
+fn dispatch_finds_holders_of_function() {
+    const QUERY: &str = r#"dispatch { func("my_write") }"#;
+    let res = run_query(TEST_INPUT_DISPATCH, QUERY);
+
+    let nodes = res.nodes.as_vec();
+    println!("dispatch_finds_holders_of_function nodes: {:?}", nodes);
+
+    assert!(nodes.contains(&SymbolInstanceId::new(105)), "my_fops");
+    assert!(nodes.contains(&SymbolInstanceId::new(108)), "field");
+    assert!(
+        !nodes.contains(&SymbolInstanceId::new(106)),
+        "register is a function, not a holder"
+    );
+}
+
+#[test]
+fn dispatch_ignores_address_of_data() {
+    const QUERY: &str = r#"data("tables") dispatch { unnest }"#;
+    let res = run_query(TEST_INPUT_DISPATCH, QUERY);
+
+    let nodes = res.nodes.as_vec();
+    println!("dispatch_ignores_address_of_data nodes: {:?}", nodes);
+
+    assert!(nodes.contains(&SymbolInstanceId::new(104)), "my_write");
+    assert!(!nodes.contains(&SymbolInstanceId::new(109)), "counter");
+}
+
+#[test]
+fn dispatch_is_direct_unless_unnest() {
+    const QUERY: &str = r#"data("tables") dispatch { func }"#;
+    let res = run_query(TEST_INPUT_DISPATCH, QUERY);
+    assert!(
+        !res.nodes.as_vec().contains(&SymbolInstanceId::new(104)),
+        "my_write is held by the nested field"
+    );
+
+    const UNNEST: &str = r#"data("tables") dispatch { unnest func }"#;
+    let res = run_query(TEST_INPUT_DISPATCH, UNNEST);
+    assert!(res.nodes.as_vec().contains(&SymbolInstanceId::new(104)));
+}
+
+#[test]
+fn dispatch_constrains_named_pair() {
+    const QUERY: &str = r#"data("my_fops") dispatch { func("my_read") }"#;
+    let res = run_query(TEST_INPUT_DISPATCH, QUERY);
+
+    let nodes = res.nodes.as_vec();
+    assert_eq!(
+        nodes,
+        vec![SymbolInstanceId::new(103), SymbolInstanceId::new(105)]
+    );
+
+    const MISMATCH: &str = r#"func("register") dispatch { func("my_write") }"#;
+    let res = run_query(TEST_INPUT_DISPATCH, MISMATCH);
+    assert!(
+        !res.nodes.as_vec().contains(&SymbolInstanceId::new(104)),
+        "register takes my_write's address but is not a holder"
+    );
+}
+
+#[test]
+fn dispatch_edges_are_address_taken_refs() {
+    const QUERY: &str = r#"data("my_fops") dispatch { func("my_read") }"#;
+    let res = run_query(TEST_INPUT_DISPATCH, QUERY);
+
+    let edges = format_edges(res.edges);
+    println!("dispatch_edges_are_address_taken_refs edges: {:?}", edges);
+    assert_eq!(edges.len(), 1);
+}
+
+#[test]
+fn derive_accepts_dispatch() {
+    const QUERY: &str = r#"func("register") derive(type="refs,dispatch") { data { func } }"#;
+    let res = run_query(TEST_INPUT_DISPATCH, QUERY);
+
+    let nodes = res.nodes.as_vec();
+    println!("derive_accepts_dispatch nodes: {:?}", nodes);
+
+    assert!(nodes.contains(&SymbolInstanceId::new(105)), "my_fops");
+    assert!(nodes.contains(&SymbolInstanceId::new(103)), "my_read");
+}
+
+#[test]
+fn unknown_derive_type_errors() {
+    const QUERY: &str = r#"data derive(type="indirect") { func }"#;
+    let res = run_query_err(TEST_INPUT_DISPATCH, QUERY);
+    assert!(res.is_err(), "Expected error for unknown derive type");
+}
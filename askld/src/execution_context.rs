@@ -77,6 +77,37 @@ pub struct ExecutionContext {
     /// order.  Lets callers (tests, diagnostics) observe whether each layer
     /// was freshly populated or served from cache.
     pub layer_activations: Vec<crate::command::LayerActivation>,
+    /// Set to collect an explain report for this execution; see
+    /// [`crate::explain`].
+    pub explain: Option<crate::explain::ExplainTrace>,
+    /// Called each time the worklist finishes a statement for the first
+    /// time, so a caller can report progress on a long query.
+    pub progress: Option<ProgressFn>,
+    /// Labelled selections carried between executions, keyed by label.
+    /// Before execution: labels an earlier query defined, which a `#label`
+    /// this query does not define itself resolves to.  After execution:
+    /// updated with every label this query defined (nodes only — a later
+    /// query re-derives relations from them).
+    pub labels: HashMap<String, Selection>,
+}
+
+/// Receives each [`StatementProgress`] of one execution.
+pub type ProgressFn = Box<dyn FnMut(&StatementProgress)>;
+
+/// A statement the worklist has finished; see [`ExecutionContext::progress`].
+#[derive(Debug, Clone)]
+pub struct StatementProgress {
+    /// The statement's id (its pre-order position, as in `explain`).
+    pub statement: usize,
+    /// The statement's own verbs as written (see
+    /// [`crate::check::CheckedStatement::text`]); empty for a bare scope.
+    pub label: String,
+    pub rows: usize,
+    /// Distinct statements finished so far, this one included.
+    pub completed: usize,
+    /// Statements in the query.  Statements whose selection stays empty are
+    /// never finished, so `completed` need not reach it.
+    pub total: usize,
 }
 
 impl ExecutionContext {
@@ -89,6 +120,9 @@ impl ExecutionContext {
             current_statement_span: None,
             eph: EphContext::rooted(roots),
             layer_activations: Vec::new(),
+            explain: None,
+            progress: None,
+            labels: HashMap::new(),
         }
     }
 }
@@ -1,6 +1,8 @@
 use std::rc::Rc;
 
 use crate::{diagnostic::Diagnostic, statement::Statement};
+use index::db_diesel::{CompositeFilter, RefKindMixin};
+use index::symbols::{RefKind, RelationKind};
 
 /// The role of a dependency in the execution state.
 ///
@@ -52,17 +54,127 @@ pub enum DependencyKind {
 /// Bitflag newtype: composable via `|`, testable via `contains()`.
 /// - REFS: Reference-based traversal (calls/uses) via symbol_refs table
 /// - HAS: Containment-based traversal (composition) via offset_range containment
+/// - EXTENDS / IMPLEMENTS / EMBEDS: Type-hierarchy traversal via symbol_relations
+/// - DISPATCH: Indirect calls — from a data/field holder to the functions
+///   whose address its initialiser takes (address-taken symbol_refs)
+///
+/// The second field narrows REFS to the ref kinds whose bits are set
+/// (bit `k - 1` for kind `k`); zero means any kind, unclassified refs
+/// included.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
-pub struct RelationshipType(u8);
+pub struct RelationshipType(u8, u8);
+
+// Every ref kind needs a bit in the mask: a kind past `u8::BITS` fails the
+// build here instead of silently dropping out of `refs(kind=...)`.
+const _: () = {
+    let mut i = 0;
+    while i < RefKind::ALL.len() {
+        let kind = RefKind::ALL[i] as u32;
+        assert!(kind >= 1 && kind <= u8::BITS);
+        i += 1;
+    }
+};
+
+/// Bit of ref kind `kind` in [`RelationshipType`]'s mask.
+fn ref_kind_bit(kind: i32) -> u8 {
+    1 << (kind - 1)
+}
 
 impl RelationshipType {
-    pub(crate) const EMPTY: Self = Self(0);
-    pub const REFS: Self = Self(0b01);
-    pub const HAS: Self = Self(0b10);
+    pub(crate) const EMPTY: Self = Self(0, 0);
+    pub const REFS: Self = Self(0b01, 0);
+    pub const HAS: Self = Self(0b10, 0);
+    pub const EXTENDS: Self = Self(0b100, 0);
+    pub const IMPLEMENTS: Self = Self(0b1000, 0);
+    pub const EMBEDS: Self = Self(0b10000, 0);
+    pub const DISPATCH: Self = Self(0b100000, 0);
+
+    /// The flag traversing relations of `kind`.
+    pub fn relation(kind: RelationKind) -> Self {
+        match kind {
+            RelationKind::Extends => Self::EXTENDS,
+            RelationKind::Implements => Self::IMPLEMENTS,
+            RelationKind::Embeds => Self::EMBEDS,
+        }
+    }
+
+    /// The `symbol_relations.kind` values this type traverses; empty when
+    /// no relation flag is set.
+    pub fn relation_kinds(self) -> Vec<i32> {
+        RelationKind::ALL
+            .into_iter()
+            .filter(|k| self.contains(Self::relation(*k)))
+            .map(|k| k.as_i32())
+            .collect()
+    }
+
+    /// Whether a relation of `kind` is traversed.
+    pub fn allows_relation_kind(self, kind: i32) -> bool {
+        RelationKind::ALL
+            .into_iter()
+            .any(|k| k.as_i32() == kind && self.contains(Self::relation(k)))
+    }
+
+    /// The traversal flags set, by their modifier names (`refs`, `has`, …).
+    pub fn names(self) -> Vec<&'static str> {
+        let mut names = Vec::new();
+        if self.contains(Self::REFS) {
+            names.push("refs");
+        }
+        if self.contains(Self::HAS) {
+            names.push("has");
+        }
+        for kind in RelationKind::ALL {
+            if self.contains(Self::relation(kind)) {
+                names.push(kind.name());
+            }
+        }
+        if self.contains(Self::DISPATCH) {
+            names.push("dispatch");
+        }
+        names
+    }
 
     pub fn contains(self, other: Self) -> bool {
         self.0 & other.0 == other.0
     }
+
+    /// Restrict REFS traversal to references of `kinds`.
+    pub fn with_ref_kinds(self, kinds: &[RefKind]) -> Self {
+        let mask = kinds
+            .iter()
+            .fold(0, |mask, k| mask | ref_kind_bit(k.as_i32()));
+        Self(self.0, mask)
+    }
+
+    /// The `symbol_refs.kind` values REFS traversal is restricted to, or
+    /// `None` when any reference qualifies.
+    pub fn ref_kinds(self) -> Option<Vec<i32>> {
+        if self.1 == 0 {
+            return None;
+        }
+        Some(
+            RefKind::ALL
+                .iter()
+                .map(|k| k.as_i32())
+                .filter(|k| self.1 & ref_kind_bit(*k) != 0)
+                .collect(),
+        )
+    }
+
+    /// The filter leaf that applies [`Self::ref_kinds`] to REFS lookups.
+    pub fn ref_kind_filter(self) -> Option<CompositeFilter> {
+        self.ref_kinds()
+            .map(|kinds| CompositeFilter::leaf(RefKindMixin::new(kinds)))
+    }
+
+    /// Whether a reference of `kind` (NULL for unclassified) qualifies.
+    pub fn allows_ref_kind(self, kind: Option<i32>) -> bool {
+        self.1 == 0
+            || kind.is_some_and(|k| {
+                RefKind::ALL.iter().any(|r| r.as_i32() == k) && self.1 & ref_kind_bit(k) != 0
+            })
+    }
 }
 
 impl Default for RelationshipType {
@@ -73,8 +185,15 @@ impl Default for RelationshipType {
 
 impl std::ops::BitOr for RelationshipType {
     type Output = Self;
+    /// A side's mask only speaks for its REFS flag; if either side allows
+    /// any kind, so does the union.
     fn bitor(self, rhs: Self) -> Self {
-        Self(self.0 | rhs.0)
+        let mask = |t: Self| t.contains(Self::REFS).then_some(t.1);
+        let kinds = match (mask(self), mask(rhs)) {
+            (Some(0), _) | (_, Some(0)) => 0,
+            (lhs, rhs) => lhs.unwrap_or(0) | rhs.unwrap_or(0),
+        };
+        Self(self.0 | rhs.0, kinds)
     }
 }
 
@@ -160,3 +279,27 @@ impl ExecutionState {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn union_with_unrestricted_refs_allows_any_kind() {
+        let write = RelationshipType::REFS.with_ref_kinds(&[RefKind::Write]);
+        assert_eq!((RelationshipType::REFS | write).ref_kinds(), None);
+        assert_eq!((write | RelationshipType::REFS).ref_kinds(), None);
+        let read = RelationshipType::REFS.with_ref_kinds(&[RefKind::Read]);
+        assert_eq!(
+            (read | write).ref_kinds(),
+            Some(vec![RefKind::Read.as_i32(), RefKind::Write.as_i32()])
+        );
+        // HAS carries no ref kinds of its own.
+        assert_eq!(
+            (RelationshipType::HAS | write).ref_kinds(),
+            Some(vec![RefKind::Write.as_i32()])
+        );
+        assert!(!write.allows_ref_kind(None));
+        assert!(!write.allows_ref_kind(Some(9)));
+    }
+}
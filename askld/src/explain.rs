@@ -0,0 +1,306 @@
+//! Explain mode: a record of how one query executed.
+//!
+//! Enabled per request by setting [`ExecutionContext::explain`]; statement
+//! execution then fills in the dependency graph, the order the worklist
+//! processed statements in, and per-statement row counts, while the index's
+//! [`SqlProfile`] records every SQL load (with its result-cache outcome and,
+//! in [`ExplainMode::Analyze`], the Postgres plan).  [`ExplainTrace::report`]
+//! turns it into the serializable [`QueryExplain`].
+//!
+//! The trace lives on the context rather than in the execution future, so a
+//! query that hits its time limit still reports everything up to that point.
+//!
+//! [`ExecutionContext::explain`]: crate::execution_context::ExecutionContext::explain
+
+use std::rc::Rc;
+use std::sync::Arc;
+use std::time::Instant;
+
+use index::db_diesel::{CacheOutcome, SqlProfile};
+use serde::Serialize;
+
+use crate::command::{LayerActivation, LayerRole};
+use crate::execution_state::DependencyRole;
+use crate::statement::Statement;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExplainMode {
+    /// Statement graph, worklist order, row counts, SQL and cache outcomes.
+    Trace,
+    /// [`Self::Trace`] plus `EXPLAIN ANALYZE` for every selection query.
+    /// Each of those runs a second time, so this is slower than the query.
+    Analyze,
+}
+
+impl ExplainMode {
+    /// Parse the `explain=` request option. `false` (or absent) is `None`.
+    pub fn from_name(name: &str) -> Result<Option<Self>, String> {
+        match name {
+            "" | "false" | "0" => Ok(None),
+            "true" | "1" => Ok(Some(ExplainMode::Trace)),
+            "analyze" => Ok(Some(ExplainMode::Analyze)),
+            other => Err(format!(
+                "unknown explain mode '{other}'; expected 'true', 'false' or 'analyze'"
+            )),
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            ExplainMode::Trace => "trace",
+            ExplainMode::Analyze => "analyze",
+        }
+    }
+}
+
+/// In-progress explain data for one execution.
+pub struct ExplainTrace {
+    mode: ExplainMode,
+    profile: Arc<SqlProfile>,
+    started: Instant,
+    /// Statements in pre-order; a statement's id is its position here.
+    statements: Vec<Rc<Statement>>,
+    rows: Vec<Option<usize>>,
+    worklist: Vec<WorklistStep>,
+}
+
+impl ExplainTrace {
+    pub fn new(mode: ExplainMode) -> Self {
+        Self {
+            mode,
+            profile: SqlProfile::new(mode == ExplainMode::Analyze),
+            started: Instant::now(),
+            statements: Vec::new(),
+            rows: Vec::new(),
+            worklist: Vec::new(),
+        }
+    }
+
+    pub(crate) fn profile(&self) -> Arc<SqlProfile> {
+        self.profile.clone()
+    }
+
+    pub(crate) fn set_statements(&mut self, statements: &[Rc<Statement>]) {
+        self.statements = statements.to_vec();
+        self.rows = vec![None; statements.len()];
+    }
+
+    fn id_of(&self, statement: &Rc<Statement>) -> Option<usize> {
+        self.statements
+            .iter()
+            .position(|s| Rc::ptr_eq(s, statement))
+    }
+
+    /// The worklist popped `statement`; `rows` is its selection after
+    /// filtering, `None` when it has none.
+    pub(crate) fn record_step(&mut self, statement: &Rc<Statement>, rows: Option<usize>) {
+        if let Some(id) = self.id_of(statement) {
+            self.worklist.push(WorklistStep {
+                statement: id,
+                rows,
+            });
+        }
+    }
+
+    /// Final selection size of `statement`, once execution has settled.
+    pub(crate) fn record_rows(&mut self, statement: &Rc<Statement>, rows: usize) {
+        if let Some(id) = self.id_of(statement) {
+            self.rows[id] = Some(rows);
+        }
+    }
+
+    pub fn report(&self, layers: &[LayerActivation]) -> QueryExplain {
+        let statements = self
+            .statements
+            .iter()
+            .enumerate()
+            .map(|(id, statement)| {
+                let span = statement.command().query_statement_span();
+                let state = statement.get_state();
+                ExplainStatement {
+                    id,
+                    text: span.as_pest_span().as_str().to_string(),
+                    start: span.start(),
+                    end: span.end(),
+                    selectors: statement
+                        .command()
+                        .selectors()
+                        .map(|s| s.name().to_string())
+                        .collect(),
+                    weak: state.weak,
+                    dependents: state
+                        .dependents
+                        .iter()
+                        .filter_map(|d| {
+                            Some(ExplainEdge {
+                                statement: self.id_of(&d.statement)?,
+                                role: role_name(&d.dependency_role),
+                                label: match &d.dependency_role {
+                                    DependencyRole::PreSeedLabel(label) => Some(label.to_string()),
+                                    _ => d.label.clone(),
+                                },
+                            })
+                        })
+                        .collect(),
+                    rows: self.rows[id],
+                }
+            })
+            .collect();
+
+        let mut sql_cache = SqlCacheSummary::default();
+        let sql = self
+            .profile
+            .events()
+            .into_iter()
+            .map(|event| {
+                match event.cache {
+                    CacheOutcome::Hit => sql_cache.hits += 1,
+                    CacheOutcome::Miss => sql_cache.misses += 1,
+                    CacheOutcome::Uncached => sql_cache.uncached += 1,
+                }
+                ExplainSql {
+                    statement: event.statement,
+                    row_type: event.row_type,
+                    cache: event.cache.as_str(),
+                    rows: event.rows,
+                    elapsed_ms: event.elapsed.as_secs_f64() * 1000.0,
+                    sql: event.sql,
+                    plan: event.plan,
+                }
+            })
+            .collect();
+
+        QueryExplain {
+            mode: self.mode.name(),
+            elapsed_ms: self.started.elapsed().as_secs_f64() * 1000.0,
+            statements,
+            worklist: self.worklist.clone(),
+            sql,
+            sql_cache,
+            layers: layers.iter().map(ExplainLayer::from).collect(),
+        }
+    }
+}
+
+fn role_name(role: &DependencyRole) -> &'static str {
+    match role {
+        DependencyRole::Parent => "parent",
+        DependencyRole::Child => "child",
+        DependencyRole::User => "user",
+        DependencyRole::PreSeedSibling => "pre_seed_sibling",
+        DependencyRole::PreSeedLabel(_) => "pre_seed_label",
+    }
+}
+
+/// The explain report for one query.
+#[derive(Debug, Serialize)]
+pub struct QueryExplain {
+    /// `trace` or `analyze`.
+    pub mode: &'static str,
+    /// Wall time from the start of execution to the report.
+    pub elapsed_ms: f64,
+    pub statements: Vec<ExplainStatement>,
+    /// Statements in the order the worklist processed them; a statement
+    /// appears again each time a dependency change rescheduled it.
+    pub worklist: Vec<WorklistStep>,
+    /// SQL loads in completion order.
+    pub sql: Vec<ExplainSql>,
+    pub sql_cache: SqlCacheSummary,
+    /// Ephemeral layers the query created or reused.
+    pub layers: Vec<ExplainLayer>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ExplainStatement {
+    pub id: usize,
+    pub text: String,
+    pub start: usize,
+    pub end: usize,
+    pub selectors: Vec<String>,
+    /// Weak statements only scope their neighbours and contribute no nodes
+    /// of their own unless nothing constrains them.
+    pub weak: bool,
+    /// Edges of the dependency graph: the statements the worklist notifies
+    /// when this one's selection changes.
+    pub dependents: Vec<ExplainEdge>,
+    /// Final selection size; `None` if it never got a selection.
+    pub rows: Option<usize>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ExplainEdge {
+    pub statement: usize,
+    pub role: &'static str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub label: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct WorklistStep {
+    pub statement: usize,
+    pub rows: Option<usize>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ExplainSql {
+    /// The statement the load was issued for; `None` for query-wide work
+    /// such as edge collection and suggestions.
+    pub statement: Option<usize>,
+    pub row_type: &'static str,
+    /// `hit`, `miss` or `uncached`.
+    pub cache: &'static str,
+    pub rows: usize,
+    pub elapsed_ms: f64,
+    pub sql: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub plan: Option<String>,
+}
+
+#[derive(Debug, Default, Serialize)]
+pub struct SqlCacheSummary {
+    pub hits: usize,
+    pub misses: usize,
+    pub uncached: usize,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ExplainLayer {
+    pub root_id: i64,
+    pub layer_id: i64,
+    pub created: bool,
+    pub truncated: bool,
+    pub role: &'static str,
+}
+
+impl From<&LayerActivation> for ExplainLayer {
+    fn from(activation: &LayerActivation) -> Self {
+        Self {
+            root_id: activation.root_id,
+            layer_id: activation.layer_id,
+            created: activation.created,
+            truncated: activation.truncated,
+            role: match activation.role {
+                LayerRole::Base => "base",
+                LayerRole::Supplement => "supplement",
+                LayerRole::PerLayer => "per_layer",
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn explain_mode_from_name() {
+        assert_eq!(ExplainMode::from_name(""), Ok(None));
+        assert_eq!(ExplainMode::from_name("false"), Ok(None));
+        assert_eq!(ExplainMode::from_name("true"), Ok(Some(ExplainMode::Trace)));
+        assert_eq!(
+            ExplainMode::from_name("analyze"),
+            Ok(Some(ExplainMode::Analyze))
+        );
+        assert!(ExplainMode::from_name("yes").is_err());
+    }
+}
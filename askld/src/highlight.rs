@@ -0,0 +1,214 @@
+//! Syntax highlighting for askl queries, straight from the pest grammar.
+//!
+//! [`highlight`] parses a query with the same grammar the server executes
+//! and classifies the spans of the grammar rules an editor cares about:
+//! verb names, strings, `$params`, labels, `define`.  Comments are silent
+//! rules in the grammar, so they are recovered from the text between
+//! tokens, where nothing but whitespace, punctuation and comments can sit.
+//! Offsets are byte offsets into the query, like every other askl span.
+
+use pest::error::Error;
+use pest::iterators::Pair;
+use pest::Parser;
+use serde::Serialize;
+
+use crate::parser::{AsklParser, Rule};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TokenKind {
+    /// `define`.
+    Keyword,
+    /// The name of a verb or macro call: `func`, `refs`, `callers_of`.
+    Verb,
+    /// The name a `define` introduces.
+    Macro,
+    /// The name of a named argument: `kind` in `refs(kind="call")`.
+    Argument,
+    /// A quoted string with its quotes and prefix: `"main"`, `g"vfs_*"`.
+    String,
+    /// `$fn` placeholders and macro parameters.
+    Param,
+    /// `@label` and `@@label`.
+    Label,
+    /// `#label`.
+    LabelUse,
+    /// The `!` of a forced verb.
+    Operator,
+    Comment,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct HighlightToken {
+    pub start: usize,
+    pub end: usize,
+    pub kind: TokenKind,
+}
+
+/// Classify the tokens of `query`, in order.  Fails with the parse error when
+/// the query does not parse; the error carries the position to mark.
+pub fn highlight(query: &str) -> Result<Vec<HighlightToken>, Error<Rule>> {
+    let mut tokens = Vec::new();
+    for pair in AsklParser::parse(Rule::ask, query)? {
+        classify(pair, &mut tokens);
+    }
+    tokens.sort_by_key(|token| token.start);
+
+    let mut comments = Vec::new();
+    let mut gap_start = 0;
+    for token in tokens.iter().chain(std::iter::once(&HighlightToken {
+        start: query.len(),
+        end: query.len(),
+        kind: TokenKind::Comment,
+    })) {
+        find_comments(query, gap_start, token.start, &mut comments);
+        gap_start = token.end;
+    }
+    tokens.extend(comments);
+    tokens.sort_by_key(|token| token.start);
+    Ok(tokens)
+}
+
+fn push(tokens: &mut Vec<HighlightToken>, start: usize, end: usize, kind: TokenKind) {
+    tokens.push(HighlightToken { start, end, kind });
+}
+
+fn push_pair(tokens: &mut Vec<HighlightToken>, pair: &Pair<Rule>, kind: TokenKind) {
+    let span = pair.as_span();
+    push(tokens, span.start(), span.end(), kind);
+}
+
+fn classify(pair: Pair<Rule>, tokens: &mut Vec<HighlightToken>) {
+    match pair.as_rule() {
+        Rule::generic_verb | Rule::named_argument | Rule::macro_definition => {
+            let name_kind = match pair.as_rule() {
+                Rule::generic_verb => TokenKind::Verb,
+                Rule::named_argument => TokenKind::Argument,
+                _ => TokenKind::Macro,
+            };
+            let mut named = false;
+            for inner in pair.into_inner() {
+                if inner.as_rule() == Rule::ident && !named {
+                    push_pair(tokens, &inner, name_kind);
+                    named = true;
+                } else {
+                    classify(inner, tokens);
+                }
+            }
+        }
+        Rule::macro_params => {
+            for inner in pair.into_inner() {
+                push_pair(tokens, &inner, TokenKind::Param);
+            }
+        }
+        // Only reached inside the silent `quoted_string`, whose quotes sit
+        // right outside the `string` span.
+        Rule::string => {
+            let span = pair.as_span();
+            push(tokens, span.start() - 1, span.end() + 1, TokenKind::String);
+        }
+        Rule::prefixed_string => push_pair(tokens, &pair, TokenKind::String),
+        Rule::param => push_pair(tokens, &pair, TokenKind::Param),
+        Rule::label_shortcut | Rule::inherit_label_shortcut => {
+            push_pair(tokens, &pair, TokenKind::Label)
+        }
+        Rule::use_shortcut => push_pair(tokens, &pair, TokenKind::LabelUse),
+        Rule::define_keyword => push_pair(tokens, &pair, TokenKind::Keyword),
+        Rule::forced_verb => {
+            let start = pair.as_span().start();
+            push(tokens, start, start + 1, TokenKind::Operator);
+            for inner in pair.into_inner() {
+                classify(inner, tokens);
+            }
+        }
+        _ => {
+            for inner in pair.into_inner() {
+                classify(inner, tokens);
+            }
+        }
+    }
+}
+
+/// Collect the comments in `query[start..end]`, a stretch between tokens.
+fn find_comments(query: &str, start: usize, end: usize, out: &mut Vec<HighlightToken>) {
+    let bytes = query.as_bytes();
+    let mut at = start;
+    while at + 1 < end {
+        let close = match &bytes[at..at + 2] {
+            b"//" => query[at..end].find('\n').map_or(end, |i| at + i),
+            b"/*" => query[at + 2..end]
+                .find("*/")
+                .map_or(end, |i| at + 2 + i + 2),
+            _ => {
+                at += 1;
+                continue;
+            }
+        };
+        push(out, at, close, TokenKind::Comment);
+        at = close;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn kinds(query: &str) -> Vec<(&str, TokenKind)> {
+        highlight(query)
+            .unwrap()
+            .into_iter()
+            .map(|token| (&query[token.start..token.end], token.kind))
+            .collect()
+    }
+
+    #[test]
+    fn highlight_verbs_strings_and_labels() {
+        assert_eq!(
+            kinds(r#"func "main" @m { refs(kind="call") !"x" }; #m"#),
+            vec![
+                ("func", TokenKind::Verb),
+                ("\"main\"", TokenKind::String),
+                ("@m", TokenKind::Label),
+                ("refs", TokenKind::Verb),
+                ("kind", TokenKind::Argument),
+                ("\"call\"", TokenKind::String),
+                ("!", TokenKind::Operator),
+                ("\"x\"", TokenKind::String),
+                ("#m", TokenKind::LabelUse),
+            ]
+        );
+    }
+
+    #[test]
+    fn highlight_macros_params_and_prefixed_strings() {
+        assert_eq!(
+            kinds("define callers_of(x) = func { $x };\ncallers_of(g\"vfs_*\")"),
+            vec![
+                ("define", TokenKind::Keyword),
+                ("callers_of", TokenKind::Macro),
+                ("x", TokenKind::Param),
+                ("func", TokenKind::Verb),
+                ("$x", TokenKind::Param),
+                ("callers_of", TokenKind::Verb),
+                ("g\"vfs_*\"", TokenKind::String),
+            ]
+        );
+    }
+
+    #[test]
+    fn highlight_comments_between_tokens() {
+        assert_eq!(
+            kinds("// who calls it\n\"main\" /* not \"this\" */ {}"),
+            vec![
+                ("// who calls it", TokenKind::Comment),
+                ("\"main\"", TokenKind::String),
+                ("/* not \"this\" */", TokenKind::Comment),
+            ]
+        );
+    }
+
+    #[test]
+    fn highlight_reports_parse_errors() {
+        assert!(highlight("\"main\" {").is_err());
+    }
+}
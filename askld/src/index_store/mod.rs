@@ -18,7 +18,14 @@ use index::schema_diesel as index_schema;
 use index::symbols::FileId;
 
 mod query;
+mod schema;
 mod upload;
+mod validate;
+
+pub use schema::{IndexCapabilities, MIN_SCHEMA_VERSION, SCHEMA_VERSION};
+pub use validate::{
+    enum_name, validate_upload, IssueKind, ValidationIssue, ValidationReport, ValidationStats,
+};
 
 #[cfg(test)]
 mod tests;
@@ -114,6 +121,72 @@ pub struct ProjectDetails {
     pub committed_object_chunks: Vec<i32>,
 }
 
+/// One symbol of the persistent index with where it occurs and how many
+/// functions it calls or is called by.
+#[derive(Debug, Serialize)]
+pub struct SymbolSummary {
+    pub id: i64,
+    pub name: String,
+    pub symbol_type: String,
+    pub project_id: i32,
+    pub project_name: String,
+    pub instances: Vec<SymbolInstanceSummary>,
+    /// Distinct functions whose definitions reference this symbol.
+    pub caller_count: i64,
+    /// Distinct functions referenced from this symbol's definitions.
+    pub callee_count: i64,
+    /// The callers referencing it most often, at most
+    /// [`SYMBOL_SUMMARY_TOP`].
+    pub callers: Vec<SymbolNeighbour>,
+    /// The callees it references most often, at most
+    /// [`SYMBOL_SUMMARY_TOP`].
+    pub callees: Vec<SymbolNeighbour>,
+    /// Files, modules and directories containing its definitions and
+    /// declarations.
+    pub containers: Vec<SymbolContainer>,
+}
+
+/// How many callers and callees a [`SymbolSummary`] names.
+pub const SYMBOL_SUMMARY_TOP: i64 = 10;
+
+/// A caller or callee of a summarised symbol.
+#[derive(Debug, Serialize, QueryableByName)]
+pub struct SymbolNeighbour {
+    #[diesel(sql_type = diesel::sql_types::BigInt)]
+    pub id: i64,
+    #[diesel(sql_type = diesel::sql_types::Text)]
+    pub name: String,
+    /// References between the two.
+    #[diesel(sql_type = diesel::sql_types::BigInt)]
+    pub refs: i64,
+}
+
+#[derive(Debug, Serialize, QueryableByName)]
+pub struct SymbolContainer {
+    #[diesel(sql_type = diesel::sql_types::BigInt)]
+    pub id: i64,
+    #[diesel(sql_type = diesel::sql_types::Text)]
+    pub name: String,
+    #[diesel(sql_type = diesel::sql_types::Text)]
+    pub symbol_type: String,
+}
+
+#[derive(Debug, Serialize, QueryableByName)]
+pub struct SymbolInstanceSummary {
+    #[diesel(sql_type = diesel::sql_types::BigInt)]
+    pub id: i64,
+    #[diesel(sql_type = diesel::sql_types::Text)]
+    pub instance_type: String,
+    #[diesel(sql_type = diesel::sql_types::Integer)]
+    pub object_id: i32,
+    #[diesel(sql_type = diesel::sql_types::Text)]
+    pub path: String,
+    #[diesel(sql_type = diesel::sql_types::Integer)]
+    pub start_offset: i32,
+    #[diesel(sql_type = diesel::sql_types::Integer)]
+    pub end_offset: i32,
+}
+
 #[derive(Debug, Serialize, Clone, PartialEq, Eq)]
 #[serde(rename_all = "snake_case")]
 pub enum NodeType {
@@ -135,6 +208,69 @@ pub struct ProjectTreeNode {
     pub compact_path: Option<String>,
 }
 
+/// A position in a file: a byte offset, or a 1-based line and byte column.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FilePosition {
+    Offset(usize),
+    LineCol { line: usize, col: usize },
+}
+
+#[derive(Debug)]
+pub enum PositionLookup {
+    ProjectNotFound,
+    FileNotFound,
+    /// The position is past the end of its line or of the file.
+    OutOfRange,
+    Found(Box<SymbolAt>),
+}
+
+/// What sits at one position of a file.
+#[derive(Debug, Serialize)]
+pub struct SymbolAt {
+    pub object_id: i32,
+    pub path: String,
+    pub offset: usize,
+    pub line: usize,
+    pub col: usize,
+    /// The innermost symbol instance covering the position.
+    pub instance: Option<SymbolAtInstance>,
+    /// The symbol referenced, when the position is inside a reference.
+    pub reference: Option<SymbolAtRef>,
+}
+
+#[derive(Debug, Serialize, QueryableByName)]
+pub struct SymbolAtInstance {
+    #[diesel(sql_type = diesel::sql_types::BigInt)]
+    pub symbol_id: i64,
+    #[diesel(sql_type = diesel::sql_types::Text)]
+    pub name: String,
+    #[diesel(sql_type = diesel::sql_types::Text)]
+    pub symbol_type: String,
+    #[diesel(sql_type = diesel::sql_types::Text)]
+    pub instance_type: String,
+    #[diesel(sql_type = diesel::sql_types::Integer)]
+    pub start_offset: i32,
+    #[diesel(sql_type = diesel::sql_types::Integer)]
+    pub end_offset: i32,
+}
+
+#[derive(Debug, Serialize, QueryableByName)]
+pub struct SymbolAtRef {
+    #[diesel(sql_type = diesel::sql_types::BigInt)]
+    pub symbol_id: i64,
+    #[diesel(sql_type = diesel::sql_types::Text)]
+    pub name: String,
+    #[diesel(sql_type = diesel::sql_types::Text)]
+    pub symbol_type: String,
+    /// `call`, `read`, ...; absent when the indexer did not classify it.
+    #[diesel(sql_type = diesel::sql_types::Nullable<diesel::sql_types::Text>)]
+    pub kind: Option<String>,
+    #[diesel(sql_type = diesel::sql_types::Integer)]
+    pub start_offset: i32,
+    #[diesel(sql_type = diesel::sql_types::Integer)]
+    pub end_offset: i32,
+}
+
 #[derive(Debug)]
 pub enum MultiTreeResult {
     ProjectNotFound,
@@ -203,6 +339,26 @@ struct NewSymbol {
     layer: i64,        // the project's root layer — every row belongs to a layer
 }
 
+#[derive(Insertable, Clone, Debug, PartialEq)]
+#[diesel(table_name = index_schema::symbol_metadata)]
+struct NewSymbolMetadata {
+    symbol: i64,
+    signature: Option<String>,
+    return_type: Option<String>,
+    param_names: Vec<String>,
+    param_types: Vec<String>,
+    storage_class: Option<String>,
+    visibility: Option<String>,
+}
+
+#[derive(Insertable, Clone, Debug, PartialEq)]
+#[diesel(table_name = index_schema::symbol_relations)]
+struct NewSymbolRelation {
+    from_symbol: i64,
+    to_symbol: i64,
+    kind: i32,
+}
+
 #[derive(Insertable, Clone)]
 #[diesel(table_name = index_schema::symbol_instances)]
 struct NewSymbolInstance {
@@ -220,6 +376,7 @@ struct NewSymbolRef {
     from_object: i32,
     from_offset_range: std::ops::Range<i32>,
     layer: i64, // the project's root layer
+    kind: Option<i32>,
 }
 
 #[derive(Debug, QueryableByName)]
@@ -4,21 +4,26 @@ use diesel_async::{AsyncConnection, AsyncPgConnection, RunQueryDsl};
 
 use std::collections::HashMap;
 
+use crate::line_index::{line_to_offset, next_line_offset, LineIndex};
+use crate::query_store::invalidate_query_cache;
 use index::models_diesel::ContentRow;
 use index::schema_diesel as index_schema;
-use index::symbols::{FileId, SymbolType};
+use index::symbols::{FileId, InstanceType, SymbolType};
 
 use super::{
-    normalize_full_path, path_basename, BatchedDirRow, BatchedFileRow, CompactableRow, IndexStore,
-    MultiTreeResult, NodeType, ProjectDetails, ProjectInfo, ProjectTreeNode, StoreError,
-    UploadStatus,
+    normalize_full_path, path_basename, BatchedDirRow, BatchedFileRow, CompactableRow,
+    FilePosition, IndexStore, MultiTreeResult, NodeType, PositionLookup, ProjectDetails,
+    ProjectInfo, ProjectTreeNode, StoreError, SymbolAt, SymbolAtInstance, SymbolAtRef,
+    SymbolContainer, SymbolInstanceSummary, SymbolNeighbour, SymbolSummary, UploadStatus,
+    SYMBOL_SUMMARY_TOP,
 };
 
 impl IndexStore {
-    /// Clear both caches so the next query measures cold latency: the in-RAM SQL
-    /// result cache (shared with the query-side `Index`) and the DB-side
-    /// ephemeral-layer cache (`purge_eph_cache` deletes all non-protected
-    /// `layers` rows, so `search()`/`loc()` re-materialise). For the perf harness
+    /// Clear every cache so the next query measures cold latency: the in-RAM SQL
+    /// result cache (shared with the query-side `Index`), the persistent query
+    /// result cache, and the DB-side ephemeral-layer cache (`purge_eph_cache`
+    /// deletes all non-protected `layers` rows, so `search()`/`loc()`
+    /// re-materialise). For the perf harness
     /// via the loopback `/admin/clear-cache` endpoint — no restart needed.
     /// Returns the number of ephemeral layer rows purged.
     pub async fn clear_caches(&self) -> Result<usize, StoreError> {
@@ -28,6 +33,7 @@ impl IndexStore {
         // transaction (mirrors delete_project / finalize_project).
         let purged = conn
             .transaction::<usize, StoreError, _>(async move |conn| {
+                invalidate_query_cache(conn).await?;
                 Ok(index::db_diesel::purge_eph_cache(conn).await?)
             })
             .await?;
@@ -151,6 +157,190 @@ impl IndexStore {
         }))
     }
 
+    /// Summarise one symbol of the persistent index: its instances with
+    /// their files, how many distinct functions reference it from their
+    /// definitions (callers) or are referenced from its definitions
+    /// (callees) with the most frequent of each, and the files, modules and
+    /// directories it sits in.  `None` for unknown ids, ephemeral symbols
+    /// and internal fixture projects.
+    pub async fn get_symbol_summary(
+        &self,
+        symbol_id: i64,
+    ) -> Result<Option<SymbolSummary>, StoreError> {
+        use diesel::sql_types::{BigInt, Integer};
+
+        #[derive(QueryableByName)]
+        struct CountRow {
+            #[diesel(sql_type = BigInt)]
+            n: i64,
+        }
+
+        let mut conn = self.get_conn().await?;
+
+        // Persistent symbols live in their project's root layer; anything
+        // else is an eph-layer symbol that only means something inside the
+        // request that created it.
+        let row: Option<(String, String, i32, String, i64)> = index_schema::symbols::table
+            .inner_join(index_schema::projects::table)
+            .inner_join(index_schema::symbol_types::table)
+            .filter(index_schema::symbols::id.eq(symbol_id))
+            .filter(index_schema::projects::id.gt(0))
+            .filter(index_schema::symbols::layer.eq(index_schema::projects::root_layer_id))
+            .select((
+                index_schema::symbols::name,
+                index_schema::symbol_types::name,
+                index_schema::projects::id,
+                index_schema::projects::project_name,
+                index_schema::projects::root_layer_id,
+            ))
+            .first(&mut conn)
+            .await
+            .optional()?;
+        let Some((name, symbol_type, project_id, project_name, layer)) = row else {
+            return Ok(None);
+        };
+
+        let instances: Vec<SymbolInstanceSummary> = diesel::sql_query(
+            r#"
+            SELECT si.id, it.name AS instance_type, si.object_id,
+                   o.filesystem_path AS path,
+                   lower(si.offset_range) AS start_offset,
+                   upper(si.offset_range) AS end_offset
+            FROM index.symbol_instances si
+            JOIN index.instance_types it ON it.id = si.instance_type
+            JOIN index.objects o ON o.id = si.object_id
+            WHERE si.symbol = $1 AND si.layer = $2
+            ORDER BY si.instance_type, o.filesystem_path, si.offset_range
+            "#,
+        )
+        .bind::<BigInt, _>(symbol_id)
+        .bind::<BigInt, _>(layer)
+        .load(&mut conn)
+        .await?;
+
+        // The same containment join the query engine uses for callers/
+        // callees: a ref belongs to every definition whose range covers it.
+        let caller_count = diesel::sql_query(
+            r#"
+            SELECT COUNT(DISTINCT pd.symbol) AS n
+            FROM index.symbol_refs sr
+            JOIN index.symbol_instances pd ON pd.object_id = sr.from_object
+              AND pd.offset_range @> sr.from_offset_range
+            JOIN index.symbols ps ON ps.id = pd.symbol
+            WHERE sr.to_symbol = $1 AND sr.layer = $2 AND pd.layer = $2
+              AND pd.instance_type = $3 AND ps.symbol_type = $4
+            "#,
+        )
+        .bind::<BigInt, _>(symbol_id)
+        .bind::<BigInt, _>(layer)
+        .bind::<Integer, _>(InstanceType::Definition as i32)
+        .bind::<Integer, _>(SymbolType::Function as i32)
+        .get_result::<CountRow>(&mut conn)
+        .await?
+        .n;
+        let callee_count = diesel::sql_query(
+            r#"
+            SELECT COUNT(DISTINCT sr.to_symbol) AS n
+            FROM index.symbol_instances d
+            JOIN index.symbol_refs sr ON sr.from_object = d.object_id
+              AND d.offset_range @> sr.from_offset_range
+            JOIN index.symbols ts ON ts.id = sr.to_symbol
+            WHERE d.symbol = $1 AND d.layer = $2 AND sr.layer = $2
+              AND d.instance_type = $3 AND ts.symbol_type = $4
+            "#,
+        )
+        .bind::<BigInt, _>(symbol_id)
+        .bind::<BigInt, _>(layer)
+        .bind::<Integer, _>(InstanceType::Definition as i32)
+        .bind::<Integer, _>(SymbolType::Function as i32)
+        .get_result::<CountRow>(&mut conn)
+        .await?
+        .n;
+
+        let callers: Vec<SymbolNeighbour> = diesel::sql_query(
+            r#"
+            SELECT ps.id, ps.name, COUNT(*) AS refs
+            FROM index.symbol_refs sr
+            JOIN index.symbol_instances pd ON pd.object_id = sr.from_object
+              AND pd.offset_range @> sr.from_offset_range
+            JOIN index.symbols ps ON ps.id = pd.symbol
+            WHERE sr.to_symbol = $1 AND sr.layer = $2 AND pd.layer = $2
+              AND pd.instance_type = $3 AND ps.symbol_type = $4
+            GROUP BY ps.id, ps.name
+            ORDER BY refs DESC, ps.name
+            LIMIT $5
+            "#,
+        )
+        .bind::<BigInt, _>(symbol_id)
+        .bind::<BigInt, _>(layer)
+        .bind::<Integer, _>(InstanceType::Definition as i32)
+        .bind::<Integer, _>(SymbolType::Function as i32)
+        .bind::<BigInt, _>(SYMBOL_SUMMARY_TOP)
+        .load(&mut conn)
+        .await?;
+        let callees: Vec<SymbolNeighbour> = diesel::sql_query(
+            r#"
+            SELECT ts.id, ts.name, COUNT(*) AS refs
+            FROM index.symbol_instances d
+            JOIN index.symbol_refs sr ON sr.from_object = d.object_id
+              AND d.offset_range @> sr.from_offset_range
+            JOIN index.symbols ts ON ts.id = sr.to_symbol
+            WHERE d.symbol = $1 AND d.layer = $2 AND sr.layer = $2
+              AND d.instance_type = $3 AND ts.symbol_type = $4
+            GROUP BY ts.id, ts.name
+            ORDER BY refs DESC, ts.name
+            LIMIT $5
+            "#,
+        )
+        .bind::<BigInt, _>(symbol_id)
+        .bind::<BigInt, _>(layer)
+        .bind::<Integer, _>(InstanceType::Definition as i32)
+        .bind::<Integer, _>(SymbolType::Function as i32)
+        .bind::<BigInt, _>(SYMBOL_SUMMARY_TOP)
+        .load(&mut conn)
+        .await?;
+
+        // Same containment join again, against the file, module and
+        // directory symbols whose instances cover the whole object.
+        let containers: Vec<SymbolContainer> = diesel::sql_query(
+            r#"
+            SELECT DISTINCT cs.id, cs.name, ct.name AS symbol_type, cs.symbol_type AS kind
+            FROM index.symbol_instances si
+            JOIN index.symbol_instances ci ON ci.object_id = si.object_id
+              AND ci.offset_range @> si.offset_range
+            JOIN index.symbols cs ON cs.id = ci.symbol
+            JOIN index.symbol_types ct ON ct.id = cs.symbol_type
+            WHERE si.symbol = $1 AND si.layer = $2 AND ci.layer = $2
+              AND si.instance_type IN ($3, $4)
+              AND cs.symbol_type IN ($5, $6, $7) AND cs.id <> $1
+            ORDER BY kind, cs.name
+            "#,
+        )
+        .bind::<BigInt, _>(symbol_id)
+        .bind::<BigInt, _>(layer)
+        .bind::<Integer, _>(InstanceType::Definition as i32)
+        .bind::<Integer, _>(InstanceType::Declaration as i32)
+        .bind::<Integer, _>(SymbolType::File as i32)
+        .bind::<Integer, _>(SymbolType::Module as i32)
+        .bind::<Integer, _>(SymbolType::Directory as i32)
+        .load(&mut conn)
+        .await?;
+
+        Ok(Some(SymbolSummary {
+            id: symbol_id,
+            name,
+            symbol_type,
+            project_id,
+            project_name,
+            instances,
+            caller_count,
+            callee_count,
+            callers,
+            callees,
+            containers,
+        }))
+    }
+
     /// Delete a project and all its data.
     ///
     /// Proceeds in dependency order to avoid per-row ON DELETE CASCADE overhead:
@@ -226,6 +416,29 @@ impl IndexStore {
                 "delete_project: symbol_instances done"
             );
 
+            // Relations stay within a project, so the from side covers them.
+            let n = diesel::delete(
+                index_schema::symbol_relations::table
+                    .filter(index_schema::symbol_relations::from_symbol.ge(lower))
+                    .filter(index_schema::symbol_relations::from_symbol.lt(upper)),
+            )
+            .execute(&mut *conn)
+            .await?;
+            tracing::info!(
+                project_id,
+                rows = n,
+                "delete_project: symbol_relations done"
+            );
+
+            let n = diesel::delete(
+                index_schema::symbol_metadata::table
+                    .filter(index_schema::symbol_metadata::symbol.ge(lower))
+                    .filter(index_schema::symbol_metadata::symbol.lt(upper)),
+            )
+            .execute(&mut *conn)
+            .await?;
+            tracing::info!(project_id, rows = n, "delete_project: symbol_metadata done");
+
             let n = diesel::delete(
                 index_schema::symbols::table
                     .filter(index_schema::symbols::project_id.eq(project_id)),
@@ -263,7 +476,13 @@ impl IndexStore {
             }
 
             let purged = index::db_diesel::purge_eph_cache(conn).await?;
-            tracing::info!(project_id, purged, "delete_project: complete");
+            let purged_results = invalidate_query_cache(conn).await?;
+            tracing::info!(
+                project_id,
+                purged,
+                purged_results,
+                "delete_project: complete"
+            );
 
             Ok(())
         })
@@ -390,6 +609,59 @@ impl IndexStore {
         Ok(MultiTreeResult::Nodes(result))
     }
 
+    /// Number of symbol definitions in each of `paths` of a project: in the
+    /// file itself, or in every file below a directory. File, module and
+    /// directory symbols are structure, not code, and are not counted.
+    /// Paths with no definitions are absent from the map.
+    pub async fn count_definitions_under(
+        &self,
+        project_id: i32,
+        paths: &[String],
+    ) -> Result<HashMap<String, i64>, StoreError> {
+        use diesel::sql_types::{Array, BigInt, Integer, Text};
+
+        #[derive(QueryableByName)]
+        struct PathCountRow {
+            #[diesel(sql_type = Text)]
+            path: String,
+            #[diesel(sql_type = BigInt)]
+            n: i64,
+        }
+
+        if paths.is_empty() {
+            return Ok(HashMap::new());
+        }
+        let mut conn = self.get_conn().await?;
+        // Prefix arithmetic rather than LIKE, so `_` and `%` in paths stay
+        // literal; "/" is the whole project.
+        let rows: Vec<PathCountRow> = diesel::sql_query(
+            r#"
+            SELECT p.path, COUNT(*) AS n
+            FROM unnest($2::text[]) AS p(path)
+            JOIN index.projects pr ON pr.id = $1
+            JOIN index.objects o ON o.project_id = pr.id AND o.layer = pr.root_layer_id
+              AND (p.path = '/' OR o.filesystem_path = p.path
+                   OR left(o.filesystem_path, length(p.path) + 1) = p.path || '/')
+            JOIN index.symbol_instances si ON si.object_id = o.id
+              AND si.layer = pr.root_layer_id AND si.instance_type = $3
+            JOIN index.symbols s ON s.id = si.symbol
+            WHERE s.symbol_type <> ALL($4)
+            GROUP BY p.path
+            "#,
+        )
+        .bind::<Integer, _>(project_id)
+        .bind::<Array<Text>, _>(paths.to_vec())
+        .bind::<Integer, _>(InstanceType::Definition as i32)
+        .bind::<Array<Integer>, _>(vec![
+            SymbolType::File as i32,
+            SymbolType::Module as i32,
+            SymbolType::Directory as i32,
+        ])
+        .load(&mut conn)
+        .await?;
+        Ok(rows.into_iter().map(|row| (row.path, row.n)).collect())
+    }
+
     pub async fn get_project_file_contents_by_path(
         &self,
         project_id: i32,
@@ -419,6 +691,138 @@ impl IndexStore {
         Ok(content)
     }
 
+    /// What is at `position` in the file `path` of a project: the innermost
+    /// persistent symbol instance covering it and, when it falls inside a
+    /// reference, the referenced symbol. Both lookups are containment
+    /// probes on the object's ranges (the instance one served by the
+    /// `(object_id, offset_range)` GiST index).
+    pub async fn symbol_at(
+        &self,
+        project_id: i32,
+        path: &str,
+        position: FilePosition,
+    ) -> Result<PositionLookup, StoreError> {
+        use diesel::sql_types::{BigInt, Integer, Text};
+
+        #[derive(QueryableByName)]
+        struct FileRow {
+            #[diesel(sql_type = Integer)]
+            object_id: i32,
+            #[diesel(sql_type = Text)]
+            path: String,
+            #[diesel(sql_type = diesel::sql_types::Nullable<diesel::sql_types::Binary>)]
+            content: Option<Vec<u8>>,
+        }
+
+        let mut conn = self.get_conn().await?;
+
+        let layer: Option<i64> = index_schema::projects::table
+            .filter(index_schema::projects::id.eq(project_id))
+            .filter(index_schema::projects::id.gt(0))
+            .select(index_schema::projects::root_layer_id)
+            .first(&mut conn)
+            .await
+            .optional()?;
+        let Some(layer) = layer else {
+            return Ok(PositionLookup::ProjectNotFound);
+        };
+
+        let file: Option<FileRow> = diesel::sql_query(
+            r#"
+            SELECT o.id AS object_id, o.filesystem_path AS path,
+                   COALESCE(oc.content, cs.content) AS content
+            FROM index.objects o
+            LEFT JOIN index.object_contents oc ON oc.object_id = o.id
+            LEFT JOIN index.content_store cs ON cs.content_hash = o.content_hash
+            WHERE o.project_id = $1
+              AND o.filesystem_path = $2
+            LIMIT 1
+            "#,
+        )
+        .bind::<Integer, _>(project_id)
+        .bind::<Text, _>(normalize_full_path(path))
+        .get_result(&mut conn)
+        .await
+        .optional()?;
+        let Some(file) = file else {
+            return Ok(PositionLookup::FileNotFound);
+        };
+        // A file whose content never arrived has no positions to resolve.
+        let content = file.content.unwrap_or_default();
+
+        let offset = match position {
+            FilePosition::Offset(offset) if offset <= content.len() => offset,
+            FilePosition::LineCol { line, col } if col > 0 => {
+                let Some(start) = line_to_offset(&content, line) else {
+                    return Ok(PositionLookup::OutOfRange);
+                };
+                let end = next_line_offset(&content, start) as usize;
+                match (start as usize).checked_add(col - 1) {
+                    Some(offset) if offset <= end => offset,
+                    _ => return Ok(PositionLookup::OutOfRange),
+                }
+            }
+            _ => return Ok(PositionLookup::OutOfRange),
+        };
+        let (line, col) = LineIndex::new(&content).line_col_of(offset);
+
+        // Innermost first; on equal ranges a file beats the directory
+        // containing it, and a definition its other instance kinds.
+        let instance: Option<SymbolAtInstance> = diesel::sql_query(
+            r#"
+            SELECT s.id AS symbol_id, s.name, st.name AS symbol_type,
+                   it.name AS instance_type,
+                   lower(si.offset_range) AS start_offset,
+                   upper(si.offset_range) AS end_offset
+            FROM index.symbol_instances si
+            JOIN index.symbols s ON s.id = si.symbol
+            JOIN index.symbol_types st ON st.id = s.symbol_type
+            JOIN index.instance_types it ON it.id = si.instance_type
+            WHERE si.object_id = $1 AND si.offset_range @> $2 AND si.layer = $3
+            ORDER BY upper(si.offset_range) - lower(si.offset_range),
+                     s.symbol_type, si.instance_type
+            LIMIT 1
+            "#,
+        )
+        .bind::<Integer, _>(file.object_id)
+        .bind::<Integer, _>(offset as i32)
+        .bind::<BigInt, _>(layer)
+        .get_result(&mut conn)
+        .await
+        .optional()?;
+
+        let reference: Option<SymbolAtRef> = diesel::sql_query(
+            r#"
+            SELECT s.id AS symbol_id, s.name, st.name AS symbol_type, rk.name AS kind,
+                   lower(sr.from_offset_range) AS start_offset,
+                   upper(sr.from_offset_range) AS end_offset
+            FROM index.symbol_refs sr
+            JOIN index.symbols s ON s.id = sr.to_symbol
+            JOIN index.symbol_types st ON st.id = s.symbol_type
+            LEFT JOIN index.ref_kinds rk ON rk.id = sr.kind
+            WHERE sr.from_object = $1 AND sr.from_offset_range @> $2 AND sr.layer = $3
+            ORDER BY upper(sr.from_offset_range) - lower(sr.from_offset_range), s.id
+            LIMIT 1
+            "#,
+        )
+        .bind::<Integer, _>(file.object_id)
+        .bind::<Integer, _>(offset as i32)
+        .bind::<BigInt, _>(layer)
+        .get_result(&mut conn)
+        .await
+        .optional()?;
+
+        Ok(PositionLookup::Found(Box::new(SymbolAt {
+            object_id: file.object_id,
+            path: file.path,
+            offset,
+            line,
+            col,
+            instance,
+            reference,
+        })))
+    }
+
     /// Return which of the given content hashes already exist in the content store.
     /// Used by clients to skip uploading content that is already present.
     pub async fn check_content_hashes(
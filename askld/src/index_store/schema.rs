@@ -0,0 +1,234 @@
+//! Upload schema versioning. An indexer stamps `Project.schema_version`
+//! with the `index.proto` revision it was built against; the server accepts
+//! every revision up to its own and names the mismatch when it is newer.
+//! `GET /v1/index/capabilities` publishes the same tables so an indexer can
+//! check before it uploads.
+
+use serde::{Deserialize, Serialize};
+
+use crate::proto::askl::index::{
+    InstanceType, Project as UploadProject, RefKind, RelationKind, SchemaVersion, SymbolType,
+};
+
+use super::UploadError;
+
+/// The `index.proto` revision this server understands, as the indexers
+/// built from the same file stamp it; `index.proto` lists the revisions.
+///
+/// An unset version (0) predates versioning and is read as the current one.
+pub const SCHEMA_VERSION: u32 = SchemaVersion::Current as u32;
+/// Oldest revision still accepted.
+pub const MIN_SCHEMA_VERSION: u32 = 1;
+
+pub(super) const SYMBOL_TYPES: &[i32] = &[
+    index::db_diesel::SYMBOL_TYPE_FUNCTION,
+    index::db_diesel::SYMBOL_TYPE_FILE,
+    index::db_diesel::SYMBOL_TYPE_MODULE,
+    index::db_diesel::SYMBOL_TYPE_DIRECTORY,
+    index::db_diesel::SYMBOL_TYPE_TYPE,
+    index::db_diesel::SYMBOL_TYPE_DATA,
+    index::db_diesel::SYMBOL_TYPE_MACRO,
+    index::db_diesel::SYMBOL_TYPE_FIELD,
+];
+
+pub(super) const INSTANCE_TYPES: &[i32] = &[
+    index::db_diesel::INSTANCE_TYPE_DEFINITION,
+    index::db_diesel::INSTANCE_TYPE_DECLARATION,
+    index::db_diesel::INSTANCE_TYPE_EXPANSION,
+    index::db_diesel::INSTANCE_TYPE_SENTINEL,
+    index::db_diesel::INSTANCE_TYPE_CONTAINMENT,
+    index::db_diesel::INSTANCE_TYPE_SOURCE,
+    index::db_diesel::INSTANCE_TYPE_HEADER,
+    index::db_diesel::INSTANCE_TYPE_BUILD,
+    index::db_diesel::INSTANCE_TYPE_FILE,
+    index::db_diesel::INSTANCE_TYPE_DOCUMENTATION,
+];
+
+pub(super) const REF_KINDS: &[i32] = &[
+    index::db_diesel::REF_KIND_CALL,
+    index::db_diesel::REF_KIND_READ,
+    index::db_diesel::REF_KIND_WRITE,
+    index::db_diesel::REF_KIND_ADDRESS_TAKEN,
+    index::db_diesel::REF_KIND_TYPE_USE,
+];
+
+pub(super) const RELATION_KINDS: &[i32] = &[
+    index::db_diesel::RELATION_KIND_EXTENDS,
+    index::db_diesel::RELATION_KIND_IMPLEMENTS,
+    index::db_diesel::RELATION_KIND_EMBEDS,
+];
+
+/// Largest `Symbol.local_id` the symbol id layout can hold.
+pub const MAX_SYMBOL_LOCAL_ID: i64 = (1i64 << 32) - 1;
+
+/// One accepted protobuf enum value.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct EnumValue {
+    pub id: i32,
+    pub name: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct UploadLimits {
+    /// Largest request body any upload endpoint accepts.
+    pub max_upload_bytes: usize,
+    pub max_symbol_local_id: i64,
+}
+
+/// Body of `GET /v1/index/capabilities`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct IndexCapabilities {
+    pub schema_version: u32,
+    pub min_schema_version: u32,
+    pub symbol_types: Vec<EnumValue>,
+    pub instance_types: Vec<EnumValue>,
+    pub ref_kinds: Vec<EnumValue>,
+    pub relation_kinds: Vec<EnumValue>,
+    pub limits: UploadLimits,
+}
+
+impl IndexCapabilities {
+    /// `max_upload_bytes` is the server's configured body limit.
+    pub fn new(max_upload_bytes: usize) -> Self {
+        fn values<E>(ids: &[i32], name: impl Fn(E) -> &'static str) -> Vec<EnumValue>
+        where
+            E: TryFrom<i32>,
+        {
+            ids.iter()
+                .filter_map(|&id| {
+                    E::try_from(id).ok().map(|e| EnumValue {
+                        id,
+                        name: name(e).to_string(),
+                    })
+                })
+                .collect()
+        }
+
+        Self {
+            schema_version: SCHEMA_VERSION,
+            min_schema_version: MIN_SCHEMA_VERSION,
+            symbol_types: values(SYMBOL_TYPES, |t: SymbolType| t.as_str_name()),
+            instance_types: values(INSTANCE_TYPES, |t: InstanceType| t.as_str_name()),
+            ref_kinds: values(REF_KINDS, |k: RefKind| k.as_str_name()),
+            relation_kinds: values(RELATION_KINDS, |k: RelationKind| k.as_str_name()),
+            limits: UploadLimits {
+                max_upload_bytes,
+                max_symbol_local_id: MAX_SYMBOL_LOCAL_ID,
+            },
+        }
+    }
+}
+
+/// Check `upload`'s schema version and read it as the current revision:
+/// fields an older revision did not define are cleared, so a stale value
+/// in a since-assigned field number is never misread.
+pub(super) fn upgrade(upload: &mut UploadProject) -> Result<(), UploadError> {
+    let version = match upload.schema_version {
+        0 => SCHEMA_VERSION,
+        version => version,
+    };
+    if version > SCHEMA_VERSION {
+        return Err(UploadError::Invalid(format!(
+            "index schema_version {} is newer than this server supports ({}); \
+             upgrade askld or use an indexer built for schema_version {}",
+            version, SCHEMA_VERSION, SCHEMA_VERSION
+        )));
+    }
+    if version < MIN_SCHEMA_VERSION {
+        return Err(UploadError::Invalid(format!(
+            "index schema_version {} is no longer supported (oldest accepted: {})",
+            version, MIN_SCHEMA_VERSION
+        )));
+    }
+    if version < 2 {
+        for object in &mut upload.objects {
+            for reference in &mut object.refs {
+                reference.kind = RefKind::Unspecified as i32;
+            }
+        }
+    }
+    if version < 3 {
+        for symbol in &mut upload.symbols {
+            symbol.metadata = None;
+        }
+    }
+    if version < 4 {
+        upload.relations.clear();
+    }
+    upload.schema_version = SCHEMA_VERSION;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::proto::askl::index::{
+        Object, Project, Symbol, SymbolMetadata, SymbolRef, SymbolRelation,
+    };
+
+    use super::{upgrade, IndexCapabilities, SCHEMA_VERSION};
+
+    fn project(schema_version: u32) -> Project {
+        Project {
+            schema_version,
+            symbols: vec![Symbol {
+                metadata: Some(SymbolMetadata::default()),
+                ..Default::default()
+            }],
+            objects: vec![Object {
+                refs: vec![SymbolRef {
+                    kind: 1,
+                    ..Default::default()
+                }],
+                ..Default::default()
+            }],
+            relations: vec![SymbolRelation::default()],
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn unversioned_upload_is_read_as_current() {
+        let mut upload = project(0);
+        upgrade(&mut upload).unwrap();
+        assert_eq!(upload, project(SCHEMA_VERSION));
+    }
+
+    #[test]
+    fn current_version_is_untouched() {
+        let mut upload = project(SCHEMA_VERSION);
+        upgrade(&mut upload).unwrap();
+        assert_eq!(upload, project(SCHEMA_VERSION));
+    }
+
+    #[test]
+    fn newer_version_is_rejected_with_both_versions() {
+        let err = upgrade(&mut project(SCHEMA_VERSION + 1)).unwrap_err();
+        let msg = format!("{:?}", err);
+        assert!(
+            msg.contains(&(SCHEMA_VERSION + 1).to_string())
+                && msg.contains(&SCHEMA_VERSION.to_string()),
+            "{}",
+            msg
+        );
+    }
+
+    #[test]
+    fn older_version_drops_fields_it_did_not_define() {
+        let mut upload = project(1);
+        upgrade(&mut upload).unwrap();
+        assert_eq!(upload.schema_version, SCHEMA_VERSION);
+        assert_eq!(upload.objects[0].refs[0].kind, 0);
+        assert_eq!(upload.symbols[0].metadata, None);
+        assert!(upload.relations.is_empty());
+    }
+
+    #[test]
+    fn capabilities_name_every_accepted_value() {
+        let caps = IndexCapabilities::new(1024);
+        assert_eq!(caps.symbol_types.len(), super::SYMBOL_TYPES.len());
+        assert_eq!(caps.instance_types.len(), super::INSTANCE_TYPES.len());
+        assert_eq!(caps.symbol_types[0].name, "FUNCTION");
+        assert_eq!(caps.relation_kinds[2].name, "EMBEDS");
+        assert_eq!(caps.limits.max_upload_bytes, 1024);
+    }
+}
@@ -8,14 +8,18 @@ use tracing::Instrument;
 
 use crate::proto::askl::index::{
     ContentBatch, Object as UploadObject, Project as UploadProject, Symbol as UploadSymbol,
+    SymbolRelation as UploadRelation,
 };
+use crate::query_store::invalidate_query_cache;
 use index::schema_diesel as index_schema;
-use index::symbols::symbol_path_and_leaf;
+use index::symbols::{normalize_type_text, symbol_path_and_leaf};
 
+use super::schema::{self, INSTANCE_TYPES, REF_KINDS, RELATION_KINDS, SYMBOL_TYPES};
 use super::{
     hash_bytes, normalize_full_path, IndexStore, NewContentStoreRow, NewObject, NewProject,
-    NewProjectObjectChunk, NewProjectSymbolChunk, NewSymbol, NewSymbolInstance, NewSymbolRef,
-    UploadError, UploadStatus, MAX_INSERT_ROWS, MAX_SYMBOL_INSERT_ROWS,
+    NewProjectObjectChunk, NewProjectSymbolChunk, NewSymbol, NewSymbolInstance, NewSymbolMetadata,
+    NewSymbolRef, NewSymbolRelation, UploadError, UploadStatus, MAX_INSERT_ROWS,
+    MAX_SYMBOL_INSERT_ROWS,
 };
 
 struct ObjectInsert {
@@ -39,15 +43,21 @@ impl IndexStore {
     /// - `Complete` → conflict error
     pub async fn upload_index(
         &self,
-        upload: UploadProject,
+        mut upload: UploadProject,
         symbol_chunks_total: Option<i32>,
         object_chunks_total: Option<i32>,
     ) -> Result<(i32, bool), UploadError> {
+        schema::upgrade(&mut upload)?;
         if !upload.symbols.is_empty() {
             return Err(UploadError::Invalid(
                 "symbols must be uploaded via POST /v1/index/projects/{id}/symbols".to_string(),
             ));
         }
+        if !upload.relations.is_empty() {
+            return Err(UploadError::Invalid(
+                "relations must be uploaded with object chunks".to_string(),
+            ));
+        }
         let project_name = upload.project_name.trim().to_string();
         if project_name.is_empty() {
             return Err(UploadError::Invalid("project_name is required".to_string()));
@@ -87,8 +97,10 @@ impl IndexStore {
         &self,
         project_id: i32,
         seq: i32,
-        symbols: Vec<UploadSymbol>,
+        mut upload: UploadProject,
     ) -> Result<(), UploadError> {
+        schema::upgrade(&mut upload)?;
+        let symbols = upload.symbols;
         let mut conn = self.get_upload_conn().await?;
         // Chunk rows are query-visible immediately (queries do not filter
         // by upload_status), so each committed chunk must invalidate the
@@ -118,6 +130,14 @@ impl IndexStore {
                     .await
                     .map_err(|e| UploadError::Storage(e.to_string()))?;
             }
+            let metadata_rows = build_symbol_metadata(project_id, &symbols)?;
+            for chunk in metadata_rows.chunks(MAX_INSERT_ROWS) {
+                diesel::insert_into(index_schema::symbol_metadata::table)
+                    .values(chunk)
+                    .execute(conn)
+                    .await
+                    .map_err(|e| UploadError::Storage(e.to_string()))?;
+            }
             Ok(())
         })
         .await
@@ -131,8 +151,9 @@ impl IndexStore {
         &self,
         project_id: i32,
         seq: i32,
-        upload: UploadProject,
+        mut upload: UploadProject,
     ) -> Result<(), UploadError> {
+        schema::upgrade(&mut upload)?;
         if !upload.symbols.is_empty() {
             return Err(UploadError::Invalid(
                 "symbols must be uploaded in phase 1 (header) only".to_string(),
@@ -238,10 +259,12 @@ impl IndexStore {
                         // results would otherwise re-surface against the
                         // pre-mutation state.
                         let purged = index::db_diesel::purge_eph_cache(conn).await?;
+                        let purged_results = invalidate_query_cache(conn).await?;
                         tracing::info!(
                             project_id,
                             purged_layers = purged,
-                            "purged ephemeral layer cache after project finalize"
+                            purged_results,
+                            "purged ephemeral layer and query result caches after project finalize"
                         );
                         Ok(true)
                     }
@@ -367,6 +390,7 @@ async fn create_project(
                 // cache is cleared by the ClearOnDrop guard in
                 // upload_index.
                 let purged = index::db_diesel::purge_eph_cache(conn).await?;
+                invalidate_query_cache(conn).await?;
                 tracing::info!(
                     project_id = existing_id,
                     purged,
@@ -493,6 +517,20 @@ async fn do_upload_objects(
     insert_symbol_refs(conn, &ref_rows).await?;
     tracing::info!("upload_objects: refs done");
 
+    let relation_rows = build_symbol_relations(project_id, &upload.relations)?;
+    tracing::info!(
+        count = relation_rows.len(),
+        "upload_objects: inserting relations"
+    );
+    for chunk in relation_rows.chunks(MAX_INSERT_ROWS) {
+        diesel::insert_into(index_schema::symbol_relations::table)
+            .values(chunk)
+            .on_conflict_do_nothing()
+            .execute(conn)
+            .await?;
+    }
+    tracing::info!("upload_objects: relations done");
+
     Ok(())
 }
 
@@ -551,38 +589,34 @@ fn validate_type(value: i32, valid: &[i32], label: &str) -> Result<i32, UploadEr
     if valid.contains(&value) {
         Ok(value)
     } else {
-        Err(UploadError::Invalid(format!("invalid {} {}", label, value)))
+        Err(UploadError::Invalid(format!(
+            "invalid {} {} (schema_version {}; accepted values are listed at \
+             GET /v1/index/capabilities)",
+            label,
+            value,
+            schema::SCHEMA_VERSION
+        )))
     }
 }
 
 fn validate_symbol_type(proto_type: i32) -> Result<i32, UploadError> {
-    const VALID: &[i32] = &[
-        index::db_diesel::SYMBOL_TYPE_FUNCTION,
-        index::db_diesel::SYMBOL_TYPE_FILE,
-        index::db_diesel::SYMBOL_TYPE_MODULE,
-        index::db_diesel::SYMBOL_TYPE_DIRECTORY,
-        index::db_diesel::SYMBOL_TYPE_TYPE,
-        index::db_diesel::SYMBOL_TYPE_DATA,
-        index::db_diesel::SYMBOL_TYPE_MACRO,
-        index::db_diesel::SYMBOL_TYPE_FIELD,
-    ];
-    validate_type(proto_type, VALID, "symbol type")
+    validate_type(proto_type, SYMBOL_TYPES, "symbol type")
 }
 
 fn validate_instance_type(proto_type: i32) -> Result<i32, UploadError> {
-    const VALID: &[i32] = &[
-        index::db_diesel::INSTANCE_TYPE_DEFINITION,
-        index::db_diesel::INSTANCE_TYPE_DECLARATION,
-        index::db_diesel::INSTANCE_TYPE_EXPANSION,
-        index::db_diesel::INSTANCE_TYPE_SENTINEL,
-        index::db_diesel::INSTANCE_TYPE_CONTAINMENT,
-        index::db_diesel::INSTANCE_TYPE_SOURCE,
-        index::db_diesel::INSTANCE_TYPE_HEADER,
-        index::db_diesel::INSTANCE_TYPE_BUILD,
-        index::db_diesel::INSTANCE_TYPE_FILE,
-        index::db_diesel::INSTANCE_TYPE_DOCUMENTATION,
-    ];
-    validate_type(proto_type, VALID, "instance type")
+    validate_type(proto_type, INSTANCE_TYPES, "instance type")
+}
+
+/// Unspecified (0) stores as NULL: the indexer could not classify the ref.
+fn validate_ref_kind(proto_kind: i32) -> Result<Option<i32>, UploadError> {
+    if proto_kind == 0 {
+        return Ok(None);
+    }
+    validate_type(proto_kind, REF_KINDS, "ref kind").map(Some)
+}
+
+fn validate_relation_kind(proto_kind: i32) -> Result<i32, UploadError> {
+    validate_type(proto_kind, RELATION_KINDS, "relation kind")
 }
 
 fn build_objects(
@@ -736,6 +770,62 @@ fn build_symbols(
     Ok(rows)
 }
 
+/// Metadata rows for the symbols that carry any. Whitespace in signatures is
+/// collapsed so multi-line declarations render on one line; types are stored
+/// in [`normalize_type_text`] form so `returns=` filters compare reliably.
+fn build_symbol_metadata(
+    project_id: i32,
+    symbols: &[UploadSymbol],
+) -> Result<Vec<NewSymbolMetadata>, UploadError> {
+    fn non_empty(text: String) -> Option<String> {
+        (!text.is_empty()).then_some(text)
+    }
+
+    let mut rows = Vec::new();
+    for symbol in symbols {
+        let Some(metadata) = &symbol.metadata else {
+            continue;
+        };
+        let mut param_names = Vec::with_capacity(metadata.parameters.len());
+        let mut param_types = Vec::with_capacity(metadata.parameters.len());
+        for (i, param) in metadata.parameters.iter().enumerate() {
+            let type_text = normalize_type_text(&param.r#type);
+            if type_text.is_empty() {
+                return Err(UploadError::Invalid(format!(
+                    "parameter {} of symbol {} has no type",
+                    i, symbol.local_id
+                )));
+            }
+            param_names.push(param.name.trim().to_string());
+            param_types.push(type_text);
+        }
+        let row = NewSymbolMetadata {
+            symbol: compute_symbol_id(project_id, symbol.local_id)?,
+            signature: non_empty(
+                metadata
+                    .signature
+                    .split_whitespace()
+                    .collect::<Vec<_>>()
+                    .join(" "),
+            ),
+            return_type: non_empty(normalize_type_text(&metadata.return_type)),
+            param_names,
+            param_types,
+            storage_class: non_empty(metadata.storage_class.trim().to_string()),
+            visibility: non_empty(metadata.visibility.trim().to_string()),
+        };
+        let empty = row.signature.is_none()
+            && row.return_type.is_none()
+            && row.param_types.is_empty()
+            && row.storage_class.is_none()
+            && row.visibility.is_none();
+        if !empty {
+            rows.push(row);
+        }
+    }
+    Ok(rows)
+}
+
 fn build_symbol_instances(
     project_id: i32,
     root_layer_id: i64,
@@ -775,17 +865,35 @@ fn build_symbol_refs(
         let object_id = resolve_object_id(object_map, object.local_id)?;
         for reference in &object.refs {
             let symbol_id = compute_symbol_id(project_id, reference.to_symbol_local_id)?;
+            let kind = validate_ref_kind(reference.kind)?;
             rows.push(NewSymbolRef {
                 to_symbol: symbol_id,
                 from_object: object_id,
                 from_offset_range: reference.from_offset_start..reference.from_offset_end,
                 layer: root_layer_id,
+                kind,
             });
         }
     }
     Ok(rows)
 }
 
+fn build_symbol_relations(
+    project_id: i32,
+    relations: &[UploadRelation],
+) -> Result<Vec<NewSymbolRelation>, UploadError> {
+    relations
+        .iter()
+        .map(|relation| {
+            Ok(NewSymbolRelation {
+                from_symbol: compute_symbol_id(project_id, relation.from_symbol_local_id)?,
+                to_symbol: compute_symbol_id(project_id, relation.to_symbol_local_id)?,
+                kind: validate_relation_kind(relation.kind)?,
+            })
+        })
+        .collect()
+}
+
 async fn insert_symbol_instances(
     conn: &mut AsyncPgConnection,
     rows: &[NewSymbolInstance],
@@ -831,12 +939,14 @@ mod tests {
     use std::collections::HashMap;
 
     use crate::proto::askl::index::{
-        Object as UploadObject, Symbol as UploadSymbol, SymbolInstance, SymbolRef,
+        Object as UploadObject, Parameter, RefKind, RelationKind, Symbol as UploadSymbol,
+        SymbolInstance, SymbolMetadata, SymbolRef, SymbolRelation,
     };
 
     use super::{
-        build_objects, build_symbol_instances, build_symbol_refs, build_symbols, compute_symbol_id,
-        resolve_object_id, validate_instance_type, validate_symbol_type, validate_type,
+        build_objects, build_symbol_instances, build_symbol_metadata, build_symbol_refs,
+        build_symbol_relations, build_symbols, compute_symbol_id, resolve_object_id,
+        validate_instance_type, validate_symbol_type, validate_type,
     };
 
     // --- validate_type ---
@@ -942,6 +1052,7 @@ mod tests {
             name: name.to_string(),
             r#type,
             scope,
+            metadata: None,
         }
     }
 
@@ -977,6 +1088,72 @@ mod tests {
         assert!(build_symbols(1, 1000001, &[sym(1, "a", 99, 0)]).is_err());
     }
 
+    // --- build_symbol_metadata ---
+
+    fn sym_with_metadata(local_id: i64, metadata: SymbolMetadata) -> UploadSymbol {
+        UploadSymbol {
+            metadata: Some(metadata),
+            ..sym(local_id, "f", 1, 0)
+        }
+    }
+
+    #[test]
+    fn build_symbol_metadata_normalizes_text() {
+        let metadata = SymbolMetadata {
+            signature: "static char *\ncopy(char *dst,\n     const char *src)".to_string(),
+            return_type: "char  *".to_string(),
+            parameters: vec![
+                Parameter {
+                    name: "dst".to_string(),
+                    r#type: "char *".to_string(),
+                },
+                Parameter {
+                    name: String::new(),
+                    r#type: "const char *".to_string(),
+                },
+            ],
+            storage_class: " static ".to_string(),
+            visibility: String::new(),
+        };
+        let rows = build_symbol_metadata(3, &[sym_with_metadata(7, metadata)]).unwrap();
+        assert_eq!(rows.len(), 1);
+        let row = &rows[0];
+        assert_eq!(row.symbol, (3i64 << 32) | 7);
+        assert_eq!(
+            row.signature.as_deref(),
+            Some("static char * copy(char *dst, const char *src)")
+        );
+        assert_eq!(row.return_type.as_deref(), Some("char*"));
+        assert_eq!(row.param_names, vec!["dst".to_string(), String::new()]);
+        assert_eq!(
+            row.param_types,
+            vec!["char*".to_string(), "const char*".to_string()]
+        );
+        assert_eq!(row.storage_class.as_deref(), Some("static"));
+        assert_eq!(row.visibility, None);
+    }
+
+    #[test]
+    fn build_symbol_metadata_skips_absent_and_empty() {
+        let symbols = [
+            sym(1, "a", 1, 0),
+            sym_with_metadata(2, SymbolMetadata::default()),
+        ];
+        assert!(build_symbol_metadata(1, &symbols).unwrap().is_empty());
+    }
+
+    #[test]
+    fn build_symbol_metadata_untyped_parameter_is_err() {
+        let metadata = SymbolMetadata {
+            parameters: vec![Parameter {
+                name: "x".to_string(),
+                r#type: " ".to_string(),
+            }],
+            ..Default::default()
+        };
+        assert!(build_symbol_metadata(1, &[sym_with_metadata(1, metadata)]).is_err());
+    }
+
     // --- build_objects ---
 
     fn obj(local_id: i64, path: &str, content: &[u8], hash: &str) -> UploadObject {
@@ -1138,6 +1315,7 @@ mod tests {
             to_symbol_local_id,
             from_offset_start: from_start,
             from_offset_end: from_end,
+            kind: 0,
         }
     }
 
@@ -1154,6 +1332,36 @@ mod tests {
         assert_eq!(rows[0].to_symbol, (3i64 << 32) | 20);
         assert_eq!(rows[0].from_object, 50);
         assert_eq!(rows[0].from_offset_range, 3..7);
+        assert_eq!(rows[0].kind, None);
+    }
+
+    #[test]
+    fn build_symbol_refs_keeps_kind() {
+        let object = UploadObject {
+            local_id: 1,
+            refs: vec![SymbolRef {
+                kind: RefKind::Write as i32,
+                ..sref(20, 3, 7)
+            }],
+            ..Default::default()
+        };
+        let obj_map = HashMap::from([(1i64, 50i32)]);
+        let rows = build_symbol_refs(3, 1000001, &[object], &obj_map).unwrap();
+        assert_eq!(rows[0].kind, Some(index::db_diesel::REF_KIND_WRITE));
+    }
+
+    #[test]
+    fn build_symbol_refs_unknown_kind_is_err() {
+        let object = UploadObject {
+            local_id: 1,
+            refs: vec![SymbolRef {
+                kind: 99,
+                ..sref(20, 3, 7)
+            }],
+            ..Default::default()
+        };
+        let obj_map = HashMap::from([(1i64, 50i32)]);
+        assert!(build_symbol_refs(3, 1000001, &[object], &obj_map).is_err());
     }
 
     #[test]
@@ -1176,4 +1384,36 @@ mod tests {
         let obj_map = HashMap::from([(1i64, 1i32)]);
         assert!(build_symbol_refs(1, 1000001, &[object], &obj_map).is_err());
     }
+
+    // --- build_symbol_relations ---
+
+    fn rel(from: i64, to: i64, kind: i32) -> SymbolRelation {
+        SymbolRelation {
+            from_symbol_local_id: from,
+            to_symbol_local_id: to,
+            kind,
+        }
+    }
+
+    #[test]
+    fn build_symbol_relations_maps_local_ids_and_kind() {
+        let rows =
+            build_symbol_relations(2, &[rel(5, 6, RelationKind::Implements as i32)]).unwrap();
+        assert_eq!(rows.len(), 1);
+        assert_eq!(rows[0].from_symbol, compute_symbol_id(2, 5).unwrap());
+        assert_eq!(rows[0].to_symbol, compute_symbol_id(2, 6).unwrap());
+        assert_eq!(rows[0].kind, index::db_diesel::RELATION_KIND_IMPLEMENTS);
+    }
+
+    #[test]
+    fn build_symbol_relations_unspecified_kind_is_err() {
+        assert!(build_symbol_relations(2, &[rel(5, 6, 0)]).is_err());
+        assert!(build_symbol_relations(2, &[rel(5, 6, 99)]).is_err());
+    }
+
+    #[test]
+    fn build_symbol_relations_out_of_range_symbol_is_err() {
+        let kind = RelationKind::Extends as i32;
+        assert!(build_symbol_relations(2, &[rel(1i64 << 33, 6, kind)]).is_err());
+    }
 }
@@ -0,0 +1,591 @@
+//! Offline upload validation. Where the upload path stops at the first bad
+//! row, [`validate_upload`] walks the whole `Project` and reports every
+//! problem it finds, together with summary statistics. It backs
+//! `POST /v1/index/projects?dry_run=true` and `askld index validate`.
+
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+use crate::proto::askl::index::{
+    InstanceType, Object as UploadObject, Project as UploadProject, RefKind, RelationKind,
+    SymbolType,
+};
+
+use super::schema::{
+    self, INSTANCE_TYPES, MAX_SYMBOL_LOCAL_ID, REF_KINDS, RELATION_KINDS, SYMBOL_TYPES,
+};
+use super::{hash_bytes, UploadError};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum IssueKind {
+    /// `schema_version` is newer or older than this server accepts.
+    SchemaVersion,
+    /// A required field (project name, root path, object path) is empty or malformed.
+    MissingField,
+    DuplicateLocalId,
+    /// Two objects have the same `filesystem_path` once normalised.
+    DuplicatePath,
+    LocalIdOutOfRange,
+    UnknownEnumValue,
+    /// An instance, ref or relation names a symbol `local_id` that is not defined.
+    DanglingSymbol,
+    /// A byte range ends before it starts or starts below zero.
+    InvalidRange,
+    /// A byte range runs past the end of the object's content.
+    OutOfBounds,
+    /// Two instances of one symbol and type overlap within an object.
+    DuplicateInstance,
+    ContentHashMismatch,
+    InvalidMetadata,
+}
+
+impl fmt::Display for IssueKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            IssueKind::SchemaVersion => "schema_version",
+            IssueKind::MissingField => "missing_field",
+            IssueKind::DuplicateLocalId => "duplicate_local_id",
+            IssueKind::DuplicatePath => "duplicate_path",
+            IssueKind::LocalIdOutOfRange => "local_id_out_of_range",
+            IssueKind::UnknownEnumValue => "unknown_enum_value",
+            IssueKind::DanglingSymbol => "dangling_symbol",
+            IssueKind::InvalidRange => "invalid_range",
+            IssueKind::OutOfBounds => "out_of_bounds",
+            IssueKind::DuplicateInstance => "duplicate_instance",
+            IssueKind::ContentHashMismatch => "content_hash_mismatch",
+            IssueKind::InvalidMetadata => "invalid_metadata",
+        })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ValidationIssue {
+    pub kind: IssueKind,
+    pub message: String,
+    /// `filesystem_path` of the object the issue was found in.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub object: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ValidationStats {
+    pub symbols: usize,
+    /// Keyed by protobuf enum name; unknown values are keyed by number.
+    pub symbols_by_type: BTreeMap<String, usize>,
+    pub objects: usize,
+    /// Objects whose content was neither embedded nor found in a content
+    /// batch; their ranges are not bounds-checked.
+    pub objects_without_content: usize,
+    pub content_bytes: u64,
+    pub instances: usize,
+    pub refs: usize,
+    pub refs_by_kind: BTreeMap<String, usize>,
+    pub relations: usize,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ValidationReport {
+    /// The revision the upload declares; 0 when unversioned.
+    pub schema_version: u32,
+    pub valid: bool,
+    pub issues: Vec<ValidationIssue>,
+    /// Issue counts by kind, for a quick summary of long reports.
+    pub issue_counts: BTreeMap<IssueKind, usize>,
+    pub stats: ValidationStats,
+}
+
+/// The protobuf name of an enum value, or the raw number when `value` names
+/// no variant.
+pub fn enum_name<E: TryFrom<i32>>(value: i32, name: impl Fn(E) -> &'static str) -> String {
+    E::try_from(value)
+        .map(|e| name(e).to_string())
+        .unwrap_or_else(|_| value.to_string())
+}
+
+struct Collector {
+    issues: Vec<ValidationIssue>,
+}
+
+impl Collector {
+    fn push(&mut self, kind: IssueKind, object: Option<&UploadObject>, message: String) {
+        self.issues.push(ValidationIssue {
+            kind,
+            message,
+            object: object.map(|o| o.filesystem_path.clone()),
+        });
+    }
+
+    fn check_enum(
+        &mut self,
+        value: i32,
+        valid: &[i32],
+        label: &str,
+        object: Option<&UploadObject>,
+    ) {
+        if !valid.contains(&value) {
+            self.push(
+                IssueKind::UnknownEnumValue,
+                object,
+                format!("invalid {} {}", label, value),
+            );
+        }
+    }
+
+    fn check_symbol(
+        &mut self,
+        symbols: &HashSet<i64>,
+        local_id: i64,
+        what: &str,
+        object: Option<&UploadObject>,
+    ) {
+        if !symbols.contains(&local_id) {
+            self.push(
+                IssueKind::DanglingSymbol,
+                object,
+                format!("{} names undefined symbol local_id {}", what, local_id),
+            );
+        }
+    }
+
+    fn check_range(
+        &mut self,
+        start: i32,
+        end: i32,
+        content_len: Option<usize>,
+        what: &str,
+        object: &UploadObject,
+    ) {
+        if start < 0 || end < start {
+            self.push(
+                IssueKind::InvalidRange,
+                Some(object),
+                format!("{} has invalid range {}..{}", what, start, end),
+            );
+        } else if let Some(len) = content_len {
+            if end as usize > len {
+                self.push(
+                    IssueKind::OutOfBounds,
+                    Some(object),
+                    format!(
+                        "{} range {}..{} is beyond content length {}",
+                        what, start, end, len
+                    ),
+                );
+            }
+        }
+    }
+}
+
+/// Check `upload` the way the upload endpoints would, collecting every
+/// problem instead of stopping at the first. `content_lengths` maps content
+/// hashes to byte lengths for objects that carry only `content_hash`; an
+/// object found in neither place is not bounds-checked.
+pub fn validate_upload(
+    mut upload: UploadProject,
+    content_lengths: &HashMap<String, usize>,
+) -> ValidationReport {
+    let mut out = Collector { issues: Vec::new() };
+    let mut stats = ValidationStats::default();
+
+    let schema_version = upload.schema_version;
+    if let Err(UploadError::Invalid(message)) = schema::upgrade(&mut upload) {
+        out.push(IssueKind::SchemaVersion, None, message);
+    }
+    if upload.project_name.trim().is_empty() {
+        out.push(
+            IssueKind::MissingField,
+            None,
+            "project_name is required".to_string(),
+        );
+    }
+    let root_path = upload.root_path.trim();
+    if root_path.is_empty() {
+        out.push(
+            IssueKind::MissingField,
+            None,
+            "root_path is required".to_string(),
+        );
+    } else if !root_path.starts_with('/') {
+        out.push(
+            IssueKind::MissingField,
+            None,
+            "root_path must be an absolute path".to_string(),
+        );
+    }
+
+    let mut symbol_ids = HashSet::new();
+    for symbol in &upload.symbols {
+        stats.symbols += 1;
+        *stats
+            .symbols_by_type
+            .entry(enum_name(symbol.r#type, |t: SymbolType| t.as_str_name()))
+            .or_default() += 1;
+        if !symbol_ids.insert(symbol.local_id) {
+            out.push(
+                IssueKind::DuplicateLocalId,
+                None,
+                format!("duplicate symbol local_id {}", symbol.local_id),
+            );
+        }
+        if symbol.local_id < 0 || symbol.local_id > MAX_SYMBOL_LOCAL_ID {
+            out.push(
+                IssueKind::LocalIdOutOfRange,
+                None,
+                format!(
+                    "symbol local_id {} is out of range [0, 2^32)",
+                    symbol.local_id
+                ),
+            );
+        }
+        out.check_enum(symbol.r#type, SYMBOL_TYPES, "symbol type", None);
+        if let Some(metadata) = &symbol.metadata {
+            for (i, param) in metadata.parameters.iter().enumerate() {
+                if param.r#type.trim().is_empty() {
+                    out.push(
+                        IssueKind::InvalidMetadata,
+                        None,
+                        format!("parameter {} of symbol {} has no type", i, symbol.local_id),
+                    );
+                }
+            }
+        }
+    }
+
+    let mut object_ids = HashSet::new();
+    let mut object_paths = HashSet::new();
+    for object in &upload.objects {
+        stats.objects += 1;
+        if !object_ids.insert(object.local_id) {
+            out.push(
+                IssueKind::DuplicateLocalId,
+                Some(object),
+                format!("duplicate object local_id {}", object.local_id),
+            );
+        }
+        let path = object.filesystem_path.trim();
+        if path.is_empty() {
+            out.push(
+                IssueKind::MissingField,
+                Some(object),
+                format!("filesystem_path is required for object {}", object.local_id),
+            );
+        } else if !path.starts_with('/') {
+            out.push(
+                IssueKind::MissingField,
+                Some(object),
+                format!(
+                    "filesystem_path must be an absolute path for object {}",
+                    object.local_id
+                ),
+            );
+        } else if !object_paths.insert(super::normalize_full_path(path)) {
+            out.push(
+                IssueKind::DuplicatePath,
+                Some(object),
+                format!(
+                    "object {} repeats filesystem_path {}",
+                    object.local_id, path
+                ),
+            );
+        }
+
+        let content_len = if !object.content.is_empty() || object.content_hash.is_empty() {
+            if !object.content_hash.is_empty() {
+                let computed = hash_bytes(&object.content);
+                if computed != object.content_hash {
+                    out.push(
+                        IssueKind::ContentHashMismatch,
+                        Some(object),
+                        format!(
+                            "content_hash mismatch for object {}: sent {} but computed {}",
+                            object.local_id, object.content_hash, computed
+                        ),
+                    );
+                }
+            }
+            Some(object.content.len())
+        } else {
+            content_lengths.get(&object.content_hash).copied()
+        };
+        match content_len {
+            Some(len) => stats.content_bytes += len as u64,
+            None => stats.objects_without_content += 1,
+        }
+
+        for instance in &object.symbol_instances {
+            stats.instances += 1;
+            out.check_symbol(
+                &symbol_ids,
+                instance.symbol_local_id,
+                "instance",
+                Some(object),
+            );
+            if instance.instance_type != 0 {
+                out.check_enum(
+                    instance.instance_type,
+                    INSTANCE_TYPES,
+                    "instance type",
+                    Some(object),
+                );
+            }
+            out.check_range(
+                instance.start_offset,
+                instance.end_offset,
+                content_len,
+                &format!("instance of symbol {}", instance.symbol_local_id),
+                object,
+            );
+        }
+        check_overlapping_instances(&mut out, object);
+
+        for reference in &object.refs {
+            stats.refs += 1;
+            *stats
+                .refs_by_kind
+                .entry(enum_name(reference.kind, |k: RefKind| k.as_str_name()))
+                .or_default() += 1;
+            out.check_symbol(
+                &symbol_ids,
+                reference.to_symbol_local_id,
+                "ref",
+                Some(object),
+            );
+            if reference.kind != 0 {
+                out.check_enum(reference.kind, REF_KINDS, "ref kind", Some(object));
+            }
+            out.check_range(
+                reference.from_offset_start,
+                reference.from_offset_end,
+                content_len,
+                &format!("ref to symbol {}", reference.to_symbol_local_id),
+                object,
+            );
+        }
+    }
+
+    for relation in &upload.relations {
+        stats.relations += 1;
+        let what = format!(
+            "{} relation",
+            enum_name(relation.kind, |k: RelationKind| k.as_str_name())
+        );
+        out.check_symbol(&symbol_ids, relation.from_symbol_local_id, &what, None);
+        out.check_symbol(&symbol_ids, relation.to_symbol_local_id, &what, None);
+        out.check_enum(relation.kind, RELATION_KINDS, "relation kind", None);
+    }
+
+    let mut issue_counts = BTreeMap::new();
+    for issue in &out.issues {
+        *issue_counts.entry(issue.kind).or_default() += 1;
+    }
+    ValidationReport {
+        schema_version,
+        valid: out.issues.is_empty(),
+        issues: out.issues,
+        issue_counts,
+        stats,
+    }
+}
+
+/// Flag instances of the same symbol and type whose ranges overlap; the
+/// upload keeps only one of each exact duplicate and both of any other
+/// overlap, which is rarely what the indexer meant.
+fn check_overlapping_instances(out: &mut Collector, object: &UploadObject) {
+    let mut instances: Vec<_> = object.symbol_instances.iter().collect();
+    instances.sort_by_key(|i| {
+        (
+            i.symbol_local_id,
+            i.instance_type,
+            i.start_offset,
+            i.end_offset,
+        )
+    });
+    for pair in instances.windows(2) {
+        let (a, b) = (pair[0], pair[1]);
+        if a.symbol_local_id != b.symbol_local_id || a.instance_type != b.instance_type {
+            continue;
+        }
+        let identical = a.start_offset == b.start_offset && a.end_offset == b.end_offset;
+        if identical || b.start_offset < a.end_offset {
+            out.push(
+                IssueKind::DuplicateInstance,
+                Some(object),
+                format!(
+                    "{} instances of symbol {} overlap: {}..{} and {}..{}",
+                    enum_name(a.instance_type, |t: InstanceType| t.as_str_name()),
+                    a.symbol_local_id,
+                    a.start_offset,
+                    a.end_offset,
+                    b.start_offset,
+                    b.end_offset
+                ),
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use crate::proto::askl::index::{
+        Object, Project, Symbol, SymbolInstance, SymbolRef, SymbolRelation,
+    };
+
+    use super::{validate_upload, IssueKind};
+
+    fn project() -> Project {
+        Project {
+            project_name: "p".to_string(),
+            root_path: "/p".to_string(),
+            symbols: vec![
+                Symbol {
+                    local_id: 1,
+                    name: "main".to_string(),
+                    r#type: 1,
+                    ..Default::default()
+                },
+                Symbol {
+                    local_id: 2,
+                    name: "helper".to_string(),
+                    r#type: 1,
+                    ..Default::default()
+                },
+            ],
+            objects: vec![Object {
+                local_id: 1,
+                filesystem_path: "/p/main.c".to_string(),
+                content: b"int main() { helper(); }".to_vec(),
+                symbol_instances: vec![SymbolInstance {
+                    symbol_local_id: 1,
+                    instance_type: 1,
+                    start_offset: 0,
+                    end_offset: 24,
+                }],
+                refs: vec![SymbolRef {
+                    to_symbol_local_id: 2,
+                    from_offset_start: 13,
+                    from_offset_end: 19,
+                    kind: 1,
+                }],
+                ..Default::default()
+            }],
+            ..Default::default()
+        }
+    }
+
+    fn kinds(upload: Project) -> Vec<IssueKind> {
+        validate_upload(upload, &HashMap::new())
+            .issues
+            .into_iter()
+            .map(|i| i.kind)
+            .collect()
+    }
+
+    #[test]
+    fn clean_project_is_valid_with_stats() {
+        let report = validate_upload(project(), &HashMap::new());
+        assert!(report.valid, "{:?}", report.issues);
+        assert_eq!(report.stats.symbols, 2);
+        assert_eq!(report.stats.symbols_by_type["FUNCTION"], 2);
+        assert_eq!(report.stats.instances, 1);
+        assert_eq!(report.stats.refs_by_kind["CALL"], 1);
+        assert_eq!(report.stats.content_bytes, 24);
+    }
+
+    #[test]
+    fn reports_every_problem_at_once() {
+        let mut upload = project();
+        upload.symbols[1].r#type = 42;
+        upload.objects[0].refs[0].to_symbol_local_id = 7;
+        upload.objects[0].refs[0].from_offset_end = 100;
+        upload.relations.push(SymbolRelation {
+            from_symbol_local_id: 1,
+            to_symbol_local_id: 9,
+            kind: 1,
+        });
+        let report = validate_upload(upload, &HashMap::new());
+        assert!(!report.valid);
+        assert_eq!(report.issue_counts[&IssueKind::UnknownEnumValue], 1);
+        assert_eq!(report.issue_counts[&IssueKind::DanglingSymbol], 2);
+        assert_eq!(report.issue_counts[&IssueKind::OutOfBounds], 1);
+        assert_eq!(
+            report.issues[2].object.as_deref(),
+            Some("/p/main.c"),
+            "{:?}",
+            report.issues
+        );
+    }
+
+    #[test]
+    fn overlapping_instances_are_flagged() {
+        let mut upload = project();
+        upload.objects[0].symbol_instances.push(SymbolInstance {
+            symbol_local_id: 1,
+            instance_type: 1,
+            start_offset: 4,
+            end_offset: 8,
+        });
+        assert_eq!(kinds(upload), vec![IssueKind::DuplicateInstance]);
+
+        let mut upload = project();
+        upload.objects[0].symbol_instances.push(SymbolInstance {
+            symbol_local_id: 1,
+            instance_type: 2,
+            start_offset: 4,
+            end_offset: 8,
+        });
+        assert!(kinds(upload).is_empty(), "different types may overlap");
+    }
+
+    #[test]
+    fn hash_only_content_uses_supplied_lengths() {
+        let mut upload = project();
+        upload.objects[0].content.clear();
+        upload.objects[0].content_hash = "abc".to_string();
+        let report = validate_upload(upload.clone(), &HashMap::new());
+        assert!(report.valid);
+        assert_eq!(report.stats.objects_without_content, 1);
+
+        let lengths = HashMap::from([("abc".to_string(), 10)]);
+        let report = validate_upload(upload, &lengths);
+        assert_eq!(report.issue_counts[&IssueKind::OutOfBounds], 2);
+    }
+
+    #[test]
+    fn invalid_range_and_hash_mismatch() {
+        let mut upload = project();
+        upload.objects[0].symbol_instances[0].start_offset = 30;
+        upload.objects[0].content_hash = "deadbeef".to_string();
+        assert_eq!(
+            kinds(upload),
+            vec![IssueKind::ContentHashMismatch, IssueKind::InvalidRange]
+        );
+    }
+
+    #[test]
+    fn repeated_path_is_a_duplicate_path() {
+        let mut upload = project();
+        let mut copy = upload.objects[0].clone();
+        copy.local_id = 2;
+        copy.filesystem_path = "/p//main.c".to_string();
+        upload.objects.push(copy);
+        assert_eq!(kinds(upload), vec![IssueKind::DuplicatePath]);
+    }
+
+    #[test]
+    fn issue_kind_display_matches_json() {
+        let json = serde_json::to_value(IssueKind::DanglingSymbol).unwrap();
+        assert_eq!(json, IssueKind::DanglingSymbol.to_string());
+    }
+
+    #[test]
+    fn newer_schema_version_is_reported() {
+        let mut upload = project();
+        upload.schema_version = super::schema::SCHEMA_VERSION + 1;
+        assert_eq!(kinds(upload), vec![IssueKind::SchemaVersion]);
+    }
+}
@@ -1,18 +1,23 @@
 pub mod auth;
 pub mod cfg;
+pub mod check;
 pub mod command;
 pub mod diagnostic;
 pub mod execution_context;
 pub mod execution_state;
+pub mod explain;
 pub mod group;
 pub mod hierarchy;
+pub mod highlight;
 pub mod index_store;
 pub mod line_index;
+pub mod macros;
 pub mod name_pattern;
 pub mod offset_range;
 pub mod parser;
 pub mod parser_context;
 pub mod proto;
+pub mod query_store;
 pub mod scope;
 pub mod span;
 pub mod statement;
@@ -25,11 +30,23 @@ mod all_tests;
 #[cfg(test)]
 mod dependency_test;
 #[cfg(test)]
+mod dispatch_test;
+#[cfg(test)]
 #[cfg(any())] // Disable group tests for now
 mod group_test;
 #[cfg(test)]
 mod parser_test;
 #[cfg(test)]
+mod ref_kind_test;
+#[cfg(test)]
+mod symbol_at_test;
+#[cfg(test)]
+mod symbol_metadata_test;
+#[cfg(test)]
+mod symbol_relations_test;
+#[cfg(test)]
+mod symbol_summary_test;
+#[cfg(test)]
 mod test_util;
 #[cfg(test)]
 mod timeout_test;
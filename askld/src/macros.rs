@@ -0,0 +1,268 @@
+//! Query macros: `define callers_of(x) = func { $x };`.
+//!
+//! A macro is a named statement with `$name` placeholders for its parameters.
+//! A statement consisting of nothing but a call, `callers_of("vfs_read")`, is
+//! replaced at parse time by the macro body built with the arguments bound as
+//! query parameters.  Macros come from the query's own leading `define`s or
+//! from a library loaded by the server (`askld serve --macros`).
+//!
+//! Expansion is hygienic for labels: labels defined or used inside a body get
+//! a per-expansion suffix, so two calls of the same macro — or a macro and its
+//! caller — never see each other's labels.
+
+use crate::parser::{AsklParser, Identifier, NamedArgument, PositionalArgument, QueryParams, Rule};
+use crate::parser_context::ParserContext;
+use crate::span::Span;
+use crate::statement::{build_statement, Statement};
+use crate::verb::is_builtin_verb;
+use pest::error::{Error, ErrorVariant::CustomError};
+use pest::iterators::Pair;
+use pest::Parser;
+use std::collections::{HashMap, HashSet};
+use std::rc::Rc;
+use std::sync::Arc;
+
+/// Deepest allowed chain of macros expanding other macros.  Macros may call
+/// each other, so this is what stops a (mutually) recursive definition.
+const MAX_EXPANSION_DEPTH: usize = 16;
+
+fn custom_error(message: String, span: pest::Span<'_>) -> Error<Rule> {
+    Error::new_from_span(CustomError { message }, span)
+}
+
+#[derive(Debug)]
+pub struct MacroDef {
+    pub name: String,
+    pub params: Vec<String>,
+    /// Source text of the body statement, re-parsed at every expansion.
+    body: Arc<String>,
+}
+
+impl MacroDef {
+    pub(crate) fn build(pair: Pair<Rule>) -> Result<MacroDef, Error<Rule>> {
+        let mut inner = pair.into_inner();
+        let _keyword = inner.next().unwrap();
+        let ident = inner.next().unwrap();
+        let name_span = ident.as_span();
+        let name = Identifier::build(ident)?.0;
+        if is_builtin_verb(&name) {
+            return Err(custom_error(
+                format!("cannot define macro '{}': it is a built-in verb", name),
+                name_span,
+            ));
+        }
+
+        let mut params = vec![];
+        for param in inner.next().unwrap().into_inner() {
+            if params.iter().any(|p| p == param.as_str()) {
+                return Err(custom_error(
+                    format!("duplicate macro parameter '{}'", param.as_str()),
+                    param.as_span(),
+                ));
+            }
+            params.push(param.as_str().to_string());
+        }
+
+        let body = inner.next().unwrap();
+        for placeholder in body.clone().into_inner().flatten() {
+            if placeholder.as_rule() != Rule::param {
+                continue;
+            }
+            let used = placeholder.clone().into_inner().next().unwrap().as_str();
+            if !params.iter().any(|p| p == used) {
+                return Err(custom_error(
+                    format!(
+                        "macro '{}' uses '${}', which is not one of its parameters",
+                        name, used
+                    ),
+                    placeholder.as_span(),
+                ));
+            }
+        }
+
+        Ok(MacroDef {
+            name,
+            params,
+            body: Arc::new(body.as_str().to_string()),
+        })
+    }
+
+    /// The body statement as written in the definition.
+    pub fn body(&self) -> &str {
+        &self.body
+    }
+}
+
+/// A set of macros by name.
+#[derive(Debug, Clone, Default)]
+pub struct MacroLibrary {
+    macros: HashMap<String, Arc<MacroDef>>,
+}
+
+impl MacroLibrary {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Parse a library file: a sequence of `define` declarations and nothing
+    /// else.  Defining the same name twice is an error.
+    pub fn parse(source: &str) -> Result<MacroLibrary, Error<Rule>> {
+        let pairs = AsklParser::parse(Rule::macro_library, source)?;
+        let mut library = MacroLibrary::new();
+        library.define_all(pairs.filter(|p| p.as_rule() == Rule::macro_definition))?;
+        Ok(library)
+    }
+
+    /// Add the definitions in `pairs`.  They may shadow macros already in the
+    /// library (a query overriding a server macro) but not each other.
+    pub(crate) fn define_all<'i>(
+        &mut self,
+        pairs: impl Iterator<Item = Pair<'i, Rule>>,
+    ) -> Result<(), Error<Rule>> {
+        let mut defined = HashSet::new();
+        for pair in pairs {
+            let span = pair.as_span();
+            let def = MacroDef::build(pair)?;
+            if !defined.insert(def.name.clone()) {
+                return Err(custom_error(
+                    format!("macro '{}' is defined more than once", def.name),
+                    span,
+                ));
+            }
+            self.macros.insert(def.name.clone(), Arc::new(def));
+        }
+        Ok(())
+    }
+
+    pub fn get(&self, name: &str) -> Option<&Arc<MacroDef>> {
+        self.macros.get(name)
+    }
+
+    /// All macros, ordered by name.
+    pub fn iter(&self) -> impl Iterator<Item = &MacroDef> {
+        let mut defs: Vec<_> = self.macros.values().map(|d| d.as_ref()).collect();
+        defs.sort_by(|a, b| a.name.cmp(&b.name));
+        defs.into_iter()
+    }
+
+    pub fn len(&self) -> usize {
+        self.macros.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.macros.is_empty()
+    }
+}
+
+/// If `pair` is a statement made of a single call to a known macro, build the
+/// macro body in its place.  Returns `None` for any other statement.
+pub(crate) fn try_expand(
+    ctx: &Rc<ParserContext>,
+    pair: &Pair<Rule>,
+) -> Result<Option<Rc<Statement>>, Error<Rule>> {
+    let mut children = pair.clone().into_inner();
+    let (Some(verb), None) = (children.next(), children.next()) else {
+        return Ok(None);
+    };
+    if verb.as_rule() != Rule::verb {
+        return Ok(None);
+    }
+    let Some(call) = verb.into_inner().next() else {
+        return Ok(None);
+    };
+    if call.as_rule() != Rule::generic_verb {
+        return Ok(None);
+    }
+    let call_span = call.as_span();
+    let mut call = call.into_inner();
+    let ident = call.next().unwrap();
+    let Some(def) = ctx.macros().get(ident.as_str()).cloned() else {
+        return Ok(None);
+    };
+
+    if ctx.macro_depth() >= MAX_EXPANSION_DEPTH {
+        return Err(custom_error(
+            format!(
+                "macro '{}' expands more than {} levels deep; is it recursive?",
+                def.name, MAX_EXPANSION_DEPTH
+            ),
+            call_span,
+        ));
+    }
+
+    let params = bind_arguments(ctx, &def, call, call_span)?;
+
+    let source = def.body.clone();
+    let mut body = AsklParser::parse(Rule::macro_expansion, &source)?;
+    let statement = body.next().unwrap();
+    let expansion_ctx = ParserContext::derive_expansion(
+        ctx.clone(),
+        Span::from_pest(statement.as_span(), source.clone()),
+        source.clone(),
+        params,
+    );
+    // `build_statement` only keeps a weak reference to its parent context;
+    // hold the expansion context until the body is built.
+    build_statement(expansion_ctx.clone(), statement).map(Some)
+}
+
+/// Bind the call's arguments to the macro's parameters: positional arguments
+/// in order, then named ones by name.  Every parameter must be bound once.
+fn bind_arguments(
+    ctx: &Rc<ParserContext>,
+    def: &MacroDef,
+    args: pest::iterators::Pairs<Rule>,
+    call_span: pest::Span<'_>,
+) -> Result<QueryParams, Error<Rule>> {
+    let mut bound: HashMap<&str, crate::parser::Value> = HashMap::new();
+    let mut next_positional = 0;
+    for arg in args {
+        match arg.as_rule() {
+            Rule::positional_argument => {
+                let span = arg.as_span();
+                let value = PositionalArgument::build(arg, ctx.params())?.value;
+                let Some(name) = def.params.get(next_positional) else {
+                    return Err(custom_error(
+                        format!(
+                            "macro '{}' takes {} argument(s)",
+                            def.name,
+                            def.params.len()
+                        ),
+                        span,
+                    ));
+                };
+                next_positional += 1;
+                bound.insert(name, value);
+            }
+            Rule::named_argument => {
+                let span = arg.as_span();
+                let arg = NamedArgument::build(arg, ctx.params())?;
+                let Some(name) = def.params.iter().find(|p| **p == arg.name.0) else {
+                    return Err(custom_error(
+                        format!("macro '{}' has no parameter '{}'", def.name, arg.name.0),
+                        span,
+                    ));
+                };
+                if bound.insert(name, arg.value).is_some() {
+                    return Err(custom_error(
+                        format!("macro parameter '{}' is bound twice", name),
+                        span,
+                    ));
+                }
+            }
+            _ => unreachable!("Unknown rule: {:#?}", arg.as_rule()),
+        }
+    }
+
+    let mut params = QueryParams::new();
+    for name in &def.params {
+        let Some(value) = bound.remove(name.as_str()) else {
+            return Err(custom_error(
+                format!("macro '{}' is missing argument '{}'", def.name, name),
+                call_span,
+            ));
+        };
+        params.insert(name.as_str(), value);
+    }
+    Ok(params)
+}
@@ -1,5 +1,6 @@
 use crate::{
     hierarchy,
+    macros::MacroLibrary,
     parser_context::{ParserContext, ScopeFactory},
     span::Span,
     statement::{build_statement, Statement},
@@ -8,12 +9,13 @@ use anyhow::Result;
 use core::fmt::Debug;
 use pest::{error::Error, Parser};
 use pest_derive::Parser;
+use std::collections::HashMap;
 use std::rc::Rc;
 use std::sync::Arc;
 
 #[derive(Parser)]
 #[grammar = "askl.pest"]
-struct AsklParser;
+pub(crate) struct AsklParser;
 
 #[derive(Debug)]
 pub struct Identifier(pub String);
@@ -75,8 +77,23 @@ impl Value {
         }
     }
 
-    pub fn build(pair: pest::iterators::Pair<Rule>) -> Result<Value, Error<Rule>> {
+    pub fn build(
+        pair: pest::iterators::Pair<Rule>,
+        params: &QueryParams,
+    ) -> Result<Value, Error<Rule>> {
         match pair.as_rule() {
+            Rule::param => {
+                let span = pair.as_span();
+                let name = pair.into_inner().next().unwrap().as_str();
+                params.get(name).cloned().ok_or_else(|| {
+                    Error::new_from_span(
+                        pest::error::ErrorVariant::CustomError {
+                            message: format!("unbound query parameter '${}'", name),
+                        },
+                        span,
+                    )
+                })
+            }
             Rule::string => Ok(Value::Str {
                 kind: StringKind::Plain,
                 text: pair.as_str().into(),
@@ -121,11 +138,96 @@ impl Value {
     }
 }
 
+/// Values bound to `$name` placeholders for a single query.  Binding happens
+/// at the AST level: the value replaces the placeholder wherever a string
+/// literal may appear, so it is type checked by the verb like any literal and
+/// never needs quoting.
+#[derive(Debug, Clone, Default)]
+pub struct QueryParams(HashMap<String, Value>);
+
+impl QueryParams {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn insert(&mut self, name: impl Into<String>, value: Value) {
+        self.0.insert(name.into(), value);
+    }
+
+    pub fn get(&self, name: &str) -> Option<&Value> {
+        self.0.get(name)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// Merge `other` into `self`; bindings in `other` win.
+    pub fn extend(&mut self, other: QueryParams) {
+        self.0.extend(other.0);
+    }
+
+    /// Build parameters from a JSON object.  Strings bind as plain strings,
+    /// numbers and booleans as their textual form, and `{"glob": "..."}` as a
+    /// glob string.  Anything else is rejected.
+    pub fn from_json(json: &serde_json::Value) -> anyhow::Result<Self> {
+        let serde_json::Value::Object(map) = json else {
+            anyhow::bail!("query parameters must be a JSON object");
+        };
+        let mut params = Self::new();
+        for (name, value) in map {
+            let value = match value {
+                serde_json::Value::String(s) => Value::plain(s.as_str()),
+                serde_json::Value::Number(n) => Value::plain(n.to_string()),
+                serde_json::Value::Bool(b) => Value::plain(b.to_string()),
+                serde_json::Value::Object(obj) => match (obj.len(), obj.get("glob")) {
+                    (1, Some(serde_json::Value::String(s))) => Value::Str {
+                        kind: StringKind::Glob,
+                        text: s.clone(),
+                    },
+                    _ => anyhow::bail!(
+                        "parameter '{}': expected {{\"glob\": \"...\"}} for a typed value",
+                        name
+                    ),
+                },
+                _ => anyhow::bail!(
+                    "parameter '{}': expected a string, number, boolean or {{\"glob\": \"...\"}}",
+                    name
+                ),
+            };
+            params.insert(name.as_str(), value);
+        }
+        Ok(params)
+    }
+
+    /// The inverse of [`QueryParams::from_json`], with keys in sorted order,
+    /// so equal bindings always serialize identically.
+    pub fn to_json(&self) -> serde_json::Value {
+        let mut names: Vec<&String> = self.0.keys().collect();
+        names.sort();
+        let mut map = serde_json::Map::new();
+        for name in names {
+            let value = match &self.0[name] {
+                Value::Str {
+                    kind: StringKind::Plain,
+                    text,
+                } => serde_json::Value::String(text.clone()),
+                Value::Str {
+                    kind: StringKind::Glob,
+                    text,
+                } => serde_json::json!({ "glob": text }),
+            };
+            map.insert(name.clone(), value);
+        }
+        serde_json::Value::Object(map)
+    }
+}
+
 /// Look up an optional named argument and require it to be a plain string.
 /// Collapses the `named.get(k).map(|v| v.as_plain()).transpose()?` pattern
 /// that verb constructors would otherwise repeat.
 pub fn named_plain<'a>(
-    named: &'a std::collections::HashMap<String, Value>,
+    named: &'a HashMap<String, Value>,
     key: &str,
 ) -> anyhow::Result<Option<&'a str>> {
     named.get(key).map(|v| v.as_plain()).transpose()
@@ -138,12 +240,15 @@ pub struct NamedArgument {
 }
 
 impl NamedArgument {
-    pub fn build(pair: pest::iterators::Pair<Rule>) -> Result<NamedArgument, Error<Rule>> {
+    pub fn build(
+        pair: pest::iterators::Pair<Rule>,
+        params: &QueryParams,
+    ) -> Result<NamedArgument, Error<Rule>> {
         let mut pair = pair.into_inner();
         let ident = pair.next().unwrap();
         let ident = Identifier::build(ident)?;
         let value = pair.next().unwrap();
-        let value = Value::build(value)?;
+        let value = Value::build(value, params)?;
         Ok(NamedArgument {
             name: ident,
             value: value,
@@ -157,19 +262,47 @@ pub struct PositionalArgument {
 }
 
 impl PositionalArgument {
-    pub fn build(pair: pest::iterators::Pair<Rule>) -> Result<Self, Error<Rule>> {
+    pub fn build(
+        pair: pest::iterators::Pair<Rule>,
+        params: &QueryParams,
+    ) -> Result<Self, Error<Rule>> {
         let mut pair = pair.into_inner();
         let value = pair.next().unwrap();
-        let value = Value::build(value)?;
+        let value = Value::build(value, params)?;
         Ok(Self { value })
     }
 }
 
 pub fn parse(ask_code: &str) -> Result<Rc<Statement>, pest::error::Error<Rule>> {
+    parse_with_params(ask_code, QueryParams::new())
+}
+
+/// Parse a query, binding its `$name` placeholders from `params`.  An unbound
+/// placeholder is a parse error pointing at the placeholder.
+pub fn parse_with_params(
+    ask_code: &str,
+    params: QueryParams,
+) -> Result<Rc<Statement>, pest::error::Error<Rule>> {
+    parse_with_macros(ask_code, params, &MacroLibrary::new())
+}
+
+/// Parse a query with `macros` callable in addition to the query's own
+/// `define`s, which take precedence.
+pub fn parse_with_macros(
+    ask_code: &str,
+    params: QueryParams,
+    macros: &MacroLibrary,
+) -> Result<Rc<Statement>, pest::error::Error<Rule>> {
     let source = Arc::new(ask_code.to_string());
     let pairs = AsklParser::parse(Rule::ask, &source)?;
+    let (definitions, pairs): (Vec<_>, Vec<_>) = pairs
+        .into_iter()
+        .partition(|pair| pair.as_rule() == Rule::macro_definition);
+
+    let mut macros = macros.clone();
+    macros.define_all(definitions.into_iter())?;
 
-    let ctx = ParserContext::new(source.clone(), ScopeFactory::Children);
+    let ctx = ParserContext::with_bindings(source.clone(), ScopeFactory::Children, params, macros);
     let mut ast = vec![];
     for pair in pairs {
         match pair.as_rule() {
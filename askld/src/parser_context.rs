@@ -1,6 +1,8 @@
 use crate::{
     command::Command,
     execution_state::RelationshipType,
+    macros::MacroLibrary,
+    parser::QueryParams,
     scope::{DefaultScope, Scope},
     span::Span,
     statement::Statement,
@@ -8,7 +10,7 @@ use crate::{
 };
 use anyhow::Result;
 use std::{
-    cell::RefCell,
+    cell::{Cell, RefCell},
     rc::{Rc, Weak},
     sync::Arc,
 };
@@ -64,10 +66,38 @@ pub struct ParserContext {
     /// Set by LayerVerb::update_context(), inherited by derive() so child
     /// contexts push ops to the same vec.
     eph_ops: RefCell<Option<EphemeralOps>>,
+    /// Values bound to `$name` placeholders, shared by all derived contexts.
+    /// Inside a macro expansion these are the macro's arguments instead.
+    params: Rc<QueryParams>,
+    /// Macros callable from this query: the server library plus the query's
+    /// own `define`s.
+    macros: Rc<MacroLibrary>,
+    /// Set inside a macro expansion: suffix appended to every label name so
+    /// labels in the body never clash with the caller's or another expansion's.
+    label_suffix: Option<Rc<str>>,
+    /// Nesting depth of macro expansions, bounded to stop recursive macros.
+    macro_depth: usize,
+    /// Number of macro expansions so far in this query (source of unique label
+    /// suffixes).
+    expansions: Rc<Cell<usize>>,
 }
 
 impl ParserContext {
     pub fn new(source: Arc<String>, scope_factory: ScopeFactory) -> Rc<Self> {
+        Self::with_bindings(
+            source,
+            scope_factory,
+            QueryParams::new(),
+            MacroLibrary::new(),
+        )
+    }
+
+    pub fn with_bindings(
+        source: Arc<String>,
+        scope_factory: ScopeFactory,
+        params: QueryParams,
+        macros: MacroLibrary,
+    ) -> Rc<Self> {
         let command = Command::new(Span::entire(source.clone()));
         Rc::new(Self {
             source,
@@ -80,12 +110,56 @@ impl ParserContext {
             has_relationship_modifier: RefCell::new(false),
             inherit_relationship_modifier: RefCell::new(false),
             eph_ops: RefCell::new(None),
+            params: Rc::new(params),
+            macros: Rc::new(macros),
+            label_suffix: None,
+            macro_depth: 0,
+            expansions: Rc::new(Cell::new(0)),
         })
     }
 
     pub fn derive(from: Rc<Self>, span: Span) -> Rc<Self> {
+        let source = from.source.clone();
+        let params = from.params.clone();
+        let label_suffix = from.label_suffix.clone();
+        let macro_depth = from.macro_depth;
+        Self::derive_with(from, span, source, params, label_suffix, macro_depth)
+    }
+
+    /// Derive the context a macro body is built in: the body's own source,
+    /// the call's arguments as parameters, and a fresh label suffix.  Other
+    /// state (relationship type, inherited verbs) flows in from the call site
+    /// as if the body had been written there.
+    pub(crate) fn derive_expansion(
+        from: Rc<Self>,
+        span: Span,
+        source: Arc<String>,
+        params: QueryParams,
+    ) -> Rc<Self> {
+        let n = from.expansions.get() + 1;
+        from.expansions.set(n);
+        let label_suffix: Rc<str> = format!("#{}", n).into();
+        let macro_depth = from.macro_depth + 1;
+        Self::derive_with(
+            from,
+            span,
+            source,
+            Rc::new(params),
+            Some(label_suffix),
+            macro_depth,
+        )
+    }
+
+    fn derive_with(
+        from: Rc<Self>,
+        span: Span,
+        source: Arc<String>,
+        params: Rc<QueryParams>,
+        label_suffix: Option<Rc<str>>,
+        macro_depth: usize,
+    ) -> Rc<Self> {
         Rc::new(Self {
-            source: from.source.clone(),
+            source,
             prev: Some(Rc::downgrade(&from)),
             alternative_context: RefCell::new(from.alternative_context.borrow().clone()),
             command: RefCell::new(from.command.borrow().derive(span)),
@@ -101,6 +175,11 @@ impl ParserContext {
                 *from.inherit_relationship_modifier.borrow(),
             ),
             eph_ops: RefCell::new(from.get_eph_ops()),
+            params,
+            macros: from.macros.clone(),
+            label_suffix,
+            macro_depth,
+            expansions: from.expansions.clone(),
         })
     }
 
@@ -147,7 +226,7 @@ impl ParserContext {
     pub fn command(&self, span: Span) -> Command {
         let mut command = self.command.take();
         if command.selectors().count() == 0 {
-            command.extend(UnitVerb::new(span.clone()));
+            command.extend(UnitVerb::implicit(span.clone()));
         }
         command
     }
@@ -166,6 +245,27 @@ impl ParserContext {
         self.source.clone()
     }
 
+    pub fn params(&self) -> &QueryParams {
+        &self.params
+    }
+
+    pub fn macros(&self) -> &MacroLibrary {
+        &self.macros
+    }
+
+    pub fn macro_depth(&self) -> usize {
+        self.macro_depth
+    }
+
+    /// The name a label written as `name` gets in this context.  Outside macro
+    /// expansions it is unchanged.
+    pub fn label_name(&self, name: &str) -> String {
+        match &self.label_suffix {
+            Some(suffix) => format!("{}{}", name, suffix),
+            None => name.to_string(),
+        }
+    }
+
     /// Set the relationship type explicitly from a verb (@has or @refs).
     /// Marks that a relationship modifier was used, affecting child scope behavior.
     pub fn set_relationship_type_explicit(&self, rel_type: RelationshipType) {
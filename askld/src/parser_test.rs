@@ -1,4 +1,5 @@
-use crate::parser::parse;
+use crate::macros::MacroLibrary;
+use crate::parser::{parse, parse_with_macros, parse_with_params, QueryParams, Value};
 
 #[test]
 fn parse_query() {
@@ -268,3 +269,154 @@ fn extra_semicolons_do_not_create_statements() {
     assert_eq!(stmts.len(), 1); // just "foo" + scope
     assert_eq!(stmts[0].scope().statements().count(), 1); // just "bar"
 }
+
+// === Query parameters ===
+
+fn params(pairs: &[(&str, &str)]) -> QueryParams {
+    let mut params = QueryParams::new();
+    for (name, value) in pairs {
+        params.insert(*name, Value::plain(*value));
+    }
+    params
+}
+
+#[test]
+fn param_binds_selector_and_arguments() {
+    let ast = parse_with_params(
+        "$fn { func; project($proj) }",
+        params(&[("fn", "vfs_read"), ("proj", "linux")]),
+    )
+    .unwrap();
+    assert_eq!(ast.scope().statements().count(), 1);
+}
+
+#[test]
+fn unbound_param_is_error() {
+    let err = parse_with_params("$fn { }", QueryParams::new()).unwrap_err();
+    assert!(err.to_string().contains("unbound query parameter '$fn'"));
+}
+
+#[test]
+fn param_value_is_not_reparsed() {
+    // A value containing quotes and braces is a single literal, not query text.
+    let ast = parse_with_params(r#"$q"#, params(&[("q", r#"a" } { "b"#)])).unwrap();
+    assert_eq!(ast.scope().statements().count(), 1);
+}
+
+#[test]
+fn param_is_type_checked_by_argument_position() {
+    // `search` accepts only plain strings; a glob-typed parameter is rejected.
+    let json = serde_json::json!({ "q": { "glob": "vfs_*" } });
+    let params = QueryParams::from_json(&json).unwrap();
+    assert!(parse_with_params("search($q)", params.clone()).is_err());
+    assert!(parse_with_params("$q", params).is_ok());
+}
+
+#[test]
+fn params_from_json_rejects_unsupported_types() {
+    assert!(QueryParams::from_json(&serde_json::json!({ "a": [1] })).is_err());
+    assert!(QueryParams::from_json(&serde_json::json!({ "a": null })).is_err());
+    assert!(QueryParams::from_json(&serde_json::json!({ "a": { "regex": "x" } })).is_err());
+    assert!(QueryParams::from_json(&serde_json::json!(["a"])).is_err());
+    let params = QueryParams::from_json(&serde_json::json!({ "n": 5, "b": true })).unwrap();
+    assert_eq!(params.get("n").unwrap().as_plain().unwrap(), "5");
+    assert_eq!(params.get("b").unwrap().as_plain().unwrap(), "true");
+}
+
+#[test]
+fn params_to_json_round_trips_sorted() {
+    let json = serde_json::json!({ "z": "a", "a": { "glob": "vfs_*" } });
+    let params = QueryParams::from_json(&json).unwrap();
+    assert_eq!(params.to_json(), json);
+    assert_eq!(
+        params.to_json().to_string(),
+        r#"{"a":{"glob":"vfs_*"},"z":"a"}"#
+    );
+}
+
+// === Macros ===
+
+#[test]
+fn macro_call_expands_body() {
+    let ast = parse(
+        "define callers_of(x) = func { $x }\ncallers_of(\"vfs_read\")\ncallers_of(x=\"vfs_write\")",
+    )
+    .unwrap();
+    let statements: Vec<_> = ast.scope().statements().collect();
+    assert_eq!(statements.len(), 2);
+    // Each expansion is the body statement: a `func` with one nested statement.
+    for statement in statements {
+        assert_eq!(statement.scope().statements().count(), 1);
+    }
+}
+
+#[test]
+fn macro_from_library_and_query_shadowing() {
+    let library = MacroLibrary::parse("define sel(x) = $x\n\ndefine callees(x) = $x { };").unwrap();
+    assert_eq!(library.len(), 2);
+    let ast = parse_with_macros("callees(\"a\")", QueryParams::new(), &library).unwrap();
+    assert_eq!(ast.scope().statements().count(), 1);
+
+    // A query-local define shadows the library macro of the same name.
+    let ast = parse_with_macros(
+        "define callees() = \"b\"; callees",
+        QueryParams::new(),
+        &library,
+    )
+    .unwrap();
+    let statement = ast.scope().statements().next().unwrap();
+    assert_eq!(statement.scope().statements().count(), 0);
+}
+
+#[test]
+fn macro_arguments_may_be_query_params() {
+    let ast = parse_with_params(
+        "define callers_of(x) = func { $x }; callers_of($fn)",
+        params(&[("fn", "vfs_read")]),
+    )
+    .unwrap();
+    assert_eq!(ast.scope().statements().count(), 1);
+}
+
+#[test]
+fn macro_labels_are_hygienic() {
+    let ast = parse("define tagged(x) = @a $x\n@a \"c\"\ntagged(\"f\")\ntagged(\"g\")").unwrap();
+    let labels: Vec<_> = ast
+        .scope()
+        .statements()
+        .flat_map(|s| s.command().get_labels())
+        .collect();
+    assert_eq!(labels, vec!["a", "a#1", "a#2"]);
+}
+
+#[test]
+fn macro_errors() {
+    let cases = [
+        ("define f(x) = $y; f(\"a\")", "not one of its parameters"),
+        ("define f(x, x) = $x", "duplicate macro parameter"),
+        ("define func(x) = $x", "built-in verb"),
+        (
+            "define f(x) = $x\ndefine f(y) = $y",
+            "defined more than once",
+        ),
+        ("define f(x) = $x; f", "missing argument 'x'"),
+        ("define f(x) = $x; f(\"a\", \"b\")", "takes 1 argument(s)"),
+        ("define f(x) = $x; f(y=\"a\")", "no parameter 'y'"),
+        ("define f(x) = $x; f(\"a\") { }", "statement of its own"),
+        ("define f() = f; f", "levels deep"),
+    ];
+    for (query, expected) in cases {
+        let err = match parse(query) {
+            Ok(_) => panic!("expected an error for {:?}", query),
+            Err(err) => err.to_string(),
+        };
+        assert!(err.contains(expected), "{:?}: {}", query, err);
+    }
+}
+
+#[test]
+fn define_prefix_is_not_a_keyword() {
+    // `defined` is an ordinary (unknown) verb, not `define d`.
+    let err = parse("defined(\"x\")").unwrap_err().to_string();
+    assert!(err.contains("unknown verb"), "{}", err);
+}
@@ -0,0 +1,410 @@
+//! Persistent askl query result cache and per-user query history.
+//!
+//! ## What is cached
+//!
+//! The rendered response body of a successful query (JSON graph or markdown),
+//! keyed by everything that shapes it: the cache generation, the visible
+//! projects' root layers (their versions), the normalized query text, its
+//! bound parameters, the effective symbol limit, the output format and
+//! projection, and the server's macro library.  Errors are never cached.
+//!
+//! Unlike the in-RAM [`SqlResultCache`](index::db_diesel::SqlResultCache),
+//! entries live in Postgres, so they survive restarts and are shared by every
+//! replica pointing at the same database.
+//!
+//! ## Normalization
+//!
+//! Result nodes carry byte spans into the query text (`query_statements`),
+//! so normalization is limited to edits that move no offset: trailing
+//! whitespace is dropped, nothing else.
+//!
+//! ## Invalidation
+//!
+//! Mutation paths call [`invalidate_query_cache`] inside the transaction
+//! that also runs `purge_eph_cache`.  It bumps `queries.cache_generation` and
+//! drops every entry.  Lookups hash the generation read BEFORE the query
+//! runs, and [`QueryStore::store`] inserts only while that generation is
+//! still current, so a result computed from pre-mutation data is neither
+//! served nor stored after the mutation commits.  As with the ephemeral-layer
+//! cache, chunks landing in a project that is still uploading do not
+//! invalidate; its finalize does.
+
+use chrono::{DateTime, Utc};
+use diesel::prelude::*;
+use diesel::OptionalExtension;
+use diesel_async::pooled_connection::{bb8, bb8::Pool};
+use diesel_async::{AsyncPgConnection, RunQueryDsl};
+use index::db_diesel::RootLayer;
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use uuid::Uuid;
+
+use crate::index_store::StoreError;
+use crate::macros::MacroLibrary;
+use crate::parser::QueryParams;
+
+mod schema {
+    diesel::table! {
+        queries.cache_generation (singleton) {
+            singleton -> Bool,
+            generation -> BigInt,
+        }
+    }
+
+    diesel::table! {
+        queries.result_cache (key) {
+            key -> Bytea,
+            body -> Text,
+            created_at -> Timestamptz,
+            last_hit_at -> Timestamptz,
+            hits -> BigInt,
+        }
+    }
+
+    diesel::table! {
+        queries.history (id) {
+            id -> BigInt,
+            user_id -> Uuid,
+            query -> Text,
+            params -> Nullable<Text>,
+            output -> Text,
+            status -> Text,
+            cache_hit -> Bool,
+            duration_ms -> BigInt,
+            total_symbols -> Nullable<BigInt>,
+            created_at -> Timestamptz,
+        }
+    }
+}
+
+use schema::{cache_generation, history, result_cache};
+
+/// Drop trailing whitespace — the only edit that leaves every byte offset
+/// into the query text unchanged.
+pub fn normalize_query(query: &str) -> &str {
+    query.trim_end()
+}
+
+/// Everything that determines a cached response body.
+pub struct CacheKeyInput<'a> {
+    pub generation: i64,
+    pub roots: &'a [RootLayer],
+    pub query: &'a str,
+    pub params: &'a QueryParams,
+    /// The effective symbol cap (`0` = unlimited), after server defaults.
+    pub limit: usize,
+    /// Output format and projection, e.g. `json` or `markdown:signature`.
+    pub output: &'a str,
+    pub macros: &'a MacroLibrary,
+    /// Whether the query runs in an MCP session, where a cached body is only
+    /// valid if the run left the session as empty as it found it.
+    pub session: bool,
+}
+
+impl CacheKeyInput<'_> {
+    /// SHA-256 over every component.  Variable-length fields are
+    /// length-prefixed so adjacent fields cannot run into each other.
+    pub fn digest(&self) -> Vec<u8> {
+        fn field(hasher: &mut Sha256, bytes: &[u8]) {
+            hasher.update((bytes.len() as u64).to_le_bytes());
+            hasher.update(bytes);
+        }
+
+        let mut hasher = Sha256::new();
+        hasher.update(self.generation.to_le_bytes());
+        hasher.update((self.roots.len() as u64).to_le_bytes());
+        for root in self.roots {
+            hasher.update(root.id.to_le_bytes());
+            field(&mut hasher, &root.hash);
+        }
+        field(&mut hasher, normalize_query(self.query).as_bytes());
+        field(&mut hasher, self.params.to_json().to_string().as_bytes());
+        hasher.update((self.limit as u64).to_le_bytes());
+        field(&mut hasher, self.output.as_bytes());
+        hasher.update((self.macros.len() as u64).to_le_bytes());
+        for def in self.macros.iter() {
+            field(&mut hasher, def.name.as_bytes());
+            field(&mut hasher, def.params.join(",").as_bytes());
+            field(&mut hasher, def.body().as_bytes());
+        }
+        hasher.update([self.session as u8]);
+        hasher.finalize().to_vec()
+    }
+}
+
+/// Bump the cache generation and drop every cached result.  Run this inside
+/// the transaction that mutates the persistent index, next to
+/// `purge_eph_cache`, so the invalidation commits (or rolls back) with it.
+pub async fn invalidate_query_cache(
+    conn: &mut AsyncPgConnection,
+) -> Result<usize, diesel::result::Error> {
+    diesel::update(cache_generation::table)
+        .set(cache_generation::generation.eq(cache_generation::generation + 1))
+        .execute(&mut *conn)
+        .await?;
+    diesel::delete(result_cache::table).execute(conn).await
+}
+
+/// One query run, as recorded in the history.  Only authenticated runs are
+/// recorded: an anonymous one could never be listed.
+#[derive(Debug, Insertable)]
+#[diesel(table_name = history)]
+pub struct HistoryRecord {
+    pub user_id: Uuid,
+    pub query: String,
+    /// Bound parameters as a JSON object, when any were given.
+    pub params: Option<String>,
+    pub output: String,
+    /// `ok`, `error`, `timeout` or `cancelled`.
+    pub status: String,
+    pub cache_hit: bool,
+    pub duration_ms: i64,
+    pub total_symbols: Option<i64>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct HistoryEntry {
+    pub id: i64,
+    pub query: String,
+    pub params: Option<serde_json::Value>,
+    pub output: String,
+    pub status: String,
+    pub cache_hit: bool,
+    pub duration_ms: i64,
+    pub total_symbols: Option<i64>,
+    pub created_at: String,
+}
+
+#[derive(Debug, Queryable)]
+struct HistoryRow {
+    id: i64,
+    query: String,
+    params: Option<String>,
+    output: String,
+    status: String,
+    cache_hit: bool,
+    duration_ms: i64,
+    total_symbols: Option<i64>,
+    created_at: DateTime<Utc>,
+}
+
+#[derive(Clone)]
+pub struct QueryStore {
+    pool: Pool<AsyncPgConnection>,
+    /// Whether results are cached; history is recorded regardless.
+    cache_enabled: bool,
+}
+
+impl QueryStore {
+    pub fn from_pool(pool: Pool<AsyncPgConnection>, cache_enabled: bool) -> Self {
+        Self {
+            pool,
+            cache_enabled,
+        }
+    }
+
+    pub fn cache_enabled(&self) -> bool {
+        self.cache_enabled
+    }
+
+    async fn get_conn(&self) -> Result<bb8::PooledConnection<'_, AsyncPgConnection>, StoreError> {
+        self.pool
+            .get()
+            .await
+            .map_err(|err| StoreError::Storage(err.to_string()))
+    }
+
+    /// The current cache generation; read it before running a query.
+    pub async fn generation(&self) -> Result<i64, StoreError> {
+        let mut conn = self.get_conn().await?;
+        Ok(cache_generation::table
+            .select(cache_generation::generation)
+            .first(&mut conn)
+            .await?)
+    }
+
+    /// The cached body for `key`, counting the hit.
+    pub async fn lookup(&self, key: &[u8]) -> Result<Option<String>, StoreError> {
+        let mut conn = self.get_conn().await?;
+        Ok(diesel::update(result_cache::table.find(key))
+            .set((
+                result_cache::hits.eq(result_cache::hits + 1),
+                result_cache::last_hit_at.eq(Utc::now()),
+            ))
+            .returning(result_cache::body)
+            .get_result(&mut conn)
+            .await
+            .optional()?)
+    }
+
+    /// Cache `body` under `key`, unless the generation the key was computed
+    /// with is no longer current.  Returns whether a row was inserted.
+    pub async fn store(&self, key: &[u8], generation: i64, body: &str) -> Result<bool, StoreError> {
+        use diesel::sql_types::{BigInt, Bytea, Text};
+        let mut conn = self.get_conn().await?;
+        let inserted = diesel::sql_query(
+            "INSERT INTO queries.result_cache (key, body) \
+             SELECT $1, $2 FROM queries.cache_generation WHERE generation = $3 \
+             ON CONFLICT (key) DO NOTHING",
+        )
+        .bind::<Bytea, _>(key)
+        .bind::<Text, _>(body)
+        .bind::<BigInt, _>(generation)
+        .execute(&mut conn)
+        .await?;
+        Ok(inserted > 0)
+    }
+
+    pub async fn record(&self, record: HistoryRecord) -> Result<(), StoreError> {
+        let mut conn = self.get_conn().await?;
+        diesel::insert_into(history::table)
+            .values(record)
+            .execute(&mut conn)
+            .await?;
+        Ok(())
+    }
+
+    /// A user's most recent queries, newest first.
+    pub async fn history(
+        &self,
+        user_id: Uuid,
+        limit: i64,
+    ) -> Result<Vec<HistoryEntry>, StoreError> {
+        let mut conn = self.get_conn().await?;
+        let rows: Vec<HistoryRow> = history::table
+            .filter(history::user_id.eq(user_id))
+            .order((history::created_at.desc(), history::id.desc()))
+            .limit(limit)
+            .select((
+                history::id,
+                history::query,
+                history::params,
+                history::output,
+                history::status,
+                history::cache_hit,
+                history::duration_ms,
+                history::total_symbols,
+                history::created_at,
+            ))
+            .load(&mut conn)
+            .await?;
+        Ok(rows
+            .into_iter()
+            .map(|row| HistoryEntry {
+                id: row.id,
+                query: row.query,
+                params: row
+                    .params
+                    .and_then(|params| serde_json::from_str(&params).ok()),
+                output: row.output,
+                status: row.status,
+                cache_hit: row.cache_hit,
+                duration_ms: row.duration_ms,
+                total_symbols: row.total_symbols,
+                created_at: row.created_at.to_rfc3339(),
+            })
+            .collect())
+    }
+
+    /// Drop cache entries not hit within `cache_ttl` and history older than
+    /// `history_ttl`.  Returns `(cache entries, history rows)` removed.
+    pub async fn prune(
+        &self,
+        cache_ttl: std::time::Duration,
+        history_ttl: std::time::Duration,
+    ) -> Result<(usize, usize), StoreError> {
+        let cutoff = |ttl: std::time::Duration| {
+            Utc::now() - chrono::Duration::from_std(ttl).unwrap_or(chrono::Duration::MAX)
+        };
+        let mut conn = self.get_conn().await?;
+        let cache = diesel::delete(
+            result_cache::table.filter(result_cache::last_hit_at.lt(cutoff(cache_ttl))),
+        )
+        .execute(&mut conn)
+        .await?;
+        let history =
+            diesel::delete(history::table.filter(history::created_at.lt(cutoff(history_ttl))))
+                .execute(&mut conn)
+                .await?;
+        Ok((cache, history))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::Value;
+
+    fn root(id: i64) -> RootLayer {
+        RootLayer {
+            id,
+            project_id: id as i32,
+            hash: vec![id as u8; 4],
+        }
+    }
+
+    fn key(generation: i64, roots: &[RootLayer], query: &str, params: &QueryParams) -> Vec<u8> {
+        CacheKeyInput {
+            generation,
+            roots,
+            query,
+            params,
+            limit: 100,
+            output: "json",
+            macros: &MacroLibrary::new(),
+            session: false,
+        }
+        .digest()
+    }
+
+    #[test]
+    fn trailing_whitespace_does_not_change_the_key() {
+        let params = QueryParams::new();
+        let roots = [root(1)];
+        assert_eq!(
+            key(0, &roots, "\"a\" {}", &params),
+            key(0, &roots, "\"a\" {}\n\n  ", &params)
+        );
+        assert_ne!(
+            key(0, &roots, "\"a\" {}", &params),
+            key(0, &roots, " \"a\" {}", &params)
+        );
+    }
+
+    #[test]
+    fn key_covers_generation_projects_and_params() {
+        let mut params = QueryParams::new();
+        params.insert("name", Value::plain("vfs_read"));
+        let base = key(0, &[root(1)], "$name", &params);
+
+        assert_ne!(base, key(1, &[root(1)], "$name", &params));
+        assert_ne!(base, key(0, &[root(2)], "$name", &params));
+        assert_ne!(base, key(0, &[root(1), root(2)], "$name", &params));
+
+        let mut other = QueryParams::new();
+        other.insert("name", Value::plain("vfs_write"));
+        assert_ne!(base, key(0, &[root(1)], "$name", &other));
+    }
+
+    #[test]
+    fn key_covers_limit_output_and_macros() {
+        let params = QueryParams::new();
+        let roots = [root(1)];
+        let macros = MacroLibrary::parse("define f(x) = $x").unwrap();
+        let input = |limit, output, macros| CacheKeyInput {
+            generation: 0,
+            roots: &roots,
+            query: "f(\"a\")",
+            params: &params,
+            limit,
+            output,
+            macros,
+            session: false,
+        };
+        let empty = MacroLibrary::new();
+        let base = input(100, "json", &empty).digest();
+        assert_ne!(base, input(0, "json", &empty).digest());
+        assert_ne!(base, input(100, "markdown:names", &empty).digest());
+        assert_ne!(base, input(100, "json", &macros).digest());
+    }
+}
@@ -0,0 +1,102 @@
+use crate::test_util::{run_query, run_query_err, TEST_INPUT_REF_KINDS};
+use index::symbols::{RefKind, SymbolInstanceId};
+
+// Test fixture (test_input_ref_kinds.sql), all in kinds.c:
+//
+// level (Data) inst=103     limit (Data) inst=104
+// get    inst=105  reads level
+// set    inst=106  writes level, reads limit, calls get
+// where  inst=107  takes &level
+// legacy inst=108  refs level, unclassified (kind NULL)
+
+#[test]
+fn refs_kind_write_finds_writers() {
+    const QUERY: &str = r#"func refs(kind="write") { data("level") }"#;
+    let res = run_query(TEST_INPUT_REF_KINDS, QUERY);
+
+    let nodes = res.nodes.as_vec();
+    println!("refs_kind_write_finds_writers nodes: {:?}", nodes);
+
+    assert!(nodes.contains(&SymbolInstanceId::new(103)), "level");
+    assert!(
+        nodes.contains(&SymbolInstanceId::new(106)),
+        "set writes level"
+    );
+    for (id, name) in [(105, "get"), (107, "where"), (108, "legacy")] {
+        assert!(
+            !nodes.contains(&SymbolInstanceId::new(id)),
+            "{} does not write level",
+            name
+        );
+    }
+
+    let kinds: Vec<_> = res
+        .edges
+        .as_vec()
+        .into_iter()
+        .map(|(f, t, _, kind)| (f.instance_id, t.instance_id, kind))
+        .collect();
+    assert_eq!(
+        kinds,
+        vec![(
+            SymbolInstanceId::new(106),
+            SymbolInstanceId::new(103),
+            Some(RefKind::Write)
+        )]
+    );
+}
+
+#[test]
+fn refs_kind_list_is_a_union() {
+    const QUERY: &str = r#"func refs(kind="read, address-taken") { data("level") }"#;
+    let res = run_query(TEST_INPUT_REF_KINDS, QUERY);
+
+    let nodes = res.nodes.as_vec();
+    println!("refs_kind_list_is_a_union nodes: {:?}", nodes);
+
+    assert!(nodes.contains(&SymbolInstanceId::new(105)), "get reads");
+    assert!(nodes.contains(&SymbolInstanceId::new(107)), "where takes &");
+    assert!(!nodes.contains(&SymbolInstanceId::new(106)), "set writes");
+    assert!(
+        !nodes.contains(&SymbolInstanceId::new(108)),
+        "unclassified refs never match a kind"
+    );
+}
+
+#[test]
+fn plain_refs_match_every_kind() {
+    const QUERY: &str = r#"func { data("level") }"#;
+    let res = run_query(TEST_INPUT_REF_KINDS, QUERY);
+
+    let nodes = res.nodes.as_vec();
+    for id in [105, 106, 107, 108] {
+        assert!(nodes.contains(&SymbolInstanceId::new(id)), "inst {}", id);
+    }
+}
+
+#[test]
+fn refs_kind_call_derives_callees_only() {
+    const QUERY: &str = r#"func("set") refs(kind="call") {}"#;
+    let res = run_query(TEST_INPUT_REF_KINDS, QUERY);
+
+    let nodes = res.nodes.as_vec();
+    println!("refs_kind_call_derives_callees_only nodes: {:?}", nodes);
+
+    assert!(nodes.contains(&SymbolInstanceId::new(106)), "set");
+    assert!(nodes.contains(&SymbolInstanceId::new(105)), "get is called");
+    assert!(
+        !nodes.contains(&SymbolInstanceId::new(103)),
+        "level is written"
+    );
+    assert!(
+        !nodes.contains(&SymbolInstanceId::new(104)),
+        "limit is read"
+    );
+}
+
+#[test]
+fn refs_unknown_kind_errors() {
+    const QUERY: &str = r#"func refs(kind="modify") { data("level") }"#;
+    let res = run_query_err(TEST_INPUT_REF_KINDS, QUERY);
+    assert!(res.is_err(), "Expected error for unknown ref kind");
+}
@@ -1,7 +1,10 @@
-use crate::cfg::{ControlFlowGraph, EdgeList, HasEdge, HasEdgeList, NodeList, SymbolNodeId};
+use crate::cfg::{
+    ControlFlowGraph, EdgeList, HasEdge, HasEdgeList, NodeList, RelationEdge, RelationEdgeList,
+    SymbolNodeId,
+};
 use crate::command::{Command, ComputeResult, LabeledStatements};
 use crate::diagnostic::{Diagnostic, DiagnosticKind};
-use crate::execution_context::ExecutionContext;
+use crate::execution_context::{ExecutionContext, StatementProgress};
 use crate::execution_state::{
     DependencyRole, ExecutionState, RelationshipType, StatementDependency, StatementDependent,
 };
@@ -14,7 +17,7 @@ use crate::verb::{name_filter, LabelResolutions, NotificationContext};
 use anyhow::Result;
 use core::fmt::Debug;
 use index::db_diesel::{ScopeContext, Selection};
-use index::symbols::{FileId, Occurrence, SymbolId, SymbolInstanceId};
+use index::symbols::{FileId, Occurrence, RefKind, RelationKind, SymbolId, SymbolInstanceId};
 use std::cell::{Ref, RefCell, RefMut};
 use std::collections::HashMap;
 use std::collections::HashSet;
@@ -33,6 +36,7 @@ pub struct ExecutionResult {
     pub nodes: NodeList,
     pub edges: EdgeList,
     pub has_edges: HasEdgeList,
+    pub relation_edges: RelationEdgeList,
     pub warnings: Vec<Diagnostic>,
 }
 
@@ -41,12 +45,14 @@ impl ExecutionResult {
         nodes: NodeList,
         edges: EdgeList,
         has_edges: HasEdgeList,
+        relation_edges: RelationEdgeList,
         warnings: Vec<Diagnostic>,
     ) -> ExecutionResult {
         ExecutionResult {
             nodes,
             edges,
             has_edges,
+            relation_edges,
             warnings,
         }
     }
@@ -310,8 +316,31 @@ impl Statement {
         }
 
         let mut pending: Vec<PendingCompute<'_>> = vec![];
-
-        for statement in statements.iter() {
+        let defined_labels: HashSet<String> = statements
+            .iter()
+            .flat_map(|s| s.command().get_labels())
+            .collect();
+        // A label an earlier execution defined and this query does not.
+        fn imported<'a>(
+            labels: &'a HashMap<String, Selection>,
+            defined: &HashSet<String>,
+            label: &str,
+        ) -> Option<&'a Selection> {
+            labels.get(label).filter(|_| !defined.contains(label))
+        }
+        let imported_resolutions: Vec<(Rc<str>, Vec<i64>)> = statements
+            .iter()
+            .flat_map(|s| s.command().layer_label_refs())
+            .filter_map(|label| {
+                let sel = imported(&ctx.labels, &defined_labels, &label)?;
+                Some((
+                    Rc::from(label.as_str()),
+                    canonical_ids(sel.nodes.iter().map(|n| n.symbol.id)),
+                ))
+            })
+            .collect();
+
+        for (id, statement) in statements.iter().enumerate() {
             // Pre-drain when this statement has any PreSeed dependency.
             // A PreSeed dep records "compute me after this dep's selection
             // has applied" — used for sibling ordering between top-level
@@ -331,6 +360,9 @@ impl Statement {
             // + Option<Rc<str>>) so we don't hold a borrow on `statement`
             // across the await below.
             let mut resolved = LabelResolutions::new();
+            for (label, ids) in &imported_resolutions {
+                resolved.insert(label.clone(), ids.clone());
+            }
             let pre_seed_deps: Vec<(Rc<Statement>, Option<Rc<str>>)> = statement
                 .get_state()
                 .dependencies
@@ -376,15 +408,29 @@ impl Statement {
 
             pending.push(PendingCompute {
                 statement: stmt.clone(),
-                future: Box::pin(async move {
+                future: Box::pin(index::db_diesel::attributed(id, async move {
                     stmt.command()
                         .compute_selected(cfg, parent_scope, children_scope, &eph, &resolved)
                         .await
-                }),
+                })),
             });
         }
 
         drain_pending(&mut pending, ctx).await?;
+
+        // `#label`s an earlier execution defined have no statement to
+        // notify them; seed them here so `run_worklist` picks them up.
+        for statement in statements {
+            for selector in statement.command().selectors() {
+                let Some(label) = selector.get_label() else {
+                    continue;
+                };
+                if let Some(sel) = imported(&ctx.labels, &defined_labels, &label) {
+                    let selection = selector.derive_from_import(sel);
+                    ctx.registry.add(selector, selection);
+                }
+            }
+        }
         Ok(())
     }
 
@@ -508,6 +554,9 @@ impl Statement {
         statements: &[Rc<Statement>],
     ) -> Result<(), pest::error::Error<Rule>> {
         let mut worklist = Worklist::new();
+        let ids = statement_ids(statements);
+        let mut finished = vec![false; statements.len()];
+        let mut finished_count = 0;
 
         // Seed: every statement that already has a selection after root initialization.
         for stmt in statements {
@@ -528,8 +577,13 @@ impl Statement {
                 },
             );
 
+            let rows = current_statement.selection_node_count(ctx);
+            if let Some(trace) = &mut ctx.explain {
+                trace.record_step(&current_statement, rows);
+            }
+
             // No selection → nothing to propagate.
-            if !current_statement.has_selection(ctx) {
+            if rows.is_none() {
                 continue;
             }
 
@@ -549,11 +603,37 @@ impl Statement {
             // Notify dependents; reschedule those whose selection changed.
             let dependents = current_statement.get_state().dependents.clone();
             for dependent in dependents {
-                let result = current_statement.notify(ctx, cfg, &dependent).await?;
+                // The SQL a notification issues narrows the dependent, so
+                // explain mode attributes it to the dependent.
+                let explain_id = match ctx.explain {
+                    Some(_) => ids.get(&Rc::as_ptr(&dependent.statement)).copied(),
+                    None => None,
+                };
+                let notify = current_statement.notify(ctx, cfg, &dependent);
+                let result = match explain_id {
+                    Some(id) => index::db_diesel::attributed(id, notify).await?,
+                    None => notify.await?,
+                };
                 if result.changed {
                     worklist.schedule(dependent.statement.clone());
                 }
             }
+
+            if let (Some(progress), Some(&id)) = (
+                ctx.progress.as_mut(),
+                ids.get(&Rc::as_ptr(&current_statement)),
+            ) {
+                if !std::mem::replace(&mut finished[id], true) {
+                    finished_count += 1;
+                    progress(&StatementProgress {
+                        statement: id,
+                        label: crate::check::own_text(&current_statement),
+                        rows: rows.unwrap_or(0),
+                        completed: finished_count,
+                        total: statements.len(),
+                    });
+                }
+            }
         }
 
         Ok(())
@@ -564,7 +644,36 @@ impl Statement {
         ctx: &mut ExecutionContext,
         cfg: &ControlFlowGraph,
     ) -> Result<Vec<Rc<Statement>>, pest::error::Error<Rule>> {
-        let mut labeled_statements = LabeledStatements::new();
+        let imported_labels: HashSet<String> = ctx.labels.keys().cloned().collect();
+        let statements = self.plan(&imported_labels)?;
+
+        if let Some(trace) = &mut ctx.explain {
+            trace.set_statements(&statements);
+        }
+
+        // Compute initial selections.  `compute_roots` consults the
+        // `PreSeed*` edges installed by `plan` to (a) pre-drain pending
+        // futures before any statement whose `eph` capture must
+        // reflect prior layer materialisation, and (b) resolve any
+        // `@label` references that come bundled with those edges.
+        self.compute_roots(ctx, cfg, &statements).await?;
+
+        self.run_worklist(ctx, cfg, &statements).await?;
+
+        export_labels(ctx, &statements);
+
+        Ok(statements)
+    }
+
+    /// The execution plan that needs no index: every statement of the query
+    /// in pre-order, with the dependency graph built and weak statements
+    /// marked.  Fails on a `#label` that no statement defines and that is
+    /// not among `imported_labels` (see [`ExecutionContext::labels`]).
+    pub fn plan(
+        &self,
+        imported_labels: &HashSet<String>,
+    ) -> Result<Vec<Rc<Statement>>, pest::error::Error<Rule>> {
+        let mut labeled_statements = LabeledStatements::new(imported_labels.clone());
         let mut statements = vec![];
         crate::scope::visit(self.scope(), &mut |statement| {
             statements.push(statement.clone());
@@ -582,15 +691,6 @@ impl Statement {
 
         self.mark_weak_statements(&statements);
 
-        // Compute initial selections.  `compute_roots` consults the
-        // `PreSeed*` edges installed above to (a) pre-drain pending
-        // futures before any statement whose `eph` capture must
-        // reflect prior layer materialisation, and (b) resolve any
-        // `@label` references that come bundled with those edges.
-        self.compute_roots(ctx, cfg, &statements).await?;
-
-        self.run_worklist(ctx, cfg, &statements).await?;
-
         Ok(statements)
     }
 
@@ -701,6 +801,7 @@ impl Statement {
                         instance_id: to_inst,
                     },
                     Some(occurrence),
+                    parent.symbol_ref.kind.map(RefKind::from),
                 );
             }
         }
@@ -743,6 +844,7 @@ impl Statement {
                         instance_id: to_inst,
                     },
                     Some(occurrence),
+                    child.symbol_ref.kind.map(RefKind::from),
                 );
             }
 
@@ -780,6 +882,7 @@ impl Statement {
                         instance_id: to_inst,
                     },
                     Some(occurrence),
+                    parent.symbol_ref.kind.map(RefKind::from),
                 );
             }
         }
@@ -833,6 +936,7 @@ impl Statement {
                             instance_id: *to_inst,
                         },
                         Some(occurrence),
+                        edge.kind.map(RefKind::from),
                     );
                 }
             }
@@ -920,13 +1024,81 @@ impl Statement {
         result
     }
 
+    /// Collect type-hierarchy edges whose both ends are selected.  The
+    /// relations are loaded once for every selected symbol, and only when
+    /// some statement traverses a relation kind.
+    async fn collect_relation_edges(
+        statements: &[Rc<Statement>],
+        all_symbols: &HashSet<i64>,
+        index: &index::db_diesel::Index,
+    ) -> RelationEdgeList {
+        let _span = tracing::debug_span!("collect_relation_edges").entered();
+        let mut result = RelationEdgeList::new();
+        if statements.iter().all(|statement| {
+            statement
+                .get_relationship_type()
+                .relation_kinds()
+                .is_empty()
+        }) {
+            return result;
+        }
+        let mut symbol_ids: Vec<i64> = all_symbols.iter().copied().collect();
+        symbol_ids.sort_unstable();
+        let relations = match index.load_relations_touching(symbol_ids).await {
+            Ok(relations) => relations,
+            Err(_) => return result,
+        };
+        for relation in relations {
+            if !all_symbols.contains(&relation.from_symbol)
+                || !all_symbols.contains(&relation.to_symbol)
+            {
+                continue;
+            }
+            result.add(RelationEdge {
+                from: SymbolId::new(relation.from_symbol),
+                to: SymbolId::new(relation.to_symbol),
+                kind: RelationKind::from(relation.kind),
+            });
+        }
+        result
+    }
+
+    /// Execute the statement tree.  With [`ExecutionContext::explain`] set,
+    /// the run is traced into it, SQL loads included.
     pub async fn execute(
         &self,
         ctx: &mut ExecutionContext,
         cfg: &ControlFlowGraph,
+    ) -> Result<ExecutionResult, pest::error::Error<Rule>> {
+        match ctx.explain.as_ref().map(|trace| trace.profile()) {
+            Some(profile) => {
+                index::db_diesel::profiled(profile, self.execute_inner(ctx, cfg)).await
+            }
+            None => self.execute_inner(ctx, cfg).await,
+        }
+    }
+
+    async fn execute_inner(
+        &self,
+        ctx: &mut ExecutionContext,
+        cfg: &ControlFlowGraph,
     ) -> Result<ExecutionResult, pest::error::Error<Rule>> {
         let statements = self.compute_nodes(ctx, cfg).await?;
 
+        if ctx.explain.is_some() {
+            let rows: Vec<_> = statements
+                .iter()
+                .map(|statement| statement.selection_node_count(ctx))
+                .collect();
+            if let Some(trace) = &mut ctx.explain {
+                for (statement, rows) in statements.iter().zip(rows) {
+                    if let Some(rows) = rows {
+                        trace.record_rows(statement, rows);
+                    }
+                }
+            }
+        }
+
         let mut warnings = self.gather_warnings(&statements);
         enrich_no_match_suggestions(&mut warnings, ctx, cfg).await;
 
@@ -960,11 +1132,15 @@ impl Statement {
 
         let ref_edges = Self::collect_ref_edges(&statements, ctx, &all_nodes, &cfg.index).await;
         let has_edges = Self::collect_has_edges(&statements, ctx, &all_nodes);
+        let all_symbols: HashSet<i64> = node_map.values().map(|n| n.symbol.id).collect();
+        let relation_edges =
+            Self::collect_relation_edges(&statements, &all_symbols, &cfg.index).await;
 
         Ok(ExecutionResult::new(
             NodeList(node_map.into_values().collect()),
             ref_edges,
             has_edges,
+            relation_edges,
             warnings,
         ))
     }
@@ -1131,6 +1307,49 @@ impl Worklist {
     }
 }
 
+/// Position of each statement in the pre-order statement list — the id
+/// explain mode and progress report it under — keyed by its `Rc` pointer.
+fn statement_ids(statements: &[Rc<Statement>]) -> HashMap<*const Statement, usize> {
+    statements
+        .iter()
+        .enumerate()
+        .map(|(id, statement)| (Rc::as_ptr(statement), id))
+        .collect()
+}
+
+/// Record every label the query defined in [`ExecutionContext::labels`],
+/// replacing what an earlier execution left under the same name.  A label on
+/// several statements selects the union of their nodes; a label whose
+/// statements selected nothing is recorded empty.
+fn export_labels(ctx: &mut ExecutionContext, statements: &[Rc<Statement>]) {
+    let mut exported: HashMap<String, (HashSet<i64>, Selection)> = HashMap::new();
+    for statement in statements {
+        let labels = statement.command().get_labels();
+        if labels.is_empty() {
+            continue;
+        }
+        let nodes = statement
+            .get_selection(ctx)
+            .map(|selection| selection.nodes)
+            .unwrap_or_default();
+        for label in labels {
+            let (seen, selection) = exported
+                .entry(label)
+                .or_insert_with(|| (HashSet::new(), Selection::new()));
+            for node in &nodes {
+                if seen.insert(node.symbol_instance.id) {
+                    selection.nodes.push(node.clone());
+                }
+            }
+        }
+    }
+    ctx.labels.extend(
+        exported
+            .into_iter()
+            .map(|(label, (_, selection))| (label, selection)),
+    );
+}
+
 /// Canonicalize resolved symbol ids for label resolution: sorted, deduped.
 ///
 /// Selection nodes are per-INSTANCE and arrive in raw, unordered SQL result
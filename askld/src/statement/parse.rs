@@ -3,6 +3,7 @@ use crate::execution_state::{
     DependencyKind, DependencyRole, RelationshipType, StatementDependency, StatementDependent,
 };
 use crate::hierarchy::Hierarchy;
+use crate::macros;
 use crate::parser::Rule;
 use crate::parser_context::ParserContext;
 use crate::scope::{build_scope, EmptyScope, Scope};
@@ -17,6 +18,10 @@ pub fn build_statement<'a>(
     ctx: Rc<ParserContext>,
     pair: pest::iterators::Pair<Rule>,
 ) -> Result<Rc<Statement>, Error<Rule>> {
+    if let Some(statement) = macros::try_expand(&ctx, &pair)? {
+        return Ok(statement);
+    }
+
     let statement_span = Span::from_pest(pair.as_span(), ctx.source());
     let mut iter = pair.into_inner();
     let sub_ctx = ParserContext::derive(ctx, statement_span.clone());
@@ -178,6 +183,10 @@ pub fn build_dependency_graph(
         let labeled_statements =
             if let Some(labeled_statements) = labeled_statements_map.get_statements(&label) {
                 labeled_statements
+            } else if labeled_statements_map.is_imported(&label) {
+                // Seeded from the earlier execution by `compute_roots`;
+                // there is no statement here to depend on.
+                continue;
             } else {
                 return Err(Error::new_from_span(
                     pest::error::ErrorVariant::CustomError {
@@ -225,6 +234,8 @@ pub fn build_dependency_graph(
         let labeled_statements =
             if let Some(labeled_statements) = labeled_statements_map.get_statements(&label) {
                 labeled_statements
+            } else if labeled_statements_map.is_imported(&label) {
+                continue;
             } else {
                 return Err(Error::new_from_span(
                     pest::error::ErrorVariant::CustomError {
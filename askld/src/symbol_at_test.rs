@@ -0,0 +1,100 @@
+use crate::index_store::{FilePosition, PositionLookup};
+use crate::test_util::{get_shared_store, TEST_INPUT_REF_KINDS, TEST_INPUT_SEARCH};
+
+#[tokio::test]
+async fn symbol_at_line_and_col_finds_the_covering_instance() {
+    // /p1/basic.c is "hello foo world\n" with fn_basic over [0, 16).
+    let store = get_shared_store(TEST_INPUT_SEARCH).await;
+    let position = FilePosition::LineCol { line: 1, col: 7 };
+    match store.symbol_at(1, "/p1/basic.c", position).await.unwrap() {
+        PositionLookup::Found(found) => {
+            assert_eq!(found.offset, 6);
+            assert_eq!((found.line, found.col), (1, 7));
+            let instance = found.instance.expect("fn_basic covers offset 6");
+            assert_eq!(instance.name, "fn_basic");
+            assert_eq!(instance.instance_type, "definition");
+            assert!(found.reference.is_none());
+        }
+        other => panic!("Expected Found, got {:?}", other),
+    }
+}
+
+#[tokio::test]
+async fn symbol_at_inside_a_reference_reports_its_target() {
+    // In /kinds.c, kinds.get [100, 200) reads kinds.level over [150, 155).
+    let store = get_shared_store(TEST_INPUT_REF_KINDS).await;
+    match store
+        .symbol_at(1, "/kinds.c", FilePosition::Offset(152))
+        .await
+        .unwrap()
+    {
+        PositionLookup::Found(found) => {
+            let instance = found.instance.expect("kinds.get covers offset 152");
+            assert_eq!(instance.symbol_id, 3);
+            assert_eq!(instance.name, "kinds.get");
+            let reference = found.reference.expect("offset 152 is in the read");
+            assert_eq!(reference.symbol_id, 7);
+            assert_eq!(reference.name, "kinds.level");
+            assert_eq!(reference.kind.as_deref(), Some("read"));
+            assert_eq!((reference.start_offset, reference.end_offset), (150, 155));
+        }
+        other => panic!("Expected Found, got {:?}", other),
+    }
+}
+
+#[tokio::test]
+async fn symbol_at_offset_past_every_instance_finds_nothing() {
+    let store = get_shared_store(TEST_INPUT_SEARCH).await;
+    match store
+        .symbol_at(1, "/p1/basic.c", FilePosition::Offset(16))
+        .await
+        .unwrap()
+    {
+        PositionLookup::Found(found) => {
+            assert_eq!((found.line, found.col), (2, 1));
+            assert!(found.instance.is_none());
+        }
+        other => panic!("Expected Found, got {:?}", other),
+    }
+}
+
+#[tokio::test]
+async fn symbol_at_rejects_positions_outside_the_file() {
+    let store = get_shared_store(TEST_INPUT_SEARCH).await;
+    for position in [
+        FilePosition::Offset(17),
+        FilePosition::LineCol { line: 3, col: 1 },
+        FilePosition::LineCol { line: 1, col: 17 },
+        FilePosition::LineCol { line: 1, col: 0 },
+        FilePosition::LineCol {
+            line: 2,
+            col: usize::MAX,
+        },
+    ] {
+        assert!(
+            matches!(
+                store.symbol_at(1, "/p1/basic.c", position).await.unwrap(),
+                PositionLookup::OutOfRange
+            ),
+            "{:?}",
+            position
+        );
+    }
+}
+
+#[tokio::test]
+async fn symbol_at_unknown_file_or_project() {
+    let store = get_shared_store(TEST_INPUT_SEARCH).await;
+    let position = FilePosition::Offset(0);
+    assert!(matches!(
+        store.symbol_at(1, "/p1/missing.c", position).await.unwrap(),
+        PositionLookup::FileNotFound
+    ));
+    assert!(matches!(
+        store
+            .symbol_at(-999999, "/p1/basic.c", position)
+            .await
+            .unwrap(),
+        PositionLookup::ProjectNotFound
+    ));
+}
@@ -0,0 +1,101 @@
+use crate::test_util::{get_shared_index, run_query, run_query_err, TEST_INPUT_SYMBOL_METADATA};
+use index::symbols::{Parameter, SymbolInstanceId};
+
+// Test fixture (test_input_symbol_metadata.sql), all in meta.c:
+//
+// add    inst=103  int add(int a, int b)          calls helper
+// name   inst=104  const char *name(void)
+// helper inst=105  static int helper(int x)
+// bare   inst=106  no metadata
+// count  inst=107  static int count (data)
+
+#[test]
+fn returns_selects_functions_by_return_type() {
+    const QUERY: &str = r#"func(returns="int")"#;
+    let res = run_query(TEST_INPUT_SYMBOL_METADATA, QUERY);
+
+    let nodes = res.nodes.as_vec();
+    println!(
+        "returns_selects_functions_by_return_type nodes: {:?}",
+        nodes
+    );
+
+    assert!(nodes.contains(&SymbolInstanceId::new(103)), "add");
+    assert!(nodes.contains(&SymbolInstanceId::new(105)), "helper");
+    assert!(!nodes.contains(&SymbolInstanceId::new(104)), "name");
+    assert!(
+        !nodes.contains(&SymbolInstanceId::new(106)),
+        "bare has no metadata"
+    );
+}
+
+#[test]
+fn returns_compares_normalized_types() {
+    const QUERY: &str = r#"func(returns="const char *")"#;
+    let res = run_query(TEST_INPUT_SYMBOL_METADATA, QUERY);
+
+    let nodes = res.nodes.as_vec();
+    assert_eq!(nodes, vec![SymbolInstanceId::new(104)]);
+}
+
+#[test]
+fn storage_combines_with_symbol_type() {
+    const QUERY: &str = r#"func(storage="static")"#;
+    let res = run_query(TEST_INPUT_SYMBOL_METADATA, QUERY);
+
+    let nodes = res.nodes.as_vec();
+    println!("storage_combines_with_symbol_type nodes: {:?}", nodes);
+
+    assert!(nodes.contains(&SymbolInstanceId::new(105)), "helper");
+    assert!(
+        !nodes.contains(&SymbolInstanceId::new(107)),
+        "count is data, not a function"
+    );
+}
+
+#[test]
+fn returns_filters_derived_children() {
+    const QUERY: &str = r#"func("add") { func(returns="int") }"#;
+    let res = run_query(TEST_INPUT_SYMBOL_METADATA, QUERY);
+
+    let nodes = res.nodes.as_vec();
+    println!("returns_filters_derived_children nodes: {:?}", nodes);
+
+    assert!(nodes.contains(&SymbolInstanceId::new(103)), "add");
+    assert!(nodes.contains(&SymbolInstanceId::new(105)), "helper");
+}
+
+#[test]
+fn empty_returns_errors() {
+    const QUERY: &str = r#"func(returns="")"#;
+    let res = run_query_err(TEST_INPUT_SYMBOL_METADATA, QUERY);
+    assert!(res.is_err(), "Expected error for empty returns=");
+}
+
+#[test]
+fn load_symbol_metadata_skips_symbols_without_any() {
+    let rt = tokio::runtime::Runtime::new().unwrap();
+    rt.block_on(async {
+        let index = get_shared_index(TEST_INPUT_SYMBOL_METADATA).await;
+        let metadata = index.load_symbol_metadata(&[3, 6]).await.unwrap();
+
+        assert_eq!(metadata.len(), 1, "bare has no metadata");
+        let add = &metadata[&3];
+        assert_eq!(add.signature.as_deref(), Some("int add(int a, int b)"));
+        assert_eq!(add.return_type.as_deref(), Some("int"));
+        assert_eq!(
+            add.parameters,
+            vec![
+                Parameter {
+                    name: "a".to_string(),
+                    type_name: "int".to_string(),
+                },
+                Parameter {
+                    name: "b".to_string(),
+                    type_name: "int".to_string(),
+                },
+            ]
+        );
+        assert_eq!(add.storage_class, None);
+    });
+}
@@ -0,0 +1,96 @@
+use crate::test_util::{run_query, run_query_err, TEST_INPUT_SYMBOL_RELATIONS};
+use index::symbols::{RelationKind, SymbolId, SymbolInstanceId};
+
+// Test fixture (test_input_symbol_relations.sql), all in shapes.go:
+//
+// Base       sym=3 inst=103
+// Derived    sym=4 inst=104  embeds Base, implements Reader
+// Reader     sym=5 inst=105
+// FileReader sym=6 inst=106  implements Reader
+// Unrelated  sym=7 inst=107
+// use        sym=8 inst=108  type-uses Derived
+
+#[test]
+fn implements_finds_implementations() {
+    const QUERY: &str = r#"type implements { type("Reader") }"#;
+    let res = run_query(TEST_INPUT_SYMBOL_RELATIONS, QUERY);
+
+    let nodes = res.nodes.as_vec();
+    println!("implements_finds_implementations nodes: {:?}", nodes);
+
+    assert!(nodes.contains(&SymbolInstanceId::new(104)), "Derived");
+    assert!(nodes.contains(&SymbolInstanceId::new(105)), "Reader");
+    assert!(nodes.contains(&SymbolInstanceId::new(106)), "FileReader");
+    assert!(!nodes.contains(&SymbolInstanceId::new(103)), "Base");
+    assert!(!nodes.contains(&SymbolInstanceId::new(107)), "Unrelated");
+}
+
+#[test]
+fn embeds_derives_targets_downward() {
+    const QUERY: &str = r#"type("Derived") embeds { type }"#;
+    let res = run_query(TEST_INPUT_SYMBOL_RELATIONS, QUERY);
+
+    let nodes = res.nodes.as_vec();
+    assert_eq!(
+        nodes,
+        vec![SymbolInstanceId::new(103), SymbolInstanceId::new(104)]
+    );
+}
+
+#[test]
+fn relation_constrains_named_pair() {
+    const QUERY: &str = r#"type("FileReader") implements { type("Reader") }"#;
+    let res = run_query(TEST_INPUT_SYMBOL_RELATIONS, QUERY);
+
+    let nodes = res.nodes.as_vec();
+    assert_eq!(
+        nodes,
+        vec![SymbolInstanceId::new(105), SymbolInstanceId::new(106)]
+    );
+
+    const MISMATCH: &str = r#"type("FileReader") extends { type("Reader") }"#;
+    let res = run_query(TEST_INPUT_SYMBOL_RELATIONS, MISMATCH);
+    assert!(
+        res.nodes.as_vec().is_empty(),
+        "FileReader does not extend Reader"
+    );
+}
+
+#[test]
+fn relation_edges_are_reported() {
+    const QUERY: &str = r#"type("Derived") derive(type="embeds,implements") { type }"#;
+    let res = run_query(TEST_INPUT_SYMBOL_RELATIONS, QUERY);
+
+    let edges: Vec<_> = res
+        .relation_edges
+        .as_vec()
+        .into_iter()
+        .map(|e| (e.from, e.to, e.kind))
+        .collect();
+    assert_eq!(
+        edges,
+        vec![
+            (SymbolId::new(4), SymbolId::new(3), RelationKind::Embeds),
+            (SymbolId::new(4), SymbolId::new(5), RelationKind::Implements),
+        ]
+    );
+}
+
+#[test]
+fn refs_do_not_follow_relations() {
+    const QUERY: &str = r#"type("Derived") refs { type }"#;
+    let res = run_query(TEST_INPUT_SYMBOL_RELATIONS, QUERY);
+
+    let nodes = res.nodes.as_vec();
+    assert!(
+        !nodes.contains(&SymbolInstanceId::new(103)),
+        "Base is embedded, not referenced"
+    );
+}
+
+#[test]
+fn unknown_derive_type_errors() {
+    const QUERY: &str = r#"type derive(type="inherits") { type }"#;
+    let res = run_query_err(TEST_INPUT_SYMBOL_RELATIONS, QUERY);
+    assert!(res.is_err(), "Expected error for unknown derive type");
+}
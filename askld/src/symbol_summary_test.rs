@@ -0,0 +1,83 @@
+use crate::test_util::{get_shared_store, TEST_INPUT_CONTAINMENT};
+
+// Test fixture (test_input_containment.sql), all in /main.go:
+//
+// "/"            sym=50 directory
+// "/main.go"     sym=51 file
+// testmodule     sym=1  module
+// testmodule.foo sym=2  calls bar
+// testmodule.bar sym=3  calls baz
+// testmodule.baz sym=4
+
+#[tokio::test]
+async fn symbol_summary_lists_callers_callees_and_containers() {
+    let store = get_shared_store(TEST_INPUT_CONTAINMENT).await;
+    let summary = store
+        .get_symbol_summary(3)
+        .await
+        .unwrap()
+        .expect("testmodule.bar is persistent");
+
+    assert_eq!(summary.name, "testmodule.bar");
+    assert_eq!(summary.symbol_type, "function");
+    assert_eq!(summary.project_name, "test_project");
+    let instances: Vec<_> = summary
+        .instances
+        .iter()
+        .map(|i| {
+            (
+                i.instance_type.as_str(),
+                i.path.as_str(),
+                i.start_offset,
+                i.end_offset,
+            )
+        })
+        .collect();
+    assert_eq!(instances, vec![("definition", "/main.go", 200, 300)]);
+
+    assert_eq!(summary.caller_count, 1);
+    let callers: Vec<_> = summary
+        .callers
+        .iter()
+        .map(|c| (c.id, c.name.as_str(), c.refs))
+        .collect();
+    assert_eq!(callers, vec![(2, "testmodule.foo", 1)]);
+
+    assert_eq!(summary.callee_count, 1);
+    let callees: Vec<_> = summary
+        .callees
+        .iter()
+        .map(|c| (c.id, c.name.as_str(), c.refs))
+        .collect();
+    assert_eq!(callees, vec![(4, "testmodule.baz", 1)]);
+
+    let containers: Vec<_> = summary
+        .containers
+        .iter()
+        .map(|c| (c.id, c.name.as_str(), c.symbol_type.as_str()))
+        .collect();
+    assert_eq!(
+        containers,
+        vec![
+            (51, "/main.go", "file"),
+            (1, "testmodule", "module"),
+            (50, "/", "directory"),
+        ]
+    );
+}
+
+#[tokio::test]
+async fn symbol_summary_of_a_leaf_has_no_callees() {
+    let store = get_shared_store(TEST_INPUT_CONTAINMENT).await;
+    let summary = store.get_symbol_summary(4).await.unwrap().unwrap();
+    assert_eq!(summary.caller_count, 1);
+    assert_eq!(summary.callers[0].name, "testmodule.bar");
+    assert_eq!(summary.callee_count, 0);
+    assert!(summary.callees.is_empty());
+}
+
+#[tokio::test]
+async fn symbol_summary_of_unknown_symbol_is_none() {
+    let store = get_shared_store(TEST_INPUT_CONTAINMENT).await;
+    assert!(store.get_symbol_summary(999).await.unwrap().is_none());
+}
@@ -1,5 +1,6 @@
 use crate::cfg::EdgeList;
 use crate::execution_context::ExecutionContext;
+use crate::index_store::IndexStore;
 use crate::statement::ExecutionResult;
 use crate::test_support::{postgres_test_image, postgres_url, wait_for_postgres};
 use crate::{cfg::ControlFlowGraph, parser::parse};
@@ -23,12 +24,16 @@ pub const TEST_INPUT_NESTED_FUNC: &'static str = index::db_diesel::Index::TEST_I
 pub const TEST_INPUT_TYPE_FILTER: &'static str = index::db_diesel::Index::TEST_INPUT_TYPE_FILTER;
 pub const VERB_TEST: &'static str = index::db_diesel::Index::VERB_TEST;
 pub const TEST_INPUT_SEARCH: &'static str = index::db_diesel::Index::TEST_INPUT_SEARCH;
+pub const TEST_INPUT_REF_KINDS: &str = index::db_diesel::Index::TEST_INPUT_REF_KINDS;
+pub const TEST_INPUT_SYMBOL_METADATA: &str = index::db_diesel::Index::TEST_INPUT_SYMBOL_METADATA;
+pub const TEST_INPUT_SYMBOL_RELATIONS: &str = index::db_diesel::Index::TEST_INPUT_SYMBOL_RELATIONS;
+pub const TEST_INPUT_DISPATCH: &str = index::db_diesel::Index::TEST_INPUT_DISPATCH;
 
 pub fn format_edges(edges: EdgeList) -> Vec<String> {
     edges
         .as_vec()
         .into_iter()
-        .map(|(f, t, _)| format!("{}-{}", f.instance_id, t.instance_id))
+        .map(|(f, t, _, _)| format!("{}-{}", f.instance_id, t.instance_id))
         .collect()
 }
 
@@ -90,6 +95,10 @@ const ALL_FIXTURES: &[&str] = &[
     TEST_INPUT_TYPE_FILTER,
     VERB_TEST,
     TEST_INPUT_SEARCH,
+    TEST_INPUT_REF_KINDS,
+    TEST_INPUT_SYMBOL_METADATA,
+    TEST_INPUT_SYMBOL_RELATIONS,
+    TEST_INPUT_DISPATCH,
 ];
 
 static FIXTURES: LazyLock<HashMap<&'static str, OnceLock<SharedFixture>>> = LazyLock::new(|| {
@@ -194,6 +203,18 @@ pub fn get_shared_db_url(fixture: &str) -> &'static str {
     &get_shared_fixture(fixture).url
 }
 
+/// An [`IndexStore`] on `fixture`'s shared database, for tests of the
+/// store's own queries rather than of the query language.
+pub async fn get_shared_store(fixture: &str) -> IndexStore {
+    let config = AsyncDieselConnectionManager::<AsyncPgConnection>::new(get_shared_db_url(fixture));
+    let pool = AsyncPool::builder()
+        .max_size(1)
+        .build(config)
+        .await
+        .unwrap();
+    IndexStore::from_pool(pool)
+}
+
 pub async fn run_query_async_err(askl_input: &str, askl_query: &str) -> Result<ExecutionResult> {
     let (res, _activations) = run_query_traced_async_err(askl_input, askl_query).await?;
     Ok(res)
@@ -241,6 +262,23 @@ pub async fn run_query_traced_with_roots(
     Ok((res, ctx.layer_activations))
 }
 
+/// Like [`run_query_traced_on`], but collecting an explain report in `mode`.
+pub async fn run_query_explained_on(
+    index: Index,
+    askl_query: &str,
+    mode: crate::explain::ExplainMode,
+) -> Result<(ExecutionResult, crate::explain::QueryExplain)> {
+    let roots = index.load_root_layers().await?;
+    let cfg = ControlFlowGraph::from_symbols(index);
+
+    let ast = parse(askl_query)?;
+    let mut ctx = ExecutionContext::new(roots);
+    ctx.explain = Some(crate::explain::ExplainTrace::new(mode));
+    let res = ast.execute(&mut ctx, &cfg).await?;
+    let report = ctx.explain.as_ref().unwrap().report(&ctx.layer_activations);
+    Ok((res, report))
+}
+
 pub async fn run_query_async(askl_input: &str, askl_query: &str) -> ExecutionResult {
     run_query_async_err(askl_input, askl_query).await.unwrap()
 }
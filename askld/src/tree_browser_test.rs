@@ -1,19 +1,9 @@
-use crate::index_store::{IndexStore, MultiTreeResult, NodeType};
-use crate::test_util::{get_shared_db_url, TEST_INPUT_TREE_BROWSER};
-use diesel_async::pooled_connection::bb8::Pool;
-use diesel_async::pooled_connection::AsyncDieselConnectionManager;
-use diesel_async::AsyncPgConnection;
-
-async fn shared_test_store() -> IndexStore {
-    let url = get_shared_db_url(TEST_INPUT_TREE_BROWSER);
-    let config = AsyncDieselConnectionManager::<AsyncPgConnection>::new(url);
-    let pool = Pool::builder().max_size(1).build(config).await.unwrap();
-    IndexStore::from_pool(pool)
-}
+use crate::index_store::{MultiTreeResult, NodeType};
+use crate::test_util::{get_shared_store, TEST_INPUT_TREE_BROWSER};
 
 #[tokio::test]
 async fn tree_browser_root_returns_direct_children() {
-    let store = shared_test_store().await;
+    let store = get_shared_store(TEST_INPUT_TREE_BROWSER).await;
     let result = store
         .list_project_tree_multi(1, &["/".to_string()], false)
         .await
@@ -49,7 +39,7 @@ async fn tree_browser_root_returns_direct_children() {
 
 #[tokio::test]
 async fn tree_browser_nested_directory_returns_children() {
-    let store = shared_test_store().await;
+    let store = get_shared_store(TEST_INPUT_TREE_BROWSER).await;
     let result = store
         .list_project_tree_multi(1, &["/src".to_string()], false)
         .await
@@ -94,7 +84,7 @@ async fn tree_browser_nested_directory_returns_children() {
 
 #[tokio::test]
 async fn tree_browser_leaf_directory_returns_only_files() {
-    let store = shared_test_store().await;
+    let store = get_shared_store(TEST_INPUT_TREE_BROWSER).await;
     let result = store
         .list_project_tree_multi(1, &["/src/util".to_string()], false)
         .await
@@ -140,7 +130,7 @@ async fn tree_browser_leaf_directory_returns_only_files() {
 
 #[tokio::test]
 async fn tree_browser_has_children_flag_correct() {
-    let store = shared_test_store().await;
+    let store = get_shared_store(TEST_INPUT_TREE_BROWSER).await;
     let result = store
         .list_project_tree_multi(1, &["/".to_string()], false)
         .await
@@ -163,7 +153,7 @@ async fn tree_browser_has_children_flag_correct() {
 
 #[tokio::test]
 async fn tree_browser_nonexistent_path_returns_not_directory() {
-    let store = shared_test_store().await;
+    let store = get_shared_store(TEST_INPUT_TREE_BROWSER).await;
     let result = store
         .list_project_tree_multi(1, &["/nonexistent".to_string()], false)
         .await
@@ -179,7 +169,7 @@ async fn tree_browser_nonexistent_path_returns_not_directory() {
 
 #[tokio::test]
 async fn tree_browser_multi_path_returns_all_paths() {
-    let store = shared_test_store().await;
+    let store = get_shared_store(TEST_INPUT_TREE_BROWSER).await;
     let result = store
         .list_project_tree_multi(
             1,
@@ -229,7 +219,7 @@ async fn tree_browser_multi_path_returns_all_paths() {
 #[tokio::test]
 async fn tree_browser_multi_path_nodes_do_not_cross_contaminate() {
     // Verify that children of /src don't appear under /docs and vice-versa.
-    let store = shared_test_store().await;
+    let store = get_shared_store(TEST_INPUT_TREE_BROWSER).await;
     let result = store
         .list_project_tree_multi(1, &["/src".to_string(), "/docs".to_string()], false)
         .await
@@ -260,7 +250,7 @@ async fn tree_browser_multi_path_nodes_do_not_cross_contaminate() {
 async fn tree_browser_has_children_true_for_dirs_with_files() {
     // has_children means "has any children" (dirs OR files).
     // /src/util and /src/config contain only files, but has_children must still be true.
-    let store = shared_test_store().await;
+    let store = get_shared_store(TEST_INPUT_TREE_BROWSER).await;
     let result = store
         .list_project_tree_multi(1, &["/src".to_string()], false)
         .await
@@ -285,7 +275,7 @@ async fn tree_browser_has_children_true_for_dirs_with_files() {
 
 #[tokio::test]
 async fn tree_browser_invalid_expand_path_is_detected() {
-    let store = shared_test_store().await;
+    let store = get_shared_store(TEST_INPUT_TREE_BROWSER).await;
     // /src is valid, /nonexistent is not.
     let result = store
         .list_project_tree_multi(1, &["/src".to_string(), "/nonexistent".to_string()], false)
@@ -305,7 +295,7 @@ async fn tree_browser_compact_mode_no_compact_path_when_not_compactable() {
     // Compaction only applies to directories whose single child is a directory.
     // /src/util and /src/config each have only file children, so they are NOT
     // compactable and compact_path must be None.
-    let store = shared_test_store().await;
+    let store = get_shared_store(TEST_INPUT_TREE_BROWSER).await;
     let result = store
         .list_project_tree_multi(1, &["/src".to_string()], true)
         .await
@@ -336,7 +326,7 @@ async fn tree_browser_compact_mode_no_compact_path_when_not_compactable() {
 async fn list_projects_excludes_canary() {
     // The __canary__ project (id -999999) is a leak-detection fixture created
     // by the layers migration; it must never appear in project listings.
-    let store = shared_test_store().await;
+    let store = get_shared_store(TEST_INPUT_TREE_BROWSER).await;
     let projects = store.list_projects().await.unwrap();
 
     assert!(
@@ -356,7 +346,7 @@ async fn list_projects_excludes_canary() {
 #[tokio::test]
 async fn get_project_details_excludes_canary() {
     // Fetching the canary project by its id must 404 (Ok(None)), not expose it.
-    let store = shared_test_store().await;
+    let store = get_shared_store(TEST_INPUT_TREE_BROWSER).await;
     let details = store.get_project_details(-999999).await.unwrap();
     assert!(
         details.is_none(),
@@ -370,7 +360,7 @@ async fn delete_project_refuses_canary() {
     // and make the server panic on next start. delete_project marks the row
     // Deleting as its first step, guarded by id > 0; for the canary that update
     // matches nothing, so it returns false before any destructive delete runs.
-    let store = shared_test_store().await;
+    let store = get_shared_store(TEST_INPUT_TREE_BROWSER).await;
     let deleted = store.delete_project(-999999).await.unwrap();
     assert!(!deleted, "canary project must not be deletable");
 }
@@ -252,6 +252,15 @@ impl Verb for DefaultTypeFilter {
         Ok(self)
     }
 
+    fn implicit_text(&self) -> Option<String> {
+        let types: Vec<&str> = self
+            .symbol_type_ids
+            .iter()
+            .map(|id| TypeSelector::name_for(*id))
+            .collect();
+        Some(format!("default types: {}", types.join(", ")))
+    }
+
     fn derive_method(&self) -> DeriveMethod {
         // Don't inherit to children - each level decides its own default types
         DeriveMethod::Skip
@@ -1,11 +1,12 @@
 use crate::name_pattern::NamePattern;
-use crate::parser::{Identifier, NamedArgument, PositionalArgument, Rule, Value};
+use crate::parser::{Identifier, NamedArgument, PositionalArgument, Rule, StringKind, Value};
 use crate::parser_context::{
     SYMBOL_TYPE_DATA, SYMBOL_TYPE_DIRECTORY, SYMBOL_TYPE_FIELD, SYMBOL_TYPE_FILE,
     SYMBOL_TYPE_FUNCTION, SYMBOL_TYPE_MACRO, SYMBOL_TYPE_MODULE, SYMBOL_TYPE_TYPE,
 };
 use crate::span::Span;
 use index::db_diesel::{CompositeFilter, CompoundNameMixin, LeafNameMixin};
+use index::symbols::RelationKind;
 use pest::error::Error;
 use pest::error::ErrorVariant::CustomError;
 use std::collections::HashMap;
@@ -33,7 +34,8 @@ pub(crate) use self::ephemeral::{EphemeralOps, LabelResolutions};
 pub(super) use self::filters::{IgnoreVerb, ProjectFilter};
 pub(super) use self::loc::LocSelector;
 pub(super) use self::modifiers::{
-    AnyModifier, DeriveModifier, HasModifier, IsolatedScope, RefsModifier, UnnestModifier,
+    AnyModifier, DeriveModifier, DispatchModifier, HasModifier, IsolatedScope, RefsModifier,
+    RelationModifier, UnnestModifier,
 };
 pub(super) use self::search::SearchSelector;
 pub(super) use self::selectors::{ForcedVerb, TypeSelector};
@@ -49,12 +51,12 @@ pub(crate) fn build_generic_verb(
     let mut named: HashMap<String, Value> = HashMap::new();
     pair.map(|pair| match pair.as_rule() {
         Rule::positional_argument => {
-            let arg = PositionalArgument::build(pair)?;
+            let arg = PositionalArgument::build(pair, ctx.params())?;
             positional.push(arg.value);
             Ok(())
         }
         Rule::named_argument => {
-            let arg = NamedArgument::build(pair)?;
+            let arg = NamedArgument::build(pair, ctx.params())?;
             named.insert(arg.name.0, arg.value);
             Ok(())
         }
@@ -71,6 +73,47 @@ pub(crate) fn build_generic_verb(
     let span = ident.as_span();
     let ident_name = Identifier::build(ident)?.0;
 
+    if ctx.macros().get(&ident_name).is_some() {
+        return Err(Error::new_from_span(
+            CustomError {
+                message: format!(
+                    "macro '{}' must be called as a statement of its own, without other verbs \
+                     or a scope",
+                    ident_name
+                ),
+            },
+            span,
+        ));
+    }
+
+    // Labels written inside a macro body are local to the expansion.
+    if ident_name == LabelVerb::NAME || ident_name == UserVerb::NAME {
+        if let Some(Value::Str {
+            kind: StringKind::Plain,
+            text,
+        }) = positional.first_mut()
+        {
+            *text = ctx.label_name(text);
+        }
+    }
+    // So are the labels ephemeral verbs take their symbols from.
+    let label_args: &[&str] = match ident_name.as_str() {
+        EphemeralInstanceVerb::NAME => &["symbol_id"],
+        EphemeralRefVerb::NAME => &["to_symbol"],
+        _ => &[],
+    };
+    for key in label_args {
+        if let Some(Value::Str {
+            kind: StringKind::Plain,
+            text,
+        }) = named.get_mut(*key)
+        {
+            if let Some(label) = text.strip_prefix('@') {
+                *text = format!("@{}", ctx.label_name(label));
+            }
+        }
+    }
+
     // Reject non-ephemeral verbs inside layer blocks.
     if ctx.get_eph_ops().is_some() {
         match ident_name.as_str() {
@@ -104,6 +147,16 @@ pub(crate) fn build_generic_verb(
         PreambleVerb::NAME => PreambleVerb::new(verb_span, &positional, &named),
         HasModifier::NAME => HasModifier::new(verb_span, &positional, &named),
         RefsModifier::NAME => RefsModifier::new(verb_span, &positional, &named),
+        DispatchModifier::NAME => DispatchModifier::new(verb_span, &positional, &named),
+        RelationModifier::NAME_EXTENDS => {
+            RelationModifier::new(verb_span, &positional, &named, RelationKind::Extends)
+        }
+        RelationModifier::NAME_IMPLEMENTS => {
+            RelationModifier::new(verb_span, &positional, &named, RelationKind::Implements)
+        }
+        RelationModifier::NAME_EMBEDS => {
+            RelationModifier::new(verb_span, &positional, &named, RelationKind::Embeds)
+        }
         DeriveModifier::NAME => DeriveModifier::new(verb_span, &positional, &named),
         UnnestModifier::NAME => UnnestModifier::new(verb_span, &positional, &named),
         AnyModifier::NAME => AnyModifier::new(verb_span, &positional, &named),
@@ -173,6 +226,48 @@ pub(crate) fn build_generic_verb(
     }
 }
 
+/// Whether `name` is a verb built into the language (and so cannot be
+/// redefined by a macro).
+pub(crate) fn is_builtin_verb(name: &str) -> bool {
+    matches!(
+        name,
+        GenericSelector::NAME
+            | GenericFilter::NAME
+            | IgnoreVerb::NAME
+            | ProjectFilter::NAME
+            | ForcedVerb::NAME
+            | IsolatedScope::NAME
+            | LabelVerb::NAME
+            | UserVerb::NAME
+            | PreambleVerb::NAME
+            | HasModifier::NAME
+            | RefsModifier::NAME
+            | DispatchModifier::NAME
+            | RelationModifier::NAME_EXTENDS
+            | RelationModifier::NAME_IMPLEMENTS
+            | RelationModifier::NAME_EMBEDS
+            | DeriveModifier::NAME
+            | UnnestModifier::NAME
+            | AnyModifier::NAME
+            | TypeSelector::NAME_FUNCTION
+            | TypeSelector::NAME_FILE
+            | TypeSelector::NAME_MODULE
+            | TypeSelector::NAME_DIRECTORY
+            | TypeSelector::NAME_TYPE
+            | TypeSelector::NAME_DATA
+            | TypeSelector::NAME_MACRO
+            | TypeSelector::NAME_FIELD
+            | TypeSelector::NAME_METHOD
+            | LocSelector::NAME
+            | SearchSelector::NAME
+            | LayerVerb::NAME
+            | EphemeralSymbolVerb::NAME
+            | EphemeralInstanceVerb::NAME
+            | EphemeralRefVerb::NAME
+            | "_"
+    )
+}
+
 /// Returns a name filter for the type-agnostic case (bare selectors).
 /// Treats '.' as a separator (code symbol convention).
 pub(crate) fn name_filter(pattern: &NamePattern) -> CompositeFilter {
@@ -6,6 +6,7 @@ use crate::span::Span;
 use anyhow::{anyhow, bail, Result};
 use async_trait::async_trait;
 use index::db_diesel::{CompositeFilter, EphContext, ScopeContext, Selection};
+use index::symbols::{RefKind, RelationKind};
 use std::collections::HashMap;
 use std::fmt::Display;
 use std::sync::Arc;
@@ -132,11 +133,114 @@ impl Display for HasModifier {
     }
 }
 
+/// RelationModifier - sets the relationship type to a type-hierarchy relation
+/// for child scopes: the parent extends / implements / embeds the children
+/// type("Derived") extends { type }       — bases of Derived
+/// type implements { type("Reader") }     — implementations of Reader
+#[derive(Debug)]
+pub(in crate::verb) struct RelationModifier {
+    span: Span,
+    kind: RelationKind,
+}
+
+impl RelationModifier {
+    pub(in crate::verb) const NAME_EXTENDS: &'static str = "extends";
+    pub(in crate::verb) const NAME_IMPLEMENTS: &'static str = "implements";
+    pub(in crate::verb) const NAME_EMBEDS: &'static str = "embeds";
+
+    pub fn new(
+        span: Span,
+        _positional: &Vec<Value>,
+        _named: &HashMap<String, Value>,
+        kind: RelationKind,
+    ) -> Result<Arc<dyn Verb>> {
+        Ok(Arc::new(Self { span, kind }))
+    }
+}
+
+impl Verb for RelationModifier {
+    fn name(&self) -> &str {
+        self.kind.name()
+    }
+
+    fn span(&self) -> pest::Span<'_> {
+        self.span.as_pest_span()
+    }
+
+    fn derive_method(&self) -> DeriveMethod {
+        DeriveMethod::Skip
+    }
+
+    /// Consumed like `has`: sets the relationship type in the parser context
+    fn update_context(&self, ctx: &ParserContext) -> Result<bool> {
+        ctx.set_relationship_type_inherited(RelationshipType::relation(self.kind));
+        Ok(true) // consumed - don't add to command
+    }
+}
+
+impl Display for RelationModifier {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "RelationModifier({})", self.kind.name())
+    }
+}
+
+/// DispatchModifier - sets the relationship type to Dispatch for child
+/// scopes: the parent data/field may call the functions whose address its
+/// initialiser takes. Without unnest only the innermost holder counts.
+/// data("ext4_file_operations") dispatch { func }  — its implementations
+/// data dispatch { func("ext4_read") }              — tables holding ext4_read
+#[derive(Debug)]
+pub(in crate::verb) struct DispatchModifier {
+    span: Span,
+}
+
+impl DispatchModifier {
+    pub(in crate::verb) const NAME: &'static str = "dispatch";
+
+    pub fn new(
+        span: Span,
+        _positional: &Vec<Value>,
+        _named: &HashMap<String, Value>,
+    ) -> Result<Arc<dyn Verb>> {
+        Ok(Arc::new(Self { span }))
+    }
+}
+
+impl Verb for DispatchModifier {
+    fn name(&self) -> &str {
+        DispatchModifier::NAME
+    }
+
+    fn span(&self) -> pest::Span<'_> {
+        self.span.as_pest_span()
+    }
+
+    fn derive_method(&self) -> DeriveMethod {
+        DeriveMethod::Skip
+    }
+
+    /// Consumed like `has`: sets the relationship type in the parser context
+    fn update_context(&self, ctx: &ParserContext) -> Result<bool> {
+        ctx.set_relationship_type_inherited(RelationshipType::DISPATCH);
+        Ok(true) // consumed - don't add to command
+    }
+}
+
+impl Display for DispatchModifier {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "DispatchModifier")
+    }
+}
+
 /// RefsModifier - explicitly sets the relationship type to Refs (reference/call-based)
 /// This is the default, but can be used to override an inherited has
+/// refs(kind="write")            — only references of the listed kinds
+/// refs(kind="read,write")       — either kind
+/// Kinds: call, read, write, address_taken, type_use
 #[derive(Debug)]
 pub(in crate::verb) struct RefsModifier {
     span: Span,
+    relationship_type: RelationshipType,
 }
 
 impl RefsModifier {
@@ -145,9 +249,27 @@ impl RefsModifier {
     pub fn new(
         span: Span,
         _positional: &Vec<Value>,
-        _named: &HashMap<String, Value>,
+        named: &HashMap<String, Value>,
     ) -> Result<Arc<dyn Verb>> {
-        Ok(Arc::new(Self { span }))
+        let mut relationship_type = RelationshipType::REFS;
+        if let Some(kind) = named.get("kind") {
+            let mut kinds = Vec::new();
+            for part in kind.as_plain()?.split(',') {
+                match RefKind::from_name(part) {
+                    Some(k) => kinds.push(k),
+                    None => bail!(
+                        "unknown reference kind '{}' in refs (expected one of {})",
+                        part.trim(),
+                        RefKind::ALL.map(|k| k.name()).join(", ")
+                    ),
+                }
+            }
+            relationship_type = relationship_type.with_ref_kinds(&kinds);
+        }
+        Ok(Arc::new(Self {
+            span,
+            relationship_type,
+        }))
     }
 }
 
@@ -166,7 +288,7 @@ impl Verb for RefsModifier {
 
     /// The refs verb consumes itself by setting the relationship type in the parser context
     fn update_context(&self, ctx: &ParserContext) -> Result<bool> {
-        ctx.set_relationship_type_inherited(RelationshipType::REFS);
+        ctx.set_relationship_type_inherited(self.relationship_type);
         Ok(true) // consumed - don't add to command
     }
 }
@@ -181,6 +303,8 @@ impl Display for RefsModifier {
 /// derive(type="refs")           — same as refs (inherits by default)
 /// derive(type="has")            — same as has (inherits by default)
 /// derive(type="refs,has")       — union: either relationship (inherits by default)
+/// derive(type="extends,implements") — either type-hierarchy relation
+/// derive(type="refs,dispatch")  — direct references plus indirect calls
 /// derive(type="has", inherit="false") — has, NOT propagated to descendants
 #[derive(Debug)]
 pub(in crate::verb) struct DeriveModifier {
@@ -207,15 +331,22 @@ impl DeriveModifier {
             match part.trim() {
                 "refs" => rel_type = rel_type | RelationshipType::REFS,
                 "has" => rel_type = rel_type | RelationshipType::HAS,
-                other => bail!(
-                    "unknown relationship type '{}' in derive (expected 'refs' or 'has')",
-                    other
-                ),
+                "dispatch" => rel_type = rel_type | RelationshipType::DISPATCH,
+                other => match RelationKind::from_name(other) {
+                    Some(kind) => rel_type = rel_type | RelationshipType::relation(kind),
+                    None => bail!(
+                        "unknown relationship type '{}' in derive (expected 'refs', 'has', 'dispatch', {})",
+                        other,
+                        RelationKind::ALL
+                            .map(|k| format!("'{}'", k.name()))
+                            .join(", ")
+                    ),
+                },
             }
         }
 
         if rel_type == RelationshipType::EMPTY {
-            bail!("derive type parameter must contain at least one of 'refs', 'has'");
+            bail!("derive type parameter must contain at least one relationship type");
         }
 
         let inherit = match named.get("inherit") {
@@ -13,7 +13,7 @@ use anyhow::{anyhow, bail, Result};
 use async_trait::async_trait;
 use index::db_diesel::{
     CompositeFilter, CompoundNameMixin, EphContext, ExactNameMixin, Index, LeafNameMixin,
-    ParentReference, ScopeContext, Selection, SymbolTypeMixin,
+    MetadataField, ParentReference, ScopeContext, Selection, SymbolMetadataMixin, SymbolTypeMixin,
 };
 use index::models_diesel::SymbolRef;
 use std::collections::HashMap;
@@ -236,6 +236,7 @@ impl Selector for ForcedVerb {
                         // parent is from a Checked selection, so the id is
                         // visible to the leak check either way.
                         layer: parent_node.symbol_instance.layer,
+                        kind: None,
                     },
                 };
                 fake_parent_references.push(reference);
@@ -257,11 +258,25 @@ impl Display for ForcedVerb {
 #[derive(Debug)]
 pub struct UnitVerb {
     span: Span,
+    /// Added by the parser to a statement without selectors, rather than
+    /// written as `_`.
+    implicit: bool,
 }
 
 impl UnitVerb {
     pub fn new(span: Span) -> Arc<dyn Verb> {
-        Arc::new(Self { span })
+        Arc::new(Self {
+            span,
+            implicit: false,
+        })
+    }
+
+    /// The unit selector of a statement that names no selector of its own.
+    pub fn implicit(span: Span) -> Arc<dyn Verb> {
+        Arc::new(Self {
+            span,
+            implicit: true,
+        })
     }
 }
 
@@ -274,6 +289,10 @@ impl Verb for UnitVerb {
         self.span.as_pest_span()
     }
 
+    fn implicit_text(&self) -> Option<String> {
+        self.implicit.then(|| "no selector: any symbol".to_string())
+    }
+
     fn as_selector<'a>(&'a self) -> Result<&'a dyn Selector> {
         Ok(self)
     }
@@ -328,6 +347,7 @@ impl Display for UnitVerb {
 /// - `func(filter="true")` -> explicitly filter mode
 /// - `func(filter="false")` -> explicitly selector mode (select all)
 /// - `func("foo", filter="true")` -> filter mode even with name
+/// - `func(returns="int")` -> selector mode; metadata constraints count as a name
 ///
 /// # Examples
 ///
@@ -351,6 +371,8 @@ pub(in crate::verb) struct TypeSelector {
     /// If true, the last query token is anchored to the last path component.
     /// Default for dir and file; can be overridden with `match="contains"`.
     leaf_anchored: bool,
+    /// Symbol metadata constraints from `returns=`, `storage=` and `visibility=`.
+    metadata: Vec<(MetadataField, String)>,
 }
 
 impl TypeSelector {
@@ -364,6 +386,12 @@ impl TypeSelector {
     pub(in crate::verb) const NAME_FIELD: &'static str = "field";
     pub(in crate::verb) const NAME_METHOD: &'static str = "method";
 
+    const METADATA_ARGS: [(&'static str, MetadataField); 3] = [
+        ("returns", MetadataField::ReturnType),
+        ("storage", MetadataField::StorageClass),
+        ("visibility", MetadataField::Visibility),
+    ];
+
     pub fn new(
         span: Span,
         positional: &Vec<Value>,
@@ -375,6 +403,17 @@ impl TypeSelector {
             .map(NamePattern::from_value)
             .transpose()?;
 
+        let mut metadata = Vec::new();
+        for (key, field) in Self::METADATA_ARGS {
+            if let Some(value) = crate::parser::named_plain(named, key)? {
+                if value.trim().is_empty() {
+                    bail!("{}= must not be empty", key);
+                }
+                metadata.push((field, value.to_string()));
+            }
+        }
+        let constrained = name_pattern.is_some() || !metadata.is_empty();
+
         // Check for explicit filter argument (true or false)
         let explicit_filter = match named.get("filter") {
             Some(v) => Some(v.as_plain()?.eq_ignore_ascii_case("true")),
@@ -384,16 +423,16 @@ impl TypeSelector {
         // Default: filter mode if no name pattern, selector mode if name provided
         // Can be overridden with explicit filter="true" or filter="false"
         let filter_only = match explicit_filter {
-            Some(true) => true,             // filter="true" forces filter mode
-            Some(false) => false,           // filter="false" forces selector mode
-            None => name_pattern.is_none(), // default based on name presence
+            Some(true) => true,   // filter="true" forces filter mode
+            Some(false) => false, // filter="false" forces selector mode
+            None => !constrained, // default based on name presence
         };
 
         // Bare type selectors (no name) inherit by default so they propagate
         // the type filter into child scopes. Named type selectors don't inherit.
         let inherit = match named.get("inherit") {
             Some(v) => v.as_plain()?.eq_ignore_ascii_case("true"),
-            None => name_pattern.is_none(),
+            None => !constrained,
         };
 
         let leaf_anchored = match crate::parser::named_plain(named, "match")? {
@@ -410,6 +449,7 @@ impl TypeSelector {
             filter_only,
             inherit,
             leaf_anchored,
+            metadata,
         }))
     }
 
@@ -459,6 +499,12 @@ impl TypeSelector {
         }
     }
 
+    fn metadata_filter_parts(&self) -> impl Iterator<Item = CompositeFilter> + '_ {
+        self.metadata
+            .iter()
+            .map(|(field, value)| CompositeFilter::leaf(SymbolMetadataMixin::new(*field, value)))
+    }
+
     /// Build composite filter parts for this type selector.
     fn build_filter_parts(&self) -> Vec<CompositeFilter> {
         let mut parts: Vec<CompositeFilter> = vec![CompositeFilter::leaf(SymbolTypeMixin::new(
@@ -471,13 +517,15 @@ impl TypeSelector {
                 self.leaf_anchored,
             ));
         }
+        parts.extend(self.metadata_filter_parts());
         parts
     }
 }
 
-impl Verb for TypeSelector {
-    fn name(&self) -> &str {
-        match self.symbol_type_id {
+impl TypeSelector {
+    /// The verb that selects symbols of `symbol_type_id` (`func`, `data`, …).
+    pub(in crate::verb) fn name_for(symbol_type_id: i32) -> &'static str {
+        match symbol_type_id {
             SYMBOL_TYPE_FUNCTION => TypeSelector::NAME_FUNCTION,
             SYMBOL_TYPE_FILE => TypeSelector::NAME_FILE,
             SYMBOL_TYPE_MODULE => TypeSelector::NAME_MODULE,
@@ -489,6 +537,12 @@ impl Verb for TypeSelector {
             _ => "type_selector",
         }
     }
+}
+
+impl Verb for TypeSelector {
+    fn name(&self) -> &str {
+        TypeSelector::name_for(self.symbol_type_id)
+    }
 
     fn span(&self) -> pest::Span<'_> {
         self.span.as_pest_span()
@@ -536,6 +590,7 @@ impl Verb for TypeSelector {
             filter_only: self.filter_only,
             inherit: self.inherit,
             leaf_anchored: self.leaf_anchored,
+            metadata: self.metadata.clone(),
         }))
     }
 
@@ -580,7 +635,7 @@ impl Verb for TypeSelector {
     fn is_non_constraining_selector(&self) -> bool {
         // Filter mode without a name pattern: this verb provides no meaningful
         // selection or constraint — it only filters by symbol type.
-        self.filter_only && self.name_pattern.is_none()
+        self.filter_only && self.name_pattern.is_none() && self.metadata.is_empty()
     }
 
     fn suppresses_default_type_filter(&self) -> bool {
@@ -594,11 +649,19 @@ impl Filter for TypeSelector {
             // When used as a namespace filter (e.g., mod("test", filter="true")),
             // only constrain by name pattern, not by type.
             let dot_is_separator = index::symbols::dot_is_separator(self.symbol_type_id);
-            match self.name_pattern.as_ref().unwrap() {
-                NamePattern::Exact(name) => Some(CompositeFilter::leaf(
-                    CompoundNameMixin::with_options(name, false, dot_is_separator),
-                )),
-                NamePattern::Glob(glob) => Some(glob.filter(dot_is_separator, true)),
+            let name =
+                match self.name_pattern.as_ref().unwrap() {
+                    NamePattern::Exact(name) => CompositeFilter::leaf(
+                        CompoundNameMixin::with_options(name, false, dot_is_separator),
+                    ),
+                    NamePattern::Glob(glob) => glob.filter(dot_is_separator, true),
+                };
+            if self.metadata.is_empty() {
+                Some(name)
+            } else {
+                let mut parts = vec![name];
+                parts.extend(self.metadata_filter_parts());
+                Some(CompositeFilter::and(parts))
             }
         } else {
             Some(CompositeFilter::and(self.build_filter_parts()))
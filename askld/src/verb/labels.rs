@@ -220,6 +220,16 @@ impl Selector for UserVerb {
         return Ok(None);
     }
 
+    fn derive_from_import(&self, selection: &Selection) -> Option<Selection> {
+        if !self.forced {
+            return Some(selection.clone());
+        }
+
+        // As with a provider: wait for the parent's notification.
+        let _ = self.selection.set(selection.clone());
+        None
+    }
+
     async fn derive_from_parent(
         &self,
         ctx: &mut ExecutionContext,
@@ -270,6 +280,7 @@ impl Selector for UserVerb {
                         // parent is from a Checked selection, so the id is
                         // visible to the leak check either way.
                         layer: parent_node.symbol_instance.layer,
+                        kind: None,
                     },
                 };
                 fake_parent_references.push(reference);
@@ -308,6 +319,7 @@ impl Selector for UserVerb {
         if !notif_ctx.unnest {
             find_parts.push(CompositeFilter::leaf(InnermostOnlyMixin::new()));
         }
+        find_parts.extend(notif_ctx.rel_type.ref_kind_filter());
         let find_filter = CompositeFilter::and(find_parts);
         let parent_ids = index
             .find_parent_instance_ids(
@@ -319,6 +331,16 @@ impl Selector for UserVerb {
             )
             .await
             .map_err(|e| anyhow::anyhow!("Failed to find parent instance IDs: {}", e))?;
+        let parent_ids = super::with_related_instance_ids(
+            index,
+            parent_ids,
+            &child,
+            notif_ctx.rel_type,
+            notif_ctx.unnest,
+            false,
+            &ctx.eph,
+        )
+        .await?;
         let parent_id_set: std::collections::HashSet<i64> =
             parent_ids.into_iter().map(Into::<i64>::into).collect();
 
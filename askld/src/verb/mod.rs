@@ -9,10 +9,12 @@ use crate::statement::Statement;
 use anyhow::{bail, Result};
 use async_trait::async_trait;
 use index::db_diesel::{
-    BaseLayerRef, CompositeFilter, DirectOnlyMixin, EphContext, EphScopedFut, EphTransaction,
-    Index, InnermostOnlyMixin, OuterParentFilterMixin, RootLayer, ScopeContext, Selection,
-    SymbolInstanceIdMixin,
+    BaseLayerRef, CompositeFilter, DirectOnlyMixin, DispatchMixin, EphContext, EphScopedFut,
+    EphTransaction, Index, InnermostOnlyMixin, OuterParentFilterMixin, RootLayer, ScopeContext,
+    Selection, SymbolInstanceIdMixin, REF_KIND_ADDRESS_TAKEN, SYMBOL_TYPE_DATA, SYMBOL_TYPE_FIELD,
+    SYMBOL_TYPE_FUNCTION,
 };
+use index::models_diesel::Symbol;
 
 /// Populate-callback type used by [`LayerSpec`].  Called by the statement-
 /// execution layer inside [`Index::with_eph_layer`] — once PER VISIBLE ROOT
@@ -189,6 +191,7 @@ use index::symbols::SymbolInstanceId;
 use log::debug;
 use pest::error::Error;
 use pest::error::ErrorVariant::CustomError;
+use std::borrow::Cow;
 use std::collections::HashMap;
 use std::fmt::Display;
 use std::ptr;
@@ -199,7 +202,7 @@ mod generic;
 mod labels;
 mod preamble;
 
-pub(crate) use self::generic::{name_filter, EphemeralOps, LabelResolutions};
+pub(crate) use self::generic::{is_builtin_verb, name_filter, EphemeralOps, LabelResolutions};
 pub use self::generic::{
     DefaultTypeFilter, DirectOnlyFilter, GenericFilter, GenericSelector, NameSelector, UnitVerb,
 };
@@ -238,30 +241,30 @@ pub fn build_verb(
         match verb.as_rule() {
             Rule::label_shortcut => {
                 let label_ident = verb.into_inner().next().unwrap();
-                let positional = vec![Value::plain(label_ident.as_str())];
+                let positional = vec![Value::plain(ctx.label_name(label_ident.as_str()))];
                 LabelVerb::new(verb_span.clone(), &positional, &HashMap::new())
             }
             Rule::inherit_label_shortcut => {
                 let label_ident = verb.into_inner().next().unwrap();
-                let positional = vec![Value::plain(label_ident.as_str())];
+                let positional = vec![Value::plain(ctx.label_name(label_ident.as_str()))];
                 let mut named = HashMap::new();
                 named.insert("inherit".to_string(), Value::plain("true"));
                 LabelVerb::new(verb_span.clone(), &positional, &named)
             }
             Rule::use_shortcut => {
                 let label_ident = verb.into_inner().next().unwrap();
-                let positional = vec![Value::plain(label_ident.as_str())];
+                let positional = vec![Value::plain(ctx.label_name(label_ident.as_str()))];
                 UserVerb::new(verb_span.clone(), &positional, &HashMap::new())
             }
             Rule::plain_filter => {
-                let value = Value::build(verb.into_inner().next().unwrap())?;
+                let value = Value::build(verb.into_inner().next().unwrap(), ctx.params())?;
                 let positional = vec![];
                 let mut named = HashMap::new();
                 named.insert("name".to_string(), value);
                 NameSelector::new(verb_span.clone(), &positional, &named)
             }
             Rule::forced_verb => {
-                let value = Value::build(verb.into_inner().next().unwrap())?;
+                let value = Value::build(verb.into_inner().next().unwrap(), ctx.params())?;
                 let positional = vec![];
                 let mut named = HashMap::new();
                 named.insert("name".to_string(), value);
@@ -400,6 +403,13 @@ pub trait Verb: std::fmt::Debug + Send + Sync {
         panic!("Verb does not have a span")
     }
 
+    /// What this verb does, for verbs the parser added rather than the query
+    /// text (such a verb's span is its whole statement). `None` for verbs
+    /// written in the query.
+    fn implicit_text(&self) -> Option<String> {
+        None
+    }
+
     /// The bare typed name for a plain-name selector (e.g. `vfs_rea`), used to
     /// offer "did you mean?" suggestions when it matches nothing. `None` for
     /// globs and non-name verbs — suggestions don't apply there.
@@ -504,7 +514,8 @@ impl SelectorState {
     fn constrain_by_parent(&mut self, parent: &Selection, rel_type: RelationshipType) {
         let check_refs = rel_type.contains(RelationshipType::REFS);
         let check_has = rel_type.contains(RelationshipType::HAS);
-        let parent_symbol_ids: std::collections::HashSet<_> = if check_refs {
+        let check_dispatch = rel_type.contains(RelationshipType::DISPATCH);
+        let parent_symbol_ids: std::collections::HashSet<_> = if check_refs || check_dispatch {
             parent.nodes.iter().map(|n| n.symbol.id).collect()
         } else {
             Default::default()
@@ -514,24 +525,35 @@ impl SelectorState {
         } else {
             Default::default()
         };
+        let related_symbol_ids = related_symbol_ids(parent, rel_type, true);
         let selection = self.selection.as_mut().unwrap();
         selection.nodes.retain(|s| {
             (check_refs
                 && parent.children.iter().any(|r| {
-                    r.symbol.id == s.symbol.id && parent_symbol_ids.contains(&r.parent_symbol.id)
+                    r.symbol.id == s.symbol.id
+                        && parent_symbol_ids.contains(&r.parent_symbol.id)
+                        && rel_type.allows_ref_kind(r.symbol_ref.kind)
                 }))
+                || (check_dispatch
+                    && parent.children.iter().any(|r| {
+                        r.symbol.id == s.symbol.id
+                            && parent_symbol_ids.contains(&r.parent_symbol.id)
+                            && is_dispatch(&r.parent_symbol, &r.symbol, r.symbol_ref.kind)
+                    }))
                 || (check_has
                     && parent.has_children.iter().any(|r| {
                         r.child_instance.id == s.symbol_instance.id
                             && parent_instance_ids.contains(&r.parent_instance.id)
                     }))
+                || related_symbol_ids.contains(&s.symbol.id)
         });
     }
 
     fn constrain_by_child(&mut self, child: &Selection, rel_type: RelationshipType) {
         let check_refs = rel_type.contains(RelationshipType::REFS);
         let check_has = rel_type.contains(RelationshipType::HAS);
-        let child_symbol_ids: std::collections::HashSet<_> = if check_refs {
+        let check_dispatch = rel_type.contains(RelationshipType::DISPATCH);
+        let child_symbol_ids: std::collections::HashSet<_> = if check_refs || check_dispatch {
             child.nodes.iter().map(|n| n.symbol.id).collect()
         } else {
             Default::default()
@@ -541,18 +563,27 @@ impl SelectorState {
         } else {
             Default::default()
         };
+        let related_symbol_ids = related_symbol_ids(child, rel_type, false);
         let selection = self.selection.as_mut().unwrap();
         selection.nodes.retain(|s| {
             (check_refs
                 && child.parents.iter().any(|r| {
                     r.from_instance.symbol == s.symbol.id
                         && child_symbol_ids.contains(&r.to_symbol.id)
+                        && rel_type.allows_ref_kind(r.symbol_ref.kind)
                 }))
+                || (check_dispatch
+                    && child.parents.iter().any(|r| {
+                        r.from_instance.symbol == s.symbol.id
+                            && child_symbol_ids.contains(&r.to_symbol.id)
+                            && is_dispatch(&s.symbol, &r.to_symbol, r.symbol_ref.kind)
+                    }))
                 || (check_has
                     && child.has_parents.iter().any(|r| {
                         r.parent_instance.id == s.symbol_instance.id
                             && child_instance_ids.contains(&r.child_instance.id)
                     }))
+                || related_symbol_ids.contains(&s.symbol.id)
         });
     }
 
@@ -790,6 +821,7 @@ pub trait Selector: std::fmt::Debug + Verb {
                 &parent_ids,
             )));
         }
+        find_parts.extend(notif_ctx.rel_type.ref_kind_filter());
         let find_filter = CompositeFilter::and(find_parts);
         let eph = &ctx.eph;
         let decl_ids = index
@@ -802,6 +834,16 @@ pub trait Selector: std::fmt::Debug + Verb {
             )
             .await
             .map_err(|e| anyhow::anyhow!("Failed to find child instance IDs: {}", e))?;
+        let decl_ids = with_related_instance_ids(
+            index,
+            decl_ids,
+            &parent_sel,
+            notif_ctx.rel_type,
+            notif_ctx.unnest,
+            true,
+            eph,
+        )
+        .await?;
 
         let selection = find_symbol_by_instance_id(
             index,
@@ -835,6 +877,7 @@ pub trait Selector: std::fmt::Debug + Verb {
         if !notif_ctx.unnest {
             find_parts.push(CompositeFilter::leaf(InnermostOnlyMixin::new()));
         }
+        find_parts.extend(notif_ctx.rel_type.ref_kind_filter());
         let find_filter = CompositeFilter::and(find_parts);
         let eph = &ctx.eph;
         let decl_ids = index
@@ -847,6 +890,16 @@ pub trait Selector: std::fmt::Debug + Verb {
             )
             .await
             .map_err(|e| anyhow::anyhow!("Failed to find parent instance IDs: {}", e))?;
+        let decl_ids = with_related_instance_ids(
+            index,
+            decl_ids,
+            &child_sel,
+            notif_ctx.rel_type,
+            notif_ctx.unnest,
+            false,
+            eph,
+        )
+        .await?;
 
         let selection = find_symbol_by_instance_id(
             index,
@@ -874,6 +927,135 @@ pub trait Selector: std::fmt::Debug + Verb {
         };
         Ok(Some(provider.clone()))
     }
+
+    /// Like [`Selector::derive_from_provider`], for a label (see
+    /// [`Selector::get_label`]) that an earlier execution defined rather
+    /// than a statement of this query: `selection` is what that label
+    /// selected then.
+    fn derive_from_import(&self, selection: &Selection) -> Option<Selection> {
+        Some(selection.clone())
+    }
+}
+
+/// Symbols at the far end of `dependency`'s relations of the kinds in
+/// `rel_type`: what its nodes extend when `outgoing`, otherwise what
+/// extends them.
+fn related_symbol_ids(
+    dependency: &Selection,
+    rel_type: RelationshipType,
+    outgoing: bool,
+) -> std::collections::HashSet<i64> {
+    if rel_type.relation_kinds().is_empty() {
+        return Default::default();
+    }
+    let node_symbol_ids: std::collections::HashSet<_> =
+        dependency.nodes.iter().map(|n| n.symbol.id).collect();
+    dependency
+        .relations
+        .iter()
+        .filter(|r| rel_type.allows_relation_kind(r.kind))
+        .filter_map(|r| {
+            let (near, far) = if outgoing {
+                (r.from_symbol, r.to_symbol)
+            } else {
+                (r.to_symbol, r.from_symbol)
+            };
+            node_symbol_ids.contains(&near).then_some(far)
+        })
+        .collect()
+}
+
+/// `dependency` with its [`Selection::relations`] loaded when `rel_type`
+/// traverses a relation kind, for [`related_symbol_ids`]; borrowed as is
+/// otherwise, so plain `refs`/`has` queries never touch the relations.
+pub(crate) async fn with_relations<'a>(
+    index: &Index,
+    dependency: &'a Selection,
+    rel_type: RelationshipType,
+) -> Result<Cow<'a, Selection>> {
+    if rel_type.relation_kinds().is_empty() {
+        return Ok(Cow::Borrowed(dependency));
+    }
+    let mut symbol_ids: Vec<i64> = dependency.nodes.iter().map(|n| n.symbol.id).collect();
+    symbol_ids.sort_unstable();
+    symbol_ids.dedup();
+    let mut loaded = dependency.clone();
+    loaded.relations = index.load_relations_touching(symbol_ids).await?;
+    Ok(Cow::Owned(loaded))
+}
+
+/// Whether a reference of `kind` from `holder` to `target` is a dispatch
+/// edge; mirrors [`DispatchMixin`] for in-memory constraints.
+fn is_dispatch(holder: &Symbol, target: &Symbol, kind: Option<i32>) -> bool {
+    kind == Some(REF_KIND_ADDRESS_TAKEN)
+        && (holder.symbol_type == SYMBOL_TYPE_DATA || holder.symbol_type == SYMBOL_TYPE_FIELD)
+        && target.symbol_type == SYMBOL_TYPE_FUNCTION
+}
+
+/// Add to `ids` the instances related to `source` through the relation
+/// kinds and dispatch in `rel_type` (none when it sets neither).
+/// `outgoing` derives children (what `source` extends or dispatches to),
+/// otherwise parents.  Outgoing dispatch honours `unnest` the way refs do.
+pub(crate) async fn with_related_instance_ids(
+    index: &Index,
+    mut ids: Vec<SymbolInstanceId>,
+    source: &Selection,
+    rel_type: RelationshipType,
+    unnest: bool,
+    outgoing: bool,
+    eph: &EphContext,
+) -> Result<Vec<SymbolInstanceId>> {
+    let kinds = rel_type.relation_kinds();
+    let dispatch = rel_type.contains(RelationshipType::DISPATCH);
+    if kinds.is_empty() && !dispatch {
+        return Ok(ids);
+    }
+    if !kinds.is_empty() {
+        let symbol_ids: Vec<i64> = source.nodes.iter().map(|n| n.symbol.id).collect();
+        let related = index
+            .find_related_instance_ids(&symbol_ids, &kinds, outgoing, eph)
+            .await
+            .map_err(|e| anyhow::anyhow!("Failed to find related instance IDs: {}", e))?;
+        ids.extend(related);
+    }
+    if dispatch {
+        let source_ids = source.get_instance_ids();
+        let mut parts = vec![CompositeFilter::leaf(DispatchMixin::new())];
+        let dispatched = if outgoing {
+            if !unnest {
+                parts.push(CompositeFilter::leaf(DirectOnlyMixin::new()));
+                parts.push(CompositeFilter::leaf(OuterParentFilterMixin::new(
+                    &source_ids,
+                )));
+            }
+            index
+                .find_child_instance_ids(
+                    &source_ids,
+                    true,
+                    false,
+                    &CompositeFilter::and(parts),
+                    eph,
+                )
+                .await
+        } else {
+            index
+                .find_parent_instance_ids(
+                    &source_ids,
+                    true,
+                    false,
+                    &CompositeFilter::and(parts),
+                    eph,
+                )
+                .await
+        };
+        ids.extend(
+            dispatched
+                .map_err(|e| anyhow::anyhow!("Failed to find dispatch instance IDs: {}", e))?,
+        );
+    }
+    ids.sort();
+    ids.dedup();
+    Ok(ids)
 }
 
 pub(crate) async fn find_symbol_by_instance_id(
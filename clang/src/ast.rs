@@ -0,0 +1,209 @@
+//! The subset of clang's `-ast-dump=json` output the indexer understands.
+//!
+//! Every variant carries `loc` and `range`, including the [`Clang::Other`]
+//! fallback: clang elides a location's `file` and `line` when they repeat the
+//! previous one, and `clang_ast` can only reconstruct them if every location
+//! in the dump is deserialized in order.
+
+use clang_ast::{BareSourceLocation, Id, SourceLocation, SourceRange};
+use serde::Deserialize;
+
+pub type Node = clang_ast::Node<Clang>;
+
+#[derive(Deserialize, Debug)]
+pub enum Clang {
+    TranslationUnitDecl(Other),
+    FunctionDecl(FunctionDecl),
+    ParmVarDecl(ParmVarDecl),
+    VarDecl(VarDecl),
+    RecordDecl(TagDecl),
+    EnumDecl(TagDecl),
+    TypedefDecl(TypedefDecl),
+    CompoundStmt(Other),
+    DeclRefExpr(DeclRefExpr),
+    CallExpr(Other),
+    BinaryOperator(Operator),
+    CompoundAssignOperator(Operator),
+    UnaryOperator(Operator),
+    ImplicitCastExpr(CastExpr),
+    ParenExpr(Other),
+    MemberExpr(Other),
+    ArraySubscriptExpr(Other),
+    Other(Other),
+}
+
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct FunctionDecl {
+    pub loc: Option<SourceLocation>,
+    pub range: Option<SourceRange>,
+    pub name: Option<String>,
+    pub storage_class: Option<String>,
+    pub previous_decl: Option<Id>,
+    #[serde(default)]
+    pub is_implicit: bool,
+    /// The function type, e.g. `int (const char *, ...)`.
+    #[serde(rename = "type")]
+    pub ty: Option<Type>,
+    #[serde(default)]
+    pub variadic: bool,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct ParmVarDecl {
+    pub loc: Option<SourceLocation>,
+    pub range: Option<SourceRange>,
+    pub name: Option<String>,
+    #[serde(rename = "type")]
+    pub ty: Option<Type>,
+}
+
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct Type {
+    pub qual_type: String,
+}
+
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct VarDecl {
+    pub loc: Option<SourceLocation>,
+    pub range: Option<SourceRange>,
+    pub name: Option<String>,
+    pub storage_class: Option<String>,
+    pub previous_decl: Option<Id>,
+    /// Initialization style (`c`, `call`, `list`) when the variable has an
+    /// initializer.
+    pub init: Option<String>,
+    #[serde(default)]
+    pub is_implicit: bool,
+}
+
+/// `RecordDecl` and `EnumDecl`.
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct TagDecl {
+    pub loc: Option<SourceLocation>,
+    pub range: Option<SourceRange>,
+    pub name: Option<String>,
+    pub previous_decl: Option<Id>,
+    #[serde(default)]
+    pub complete_definition: bool,
+    #[serde(default)]
+    pub is_implicit: bool,
+}
+
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct TypedefDecl {
+    pub loc: Option<SourceLocation>,
+    pub range: Option<SourceRange>,
+    pub name: Option<String>,
+    #[serde(default)]
+    pub is_implicit: bool,
+}
+
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct DeclRefExpr {
+    pub loc: Option<SourceLocation>,
+    pub range: Option<SourceRange>,
+    pub referenced_decl: Option<Box<Node>>,
+}
+
+/// `BinaryOperator`, `CompoundAssignOperator` and `UnaryOperator`.
+#[derive(Deserialize, Debug)]
+pub struct Operator {
+    pub loc: Option<SourceLocation>,
+    pub range: Option<SourceRange>,
+    pub opcode: String,
+}
+
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct CastExpr {
+    pub loc: Option<SourceLocation>,
+    pub range: Option<SourceRange>,
+    pub cast_kind: String,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct Other {
+    pub loc: Option<SourceLocation>,
+    pub range: Option<SourceRange>,
+}
+
+impl Clang {
+    pub fn loc(&self) -> Option<&SourceLocation> {
+        match self {
+            Clang::FunctionDecl(d) => d.loc.as_ref(),
+            Clang::ParmVarDecl(d) => d.loc.as_ref(),
+            Clang::VarDecl(d) => d.loc.as_ref(),
+            Clang::RecordDecl(d) | Clang::EnumDecl(d) => d.loc.as_ref(),
+            Clang::TypedefDecl(d) => d.loc.as_ref(),
+            Clang::DeclRefExpr(d) => d.loc.as_ref(),
+            Clang::BinaryOperator(d)
+            | Clang::CompoundAssignOperator(d)
+            | Clang::UnaryOperator(d) => d.loc.as_ref(),
+            Clang::ImplicitCastExpr(d) => d.loc.as_ref(),
+            Clang::TranslationUnitDecl(d)
+            | Clang::CompoundStmt(d)
+            | Clang::CallExpr(d)
+            | Clang::ParenExpr(d)
+            | Clang::MemberExpr(d)
+            | Clang::ArraySubscriptExpr(d)
+            | Clang::Other(d) => d.loc.as_ref(),
+        }
+    }
+
+    pub fn range(&self) -> Option<&SourceRange> {
+        match self {
+            Clang::FunctionDecl(d) => d.range.as_ref(),
+            Clang::ParmVarDecl(d) => d.range.as_ref(),
+            Clang::VarDecl(d) => d.range.as_ref(),
+            Clang::RecordDecl(d) | Clang::EnumDecl(d) => d.range.as_ref(),
+            Clang::TypedefDecl(d) => d.range.as_ref(),
+            Clang::DeclRefExpr(d) => d.range.as_ref(),
+            Clang::BinaryOperator(d)
+            | Clang::CompoundAssignOperator(d)
+            | Clang::UnaryOperator(d) => d.range.as_ref(),
+            Clang::ImplicitCastExpr(d) => d.range.as_ref(),
+            Clang::TranslationUnitDecl(d)
+            | Clang::CompoundStmt(d)
+            | Clang::CallExpr(d)
+            | Clang::ParenExpr(d)
+            | Clang::MemberExpr(d)
+            | Clang::ArraySubscriptExpr(d)
+            | Clang::Other(d) => d.range.as_ref(),
+        }
+    }
+}
+
+/// Where the tokens of `loc` appear in a file: the spelling location for
+/// tokens written as macro arguments, otherwise the expansion location.
+pub fn written_loc(loc: &SourceLocation) -> Option<&BareSourceLocation> {
+    match (&loc.spelling_loc, &loc.expansion_loc) {
+        (Some(spelling), Some(expansion)) if expansion.is_macro_arg_expansion => Some(spelling),
+        (_, Some(expansion)) => Some(expansion),
+        (spelling, None) => spelling.as_ref(),
+    }
+}
+
+/// The macro invocation `loc` was expanded from, if it came out of a macro.
+pub fn macro_expansion(loc: &SourceLocation) -> Option<&BareSourceLocation> {
+    let spelling = loc.spelling_loc.as_ref()?;
+    let expansion = loc.expansion_loc.as_ref()?;
+    if spelling.offset == expansion.offset && spelling.file == expansion.file {
+        return None;
+    }
+    Some(expansion)
+}
+
+/// Parse one `-ast-dump=json` document. Real translation units nest deeper
+/// than serde_json's default recursion limit allows.
+pub fn parse(json: &str) -> anyhow::Result<Node> {
+    let mut deserializer = serde_json::Deserializer::from_str(json);
+    deserializer.disable_recursion_limit();
+    let deserializer = serde_stacker::Deserializer::new(&mut deserializer);
+    Ok(Node::deserialize(deserializer)?)
+}
@@ -0,0 +1,222 @@
+//! Reading `compile_commands.json` and running clang for each entry.
+
+use std::path::{Path, PathBuf};
+
+use anyhow::{anyhow, bail, Context, Result};
+use serde::Deserialize;
+use tokio::process::Command;
+
+use crate::ast::{self, Node};
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct CompileCommand {
+    pub arguments: Option<Vec<String>>,
+    pub command: Option<String>,
+    pub directory: String,
+    pub file: String,
+    pub output: Option<String>,
+}
+
+impl CompileCommand {
+    /// The source file, resolved against the command's directory.
+    pub fn file_path(&self) -> PathBuf {
+        Path::new(&self.directory).join(&self.file)
+    }
+
+    fn arguments(&self) -> Result<Vec<String>> {
+        if let Some(arguments) = &self.arguments {
+            Ok(arguments.clone())
+        } else if let Some(command) = &self.command {
+            shell_words::split(command)
+                .with_context(|| format!("Failed to parse command for {}", self.file))
+        } else {
+            bail!(
+                "Either command or arguments must be defined for file: {}",
+                self.file
+            )
+        }
+    }
+}
+
+pub fn read_compile_commands(path: &Path) -> Result<Vec<CompileCommand>> {
+    let data = std::fs::read_to_string(path)
+        .with_context(|| format!("Failed to read {}", path.display()))?;
+    serde_json::from_str(&data).with_context(|| format!("Failed to parse {}", path.display()))
+}
+
+#[derive(PartialEq, Debug)]
+pub enum Language {
+    C,
+    Cxx,
+    Asm,
+}
+
+impl Language {
+    pub fn parse_path(path: &str) -> Option<Language> {
+        let lower_path = path.to_lowercase();
+
+        if lower_path.ends_with(".c") {
+            Some(Language::C)
+        } else if lower_path.ends_with(".cxx")
+            || lower_path.ends_with(".cc")
+            || lower_path.ends_with(".cpp")
+            || lower_path.ends_with(".c++")
+        {
+            Some(Language::Cxx)
+        } else if lower_path.ends_with(".s") {
+            Some(Language::Asm)
+        } else {
+            None
+        }
+    }
+}
+
+/// `-f`/`-m` options that change how a file parses: the language dialect,
+/// the signedness and size of types, and the predefined macros. Every other
+/// one is code generation or diagnostics.
+const PARSE_OPTIONS: &[&str] = &[
+    "-fms-extensions",
+    "-fms-compatibility",
+    "-fgnu89-inline",
+    "-fno-gnu89-inline",
+    "-fgnu-keywords",
+    "-fno-gnu-keywords",
+    "-fno-asm",
+    "-fblocks",
+    "-fdeclspec",
+    "-fopenmp",
+    "-ffreestanding",
+    "-fno-builtin",
+    "-fsigned-char",
+    "-funsigned-char",
+    "-fno-signed-char",
+    "-fno-unsigned-char",
+    "-fshort-enums",
+    "-fshort-wchar",
+    "-m16",
+    "-m32",
+    "-m64",
+    "-mx32",
+];
+
+fn affects_parsing(arg: &str) -> bool {
+    PARSE_OPTIONS.contains(&arg) || arg.starts_with("-fno-builtin-")
+}
+
+/// Drop the compiler and the options that only matter for code generation
+/// or diagnostics (or that clang may not understand when the project builds
+/// with gcc), keeping the `-f`/`-m` ones in [`PARSE_OPTIONS`].
+fn preprocess(arguments: Vec<String>) -> Vec<String> {
+    let mut res_arguments = Vec::new();
+    let mut skip_next = false;
+    for arg in arguments.into_iter().skip(1) {
+        if skip_next {
+            skip_next = false;
+            continue;
+        } else if arg.starts_with("-Wno-") || affects_parsing(&arg) {
+            // We keep warning relaxations and dialect options
+        } else if arg.starts_with("-W")
+            || arg.starts_with("-f")
+            || arg.starts_with("-m")
+            || arg == "-g"
+            || arg == "-c"
+        {
+            continue;
+        } else if arg == "-o" {
+            skip_next = true;
+            continue;
+        } else if arg.starts_with("-o") {
+            continue;
+        }
+
+        res_arguments.push(arg);
+    }
+
+    res_arguments
+}
+
+/// Run `clang` (the path to the clang binary) on one compile command and
+/// return the JSON AST dump.
+pub async fn run_clang_ast(clang: &str, c: &CompileCommand) -> Result<String> {
+    match Language::parse_path(&c.file) {
+        Some(Language::C) => {}
+        Some(_) => bail!("Only C files are supported for now: {}", c.file),
+        None => bail!("Failed to detect the language of {}", c.file),
+    }
+
+    let arguments: Vec<String> = [
+        "-Xclang".to_string(),
+        "-ast-dump=json".to_string(),
+        "-fsyntax-only".to_string(),
+    ]
+    .into_iter()
+    .chain(preprocess(c.arguments()?))
+    .collect();
+
+    let output = Command::new(clang)
+        .current_dir(&c.directory)
+        .args(arguments)
+        .output()
+        .await
+        .with_context(|| format!("Failed to run {clang}"))?;
+
+    if !output.status.success() {
+        return Err(anyhow!(
+            "clang failed on {}: {}",
+            c.file,
+            String::from_utf8_lossy(&output.stderr)
+        ));
+    }
+
+    Ok(String::from_utf8(output.stdout)?)
+}
+
+/// [`run_clang_ast`] and parse the result.
+pub async fn parse_compile_command(clang: &str, c: &CompileCommand) -> Result<Node> {
+    let json = run_clang_ast(clang, c).await?;
+    tokio::task::spawn_blocking(move || ast::parse(&json)).await?
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn preprocess_drops_codegen_options() {
+        let args = shell_words::split(
+            "gcc -O2 -Wall -Wno-unused -fPIC -march=native -g -Iinclude -DX=1 -c -o out.o -oother.o src/a.c",
+        )
+        .unwrap();
+        assert_eq!(
+            preprocess(args),
+            vec!["-O2", "-Wno-unused", "-Iinclude", "-DX=1", "src/a.c"]
+        );
+    }
+
+    #[test]
+    fn preprocess_keeps_options_that_change_parsing() {
+        let args = shell_words::split(
+            "gcc -m32 -mno-sse -fms-extensions -fgnu89-inline -fno-builtin-memcpy \
+             -fno-strict-aliasing -fstack-protector-strong -fdiagnostics-color=always src/a.c",
+        )
+        .unwrap();
+        assert_eq!(
+            preprocess(args),
+            vec![
+                "-m32",
+                "-fms-extensions",
+                "-fgnu89-inline",
+                "-fno-builtin-memcpy",
+                "src/a.c"
+            ]
+        );
+    }
+
+    #[test]
+    fn language_from_extension() {
+        assert_eq!(Language::parse_path("a/B.C"), Some(Language::C));
+        assert_eq!(Language::parse_path("x.cpp"), Some(Language::Cxx));
+        assert_eq!(Language::parse_path("x.S"), Some(Language::Asm));
+        assert_eq!(Language::parse_path("x.h"), None);
+    }
+}
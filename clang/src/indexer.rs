@@ -0,0 +1,905 @@
+//! Turns parsed translation units into an `askl.index.Project`.
+//!
+//! Translation units are fed one at a time to [`Indexer::add_translation_unit`];
+//! the indexer merges their declarations into project-wide symbols and, in
+//! [`Indexer::finish`], reads the files under the project root, adds macros,
+//! file and directory symbols and assigns local ids.
+//!
+//! Symbol identity follows C linkage: a function or variable with external
+//! linkage is one symbol however many files declare it, a `static` one is
+//! private to the file that first declares it, and redeclarations
+//! (`previousDecl`) join the symbol of the declaration they redeclare. A
+//! symbol's name is qualified by the file that defines it, e.g. `src.util.foo`
+//! for `foo` defined in `src/util.c`.
+
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::path::{Component, Path, PathBuf};
+use std::sync::Arc;
+
+use anyhow::Result;
+use clang_ast::{BareSourceLocation, Id, SourceRange};
+use sha2::{Digest, Sha256};
+
+use crate::ast::{macro_expansion, written_loc, Clang, FunctionDecl, Node};
+use crate::proto::askl::index::{
+    InstanceType, Object, ObjectContent, Parameter, Project, RefKind, SchemaVersion, Symbol,
+    SymbolInstance, SymbolMetadata, SymbolRef, SymbolScope, SymbolType,
+};
+
+/// The `index.proto` revision this indexer writes, stamped on the project
+/// so askld can tell which fields it meant.
+const SCHEMA_VERSION: u32 = SchemaVersion::Current as u32;
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Debug)]
+enum Kind {
+    Function,
+    Data,
+    Type,
+    Macro,
+}
+
+impl Kind {
+    fn symbol_type(self) -> SymbolType {
+        match self {
+            Kind::Function => SymbolType::Function,
+            Kind::Data => SymbolType::Data,
+            Kind::Type => SymbolType::Type,
+            Kind::Macro => SymbolType::Macro,
+        }
+    }
+}
+
+#[derive(Clone, PartialEq, Eq, Hash, PartialOrd, Ord, Debug)]
+struct SymbolKey {
+    kind: Kind,
+    name: String,
+    /// The file an internal-linkage symbol belongs to.
+    local_to: Option<String>,
+}
+
+impl SymbolKey {
+    fn global(kind: Kind, name: &str) -> Self {
+        Self {
+            kind,
+            name: name.to_string(),
+            local_to: None,
+        }
+    }
+}
+
+/// Everything recorded for one file, by symbol index and byte offsets.
+#[derive(Default)]
+struct FileEntry {
+    /// (start, end, symbol, instance type)
+    instances: BTreeSet<(usize, usize, usize, InstanceType)>,
+    /// (start, end, symbol) -> kind of the first reference seen there.
+    refs: BTreeMap<(usize, usize, usize), RefKind>,
+    /// Macro invocation sites: offset and length of the macro name token.
+    expansions: BTreeSet<(usize, usize)>,
+}
+
+/// A directory's direct children, while building the project.
+#[derive(Default)]
+struct Directory {
+    /// Object id and length of each file.
+    files: Vec<(i64, usize)>,
+    subdirs: BTreeSet<String>,
+}
+
+/// Per translation unit state: clang's declaration ids and path resolution
+/// are only meaningful within one AST dump.
+struct Unit<'a> {
+    directory: &'a Path,
+    decls: HashMap<Id, usize>,
+    paths: HashMap<Arc<str>, Option<String>>,
+}
+
+pub struct Indexer {
+    root: PathBuf,
+    symbols: Vec<SymbolKey>,
+    symbol_ids: HashMap<SymbolKey, usize>,
+    /// Files under the root, by path relative to it.
+    files: BTreeMap<String, FileEntry>,
+    /// Function metadata by symbol, and whether it came from the definition.
+    metadata: HashMap<usize, (bool, SymbolMetadata)>,
+}
+
+/// The result of indexing: the project and the contents its objects refer
+/// to by hash.
+pub struct IndexOutput {
+    pub project: Project,
+    pub contents: Vec<ObjectContent>,
+}
+
+impl Indexer {
+    /// Only files under `root` (an absolute path) are indexed.
+    pub fn new(root: impl AsRef<Path>) -> Self {
+        Self {
+            root: normalize(root.as_ref()),
+            symbols: Vec::new(),
+            symbol_ids: HashMap::new(),
+            files: BTreeMap::new(),
+            metadata: HashMap::new(),
+        }
+    }
+
+    /// Add one `-ast-dump=json` translation unit. `directory` is the compile
+    /// command's working directory, which relative paths in the dump are
+    /// relative to.
+    pub fn add_translation_unit(&mut self, directory: &Path, tu: &Node) {
+        let mut unit = Unit {
+            directory,
+            decls: HashMap::new(),
+            paths: HashMap::new(),
+        };
+        for node in &tu.inner {
+            self.visit_decl(&mut unit, node);
+        }
+        self.collect_expansions(&mut unit, tu);
+    }
+
+    fn intern(&mut self, key: SymbolKey) -> usize {
+        if let Some(&idx) = self.symbol_ids.get(&key) {
+            return idx;
+        }
+        let idx = self.symbols.len();
+        self.symbols.push(key.clone());
+        self.symbol_ids.insert(key, idx);
+        idx
+    }
+
+    /// Path of `loc`'s file relative to the root, if it is under it.
+    fn resolve(&mut self, unit: &mut Unit, loc: &BareSourceLocation) -> Option<String> {
+        if let Some(rel) = unit.paths.get(&loc.file) {
+            return rel.clone();
+        }
+        // <built-in>, <scratch space> and friends
+        let rel = if loc.file.starts_with('<') {
+            None
+        } else {
+            let path = normalize(&unit.directory.join(&*loc.file));
+            path.strip_prefix(&self.root)
+                .ok()
+                .map(|p| {
+                    p.components()
+                        .map(|c| c.as_os_str().to_string_lossy())
+                        .collect::<Vec<_>>()
+                        .join("/")
+                })
+                .filter(|p| !p.is_empty())
+        };
+        if let Some(rel) = &rel {
+            self.files.entry(rel.clone()).or_default();
+        }
+        unit.paths.insert(loc.file.clone(), rel.clone());
+        rel
+    }
+
+    /// File and byte range a declaration spans, taken at the expansion site
+    /// so that declarations produced by macros land on the invocation.
+    fn extent(
+        &mut self,
+        unit: &mut Unit,
+        range: Option<&SourceRange>,
+    ) -> Option<(String, usize, usize)> {
+        let range = range?;
+        let begin = range.begin.expansion_loc.as_ref()?;
+        let end = range.end.expansion_loc.as_ref()?;
+        if begin.file != end.file {
+            return None;
+        }
+        let file = self.resolve(unit, begin)?;
+        Some((file, begin.offset, end.offset + end.tok_len))
+    }
+
+    /// Map a declaration to its symbol and record the instance. Returns the
+    /// symbol, if the declaration has one.
+    #[allow(clippy::too_many_arguments)]
+    fn declare(
+        &mut self,
+        unit: &mut Unit,
+        node: &Node,
+        kind: Kind,
+        name: &str,
+        range: Option<&SourceRange>,
+        previous_decl: Option<Id>,
+        internal: bool,
+        instance_type: Option<InstanceType>,
+    ) -> Option<usize> {
+        let extent = self.extent(unit, range);
+        let symbol = match previous_decl.and_then(|id| unit.decls.get(&id)) {
+            Some(&symbol) => symbol,
+            None if internal => {
+                // A static declared outside the project can't be referenced
+                // by anything we index under a stable name.
+                let Some((file, _, _)) = &extent else {
+                    return None;
+                };
+                self.intern(SymbolKey {
+                    kind,
+                    name: name.to_string(),
+                    local_to: Some(file.clone()),
+                })
+            }
+            None => self.intern(SymbolKey::global(kind, name)),
+        };
+        unit.decls.insert(node.id, symbol);
+
+        if let (Some(instance_type), Some((file, start, end))) = (instance_type, extent) {
+            self.files.entry(file).or_default().instances.insert((
+                start,
+                end,
+                symbol,
+                instance_type,
+            ));
+        }
+        Some(symbol)
+    }
+
+    /// Keep one metadata record per function: the definition's, else the
+    /// first declaration's. A definition may omit the `static` its earlier
+    /// declaration spelled, so the storage class carries over.
+    fn record_metadata(&mut self, symbol: usize, definition: bool, metadata: SymbolMetadata) {
+        match self.metadata.get_mut(&symbol) {
+            None => {
+                self.metadata.insert(symbol, (definition, metadata));
+            }
+            Some((from_definition, current)) if definition && !*from_definition => {
+                let storage_class = std::mem::take(&mut current.storage_class);
+                *current = metadata;
+                if current.storage_class.is_empty() {
+                    current.storage_class = storage_class;
+                }
+                *from_definition = true;
+            }
+            Some(_) => {}
+        }
+    }
+
+    fn visit_decl(&mut self, unit: &mut Unit, node: &Node) {
+        match &node.kind {
+            Clang::FunctionDecl(decl) => {
+                let Some(name) = &decl.name else { return };
+                let instance_type = if decl.is_implicit {
+                    None
+                } else if node
+                    .inner
+                    .iter()
+                    .any(|n| matches!(n.kind, Clang::CompoundStmt(_)))
+                {
+                    Some(InstanceType::Definition)
+                } else {
+                    Some(InstanceType::Declaration)
+                };
+                let symbol = self.declare(
+                    unit,
+                    node,
+                    Kind::Function,
+                    name,
+                    decl.range.as_ref(),
+                    decl.previous_decl,
+                    decl.storage_class.as_deref() == Some("static"),
+                    instance_type,
+                );
+                if let (Some(symbol), Some(instance_type)) = (symbol, instance_type) {
+                    self.record_metadata(
+                        symbol,
+                        instance_type == InstanceType::Definition,
+                        function_metadata(name, decl, node),
+                    );
+                }
+            }
+            Clang::VarDecl(decl) => {
+                let Some(name) = &decl.name else { return };
+                let instance_type = if decl.is_implicit {
+                    None
+                } else if decl.storage_class.as_deref() == Some("extern") && decl.init.is_none() {
+                    Some(InstanceType::Declaration)
+                } else {
+                    Some(InstanceType::Definition)
+                };
+                self.declare(
+                    unit,
+                    node,
+                    Kind::Data,
+                    name,
+                    decl.range.as_ref(),
+                    decl.previous_decl,
+                    decl.storage_class.as_deref() == Some("static"),
+                    instance_type,
+                );
+            }
+            Clang::RecordDecl(decl) | Clang::EnumDecl(decl) => {
+                let Some(name) = &decl.name else { return };
+                if decl.is_implicit {
+                    return;
+                }
+                // The JSON dump only flags complete records; an enum is a
+                // definition when it lists its enumerators.
+                let defined = decl.complete_definition
+                    || (matches!(node.kind, Clang::EnumDecl(_)) && !node.inner.is_empty());
+                let instance_type = if defined {
+                    InstanceType::Definition
+                } else {
+                    InstanceType::Declaration
+                };
+                self.declare(
+                    unit,
+                    node,
+                    Kind::Type,
+                    name,
+                    decl.range.as_ref(),
+                    decl.previous_decl,
+                    false,
+                    Some(instance_type),
+                );
+                return;
+            }
+            Clang::TypedefDecl(decl) => {
+                let Some(name) = &decl.name else { return };
+                if decl.is_implicit {
+                    return;
+                }
+                self.declare(
+                    unit,
+                    node,
+                    Kind::Type,
+                    name,
+                    decl.range.as_ref(),
+                    None,
+                    false,
+                    Some(InstanceType::Definition),
+                );
+                return;
+            }
+            _ => return,
+        }
+
+        for child in &node.inner {
+            self.visit_refs(unit, child, RefKind::Read);
+        }
+    }
+
+    /// Record the references under `node`. `kind` is what the enclosing
+    /// expression does with `node`: an assignment writes its left operand,
+    /// a call calls its callee, and so on down through casts, parentheses,
+    /// member and subscript bases until an lvalue-to-rvalue conversion
+    /// turns the use into a read.
+    fn visit_refs(&mut self, unit: &mut Unit, node: &Node, kind: RefKind) {
+        if let Clang::DeclRefExpr(expr) = &node.kind {
+            if let Some(target) = expr.referenced_decl.as_deref() {
+                // Functions may be referenced before this unit declares them
+                // (implicit declarations); anything else we don't know is a
+                // local, a parameter or an enumerator.
+                let symbol = unit
+                    .decls
+                    .get(&target.id)
+                    .copied()
+                    .or_else(|| match &target.kind {
+                        Clang::FunctionDecl(f) => f
+                            .name
+                            .as_deref()
+                            .map(|name| self.intern(SymbolKey::global(Kind::Function, name))),
+                        _ => None,
+                    });
+                // A function named anywhere but as a callee decays to a
+                // pointer to it.
+                let kind = match &target.kind {
+                    Clang::FunctionDecl(_) if kind == RefKind::Read => RefKind::AddressTaken,
+                    _ => kind,
+                };
+                if let (Some(symbol), Some(loc)) = (symbol, expr.loc.as_ref().and_then(written_loc))
+                {
+                    if let Some(file) = self.resolve(unit, loc) {
+                        self.files
+                            .entry(file)
+                            .or_default()
+                            .refs
+                            .entry((loc.offset, loc.offset + loc.tok_len, symbol))
+                            .or_insert(kind);
+                    }
+                }
+            }
+        }
+
+        for (i, child) in node.inner.iter().enumerate() {
+            let child_kind = match &node.kind {
+                Clang::CallExpr(_) if i == 0 => RefKind::Call,
+                Clang::BinaryOperator(op) if i == 0 && op.opcode == "=" => RefKind::Write,
+                Clang::CompoundAssignOperator(_) if i == 0 => RefKind::Write,
+                Clang::UnaryOperator(op) => match op.opcode.as_str() {
+                    "++" | "--" => RefKind::Write,
+                    "&" => RefKind::AddressTaken,
+                    _ => RefKind::Read,
+                },
+                Clang::ImplicitCastExpr(cast) if cast.cast_kind != "LValueToRValue" => kind,
+                Clang::ParenExpr(_) => kind,
+                Clang::MemberExpr(_) | Clang::ArraySubscriptExpr(_) if i == 0 => kind,
+                _ => RefKind::Read,
+            };
+            self.visit_refs(unit, child, child_kind);
+        }
+    }
+
+    fn collect_expansions(&mut self, unit: &mut Unit, node: &Node) {
+        let range = node.kind.range();
+        let locs = [
+            node.kind.loc(),
+            range.map(|r| &r.begin),
+            range.map(|r| &r.end),
+        ];
+        for loc in locs.into_iter().flatten() {
+            if let Some(expansion) = macro_expansion(loc) {
+                if let Some(file) = self.resolve(unit, expansion) {
+                    self.files
+                        .entry(file)
+                        .or_default()
+                        .expansions
+                        .insert((expansion.offset, expansion.tok_len));
+                }
+            }
+        }
+
+        for child in &node.inner {
+            self.collect_expansions(unit, child);
+        }
+    }
+
+    /// Read the indexed files and build the project.
+    pub fn finish(mut self, project_name: &str) -> Result<IndexOutput> {
+        let mut contents: BTreeMap<String, Vec<u8>> = BTreeMap::new();
+        for rel in self.files.keys() {
+            match std::fs::read(self.root.join(rel)) {
+                Ok(content) => {
+                    contents.insert(rel.clone(), content);
+                }
+                Err(err) => log::warn!("Skipping {rel}: {err}"),
+            }
+        }
+
+        for (rel, content) in &contents {
+            for (name, start, end) in macro_definitions(content) {
+                let symbol = self.intern(SymbolKey::global(Kind::Macro, &name));
+                self.files.get_mut(rel).unwrap().instances.insert((
+                    start,
+                    end,
+                    symbol,
+                    InstanceType::Definition,
+                ));
+            }
+        }
+        for (rel, content) in &contents {
+            let entry = self.files.get_mut(rel).unwrap();
+            for &(start, len) in &entry.expansions {
+                let Some(token) = content
+                    .get(start..start + len)
+                    .and_then(|t| std::str::from_utf8(t).ok())
+                else {
+                    continue;
+                };
+                if let Some(&symbol) = self.symbol_ids.get(&SymbolKey::global(Kind::Macro, token)) {
+                    entry
+                        .instances
+                        .insert((start, start + len, symbol, InstanceType::Expansion));
+                    entry
+                        .refs
+                        .entry((start, start + len, symbol))
+                        .or_insert(RefKind::Unspecified);
+                }
+            }
+        }
+
+        // A symbol is named after the file that defines it, or failing
+        // that the first that declares it; without either it has no place
+        // in the project.
+        let mut home: Vec<Option<(bool, &str)>> = vec![None; self.symbols.len()];
+        for (rel, entry) in &self.files {
+            if !contents.contains_key(rel) {
+                continue;
+            }
+            for &(_, _, symbol, instance_type) in &entry.instances {
+                let candidate = (instance_type != InstanceType::Definition, rel.as_str());
+                let slot = &mut home[symbol];
+                if instance_type != InstanceType::Expansion
+                    && slot.is_none_or(|current| candidate < current)
+                {
+                    *slot = Some(candidate);
+                }
+            }
+        }
+
+        let mut named: Vec<(String, usize)> = self
+            .symbols
+            .iter()
+            .enumerate()
+            .filter_map(|(idx, key)| {
+                let (_, file) = home[idx]?;
+                let file = key.local_to.as_deref().unwrap_or(file);
+                Some((format!("{}.{}", module_name(file), key.name), idx))
+            })
+            .collect();
+        named.sort_by(|a, b| (&a.0, self.symbols[a.1].kind).cmp(&(&b.0, self.symbols[b.1].kind)));
+
+        let mut symbols = Vec::new();
+        let mut local_ids: HashMap<usize, i64> = HashMap::new();
+        for (name, idx) in named {
+            let key = &self.symbols[idx];
+            let local_id = symbols.len() as i64 + 1;
+            local_ids.insert(idx, local_id);
+            let scope = match key.kind {
+                Kind::Function | Kind::Data if key.local_to.is_some() => SymbolScope::Local,
+                _ => SymbolScope::Global,
+            };
+            symbols.push(Symbol {
+                local_id,
+                name,
+                scope: scope as i32,
+                r#type: key.kind.symbol_type() as i32,
+                metadata: self.metadata.remove(&idx).map(|(_, metadata)| metadata),
+            });
+        }
+
+        let mut objects = Vec::new();
+        let mut object_contents = Vec::new();
+        let mut directories: BTreeMap<String, Directory> = BTreeMap::new();
+        for (rel, content) in &contents {
+            let entry = &self.files[rel];
+            let local_id = objects.len() as i64 + 1;
+            let content_hash = format!("{:x}", Sha256::digest(content));
+
+            let mut symbol_instances: Vec<SymbolInstance> = entry
+                .instances
+                .iter()
+                .filter_map(|&(start, end, symbol, instance_type)| {
+                    Some(SymbolInstance {
+                        symbol_local_id: *local_ids.get(&symbol)?,
+                        instance_type: instance_type as i32,
+                        start_offset: start as i32,
+                        end_offset: end as i32,
+                    })
+                })
+                .collect();
+            let file_symbol = symbols.len() as i64 + 1;
+            symbols.push(Symbol {
+                local_id: file_symbol,
+                name: format!("/{rel}"),
+                scope: SymbolScope::Unspecified as i32,
+                r#type: SymbolType::File as i32,
+                metadata: None,
+            });
+            let file_instance = if rel.ends_with(".h") {
+                InstanceType::Header
+            } else {
+                InstanceType::Source
+            };
+            symbol_instances.push(SymbolInstance {
+                symbol_local_id: file_symbol,
+                instance_type: file_instance as i32,
+                start_offset: 0,
+                end_offset: content.len() as i32,
+            });
+
+            let refs = entry
+                .refs
+                .iter()
+                .filter_map(|(&(start, end, symbol), &kind)| {
+                    Some(SymbolRef {
+                        to_symbol_local_id: *local_ids.get(&symbol)?,
+                        from_offset_start: start as i32,
+                        from_offset_end: end as i32,
+                        kind: kind as i32,
+                    })
+                })
+                .collect();
+
+            objects.push(Object {
+                local_id,
+                module_path: rel.clone(),
+                filesystem_path: format!("/{rel}"),
+                filetype: Path::new(rel)
+                    .extension()
+                    .map(|e| e.to_string_lossy().to_lowercase())
+                    .unwrap_or_default(),
+                content: Vec::new(),
+                symbol_instances,
+                refs,
+                content_hash: content_hash.clone(),
+            });
+            if !object_contents
+                .iter()
+                .any(|c: &ObjectContent| c.content_hash == content_hash)
+            {
+                object_contents.push(ObjectContent {
+                    content_hash,
+                    content: content.clone(),
+                });
+            }
+
+            let mut dir = parent_dir(&format!("/{rel}"));
+            directories
+                .entry(dir.clone())
+                .or_default()
+                .files
+                .push((local_id, content.len()));
+            while dir != "/" {
+                let parent = parent_dir(&dir);
+                directories
+                    .entry(parent.clone())
+                    .or_default()
+                    .subdirs
+                    .insert(dir);
+                dir = parent;
+            }
+        }
+
+        // Directory symbols first, so that parents can refer to children.
+        let first_dir = symbols.len() as i64 + 1;
+        let dir_ids: HashMap<&str, i64> = directories
+            .keys()
+            .enumerate()
+            .map(|(i, dir)| (dir.as_str(), first_dir + i as i64))
+            .collect();
+        for dir in directories.keys() {
+            symbols.push(Symbol {
+                local_id: dir_ids[dir.as_str()],
+                name: dir.clone(),
+                scope: SymbolScope::Unspecified as i32,
+                r#type: SymbolType::Directory as i32,
+                metadata: None,
+            });
+        }
+        for (dir, Directory { files, subdirs }) in &directories {
+            let dir_symbol = dir_ids[dir.as_str()];
+            for &(object_id, len) in files {
+                objects[object_id as usize - 1]
+                    .symbol_instances
+                    .push(SymbolInstance {
+                        symbol_local_id: dir_symbol,
+                        instance_type: InstanceType::Containment as i32,
+                        start_offset: 0,
+                        end_offset: len as i32,
+                    });
+            }
+            objects.push(Object {
+                local_id: objects.len() as i64 + 1,
+                module_path: dir.clone(),
+                filesystem_path: dir.clone(),
+                filetype: "directory".to_string(),
+                content: Vec::new(),
+                symbol_instances: vec![SymbolInstance {
+                    symbol_local_id: dir_symbol,
+                    instance_type: InstanceType::Sentinel as i32,
+                    start_offset: 0,
+                    end_offset: 0,
+                }],
+                refs: subdirs
+                    .iter()
+                    .map(|subdir| SymbolRef {
+                        to_symbol_local_id: dir_ids[subdir.as_str()],
+                        from_offset_start: 0,
+                        from_offset_end: 0,
+                        kind: RefKind::Unspecified as i32,
+                    })
+                    .collect(),
+                content_hash: String::new(),
+            });
+        }
+
+        Ok(IndexOutput {
+            project: Project {
+                project_name: project_name.to_string(),
+                root_path: self.root.to_string_lossy().into_owned(),
+                objects,
+                symbols,
+                // C has no type hierarchy to report.
+                relations: Vec::new(),
+                schema_version: SCHEMA_VERSION,
+            },
+            contents: object_contents,
+        })
+    }
+}
+
+/// Metadata for a function declaration, rebuilt from the AST rather than the
+/// source text so K&R definitions and macro-wrapped declarations come out as
+/// one ordinary prototype.
+fn function_metadata(name: &str, decl: &FunctionDecl, node: &Node) -> SymbolMetadata {
+    let fn_type = decl.ty.as_ref().map(|t| t.qual_type.as_str()).unwrap_or("");
+    let parameters: Vec<Parameter> = node
+        .inner
+        .iter()
+        .filter_map(|child| match &child.kind {
+            Clang::ParmVarDecl(param) => Some(Parameter {
+                name: param.name.clone().unwrap_or_default(),
+                r#type: param.ty.as_ref()?.qual_type.clone(),
+            }),
+            _ => None,
+        })
+        .collect();
+    let return_type = return_type(fn_type).unwrap_or_default();
+    let storage_class = decl.storage_class.clone().unwrap_or_default();
+
+    let signature = if return_type.is_empty() {
+        String::new()
+    } else {
+        let mut list: Vec<String> = parameters
+            .iter()
+            .map(|p| declarator(&p.r#type, &p.name))
+            .collect();
+        if decl.variadic {
+            list.push("...".to_string());
+        }
+        if list.is_empty() && fn_type.ends_with("(void)") {
+            list.push("void".to_string());
+        }
+        let head = declarator(&return_type, &format!("{name}({})", list.join(", ")));
+        if storage_class.is_empty() {
+            head
+        } else {
+            format!("{storage_class} {head}")
+        }
+    };
+
+    SymbolMetadata {
+        signature,
+        return_type,
+        parameters,
+        storage_class,
+        visibility: String::new(),
+    }
+}
+
+/// The return type of a function type as clang prints it (`int (int, int)`).
+/// Types whose return type wraps the parameter list, like a function
+/// returning a function pointer, are not split.
+fn return_type(fn_type: &str) -> Option<String> {
+    let open = fn_type.find('(')?;
+    let mut depth = 0;
+    for (i, c) in fn_type[open..].char_indices() {
+        match c {
+            '(' => depth += 1,
+            ')' => depth -= 1,
+            _ => {}
+        }
+        if depth == 0 {
+            return (open + i + 1 == fn_type.len())
+                .then(|| fn_type[..open].trim_end().to_string())
+                .filter(|ret| !ret.is_empty());
+        }
+    }
+    None
+}
+
+/// `ty` applied to `name`, spaced the way C is usually written: `int x`,
+/// `char *p`.
+fn declarator(ty: &str, name: &str) -> String {
+    if name.is_empty() {
+        ty.to_string()
+    } else if ty.ends_with('*') {
+        format!("{ty}{name}")
+    } else {
+        format!("{ty} {name}")
+    }
+}
+
+/// Lexically resolve `.` and `..`; the indexed files need not exist where
+/// the AST was captured.
+fn normalize(path: &Path) -> PathBuf {
+    let mut out = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => {
+                out.pop();
+            }
+            other => out.push(other),
+        }
+    }
+    out
+}
+
+/// `/src/util.c` -> `/src`, `/src` -> `/`.
+fn parent_dir(path: &str) -> String {
+    match path.rfind('/') {
+        Some(0) | None => "/".to_string(),
+        Some(i) => path[..i].to_string(),
+    }
+}
+
+/// `src/util.c` -> `src.util`.
+fn module_name(rel: &str) -> String {
+    let stem = match rel.rfind('.') {
+        Some(i) if !rel[i..].contains('/') => &rel[..i],
+        _ => rel,
+    };
+    stem.replace('/', ".")
+}
+
+/// `#define` directives in `content`: macro name and the byte range of the
+/// whole directive, continuation lines included.
+fn macro_definitions(content: &[u8]) -> Vec<(String, usize, usize)> {
+    let mut result = Vec::new();
+    let mut line_start = 0;
+    while line_start < content.len() {
+        let mut line_end = line_start;
+        loop {
+            match content[line_end..].iter().position(|&b| b == b'\n') {
+                Some(p) => {
+                    let newline = line_end + p;
+                    let body = content[line_start..newline].trim_ascii_end();
+                    if body.ends_with(b"\\") {
+                        line_end = newline + 1;
+                        continue;
+                    }
+                    line_end = newline;
+                }
+                None => line_end = content.len(),
+            }
+            break;
+        }
+
+        let line = &content[line_start..line_end];
+        if let Some(name) = define_name(line) {
+            let hash = line_start + line.iter().position(|&b| b == b'#').unwrap();
+            let end = line_start + line.trim_ascii_end().len();
+            result.push((name, hash, end));
+        }
+        line_start = line_end + 1;
+    }
+    result
+}
+
+fn define_name(line: &[u8]) -> Option<String> {
+    let line = line.trim_ascii_start().strip_prefix(b"#")?;
+    let line = line.trim_ascii_start().strip_prefix(b"define")?;
+    if !line.first()?.is_ascii_whitespace() {
+        return None;
+    }
+    let line = line.trim_ascii_start();
+    let len = line
+        .iter()
+        .position(|&b| !(b.is_ascii_alphanumeric() || b == b'_'))
+        .unwrap_or(line.len());
+    if len == 0 || line[0].is_ascii_digit() {
+        return None;
+    }
+    Some(String::from_utf8_lossy(&line[..len]).into_owned())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn finds_macro_definitions() {
+        let src = b"int x;\n  #  define A 1\n#define B(x) \\\n  (x)\r\n#undef A\n#defineC\n";
+        assert_eq!(
+            macro_definitions(src),
+            vec![("A".to_string(), 9, 22), ("B".to_string(), 23, 43)]
+        );
+    }
+
+    #[test]
+    fn splits_return_types() {
+        assert_eq!(return_type("int (int, int)").as_deref(), Some("int"));
+        assert_eq!(
+            return_type("const char *(const char *, ...)").as_deref(),
+            Some("const char *")
+        );
+        assert_eq!(return_type("void (void)").as_deref(), Some("void"));
+        assert_eq!(return_type("int (*(void))(int)"), None);
+        assert_eq!(return_type("int"), None);
+    }
+
+    #[test]
+    fn names_and_paths() {
+        assert_eq!(module_name("src/util.c"), "src.util");
+        assert_eq!(module_name("v1.2/util"), "v1.2.util");
+        assert_eq!(parent_dir("/src/util.c"), "/src");
+        assert_eq!(parent_dir("/util.c"), "/");
+        assert_eq!(
+            normalize(Path::new("/a/./b/../c.h")),
+            PathBuf::from("/a/c.h")
+        );
+    }
+}
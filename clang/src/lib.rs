@@ -1 +1,5 @@
-pub mod clang;
+pub mod ast;
+pub mod compile_commands;
+pub mod indexer;
+pub mod output;
+pub mod proto;
@@ -0,0 +1,103 @@
+use std::path::{Path, PathBuf};
+
+use anyhow::{bail, Context, Result};
+use clang::compile_commands::{parse_compile_command, read_compile_commands};
+use clang::indexer::Indexer;
+use clang::output::write_index;
+use clap::Parser;
+use futures::StreamExt;
+
+/// Index a C project into protobufs for `askld index upload`.
+#[derive(Parser, Debug)]
+#[clap(author, version, about, long_about = None)]
+struct Args {
+    /// Path to compile_commands.json
+    compile_commands: PathBuf,
+
+    /// Directory to write project.pb and contents-*.pb to
+    #[clap(short, long)]
+    output: PathBuf,
+
+    /// Project root; only files under it are indexed. Defaults to the
+    /// directory containing compile_commands.json
+    #[clap(long)]
+    root: Option<PathBuf>,
+
+    /// Project name; defaults to the name of the root directory
+    #[clap(long)]
+    project_name: Option<String>,
+
+    /// Clang binary used to dump the AST
+    #[clap(long, default_value = "clang", env = "ASKL_CLANG")]
+    clang: String,
+
+    /// Number of clang processes to run in parallel
+    #[clap(short, long)]
+    jobs: Option<usize>,
+}
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    env_logger::init();
+    let args = Args::parse();
+
+    let commands = read_compile_commands(&args.compile_commands)?;
+    let root = match &args.root {
+        Some(root) => root.clone(),
+        None => args
+            .compile_commands
+            .parent()
+            .filter(|p| !p.as_os_str().is_empty())
+            .unwrap_or(Path::new("."))
+            .to_path_buf(),
+    };
+    let root = root
+        .canonicalize()
+        .with_context(|| format!("Failed to resolve project root {}", root.display()))?;
+    let project_name = match args.project_name {
+        Some(name) => name,
+        None => root
+            .file_name()
+            .map(|n| n.to_string_lossy().into_owned())
+            .unwrap_or_else(|| "project".to_string()),
+    };
+    let jobs = args.jobs.unwrap_or_else(|| {
+        std::thread::available_parallelism()
+            .map(|n| n.get())
+            .unwrap_or(1)
+    });
+
+    let mut indexer = Indexer::new(&root);
+    let clang = args.clang.as_str();
+    let mut units = futures::stream::iter(commands.iter())
+        .map(|c| async move { (c, parse_compile_command(clang, c).await) })
+        .buffer_unordered(jobs.max(1));
+
+    let (mut indexed, mut failed) = (0, 0);
+    while let Some((command, result)) = units.next().await {
+        match result {
+            Ok(tu) => {
+                indexer.add_translation_unit(Path::new(&command.directory), &tu);
+                indexed += 1;
+            }
+            Err(err) => {
+                log::warn!("Skipping {}: {:#}", command.file, err);
+                failed += 1;
+            }
+        }
+    }
+    if indexed == 0 && failed > 0 {
+        bail!("No translation unit could be indexed");
+    }
+
+    let output = indexer.finish(&project_name)?;
+    write_index(&args.output, &output)?;
+    println!(
+        "Indexed {} translation units ({} failed): {} objects, {} symbols",
+        indexed,
+        failed,
+        output.project.objects.len(),
+        output.project.symbols.len()
+    );
+    Ok(())
+}
@@ -0,0 +1,39 @@
+//! Writing an index in the directory layout `askld index upload` reads:
+//! `project.pb` plus `contents-NNNN.pb` batches of file contents.
+
+use std::path::Path;
+
+use anyhow::{Context, Result};
+use prost::Message;
+
+use crate::indexer::IndexOutput;
+use crate::proto::askl::index::ContentBatch;
+
+/// Contents are split so that no batch file grows past this.
+const MAX_BATCH_BYTES: usize = 64 * 1024 * 1024;
+
+pub fn write_index(dir: &Path, output: &IndexOutput) -> Result<()> {
+    std::fs::create_dir_all(dir).with_context(|| format!("Failed to create {}", dir.display()))?;
+
+    let project_pb = dir.join("project.pb");
+    std::fs::write(&project_pb, output.project.encode_to_vec())
+        .with_context(|| format!("Failed to write {}", project_pb.display()))?;
+
+    let mut batches = vec![ContentBatch::default()];
+    let mut batch_bytes = 0;
+    for content in &output.contents {
+        let size = content.content.len();
+        if batch_bytes > 0 && batch_bytes + size > MAX_BATCH_BYTES {
+            batches.push(ContentBatch::default());
+            batch_bytes = 0;
+        }
+        batches.last_mut().unwrap().contents.push(content.clone());
+        batch_bytes += size;
+    }
+    for (i, batch) in batches.iter().enumerate() {
+        let path = dir.join(format!("contents-{i:04}.pb"));
+        std::fs::write(&path, batch.encode_to_vec())
+            .with_context(|| format!("Failed to write {}", path.display()))?;
+    }
+    Ok(())
+}
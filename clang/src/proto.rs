@@ -0,0 +1,5 @@
+pub mod askl {
+    pub mod index {
+        include!(concat!(env!("OUT_DIR"), "/askl.index.rs"));
+    }
+}
@@ -0,0 +1,16 @@
+int level;
+int limit = 3;
+
+int get(void) {
+    return level;
+}
+
+void set(void) {
+    level = limit;
+}
+
+int *where(void) {
+    return &level;
+}
+
+int (*handler)(void) = get;
@@ -0,0 +1,12 @@
+#include "macro.h"
+
+int counter = 0;
+
+int shared(int v) {
+    counter++;
+    return SQUARE(twice(v));
+}
+
+int norm(point_t p) {
+    return shared(p.x);
+}
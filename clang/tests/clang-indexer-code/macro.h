@@ -0,0 +1,19 @@
+#ifndef MACRO_H
+#define MACRO_H
+
+#define SQUARE(x) ((x) * (x))
+
+struct point {
+    int x;
+    int y;
+};
+
+typedef struct point point_t;
+
+static inline int twice(int v) {
+    return v + v;
+}
+
+int shared(int v);
+
+#endif
@@ -1,8 +1,400 @@
-// clang indexer tests are disabled — the sqlx-based Index has been removed.
-// These tests relied on Index::new_in_memory and query methods that no longer exist.
+//! The indexer on captured `-ast-dump=json` output for the sources in
+//! `clang-indexer-code` (`test1.ast.json`, `macro.ast.json`), captured with
+//! that directory as the working directory. `kinds.ast.json` is written by
+//! hand in the same format and keeps only the nodes the indexer reads.
 
-#[ignore]
-#[tokio::test]
-async fn create_state() {
-    unimplemented!("sqlx-based Index has been removed")
+use std::path::PathBuf;
+
+use clang::ast;
+use clang::indexer::{IndexOutput, Indexer};
+use clang::output::write_index;
+use clang::proto::askl::index::{
+    ContentBatch, InstanceType, Project, RefKind, Symbol, SymbolScope, SymbolType,
+};
+use prost::Message;
+use sha2::{Digest, Sha256};
+
+fn fixture_dir() -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/clang-indexer-code")
+}
+
+fn index(units: &[&str]) -> IndexOutput {
+    let dir = fixture_dir();
+    let mut indexer = Indexer::new(&dir);
+    for unit in units {
+        let json = std::fs::read_to_string(dir.join(unit)).unwrap();
+        let tu = ast::parse(&json).unwrap();
+        indexer.add_translation_unit(&dir, &tu);
+    }
+    indexer.finish("fixtures").unwrap()
+}
+
+fn symbol<'a>(project: &'a Project, name: &str) -> &'a Symbol {
+    project
+        .symbols
+        .iter()
+        .find(|s| s.name == name)
+        .unwrap_or_else(|| panic!("no symbol {name}"))
+}
+
+fn source(module_path: &str) -> String {
+    std::fs::read_to_string(fixture_dir().join(module_path)).unwrap()
+}
+
+/// (file, instance type, covered text) for every instance of `name`.
+fn instances(project: &Project, name: &str) -> Vec<(String, InstanceType, String)> {
+    let id = symbol(project, name).local_id;
+    let mut result = Vec::new();
+    for object in &project.objects {
+        for instance in &object.symbol_instances {
+            if instance.symbol_local_id == id {
+                let text = if object.filetype == "directory" {
+                    String::new()
+                } else {
+                    source(&object.module_path)
+                        [instance.start_offset as usize..instance.end_offset as usize]
+                        .to_string()
+                };
+                result.push((object.module_path.clone(), instance.instance_type(), text));
+            }
+        }
+    }
+    result
+}
+
+/// (target symbol, referencing text, kind) for every ref in `module_path`.
+fn refs(project: &Project, module_path: &str) -> Vec<(String, String, RefKind)> {
+    let object = project
+        .objects
+        .iter()
+        .find(|o| o.module_path == module_path)
+        .unwrap();
+    let text = source(module_path);
+    object
+        .refs
+        .iter()
+        .map(|r| {
+            let target = project
+                .symbols
+                .iter()
+                .find(|s| s.local_id == r.to_symbol_local_id)
+                .unwrap();
+            (
+                target.name.clone(),
+                text[r.from_offset_start as usize..r.from_offset_end as usize].to_string(),
+                r.kind(),
+            )
+        })
+        .collect()
+}
+
+#[test]
+fn functions_declarations_and_calls() {
+    let output = index(&["test1.ast.json"]);
+    let project = &output.project;
+
+    let foo = symbol(project, "test1.foo");
+    assert_eq!(foo.r#type(), SymbolType::Function);
+    assert_eq!(foo.scope(), SymbolScope::Global);
+    assert_eq!(symbol(project, "test1.bar").scope(), SymbolScope::Local);
+    assert_eq!(symbol(project, "test1.zar").scope(), SymbolScope::Local);
+
+    let foo_instances = instances(project, "test1.foo");
+    assert_eq!(foo_instances.len(), 2);
+    assert_eq!(
+        foo_instances[0],
+        (
+            "test1.c".to_string(),
+            InstanceType::Declaration,
+            "int foo(int a, char b)".to_string()
+        )
+    );
+    assert_eq!(foo_instances[1].1, InstanceType::Definition);
+    assert!(foo_instances[1].2.starts_with("int foo(int a, char b) {"));
+    assert!(foo_instances[1].2.ends_with('}'));
+
+    // The forward declaration and the later definition are one symbol.
+    let zar: Vec<_> = instances(project, "test1.zar")
+        .into_iter()
+        .map(|(_, t, _)| t)
+        .collect();
+    assert_eq!(
+        zar,
+        vec![InstanceType::Declaration, InstanceType::Definition]
+    );
+
+    // tar is called before it is declared; the implicit declaration clang
+    // makes up at the call site is not an instance.
+    let tar: Vec<_> = instances(project, "test1.tar")
+        .into_iter()
+        .map(|(_, t, text)| (t, text))
+        .collect();
+    assert_eq!(
+        tar,
+        vec![
+            (InstanceType::Declaration, "int tar()".to_string()),
+            (
+                InstanceType::Definition,
+                "int tar() {\n    return 3;\n}".to_string()
+            ),
+        ]
+    );
+
+    let mut calls = refs(project, "test1.c");
+    calls.sort();
+    assert_eq!(
+        calls,
+        vec![
+            ("test1.bar".to_string(), "bar".to_string(), RefKind::Call),
+            ("test1.foo".to_string(), "foo".to_string(), RefKind::Call),
+            ("test1.tar".to_string(), "tar".to_string(), RefKind::Call),
+            ("test1.zar".to_string(), "zar".to_string(), RefKind::Call),
+        ]
+    );
+}
+
+#[test]
+fn function_metadata() {
+    let output = index(&["test1.ast.json"]);
+    let project = &output.project;
+
+    let metadata = |name: &str| symbol(project, name).metadata.clone().unwrap();
+
+    let foo = metadata("test1.foo");
+    assert_eq!(foo.signature, "int foo(int a, char b)");
+    assert_eq!(foo.return_type, "int");
+    let params: Vec<_> = foo
+        .parameters
+        .iter()
+        .map(|p| (p.name.as_str(), p.r#type.as_str()))
+        .collect();
+    assert_eq!(params, vec![("a", "int"), ("b", "char")]);
+    assert_eq!(foo.storage_class, "");
+
+    assert_eq!(
+        metadata("test1.main").signature,
+        "int main(int argc, char **argv)"
+    );
+    let bar = metadata("test1.bar");
+    assert_eq!(bar.signature, "static int bar(int a)");
+    assert_eq!(bar.storage_class, "static");
+    // Unprototyped: no parameters, not even `void`.
+    assert_eq!(metadata("test1.tar").signature, "int tar()");
+
+    // Files and directories carry none.
+    assert!(symbol(project, "/test1.c").metadata.is_none());
+}
+
+#[test]
+fn files_directories_and_contents() {
+    let output = index(&["test1.ast.json"]);
+    let project = &output.project;
+    let text = source("test1.c");
+
+    let file = project
+        .objects
+        .iter()
+        .find(|o| o.module_path == "test1.c")
+        .unwrap();
+    assert_eq!(file.filesystem_path, "/test1.c");
+    assert_eq!(file.filetype, "c");
+    assert!(file.content.is_empty());
+    assert_eq!(file.content_hash, format!("{:x}", Sha256::digest(&text)));
+    assert_eq!(output.contents.len(), 1);
+    assert_eq!(output.contents[0].content_hash, file.content_hash);
+    assert_eq!(output.contents[0].content, text.as_bytes());
+
+    assert_eq!(symbol(project, "/test1.c").r#type(), SymbolType::File);
+    assert_eq!(
+        instances(project, "/test1.c"),
+        vec![("test1.c".to_string(), InstanceType::Source, text.clone())]
+    );
+
+    assert_eq!(symbol(project, "/").r#type(), SymbolType::Directory);
+    assert_eq!(
+        instances(project, "/"),
+        vec![
+            ("test1.c".to_string(), InstanceType::Containment, text),
+            ("/".to_string(), InstanceType::Sentinel, String::new()),
+        ]
+    );
+    let sentinel = project
+        .objects
+        .iter()
+        .find(|o| o.module_path == "/")
+        .unwrap();
+    assert_eq!(sentinel.filetype, "directory");
+    assert_eq!(sentinel.filesystem_path, "/");
+}
+
+#[test]
+fn macros_types_and_variables() {
+    let output = index(&["macro.ast.json"]);
+    let project = &output.project;
+
+    let square = symbol(project, "macro.SQUARE");
+    assert_eq!(square.r#type(), SymbolType::Macro);
+    assert_eq!(
+        instances(project, "macro.SQUARE"),
+        vec![
+            (
+                "macro.c".to_string(),
+                InstanceType::Expansion,
+                "SQUARE".to_string()
+            ),
+            (
+                "macro.h".to_string(),
+                InstanceType::Definition,
+                "#define SQUARE(x) ((x) * (x))".to_string()
+            ),
+        ]
+    );
+    assert_eq!(
+        instances(project, "macro.MACRO_H")[0].1,
+        InstanceType::Definition
+    );
+
+    // Declared in the header, defined in the source file.
+    let shared: Vec<_> = instances(project, "macro.shared")
+        .into_iter()
+        .map(|(file, t, _)| (file, t))
+        .collect();
+    assert_eq!(
+        shared,
+        vec![
+            ("macro.c".to_string(), InstanceType::Definition),
+            ("macro.h".to_string(), InstanceType::Declaration),
+        ]
+    );
+    assert_eq!(symbol(project, "macro.twice").scope(), SymbolScope::Local);
+    assert_eq!(symbol(project, "macro.counter").r#type(), SymbolType::Data);
+    assert_eq!(symbol(project, "macro.point").r#type(), SymbolType::Type);
+    assert_eq!(
+        instances(project, "macro.point_t"),
+        vec![(
+            "macro.h".to_string(),
+            InstanceType::Definition,
+            "typedef struct point point_t".to_string()
+        )]
+    );
+
+    // twice(v) is a macro argument, so the ref sits where it is written,
+    // once, although SQUARE expands it twice.
+    let mut from_source = refs(project, "macro.c");
+    from_source.sort();
+    assert_eq!(
+        from_source,
+        vec![
+            (
+                "macro.SQUARE".to_string(),
+                "SQUARE".to_string(),
+                RefKind::Unspecified
+            ),
+            (
+                "macro.counter".to_string(),
+                "counter".to_string(),
+                RefKind::Write
+            ),
+            (
+                "macro.shared".to_string(),
+                "shared".to_string(),
+                RefKind::Call
+            ),
+            (
+                "macro.twice".to_string(),
+                "twice".to_string(),
+                RefKind::Call
+            ),
+        ]
+    );
+
+    let header = project
+        .objects
+        .iter()
+        .find(|o| o.module_path == "macro.h")
+        .unwrap();
+    assert_eq!(header.filetype, "h");
+    assert_eq!(instances(project, "/macro.h")[0].1, InstanceType::Header);
+}
+
+#[test]
+fn reference_kinds() {
+    let output = index(&["kinds.ast.json"]);
+    assert_eq!(
+        refs(&output.project, "kinds.c"),
+        vec![
+            (
+                "kinds.level".to_string(),
+                "level".to_string(),
+                RefKind::Read
+            ),
+            (
+                "kinds.level".to_string(),
+                "level".to_string(),
+                RefKind::Write
+            ),
+            (
+                "kinds.limit".to_string(),
+                "limit".to_string(),
+                RefKind::Read
+            ),
+            (
+                "kinds.level".to_string(),
+                "level".to_string(),
+                RefKind::AddressTaken
+            ),
+            (
+                "kinds.get".to_string(),
+                "get".to_string(),
+                RefKind::AddressTaken
+            ),
+        ]
+    );
+}
+
+#[test]
+fn translation_units_share_a_project() {
+    let output = index(&["test1.ast.json", "macro.ast.json"]);
+    let project = &output.project;
+    symbol(project, "test1.main");
+    symbol(project, "macro.norm");
+
+    let root: Vec<_> = instances(project, "/")
+        .into_iter()
+        .map(|(file, t, _)| (file, t))
+        .collect();
+    assert_eq!(
+        root,
+        vec![
+            ("macro.c".to_string(), InstanceType::Containment),
+            ("macro.h".to_string(), InstanceType::Containment),
+            ("test1.c".to_string(), InstanceType::Containment),
+            ("/".to_string(), InstanceType::Sentinel),
+        ]
+    );
+    let ids: std::collections::HashSet<_> = project.symbols.iter().map(|s| s.local_id).collect();
+    assert_eq!(ids.len(), project.symbols.len());
+}
+
+#[test]
+fn writes_upload_directory() {
+    let output = index(&["test1.ast.json", "macro.ast.json"]);
+    let dir = tempfile::tempdir().unwrap();
+    write_index(dir.path(), &output).unwrap();
+
+    let project = Project::decode(
+        std::fs::read(dir.path().join("project.pb"))
+            .unwrap()
+            .as_slice(),
+    )
+    .unwrap();
+    assert_eq!(project, output.project);
+    let batch = ContentBatch::decode(
+        std::fs::read(dir.path().join("contents-0000.pb"))
+            .unwrap()
+            .as_slice(),
+    )
+    .unwrap();
+    assert_eq!(batch.contents, output.contents);
+    assert!(!dir.path().join("ast.json").exists());
 }
@@ -3,12 +3,16 @@ use diesel_migrations::{embed_migrations, EmbeddedMigrations};
 
 pub const MIGRATIONS: EmbeddedMigrations = embed_migrations!("../migrations");
 
+mod cancel;
 mod cte;
 mod index_impl;
 pub(crate) mod mixins;
+mod profile;
+mod replicas;
 mod selection;
 mod sql_cache;
 
+pub use cancel::{cancellable, CancelScope, QUERY_CANCELLED};
 pub use index_impl::{
     eph_pool_manager_config, per_layer_hash, purge_eph_cache, supplement_hash, BaseLayerRef,
     EphInstanceRow, EphLayerKind, EphLayerMeta, EphRefRow, EphScopedFut, EphSymbolRow,
@@ -18,15 +22,20 @@ pub use index_impl::{
 };
 pub use mixins::{
     CompositeFilter, CompoundNameMixin, CurrentQuery, DefaultSymbolTypeMixin, DirectOnlyMixin,
-    ExactNameMixin, FilterLeaf, FullNameGlobMixin, GlobNameMixin, GlobPiece, InnermostOnlyMixin,
-    LeafNameMixin, OuterParentFilterMixin, PackageDescendantLeaf, ProjectFilterMixin,
-    SymbolInstanceIdMixin, SymbolTypeMixin, INSTANCE_TYPE_BUILD, INSTANCE_TYPE_CONTAINMENT,
+    DispatchMixin, ExactNameMixin, FilterLeaf, FullNameGlobMixin, GlobNameMixin, GlobPiece,
+    InnermostOnlyMixin, LeafNameMixin, MetadataField, OuterParentFilterMixin,
+    PackageDescendantLeaf, ProjectFilterMixin, RefKindMixin, SymbolInstanceIdMixin,
+    SymbolMetadataMixin, SymbolTypeMixin, INSTANCE_TYPE_BUILD, INSTANCE_TYPE_CONTAINMENT,
     INSTANCE_TYPE_DECLARATION, INSTANCE_TYPE_DEFINITION, INSTANCE_TYPE_DOCUMENTATION,
     INSTANCE_TYPE_EXPANSION, INSTANCE_TYPE_FILE, INSTANCE_TYPE_HEADER, INSTANCE_TYPE_SENTINEL,
-    INSTANCE_TYPE_SOURCE, SYMBOL_TYPE_CONTENT, SYMBOL_TYPE_DATA, SYMBOL_TYPE_DIRECTORY,
-    SYMBOL_TYPE_FIELD, SYMBOL_TYPE_FILE, SYMBOL_TYPE_FUNCTION, SYMBOL_TYPE_MACRO,
-    SYMBOL_TYPE_MODULE, SYMBOL_TYPE_TYPE,
+    INSTANCE_TYPE_SOURCE, REF_KIND_ADDRESS_TAKEN, REF_KIND_CALL, REF_KIND_READ, REF_KIND_TYPE_USE,
+    REF_KIND_WRITE, RELATION_KIND_EMBEDS, RELATION_KIND_EXTENDS, RELATION_KIND_IMPLEMENTS,
+    SYMBOL_TYPE_CONTENT, SYMBOL_TYPE_DATA, SYMBOL_TYPE_DIRECTORY, SYMBOL_TYPE_FIELD,
+    SYMBOL_TYPE_FILE, SYMBOL_TYPE_FUNCTION, SYMBOL_TYPE_MACRO, SYMBOL_TYPE_MODULE,
+    SYMBOL_TYPE_TYPE,
 };
+pub use profile::{attributed, profiled, CacheOutcome, SqlEvent, SqlProfile};
+pub use replicas::ReplicaSet;
 pub use selection::{
     Checked, ChildReference, EphContext, HasChildReference, HasEphLeak, HasParentReference,
     ObjectFullDiesel, ParentReference, QueryStatementRange, ReferenceFullDiesel, ReferenceResult,
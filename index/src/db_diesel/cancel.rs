@@ -0,0 +1,135 @@
+//! Cancelling the SQL of one query execution.
+//!
+//! A caller that may abandon a query runs it under [`cancellable`] with a
+//! [`CancelScope`].  Every connection a load inside that future checks out is
+//! registered with the scope for as long as it is held, so
+//! [`CancelScope::cancel`] can ask Postgres to cancel whatever those
+//! connections are running.  Once the scope is cancelled, further checkouts
+//! in it fail, so the query winds down with an error instead of issuing
+//! more SQL.
+//!
+//! Like [`super::profile`], the scope is a tokio task-local and follows the
+//! future rather than the thread.
+//!
+//! Postgres cancellation is inherently racy: the server cancels whatever the
+//! backend is running when the request lands.  A connection that went back
+//! to the pool could by then be running another request's statement, so
+//! callers cancel while the query future is suspended (its connections are
+//! still checked out) and then keep driving it until it fails, rather than
+//! dropping it.
+
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::{Arc, Mutex};
+
+use diesel_async::AsyncPgConnection;
+use tokio_postgres::CancelToken;
+
+tokio::task_local! {
+    static SCOPE: Arc<CancelScope>;
+}
+
+/// The message of the error a checkout fails with in a cancelled scope.
+pub const QUERY_CANCELLED: &str = "Query cancelled";
+
+/// The connections one query execution holds; see the module docs.
+#[derive(Default)]
+pub struct CancelScope {
+    state: Mutex<ScopeState>,
+}
+
+#[derive(Default)]
+struct ScopeState {
+    cancelled: bool,
+    next_id: u64,
+    connections: HashMap<u64, CancelToken>,
+}
+
+impl CancelScope {
+    pub fn new() -> Arc<Self> {
+        Arc::new(Self::default())
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.state.lock().unwrap().cancelled
+    }
+
+    /// Mark the scope cancelled and ask Postgres to cancel the statement
+    /// running on each connection it holds.  Returns once every request has
+    /// been sent; a request that could not be sent is logged and skipped.
+    pub async fn cancel(&self) {
+        let tokens: Vec<CancelToken> = {
+            let mut state = self.state.lock().unwrap();
+            state.cancelled = true;
+            state.connections.values().cloned().collect()
+        };
+        let requests = tokens
+            .iter()
+            .map(|token| token.cancel_query(tokio_postgres::NoTls));
+        for result in futures::future::join_all(requests).await {
+            if let Err(err) = result {
+                log::warn!("Failed to send query cancel request: {}", err);
+            }
+        }
+    }
+}
+
+/// Run `fut` with its connections registered with `scope`.
+pub async fn cancellable<F: Future>(scope: Arc<CancelScope>, fut: F) -> F::Output {
+    SCOPE.scope(scope, fut).await
+}
+
+/// Keeps a checked-out connection registered with its scope until dropped.
+/// Hold it exactly as long as the connection.
+pub(crate) struct Registration {
+    scope: Arc<CancelScope>,
+    id: u64,
+}
+
+impl Drop for Registration {
+    fn drop(&mut self) {
+        self.scope
+            .state
+            .lock()
+            .unwrap()
+            .connections
+            .remove(&self.id);
+    }
+}
+
+/// Register `connection` with the active scope, if any.  Fails when that
+/// scope has been cancelled, so the caller issues no further SQL.
+pub(crate) fn register(connection: &AsyncPgConnection) -> anyhow::Result<Option<Registration>> {
+    let Ok(scope) = SCOPE.try_with(Arc::clone) else {
+        return Ok(None);
+    };
+    let id = {
+        let mut state = scope.state.lock().unwrap();
+        if state.cancelled {
+            return Err(anyhow::anyhow!(QUERY_CANCELLED));
+        }
+        let id = state.next_id;
+        state.next_id += 1;
+        state.connections.insert(id, connection.cancel_token());
+        id
+    };
+    Ok(Some(Registration { scope, id }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn cancel_marks_the_scope_without_connections() {
+        let scope = CancelScope::new();
+        assert!(!scope.is_cancelled());
+        cancellable(scope.clone(), async {
+            SCOPE.with(|active| assert!(Arc::ptr_eq(active, &scope)));
+        })
+        .await;
+        scope.cancel().await;
+        assert!(scope.is_cancelled());
+        assert!(SCOPE.try_with(|_| ()).is_err());
+    }
+}
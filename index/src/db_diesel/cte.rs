@@ -200,7 +200,7 @@ where
 // CteFindEdgesBetween — typed wrapper around `find_edges_between`'s CTE form
 // ============================================================================
 
-use diesel::sql_types::{BigInt, Int4range, Integer};
+use diesel::sql_types::{BigInt, Int4range, Integer, Nullable};
 
 /// Build the inner CTE body for `find_edges_between`: select the full
 /// candidate `symbol_instances` row set (id + symbol + object_id +
@@ -237,15 +237,16 @@ pub(super) fn build_find_edges_cte_body(
 /// Result-row `SqlType` for `CteFindEdgesBetween`'s outer SELECT.
 /// Matches the column order in `ImplicitEdge`.
 pub(super) type FindEdgesRowSqlType = (
-    BigInt,    // ref_id
-    BigInt,    // to_symbol
-    Integer,   // from_object
-    Int4range, // from_offset_range
-    BigInt,    // to_instance_id
-    BigInt,    // from_instance_id
-    BigInt,    // sr_layer
-    BigInt,    // from_layer
-    BigInt,    // to_layer
+    BigInt,            // ref_id
+    BigInt,            // to_symbol
+    Integer,           // from_object
+    Int4range,         // from_offset_range
+    BigInt,            // to_instance_id
+    BigInt,            // from_instance_id
+    BigInt,            // sr_layer
+    BigInt,            // from_layer
+    BigInt,            // to_layer
+    Nullable<Integer>, // kind
 );
 
 /// Typed wrapper around `find_edges_between`'s CTE-form query.
@@ -294,7 +295,8 @@ where
                   from_inst.id AS from_instance_id, \
                   sr.layer AS sr_layer, \
                   from_inst.layer AS from_layer, \
-                  to_inst.layer AS to_layer \
+                  to_inst.layer AS to_layer, \
+                  sr.kind \
               FROM candidates from_inst \
               JOIN index.symbol_refs sr \
                   ON sr.from_object = from_inst.object_id \
@@ -10,7 +10,9 @@ use diesel_async::pooled_connection::{
 use diesel_async::{AsyncPgConnection, RunQueryDsl};
 use diesel_migrations::MigrationHarness;
 
-use crate::models_diesel::{ContentRow, Object, Project, Symbol, SymbolInstance, SymbolRef};
+use crate::models_diesel::{
+    ContentRow, Object, Project, Symbol, SymbolInstance, SymbolRef, SymbolRelation,
+};
 use crate::symbols::FileId;
 use std::future::Future;
 use std::pin::Pin;
@@ -168,7 +170,7 @@ impl Index {
         }
 
         let results: std::sync::Arc<Vec<(Symbol, SymbolInstance, Object, Project)>> = self
-            .cached_load(query)
+            .cached_load_explained(query, None, ReadRoute::for_eph(eph))
             .await
             .map_err(|e| anyhow::anyhow!("Failed to resolve filter to IDs: {}", e))?;
         tracing::info!(
@@ -366,6 +368,29 @@ pub struct Index {
     /// In-RAM SQL result cache; see [`super::sql_cache`] module docs.  All
     /// clones of an `Index` share one cache (Arc), mirroring the shared pool.
     sql_cache: std::sync::Arc<super::sql_cache::SqlResultCache>,
+    /// Read replicas for persistent-only reads; see [`ReadRoute`].
+    replicas: Option<std::sync::Arc<super::replicas::ReplicaSet>>,
+}
+
+/// Where a read may run.  Ephemeral layers are written on the primary
+/// within the request that reads them, so any read whose visibility can
+/// include an eph layer stays there; reads of persistent rows only may go
+/// to a healthy replica when the server has any.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum ReadRoute {
+    Primary,
+    Replica,
+}
+
+impl ReadRoute {
+    /// Replica-eligible only while the request has materialised no layers.
+    fn for_eph(eph: &EphContext) -> Self {
+        if eph.has_chain() {
+            Self::Primary
+        } else {
+            Self::Replica
+        }
+    }
 }
 
 /// Default budget for the SQL result cache (256 MiB) — used by the server
@@ -860,6 +885,23 @@ impl Index {
         self
     }
 
+    /// Builder-style: route persistent-only reads to `replicas` (see
+    /// [`ReadRoute`]); the primary pool keeps eph-layer reads and all writes.
+    pub fn with_replicas(mut self, replicas: std::sync::Arc<super::replicas::ReplicaSet>) -> Self {
+        self.replicas = Some(replicas);
+        self
+    }
+
+    /// Pool for a read on `route`, and whether it is a replica's.
+    fn read_pool(&self, route: ReadRoute) -> (&bb8::Pool<AsyncPgConnection>, bool) {
+        if route == ReadRoute::Replica {
+            if let Some(pool) = self.replicas.as_ref().and_then(|r| r.pick()) {
+                return (pool, true);
+            }
+        }
+        (&self.pool, false)
+    }
+
     /// Construct with an explicit (possibly shared) SQL result cache — the
     /// server hands the same instance to `IndexStore` so the mutation side
     /// clears the cache the query side reads.
@@ -871,6 +913,7 @@ impl Index {
             pool,
             database_url: None,
             sql_cache,
+            replicas: None,
         }
     }
 
@@ -893,42 +936,157 @@ impl Index {
     /// NOT for populate closures: those run inside an [`EphTransaction`] on
     /// `txn.connection()` and must see uncommitted state — keeping them off
     /// `Index` methods is the structural opt-out.
+    ///
+    /// Under an active [`super::SqlProfile`] every call is recorded; see
+    /// [`Self::cached_load_explained`].
     pub async fn cached_load<Q, T>(&self, query: Q) -> Result<std::sync::Arc<Vec<T>>>
     where
         Q: diesel_async::methods::LoadQuery<'static, AsyncPgConnection, T>
             + diesel::query_builder::QueryFragment<Pg>
+            + Send
+            + 'static,
+        T: Clone + Send + Sync + super::sql_cache::CacheWeight + 'static,
+    {
+        self.cached_load_explained(query, None, ReadRoute::Primary)
+            .await
+    }
+
+    /// [`Self::cached_load`] for a query that reads persistent rows only,
+    /// which may therefore run on a read replica.
+    async fn cached_load_persistent<Q, T>(&self, query: Q) -> Result<std::sync::Arc<Vec<T>>>
+    where
+        Q: diesel_async::methods::LoadQuery<'static, AsyncPgConnection, T>
+            + diesel::query_builder::QueryFragment<Pg>
+            + Send
             + 'static,
         T: Clone + Send + Sync + super::sql_cache::CacheWeight + 'static,
     {
+        self.cached_load_explained(query, None, ReadRoute::Replica)
+            .await
+    }
+
+    /// [`Self::cached_load`] plus an optional second instantiation of the
+    /// same query for `EXPLAIN ANALYZE`.  The plan is only produced when the
+    /// active profile asks for it; it runs regardless of the cache outcome,
+    /// so a hit still shows what Postgres would do for it.
+    async fn cached_load_explained<Q, T>(
+        &self,
+        query: Q,
+        explain: Option<Q>,
+        route: ReadRoute,
+    ) -> Result<std::sync::Arc<Vec<T>>>
+    where
+        Q: diesel_async::methods::LoadQuery<'static, AsyncPgConnection, T>
+            + diesel::query_builder::QueryFragment<Pg>
+            + Send
+            + 'static,
+        T: Clone + Send + Sync + super::sql_cache::CacheWeight + 'static,
+    {
+        use super::profile::{CacheOutcome, SqlEvent};
         use super::sql_cache::{vec_weight, CacheKey};
 
+        let profile = super::profile::current();
+        let started = std::time::Instant::now();
+        let sql = profile
+            .as_ref()
+            .map(|_| diesel::debug_query::<Pg, _>(&query).to_string());
+        // The plan is fetched after the load is timed, so `elapsed` never
+        // includes the EXPLAIN round trip.
+        let record = |cache: CacheOutcome,
+                      rows: usize,
+                      elapsed: std::time::Duration,
+                      plan: Option<String>| {
+            if let (Some((profile, statement)), Some(sql)) = (&profile, &sql) {
+                profile.record(SqlEvent {
+                    statement: *statement,
+                    row_type: std::any::type_name::<T>(),
+                    sql: sql.clone(),
+                    cache,
+                    rows,
+                    elapsed,
+                    plan,
+                });
+            }
+        };
+        let explain = match (&profile, explain) {
+            (Some((profile, _)), Some(explain)) if profile.analyze() => Some(explain),
+            _ => None,
+        };
+
         let key = CacheKey::for_query::<T, _>(&query);
         if let Some(k) = &key {
             if let Some(hit) = self.sql_cache.get::<T>(k) {
+                if profile.is_some() {
+                    let elapsed = started.elapsed();
+                    let plan = self.explain_plan(explain, route).await;
+                    record(CacheOutcome::Hit, hit.len(), elapsed, plan);
+                }
                 return Ok(hit);
             }
         }
 
         let epoch = self.sql_cache.epoch();
-        let mut connection = self
-            .pool
+        let (pool, from_replica) = self.read_pool(route);
+        let mut connection = pool
             .get()
             .await
             .map_err(|e| anyhow::anyhow!("Failed to get connection: {}", e))?;
+        let registration = super::cancel::register(&connection)?;
         let rows: Vec<T> = query
             .load(&mut *connection)
             .await
             .map_err(|e| anyhow::anyhow!("Failed to load cached query: {}", e))?;
+        drop(registration);
         drop(connection);
 
         let arc = std::sync::Arc::new(rows);
-        if let Some(k) = key {
+        let outcome = if key.is_some() {
+            CacheOutcome::Miss
+        } else {
+            CacheOutcome::Uncached
+        };
+        // A replica may not have replayed the mutation behind a recent
+        // clear; caching its rows would pin them until the next one.
+        let replica_may_lag = from_replica
+            && self
+                .replicas
+                .as_ref()
+                .is_some_and(|r| self.sql_cache.cleared_within(r.max_lag()));
+        if let Some(k) = key.filter(|_| !replica_may_lag) {
             let bytes = vec_weight(&arc);
             self.sql_cache.put_if_epoch(k, arc.clone(), bytes, epoch);
         }
+        if profile.is_some() {
+            let elapsed = started.elapsed();
+            let plan = self.explain_plan(explain, route).await;
+            record(outcome, arc.len(), elapsed, plan);
+        }
         Ok(arc)
     }
 
+    /// Run `EXPLAIN ANALYZE` for `query`.  Failures are reported in place of
+    /// the plan: a diagnostic must not fail the query it describes.
+    async fn explain_plan<Q>(&self, query: Option<Q>, route: ReadRoute) -> Option<String>
+    where
+        Q: diesel::query_builder::QueryFragment<Pg> + Send + 'static,
+    {
+        let query = super::profile::ExplainAnalyze(query?);
+        let plan: Result<Vec<String>> = async {
+            let mut connection = self
+                .read_pool(route)
+                .0
+                .get()
+                .await
+                .map_err(|e| anyhow::anyhow!("Failed to get connection: {}", e))?;
+            Ok(query.load::<String>(&mut *connection).await?)
+        }
+        .await;
+        Some(match plan {
+            Ok(lines) => lines.join("\n"),
+            Err(err) => format!("EXPLAIN failed: {}", err),
+        })
+    }
+
     /// The ONLY way to run an eph-visible read on the query hot path.
     ///
     /// Instantiates the query template `build` per branch via the
@@ -983,12 +1141,24 @@ impl Index {
         F: Fn(&EphVisibility) -> Q,
         Q: diesel_async::methods::LoadQuery<'static, AsyncPgConnection, T>
             + diesel::query_builder::QueryFragment<Pg>
+            + Send
             + 'static,
         T: Clone + Send + Sync + super::sql_cache::CacheWeight + super::sql_cache::RowKey + 'static,
     {
+        // Under `EXPLAIN ANALYZE` profiling each branch is instantiated a
+        // second time for the plan, from a fresh token (tokens record the
+        // columns they were applied to, so they are single-use).  A template
+        // is a pure function of its token, so the copy renders the same SQL.
+        let analyze = super::profile::current().is_some_and(|(profile, _)| profile.analyze());
+        // Root-only branches see persistent rows alone; a Combined query
+        // sees the chain too once there is one.
+        let load = |token: fn(&EphContext) -> EphVisibility, route| {
+            let explain = analyze.then(|| build(&token(eph)));
+            self.cached_load_explained(build(&token(eph)), explain, route)
+        };
+
         if chain_dependent {
-            let vis = EphVisibility::combined(eph);
-            let rows = self.cached_load(build(&vis)).await?;
+            let rows = load(EphVisibility::combined, ReadRoute::for_eph(eph)).await?;
             return Ok(rows.as_ref().clone());
         }
 
@@ -1000,9 +1170,7 @@ impl Index {
         // guaranteed-empty roundtrip per family on every plain query plus
         // the dead cache entry it would store.
         if !eph.has_chain() {
-            let persistent = self
-                .cached_load(build(&EphVisibility::root_only(eph)))
-                .await?;
+            let persistent = load(EphVisibility::root_only, ReadRoute::Replica).await?;
             return Ok(persistent.as_ref().clone());
         }
 
@@ -1012,11 +1180,12 @@ impl Index {
             evis.guard_was_taken(),
             "eph-branch query built without applying vis.guard()"
         );
+        let eph_explain = analyze.then(|| build(&EphVisibility::eph_touching(eph)));
         // The branches are independent (separate pool connections on
         // misses); run them concurrently.
         let (persistent, ephemeral) = tokio::try_join!(
-            self.cached_load(build(&EphVisibility::root_only(eph))),
-            self.cached_load(eph_query),
+            load(EphVisibility::root_only, ReadRoute::Replica),
+            self.cached_load_explained(eph_query, eph_explain, ReadRoute::Primary),
         )?;
 
         let mut seen = std::collections::HashSet::with_capacity(persistent.len() + ephemeral.len());
@@ -1038,7 +1207,7 @@ impl Index {
         use crate::schema_diesel::{layers, projects};
 
         let rows: std::sync::Arc<Vec<(i64, i32, Vec<u8>)>> = self
-            .cached_load(
+            .cached_load_persistent(
                 projects::table
                     .inner_join(layers::table.on(layers::id.eq(projects::root_layer_id)))
                     .filter(projects::id.gt(0))
@@ -1070,7 +1239,8 @@ impl Index {
         use crate::schema_diesel::objects;
 
         let connection = &mut self
-            .pool
+            .read_pool(ReadRoute::Replica)
+            .0
             .get()
             .await
             .map_err(|e| anyhow::anyhow!("Failed to get connection: {}", e))?;
@@ -1085,6 +1255,52 @@ impl Index {
         Ok(rows.into_iter().collect())
     }
 
+    /// Declaration metadata for `symbol_ids`, keyed by symbol id. Symbols the
+    /// indexer did not describe are absent from the map.
+    pub async fn load_symbol_metadata(
+        &self,
+        symbol_ids: &[i64],
+    ) -> Result<std::collections::HashMap<i64, crate::symbols::SymbolMetadata>> {
+        use crate::schema_diesel::symbol_metadata;
+
+        if symbol_ids.is_empty() {
+            return Ok(Default::default());
+        }
+        let connection = &mut self
+            .read_pool(ReadRoute::Replica)
+            .0
+            .get()
+            .await
+            .map_err(|e| anyhow::anyhow!("Failed to get connection: {}", e))?;
+
+        let rows: Vec<crate::models_diesel::SymbolMetadata> = symbol_metadata::table
+            .filter(symbol_metadata::symbol.eq_any(symbol_ids))
+            .select(crate::models_diesel::SymbolMetadata::as_select())
+            .load(&mut *connection)
+            .await
+            .map_err(|e| anyhow::anyhow!("Failed to load symbol metadata: {}", e))?;
+
+        Ok(rows
+            .into_iter()
+            .map(|row| {
+                let parameters = row
+                    .param_names
+                    .into_iter()
+                    .zip(row.param_types)
+                    .map(|(name, type_name)| crate::symbols::Parameter { name, type_name })
+                    .collect();
+                let metadata = crate::symbols::SymbolMetadata {
+                    signature: row.signature,
+                    return_type: row.return_type,
+                    parameters,
+                    storage_class: row.storage_class,
+                    visibility: row.visibility,
+                };
+                (row.symbol, metadata)
+            })
+            .collect())
+    }
+
     fn build_async_manager(database_url: &str) -> AsyncDieselConnectionManager<AsyncPgConnection> {
         AsyncDieselConnectionManager::new_with_config(database_url, eph_pool_manager_config())
     }
@@ -1109,6 +1325,7 @@ impl Index {
             pool,
             database_url: Some(database_url.to_string()),
             sql_cache: super::sql_cache::SqlResultCache::new(0),
+            replicas: None,
         };
         index.validate_canary().await?;
         Ok(index)
@@ -1174,6 +1391,7 @@ impl Index {
             pool,
             database_url: Some(database_url.to_string()),
             sql_cache: super::sql_cache::SqlResultCache::new(0),
+            replicas: None,
         })
     }
 
@@ -1187,6 +1405,10 @@ impl Index {
     pub const TEST_INPUT_TYPE_FILTER: &'static str = "test_input_type_filter.sql";
     pub const VERB_TEST: &'static str = "verb_test.sql";
     pub const TEST_INPUT_SEARCH: &'static str = "test_input_search.sql";
+    pub const TEST_INPUT_REF_KINDS: &'static str = "test_input_ref_kinds.sql";
+    pub const TEST_INPUT_SYMBOL_METADATA: &'static str = "test_input_symbol_metadata.sql";
+    pub const TEST_INPUT_SYMBOL_RELATIONS: &'static str = "test_input_symbol_relations.sql";
+    pub const TEST_INPUT_DISPATCH: &'static str = "test_input_dispatch.sql";
 
     /// Lookup table of test-fixture file name → embedded SQL.  Kept here so
     /// each new fixture only needs to land its file under `askl/sql/` and add
@@ -1229,6 +1451,22 @@ impl Index {
             "test_input_search.sql",
             include_str!("../../../sql/test_input_search.sql"),
         ),
+        (
+            "test_input_ref_kinds.sql",
+            include_str!("../../../sql/test_input_ref_kinds.sql"),
+        ),
+        (
+            "test_input_symbol_metadata.sql",
+            include_str!("../../../sql/test_input_symbol_metadata.sql"),
+        ),
+        (
+            "test_input_symbol_relations.sql",
+            include_str!("../../../sql/test_input_symbol_relations.sql"),
+        ),
+        (
+            "test_input_dispatch.sql",
+            include_str!("../../../sql/test_input_dispatch.sql"),
+        ),
     ];
 
     fn load_sql(connection: &mut PgConnection, input_path: &str) {
@@ -1271,7 +1509,8 @@ impl Index {
 
     pub async fn get_file_contents(&self, object_id: FileId) -> Result<String> {
         let connection = &mut self
-            .pool
+            .read_pool(ReadRoute::Replica)
+            .0
             .get()
             .await
             .map_err(|e| anyhow::anyhow!("Failed to get connection: {}", e))?;
@@ -1347,7 +1586,8 @@ impl Index {
     /// when offsets must stay exact (e.g. rendering `file:line` locations).
     pub async fn get_file_contents_bytes(&self, object_id: FileId) -> Result<Vec<u8>> {
         let connection = &mut self
-            .pool
+            .read_pool(ReadRoute::Replica)
+            .0
             .get()
             .await
             .map_err(|e| anyhow::anyhow!("Failed to get connection: {}", e))?;
@@ -1781,6 +2021,7 @@ impl Index {
                 children,
                 has_parents,
                 has_children,
+                relations: Vec::new(),
             })
         };
 
@@ -2015,6 +2256,127 @@ impl Index {
             .collect())
     }
 
+    /// Relations with either end among `symbol_ids` (sorted, deduped).
+    /// Relations are persistent-only and carry no layer: visibility is
+    /// decided by the selected instances at either end.  `find_symbol`
+    /// leaves [`Selection::relations`] empty; callers load them here only
+    /// when a relation kind is traversed.
+    pub async fn load_relations_touching(
+        &self,
+        symbol_ids: Vec<i64>,
+    ) -> Result<Vec<SymbolRelation>> {
+        use crate::schema_diesel::symbol_relations;
+
+        if symbol_ids.is_empty() {
+            return Ok(vec![]);
+        }
+        let _span =
+            tracing::info_span!("select_relations", source_count = symbol_ids.len()).entered();
+        let t0 = std::time::Instant::now();
+        let rows = self
+            .cached_load_persistent(
+                symbol_relations::table
+                    .filter(
+                        symbol_relations::from_symbol
+                            .eq_any(symbol_ids.clone())
+                            .or(symbol_relations::to_symbol.eq_any(symbol_ids)),
+                    )
+                    .order((
+                        symbol_relations::from_symbol,
+                        symbol_relations::to_symbol,
+                        symbol_relations::kind,
+                    ))
+                    .select(SymbolRelation::as_select()),
+            )
+            .await
+            .map_err(|e| anyhow::anyhow!("Failed to load symbol relations: {}", e))?;
+        tracing::info!(
+            elapsed_ms = t0.elapsed().as_millis() as u64,
+            result_rows = rows.len(),
+            "select_relations completed",
+        );
+        Ok(rows.as_ref().clone())
+    }
+
+    /// Query instance IDs of the symbols related to `symbol_ids` through a
+    /// relation of one of `kinds`.  `outgoing` follows relations from the
+    /// given symbols (what they extend), otherwise towards them (what
+    /// extends them).
+    pub async fn find_related_instance_ids(
+        &self,
+        symbol_ids: &[i64],
+        kinds: &[i32],
+        outgoing: bool,
+        eph: &EphContext,
+    ) -> Result<Vec<crate::symbols::SymbolInstanceId>> {
+        use crate::schema_diesel::{symbol_instances, symbol_relations};
+
+        if symbol_ids.is_empty() || kinds.is_empty() {
+            return Ok(vec![]);
+        }
+        let visible_ids = eph.visible_ids();
+        let eph_ids: &[i64] = &visible_ids;
+
+        // Canonical bind order for cache-key stability (see find_edges_between).
+        let mut source_ids = symbol_ids.to_vec();
+        source_ids.sort_unstable();
+        source_ids.dedup();
+        let mut kinds = kinds.to_vec();
+        kinds.sort_unstable();
+
+        let _span = tracing::info_span!(
+            "find_related_instance_ids",
+            source_count = source_ids.len(),
+            outgoing,
+            eph_count = eph_ids.len(),
+        )
+        .entered();
+        let t0 = std::time::Instant::now();
+        let results: Vec<SymbolInstance> = self
+            .cached_load_partitioned(eph, false, |vis| {
+                let related = if outgoing {
+                    symbol_relations::table
+                        .filter(symbol_relations::from_symbol.eq_any(source_ids.clone()))
+                        .filter(symbol_relations::kind.eq_any(kinds.clone()))
+                        .select(symbol_relations::to_symbol)
+                        .into_boxed()
+                } else {
+                    symbol_relations::table
+                        .filter(symbol_relations::to_symbol.eq_any(source_ids.clone()))
+                        .filter(symbol_relations::kind.eq_any(kinds.clone()))
+                        .select(symbol_relations::from_symbol)
+                        .into_boxed()
+                };
+                symbol_instances::table
+                    .filter(symbol_instances::symbol.eq_any(related))
+                    .filter(vis.pred("symbol_instances.layer"))
+                    .select(SymbolInstance::as_select())
+                    .into_boxed::<Pg>()
+                    .filter(vis.guard())
+            })
+            .await
+            .map_err(|e| anyhow::anyhow!("Failed to find related instance IDs: {}", e))?;
+        tracing::info!(
+            elapsed_ms = t0.elapsed().as_millis() as u64,
+            result_rows = results.len(),
+            "find_related_instance_ids completed",
+        );
+        for inst in &results {
+            if is_eph_leak(inst.layer, eph_ids) {
+                tracing::error!(?eph_ids, "layer leak in find_related_instance_ids");
+                anyhow::bail!("internal error: ephemeral layer isolation violation");
+            }
+        }
+
+        let mut all_ids: Vec<i64> = results.iter().map(|inst| inst.id).collect();
+        all_ids.sort_unstable();
+        all_ids.dedup();
+        Ok(all_ids
+            .into_iter()
+            .map(crate::symbols::SymbolInstanceId::new)
+            .collect())
+    }
+
     /// Discover all reference edges between a set of selected instances.
     pub async fn find_edges_between(
         &self,
@@ -2151,6 +2513,7 @@ impl Index {
             .get()
             .await
             .map_err(|e| anyhow::anyhow!("Failed to get connection: {}", e))?;
+        let cancel = super::cancel::register(&conn)?;
 
         diesel::sql_query("BEGIN")
             .execute(&mut *conn)
@@ -2235,6 +2598,7 @@ impl Index {
         }
 
         Ok(EphTransaction {
+            _cancel: cancel,
             conn,
             layer_id: id,
             created,
@@ -2341,6 +2705,7 @@ impl Index {
             .get()
             .await
             .map_err(|e| anyhow::anyhow!("Failed to get connection: {}", e))?;
+        let _cancel = super::cancel::register(connection)?;
         // ORDER BY makes the per-project layer order deterministic (SELECT
         // DISTINCT alone does not), so the resulting atom materialisation and
         // activation-trace order are stable across runs.
@@ -2749,26 +3114,44 @@ impl Index {
     /// (throttled: rows fresher than an hour are skipped by the WHERE
     /// clause, so repeated hits cost a no-op statement, not a write).
     pub async fn touch_eph_layers(&self, layer_ids: &[i64]) -> Result<()> {
+        self.touch_eph_layers_older_than(layer_ids, Duration::from_secs(3600))
+            .await
+            .map(|_| ())
+    }
+
+    /// Touch the last_used timestamps of those `layer_ids` not used within
+    /// `age`, returning how many rows were touched.  With a zero `age` every
+    /// layer that still exists is touched, so a count short of the number of
+    /// distinct ids means some were garbage-collected.
+    pub async fn touch_eph_layers_older_than(
+        &self,
+        layer_ids: &[i64],
+        age: Duration,
+    ) -> Result<usize> {
         if layer_ids.is_empty() {
-            return Ok(());
+            return Ok(0);
         }
         use crate::schema_diesel::layers;
-        use diesel::sql_types::Bool;
+        use diesel::sql_types::{BigInt, Bool};
         let connection = &mut self
             .pool
             .get()
             .await
             .map_err(|e| anyhow::anyhow!("Failed to get connection: {}", e))?;
 
-        diesel::update(layers::table.filter(layers::id.eq_any(layer_ids)).filter(
-            diesel::dsl::sql::<Bool>("last_used < now() - interval '1 hour'"),
-        ))
+        let touched = diesel::update(
+            layers::table.filter(layers::id.eq_any(layer_ids)).filter(
+                diesel::dsl::sql::<Bool>("last_used <= now() - make_interval(secs => ")
+                    .bind::<BigInt, _>(age.as_secs() as i64)
+                    .sql(")"),
+            ),
+        )
         .set(layers::last_used.eq(diesel::dsl::now))
         .execute(&mut *connection)
         .await
         .map_err(|e| anyhow::anyhow!("Failed to touch eph layers: {}", e))?;
 
-        Ok(())
+        Ok(touched)
     }
 
     /// Check if a symbol exists in the visible layer set (roots ∪ chain).
@@ -2776,10 +3159,12 @@ impl Index {
         use crate::schema_diesel::symbols;
 
         let connection = &mut self
-            .pool
+            .read_pool(ReadRoute::for_eph(eph))
+            .0
             .get()
             .await
             .map_err(|e| anyhow::anyhow!("Failed to get connection: {}", e))?;
+        let _cancel = super::cancel::register(connection)?;
 
         let exists = symbols::table
             .filter(symbols::id.eq(symbol_id))
@@ -2896,7 +3281,8 @@ impl Index {
             query = query.filter(projects::project_name.eq(name.to_string()));
         }
 
-        let rows: std::sync::Arc<Vec<(i32, String, String)>> = self.cached_load(query).await?;
+        let rows: std::sync::Arc<Vec<(i32, String, String)>> =
+            self.cached_load_persistent(query).await?;
 
         let mut resolved: Vec<ResolvedSourceFile> = rows
             .iter()
@@ -3196,6 +3582,8 @@ pub struct ImplicitEdge {
     pub from_layer: i64,
     #[diesel(sql_type = diesel::sql_types::BigInt)]
     pub to_layer: i64,
+    #[diesel(sql_type = diesel::sql_types::Nullable<diesel::sql_types::Integer>)]
+    pub kind: Option<i32>,
 }
 
 /// Holds a pooled connection with an open transaction for atomic ephemeral layer
@@ -3205,6 +3593,10 @@ pub struct ImplicitEdge {
 /// (e.g. early `?` return), the pool's `RecyclingMethod::CustomQuery("ROLLBACK")`
 /// cleans up the stale transaction on next checkout.
 pub struct EphTransaction<'a> {
+    /// Keeps `conn` cancellable for the life of the transaction.  Declared
+    /// before `conn` so it is dropped first: the registration must be gone
+    /// before the connection returns to the pool and serves another request.
+    _cancel: Option<super::cancel::Registration>,
     conn: bb8::PooledConnection<'a, AsyncPgConnection>,
     layer_id: i64,
     created: bool,
@@ -16,8 +16,8 @@ use crate::ltree::Ltree;
 use crate::models_diesel::{Object, Project, Symbol, SymbolInstance, SymbolRef};
 use crate::schema_diesel as index_schema;
 use crate::symbols::{
-    build_lquery, normalize_leaf_fragment, normalize_symbol_tokens, smart_case_sensitive,
-    symbol_name_to_path, SymbolInstanceId,
+    build_lquery, normalize_leaf_fragment, normalize_symbol_tokens, normalize_type_text,
+    smart_case_sensitive, symbol_name_to_path, SymbolInstanceId,
 };
 
 diesel::alias! {
@@ -1590,6 +1590,143 @@ impl FilterLeaf for PackageDescendantLeaf {
     }
 }
 
+/// RefKindMixin — restricts REFS edges to references of the given kinds.
+/// Unclassified refs (`kind IS NULL`) never match.
+#[derive(Debug, Clone)]
+pub struct RefKindMixin {
+    kinds: Vec<i32>,
+}
+
+impl RefKindMixin {
+    pub fn new(kinds: Vec<i32>) -> Self {
+        Self { kinds }
+    }
+}
+
+impl FilterLeaf for RefKindMixin {
+    fn parents_expr(&self, _vis: &EphVisibility) -> Option<ParentsBoolExpr> {
+        Some(Box::new(
+            index_schema::symbol_refs::dsl::kind
+                .assume_not_null()
+                .eq_any(self.kinds.clone()),
+        ))
+    }
+
+    fn children_expr(&self, _vis: &EphVisibility) -> Option<ChildrenBoolExpr> {
+        Some(Box::new(
+            index_schema::symbol_refs::dsl::kind
+                .assume_not_null()
+                .eq_any(self.kinds.clone()),
+        ))
+    }
+
+    fn hash_into(&self, h: &mut Sha256) {
+        h.update(b"RefKind");
+        h.update((self.kinds.len() as u32).to_le_bytes());
+        for kind in &self.kinds {
+            h.update(kind.to_le_bytes());
+        }
+    }
+}
+
+/// DispatchMixin — restricts REFS edges to dispatch: a function whose
+/// address is taken inside a data or field instance, as in a
+/// `file_operations` initialiser.  The holder may call the function.
+#[derive(Debug, Clone)]
+pub struct DispatchMixin;
+
+impl DispatchMixin {
+    pub fn new() -> Self {
+        Self
+    }
+
+    fn sql() -> String {
+        format!(
+            "symbol_refs.kind = {} \
+             AND parent_symbols.symbol_type IN ({}, {}) \
+             AND symbols.symbol_type = {}",
+            REF_KIND_ADDRESS_TAKEN, SYMBOL_TYPE_DATA, SYMBOL_TYPE_FIELD, SYMBOL_TYPE_FUNCTION,
+        )
+    }
+}
+
+impl Default for DispatchMixin {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl FilterLeaf for DispatchMixin {
+    fn parents_expr(&self, _vis: &EphVisibility) -> Option<ParentsBoolExpr> {
+        Some(Box::new(OwnedSql::<Bool>::new(Self::sql())))
+    }
+
+    fn children_expr(&self, _vis: &EphVisibility) -> Option<ChildrenBoolExpr> {
+        Some(Box::new(OwnedSql::<Bool>::new(Self::sql())))
+    }
+
+    fn hash_into(&self, h: &mut Sha256) {
+        h.update(b"Dispatch");
+    }
+}
+
+/// Which `index.symbol_metadata` column a [`SymbolMetadataMixin`] matches.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MetadataField {
+    ReturnType,
+    StorageClass,
+    Visibility,
+}
+
+/// SymbolMetadataMixin — keeps symbols whose metadata column equals `value`.
+/// Symbols without a metadata row never match. Return types compare in
+/// [`normalize_type_text`] form, which is how upload stores them.
+#[derive(Debug, Clone)]
+pub struct SymbolMetadataMixin {
+    field: MetadataField,
+    value: String,
+}
+
+impl SymbolMetadataMixin {
+    pub fn new(field: MetadataField, value: &str) -> Self {
+        let value = match field {
+            MetadataField::ReturnType => normalize_type_text(value),
+            MetadataField::StorageClass | MetadataField::Visibility => value.trim().to_string(),
+        };
+        Self { field, value }
+    }
+}
+
+impl FilterLeaf for SymbolMetadataMixin {
+    fn current_expr(&self, _vis: &EphVisibility) -> Option<CurrentBoolExpr> {
+        use index_schema::symbol_metadata::dsl as md;
+
+        let value = self.value.clone();
+        let matching = match self.field {
+            MetadataField::ReturnType => md::symbol_metadata
+                .filter(md::return_type.eq(value))
+                .select(md::symbol)
+                .into_boxed(),
+            MetadataField::StorageClass => md::symbol_metadata
+                .filter(md::storage_class.eq(value))
+                .select(md::symbol)
+                .into_boxed(),
+            MetadataField::Visibility => md::symbol_metadata
+                .filter(md::visibility.eq(value))
+                .select(md::symbol)
+                .into_boxed(),
+        };
+        Some(Box::new(index_schema::symbols::dsl::id.eq_any(matching)))
+    }
+
+    fn hash_into(&self, h: &mut Sha256) {
+        h.update(b"SymbolMetadata");
+        h.update([self.field as u8]);
+        h.update((self.value.len() as u32).to_le_bytes());
+        h.update(self.value.as_bytes());
+    }
+}
+
 /// Symbol type constants
 pub const SYMBOL_TYPE_FUNCTION: i32 = 1;
 pub const SYMBOL_TYPE_FILE: i32 = 2;
@@ -1615,6 +1752,18 @@ pub const INSTANCE_TYPE_BUILD: i32 = 8;
 pub const INSTANCE_TYPE_FILE: i32 = 9;
 pub const INSTANCE_TYPE_DOCUMENTATION: i32 = 10;
 
+/// Ref kind constants
+pub const REF_KIND_CALL: i32 = 1;
+pub const REF_KIND_READ: i32 = 2;
+pub const REF_KIND_WRITE: i32 = 3;
+pub const REF_KIND_ADDRESS_TAKEN: i32 = 4;
+pub const REF_KIND_TYPE_USE: i32 = 5;
+
+/// Relation kind constants
+pub const RELATION_KIND_EXTENDS: i32 = 1;
+pub const RELATION_KIND_IMPLEMENTS: i32 = 2;
+pub const RELATION_KIND_EMBEDS: i32 = 3;
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -0,0 +1,169 @@
+//! Opt-in SQL profiling for one query execution.
+//!
+//! A caller that wants to see what a query did to the database runs it under
+//! [`profiled`]; every load that goes through [`Index::cached_load`] inside
+//! that future is then recorded as an [`SqlEvent`]: the rendered SQL, whether
+//! the [`SqlResultCache`] answered it, the row count and the wall time.
+//! [`attributed`] tags the events of a sub-future with the statement they
+//! were issued for.
+//!
+//! Both are tokio task-locals, so attribution follows the future rather than
+//! the thread — correct as long as the profiled work is joined in-task (no
+//! `tokio::spawn`), which holds for statement execution.  Outside a profiled
+//! future the hooks cost one task-local lookup per load.
+//!
+//! [`Index::cached_load`]: super::Index::cached_load
+//! [`SqlResultCache`]: super::SqlResultCache
+
+use std::future::Future;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use diesel::pg::Pg;
+use diesel::query_builder::{AstPass, Query, QueryFragment, QueryId};
+use diesel::QueryResult;
+
+tokio::task_local! {
+    static PROFILE: Arc<SqlProfile>;
+    static STATEMENT: usize;
+}
+
+/// How a profiled load was answered.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CacheOutcome {
+    /// Served from the SQL result cache; no round trip.
+    Hit,
+    /// Ran against Postgres and was cached.
+    Miss,
+    /// Ran against Postgres; the query could not be keyed, so it is never
+    /// cached.
+    Uncached,
+}
+
+impl CacheOutcome {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            CacheOutcome::Hit => "hit",
+            CacheOutcome::Miss => "miss",
+            CacheOutcome::Uncached => "uncached",
+        }
+    }
+}
+
+/// One load observed while profiling.
+#[derive(Debug, Clone)]
+pub struct SqlEvent {
+    /// The tag passed to [`attributed`], if the load ran inside one.
+    pub statement: Option<usize>,
+    /// Rust type of the loaded rows; tells the query families apart.
+    pub row_type: &'static str,
+    /// The SQL with its bind values, as rendered by `debug_query`.
+    pub sql: String,
+    pub cache: CacheOutcome,
+    pub rows: usize,
+    /// Time spent in the load, including the pool checkout on a miss.
+    pub elapsed: Duration,
+    /// `EXPLAIN ANALYZE` output, when the profile asked for it and the
+    /// loader could re-instantiate the query.
+    pub plan: Option<String>,
+}
+
+/// Collector for [`SqlEvent`]s.  Shared by reference count so the events
+/// survive a profiled future that was dropped half-way, e.g. on timeout.
+#[derive(Debug, Default)]
+pub struct SqlProfile {
+    analyze: bool,
+    events: Mutex<Vec<SqlEvent>>,
+}
+
+impl SqlProfile {
+    /// `analyze` additionally runs `EXPLAIN ANALYZE` for loads that support
+    /// it.  That executes the query a second time, so it is strictly a
+    /// diagnostic mode.
+    pub fn new(analyze: bool) -> Arc<Self> {
+        Arc::new(Self {
+            analyze,
+            events: Mutex::new(Vec::new()),
+        })
+    }
+
+    pub fn analyze(&self) -> bool {
+        self.analyze
+    }
+
+    /// Events recorded so far, in completion order.
+    pub fn events(&self) -> Vec<SqlEvent> {
+        self.events.lock().unwrap().clone()
+    }
+
+    pub(crate) fn record(&self, event: SqlEvent) {
+        self.events.lock().unwrap().push(event);
+    }
+}
+
+/// Run `fut` with `profile` collecting its loads.
+pub async fn profiled<F: Future>(profile: Arc<SqlProfile>, fut: F) -> F::Output {
+    PROFILE.scope(profile, fut).await
+}
+
+/// Tag the loads issued by `fut` with `statement`.
+pub async fn attributed<F: Future>(statement: usize, fut: F) -> F::Output {
+    STATEMENT.scope(statement, fut).await
+}
+
+/// The active profile and statement tag, if any.
+pub(crate) fn current() -> Option<(Arc<SqlProfile>, Option<usize>)> {
+    let profile = PROFILE.try_with(Arc::clone).ok()?;
+    Some((profile, STATEMENT.try_with(|s| *s).ok()))
+}
+
+/// `EXPLAIN (ANALYZE, BUFFERS)` around an arbitrary query.  Owns the query:
+/// boxed select statements are not `Sync`, so a borrowing wrapper could not
+/// be sent to the async connection.
+pub(crate) struct ExplainAnalyze<Q>(pub Q);
+
+impl<Q: QueryFragment<Pg>> QueryFragment<Pg> for ExplainAnalyze<Q> {
+    fn walk_ast<'b>(&'b self, mut out: AstPass<'_, 'b, Pg>) -> QueryResult<()> {
+        out.unsafe_to_cache_prepared();
+        out.push_sql("EXPLAIN (ANALYZE, BUFFERS, FORMAT TEXT) ");
+        self.0.walk_ast(out.reborrow())
+    }
+}
+
+impl<Q> QueryId for ExplainAnalyze<Q> {
+    type QueryId = ();
+    const HAS_STATIC_QUERY_ID: bool = false;
+}
+
+impl<Q> Query for ExplainAnalyze<Q> {
+    type SqlType = diesel::sql_types::Text;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn scopes_nest_and_end() {
+        assert!(current().is_none());
+        let profile = SqlProfile::new(false);
+        let seen = profiled(profile.clone(), async {
+            let outer = current().map(|(_, s)| s);
+            let inner = attributed(3, async { current().map(|(_, s)| s) }).await;
+            (outer, inner)
+        })
+        .await;
+        assert_eq!(seen, (Some(None), Some(Some(3))));
+        assert!(current().is_none());
+    }
+
+    #[test]
+    fn explain_wraps_the_query() {
+        let query = diesel::sql_query("SELECT 1");
+        let sql = diesel::debug_query::<Pg, _>(&ExplainAnalyze(query)).to_string();
+        assert!(
+            sql.starts_with("EXPLAIN (ANALYZE, BUFFERS, FORMAT TEXT) SELECT 1"),
+            "{sql}"
+        );
+    }
+}
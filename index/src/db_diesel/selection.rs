@@ -1,4 +1,4 @@
-use crate::models_diesel::{Object, Project, Symbol, SymbolInstance, SymbolRef};
+use crate::models_diesel::{Object, Project, Symbol, SymbolInstance, SymbolRef, SymbolRelation};
 use crate::symbols::{FileId, Occurrence, SymbolId, SymbolInstanceId, SymbolScope, SymbolType};
 use std::hash::{Hash, Hasher};
 
@@ -292,6 +292,9 @@ pub struct Selection {
     // Containment relationships (composition)
     pub has_parents: Vec<HasParentReference>,
     pub has_children: Vec<HasChildReference>,
+    // Type-hierarchy relations touching the selected symbols, either end;
+    // only loaded when a relation kind is traversed
+    pub relations: Vec<SymbolRelation>,
 }
 
 impl Selection {
@@ -302,6 +305,7 @@ impl Selection {
             children: Vec::new(),
             has_parents: Vec::new(),
             has_children: Vec::new(),
+            relations: Vec::new(),
         }
     }
 
@@ -311,6 +315,7 @@ impl Selection {
         self.children.extend(other.children);
         self.has_parents.extend(other.has_parents);
         self.has_children.extend(other.has_children);
+        self.relations.extend(other.relations);
     }
 
     pub fn is_empty(&self) -> bool {
@@ -620,6 +625,14 @@ impl std::fmt::Debug for Selection {
                     .map(|c| c.child_symbol.name.clone())
                     .collect::<Vec<_>>(),
             )
+            .field(
+                "relations",
+                &self
+                    .relations
+                    .iter()
+                    .map(|r| (r.from_symbol, r.to_symbol, r.kind))
+                    .collect::<Vec<_>>(),
+            )
             .finish()
     }
 }
@@ -192,6 +192,11 @@ impl CacheWeight for crate::models_diesel::SymbolRef {
         0
     }
 }
+impl CacheWeight for crate::models_diesel::SymbolRelation {
+    fn heap_bytes(&self) -> usize {
+        0
+    }
+}
 impl CacheWeight for crate::db_diesel::index_impl::ImplicitEdge {
     fn heap_bytes(&self) -> usize {
         0
@@ -272,6 +277,14 @@ impl RowKey for (Symbol, SymbolInstance, Symbol, SymbolInstance, Object) {
     }
 }
 
+/// find_related_instance_ids rows.
+impl RowKey for SymbolInstance {
+    type Key = i64;
+    fn row_key(&self) -> Self::Key {
+        self.id
+    }
+}
+
 /// find_edges_between rows.
 impl RowKey for crate::db_diesel::index_impl::ImplicitEdge {
     type Key = (i64, i64, i64);
@@ -293,6 +306,8 @@ struct Inner {
     map: lru::LruCache<CacheKey, Entry>,
     used_bytes: usize,
     epoch: u64,
+    /// When [`SqlResultCache::clear`] last ran; `None` before the first clear.
+    cleared_at: Option<std::time::Instant>,
 }
 
 /// Byte-budgeted LRU cache of typed SQL results.
@@ -369,6 +384,7 @@ impl SqlResultCache {
                 map: lru::LruCache::unbounded(),
                 used_bytes: 0,
                 epoch: 0,
+                cleared_at: None,
             }),
             max_bytes,
             hits: AtomicU64::new(0),
@@ -473,11 +489,22 @@ impl SqlResultCache {
             inner.epoch += 1;
             inner.map.clear();
             inner.used_bytes = 0;
+            inner.cleared_at = Some(std::time::Instant::now());
             inner.epoch
         };
         tracing::info!(epoch, "sql result cache cleared");
     }
 
+    /// True if the cache was cleared less than `window` ago.  Loads from a
+    /// read replica are not cached inside this window: the replica may not
+    /// have replayed the mutation behind the clear yet, and the epoch check
+    /// cannot tell.
+    pub fn cleared_within(&self, window: std::time::Duration) -> bool {
+        self.lock()
+            .cleared_at
+            .is_some_and(|at| at.elapsed() < window)
+    }
+
     pub fn stats(&self) -> CacheStats {
         let inner = self.lock();
         CacheStats {
@@ -546,6 +573,16 @@ mod tests {
         );
     }
 
+    #[test]
+    fn cleared_within_tracks_last_clear() {
+        let cache = SqlResultCache::new(1000);
+        let window = std::time::Duration::from_secs(60);
+        assert!(!cache.cleared_within(window), "never cleared");
+        cache.clear();
+        assert!(cache.cleared_within(window));
+        assert!(!cache.cleared_within(std::time::Duration::ZERO));
+    }
+
     #[test]
     fn clear_empties_and_bumps_epoch() {
         let cache = SqlResultCache::new(1000);
@@ -93,6 +93,35 @@ pub struct SymbolRef {
     pub from_object: i32,
     pub from_offset_range: (Bound<i32>, Bound<i32>),
     pub layer: i64,
+    /// One of the `REF_KIND_*` constants; `None` for unclassified refs.
+    pub kind: Option<i32>,
+}
+
+/// Structured declaration metadata for one symbol; see `SymbolMetadata` in
+/// `index.proto`. `param_names` and `param_types` are parallel.
+#[derive(Clone, Queryable, Selectable, Debug, PartialEq)]
+#[diesel(table_name = crate::schema_diesel::symbol_metadata)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct SymbolMetadata {
+    pub symbol: i64,
+    pub signature: Option<String>,
+    pub return_type: Option<String>,
+    pub param_names: Vec<String>,
+    pub param_types: Vec<String>,
+    pub storage_class: Option<String>,
+    pub visibility: Option<String>,
+}
+
+/// A typed edge of the type hierarchy: `from_symbol` extends, implements or
+/// embeds `to_symbol`.
+#[derive(Clone, Queryable, Selectable, Debug, PartialEq)]
+#[diesel(table_name = crate::schema_diesel::symbol_relations)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct SymbolRelation {
+    pub from_symbol: i64,
+    pub to_symbol: i64,
+    /// One of the `RELATION_KIND_*` constants.
+    pub kind: i32,
 }
 
 #[derive(Clone, Queryable, Selectable, Identifiable, Debug, PartialEq)]
@@ -99,6 +99,7 @@ diesel::table! {
         from_object -> Integer,
         from_offset_range -> Int4range,
         layer -> BigInt,
+        kind -> Nullable<Integer>,
     }
 }
 
@@ -118,6 +119,26 @@ diesel::table! {
     }
 }
 
+diesel::table! {
+    index.symbol_metadata (symbol) {
+        symbol -> BigInt,
+        signature -> Nullable<Text>,
+        return_type -> Nullable<Text>,
+        param_names -> Array<Text>,
+        param_types -> Array<Text>,
+        storage_class -> Nullable<Text>,
+        visibility -> Nullable<Text>,
+    }
+}
+
+diesel::table! {
+    index.symbol_relations (from_symbol, to_symbol, kind) {
+        from_symbol -> BigInt,
+        to_symbol -> BigInt,
+        kind -> Integer,
+    }
+}
+
 diesel::table! {
     index.layers (id) {
         id -> BigInt,
@@ -141,6 +162,7 @@ diesel::joinable!(objects -> projects (project_id));
 diesel::joinable!(symbol_refs -> symbols (to_symbol));
 diesel::joinable!(symbols -> projects (project_id));
 diesel::joinable!(symbols -> symbol_types (symbol_type));
+diesel::joinable!(symbol_metadata -> symbols (symbol));
 diesel::allow_tables_to_appear_in_same_query!(
     layers,
     instance_types,
@@ -155,4 +177,6 @@ diesel::allow_tables_to_appear_in_same_query!(
     project_object_chunks,
     symbol_refs,
     symbols,
+    symbol_metadata,
+    symbol_relations,
 );
@@ -476,6 +476,153 @@ impl From<i32> for InstanceType {
     }
 }
 
+/// What a reference does with its target. Stored in `symbol_refs.kind`.
+#[derive(Debug, PartialEq, Eq, Copy, Clone, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+#[repr(i32)]
+pub enum RefKind {
+    Call = 1,
+    Read = 2,
+    Write = 3,
+    AddressTaken = 4,
+    TypeUse = 5,
+}
+
+impl RefKind {
+    pub const ALL: [RefKind; 5] = [
+        RefKind::Call,
+        RefKind::Read,
+        RefKind::Write,
+        RefKind::AddressTaken,
+        RefKind::TypeUse,
+    ];
+
+    pub fn as_i32(&self) -> i32 {
+        *self as i32
+    }
+
+    /// The name used in queries and in the `index.ref_kinds` table.
+    pub fn name(&self) -> &'static str {
+        match self {
+            RefKind::Call => "call",
+            RefKind::Read => "read",
+            RefKind::Write => "write",
+            RefKind::AddressTaken => "address_taken",
+            RefKind::TypeUse => "type_use",
+        }
+    }
+
+    /// Parse a kind name; `-` and `_` are interchangeable.
+    pub fn from_name(name: &str) -> Option<Self> {
+        let name = name.trim().replace('-', "_");
+        RefKind::ALL.into_iter().find(|k| k.name() == name)
+    }
+}
+
+impl From<i32> for RefKind {
+    fn from(value: i32) -> Self {
+        match value {
+            x if x == RefKind::Call as i32 => RefKind::Call,
+            x if x == RefKind::Read as i32 => RefKind::Read,
+            x if x == RefKind::Write as i32 => RefKind::Write,
+            x if x == RefKind::AddressTaken as i32 => RefKind::AddressTaken,
+            x if x == RefKind::TypeUse as i32 => RefKind::TypeUse,
+            _ => panic!("Invalid ref kind value {}", value),
+        }
+    }
+}
+
+/// How one symbol relates to another in the type hierarchy. Stored in
+/// `symbol_relations.kind`; the relation points from the deriving symbol to
+/// its base.
+#[derive(Debug, PartialEq, Eq, Copy, Clone, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+#[repr(i32)]
+pub enum RelationKind {
+    Extends = 1,
+    Implements = 2,
+    Embeds = 3,
+}
+
+impl RelationKind {
+    pub const ALL: [RelationKind; 3] = [
+        RelationKind::Extends,
+        RelationKind::Implements,
+        RelationKind::Embeds,
+    ];
+
+    pub fn as_i32(&self) -> i32 {
+        *self as i32
+    }
+
+    /// The name used in queries and in the `index.relation_kinds` table.
+    pub fn name(&self) -> &'static str {
+        match self {
+            RelationKind::Extends => "extends",
+            RelationKind::Implements => "implements",
+            RelationKind::Embeds => "embeds",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        let name = name.trim();
+        RelationKind::ALL.into_iter().find(|k| k.name() == name)
+    }
+}
+
+impl From<i32> for RelationKind {
+    fn from(value: i32) -> Self {
+        match value {
+            x if x == RelationKind::Extends as i32 => RelationKind::Extends,
+            x if x == RelationKind::Implements as i32 => RelationKind::Implements,
+            x if x == RelationKind::Embeds as i32 => RelationKind::Embeds,
+            _ => panic!("Invalid relation kind value {}", value),
+        }
+    }
+}
+
+/// Structured declaration metadata for a symbol (`index.symbol_metadata`).
+/// Every field is optional; indexers fill in what their language has.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SymbolMetadata {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub signature: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub return_type: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub parameters: Vec<Parameter>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub storage_class: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub visibility: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Parameter {
+    /// Empty for unnamed parameters.
+    pub name: String,
+    #[serde(rename = "type")]
+    pub type_name: String,
+}
+
+/// Canonical spelling of a type for comparison: whitespace runs collapse to
+/// one space, and no space precedes `*`, `&`, `)`, `[`, `]` or `,` or
+/// follows `(` or `[`. `char  *`, `char*` and `char *` all become `char*`,
+/// and `char [ 16 ]` becomes `char[16]`.
+pub fn normalize_type_text(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for token in text.split_whitespace() {
+        let glue = out.is_empty()
+            || out.ends_with(['(', '['])
+            || token.starts_with(['*', '&', ')', '[', ']', ',']);
+        if !glue {
+            out.push(' ');
+        }
+        out.push_str(token);
+    }
+    out
+}
+
 impl SymbolType {
     pub fn as_i64(&self) -> i64 {
         return *self as i64;
@@ -895,6 +1042,45 @@ mod tests {
         );
     }
 
+    #[test]
+    fn ref_kind_names_round_trip() {
+        for kind in RefKind::ALL {
+            assert_eq!(RefKind::from_name(kind.name()), Some(kind));
+            assert_eq!(RefKind::from(kind.as_i32()), kind);
+        }
+        assert_eq!(
+            RefKind::from_name("address-taken"),
+            Some(RefKind::AddressTaken)
+        );
+        assert_eq!(RefKind::from_name("calls"), None);
+    }
+
+    #[test]
+    fn relation_kind_names_round_trip() {
+        for kind in RelationKind::ALL {
+            assert_eq!(RelationKind::from_name(kind.name()), Some(kind));
+            assert_eq!(RelationKind::from(kind.as_i32()), kind);
+        }
+        assert_eq!(RelationKind::from_name("inherits"), None);
+    }
+
+    #[test]
+    fn normalize_type_text_canonicalizes_spacing() {
+        assert_eq!(normalize_type_text("int"), "int");
+        assert_eq!(normalize_type_text("  unsigned   long "), "unsigned long");
+        assert_eq!(normalize_type_text("char *"), "char*");
+        assert_eq!(
+            normalize_type_text("const char * const"),
+            "const char* const"
+        );
+        assert_eq!(normalize_type_text("int (*)(void)"), "int (*)(void)");
+        assert_eq!(normalize_type_text("char [ 16 ]"), "char[16]");
+        assert_eq!(
+            normalize_type_text("char [ 16 ]"),
+            normalize_type_text("char[16]")
+        );
+    }
+
     // symbol_path_and_leaf
 
     #[test]
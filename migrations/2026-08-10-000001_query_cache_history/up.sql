@@ -0,0 +1,48 @@
+-- Persistent askl query result cache and per-user query history.
+--
+-- `cache_generation` is a single row bumped by every transaction that
+-- mutates the persistent index (finalize / delete project), in the same
+-- transaction as the ephemeral-layer purge.  Cache keys hash the generation
+-- read before a query runs, so a result computed against pre-mutation data
+-- can never be served after the mutation commits — on this replica or any
+-- other.  A row update (not a sequence) keeps the bump transactional.
+
+CREATE SCHEMA IF NOT EXISTS queries;
+
+CREATE TABLE queries.cache_generation
+(
+    singleton BOOLEAN NOT NULL PRIMARY KEY DEFAULT TRUE CHECK (singleton),
+    generation BIGINT NOT NULL
+);
+
+INSERT INTO queries.cache_generation (singleton, generation) VALUES (TRUE, 0);
+
+CREATE TABLE queries.result_cache
+(
+    key BYTEA NOT NULL PRIMARY KEY,
+    body TEXT NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+    last_hit_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+    hits BIGINT NOT NULL DEFAULT 0
+);
+
+CREATE INDEX result_cache_last_hit_at_idx ON queries.result_cache (last_hit_at);
+
+-- Only authenticated queries are recorded: an anonymous one could never be
+-- listed.
+CREATE TABLE queries.history
+(
+    id BIGSERIAL PRIMARY KEY,
+    user_id UUID NOT NULL REFERENCES auth.users(id) ON DELETE CASCADE,
+    query TEXT NOT NULL,
+    params TEXT NULL,
+    output TEXT NOT NULL,
+    status TEXT NOT NULL,
+    cache_hit BOOLEAN NOT NULL,
+    duration_ms BIGINT NOT NULL,
+    total_symbols BIGINT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT now()
+);
+
+CREATE INDEX history_user_created_at_idx ON queries.history (user_id, created_at DESC);
+CREATE INDEX history_created_at_idx ON queries.history (created_at);
@@ -0,0 +1,2 @@
+ALTER TABLE index.symbol_refs DROP COLUMN IF EXISTS kind;
+DROP TABLE IF EXISTS index.ref_kinds;
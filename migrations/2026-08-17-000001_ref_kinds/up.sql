@@ -0,0 +1,16 @@
+CREATE TABLE IF NOT EXISTS index.ref_kinds (
+    id INTEGER PRIMARY KEY,
+    name TEXT NOT NULL UNIQUE
+);
+
+INSERT INTO index.ref_kinds (id, name) VALUES
+    (1, 'call'),
+    (2, 'read'),
+    (3, 'write'),
+    (4, 'address_taken'),
+    (5, 'type_use');
+
+-- NULL when the indexer did not classify the reference; such refs only
+-- match an unrestricted `refs`.
+ALTER TABLE index.symbol_refs
+    ADD COLUMN kind INTEGER REFERENCES index.ref_kinds(id);
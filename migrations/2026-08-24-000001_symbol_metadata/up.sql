@@ -0,0 +1,16 @@
+-- Optional per-symbol declaration metadata. One row per symbol that the
+-- indexer described; symbols without metadata have no row.
+CREATE TABLE IF NOT EXISTS index.symbol_metadata (
+    symbol BIGINT PRIMARY KEY REFERENCES index.symbols(id) ON DELETE CASCADE,
+    signature TEXT,
+    return_type TEXT,
+    -- Parallel arrays, one entry per parameter in declaration order.
+    -- Unnamed parameters store an empty name.
+    param_names TEXT[] NOT NULL DEFAULT '{}',
+    param_types TEXT[] NOT NULL DEFAULT '{}',
+    storage_class TEXT,
+    visibility TEXT
+);
+
+CREATE INDEX IF NOT EXISTS symbol_metadata_return_type_idx
+    ON index.symbol_metadata (return_type);
@@ -0,0 +1,2 @@
+DROP TABLE IF EXISTS index.symbol_relations;
+DROP TABLE IF EXISTS index.relation_kinds;
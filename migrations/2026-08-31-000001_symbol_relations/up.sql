@@ -0,0 +1,21 @@
+CREATE TABLE IF NOT EXISTS index.relation_kinds (
+    id INTEGER PRIMARY KEY,
+    name TEXT NOT NULL UNIQUE
+);
+
+INSERT INTO index.relation_kinds (id, name) VALUES
+    (1, 'extends'),
+    (2, 'implements'),
+    (3, 'embeds');
+
+-- Typed symbol-to-symbol relations (type hierarchy). Directed: the
+-- `from_symbol` extends / implements / embeds the `to_symbol`.
+CREATE TABLE IF NOT EXISTS index.symbol_relations (
+    from_symbol BIGINT NOT NULL REFERENCES index.symbols(id) ON DELETE CASCADE,
+    to_symbol BIGINT NOT NULL REFERENCES index.symbols(id) ON DELETE CASCADE,
+    kind INTEGER NOT NULL REFERENCES index.relation_kinds(id),
+    PRIMARY KEY (from_symbol, to_symbol, kind)
+);
+
+CREATE INDEX IF NOT EXISTS symbol_relations_to_symbol_idx
+    ON index.symbol_relations (to_symbol);
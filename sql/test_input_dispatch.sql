@@ -0,0 +1,66 @@
+SET search_path TO index, public;
+
+-- Root layer for the fixture project.  Persistent inserts below inherit it
+-- via the layer column DEFAULT.
+INSERT INTO layers (id, parent_id, hash, kind, populated)
+OVERRIDING SYSTEM VALUE
+VALUES (1000001, NULL, decode(md5('fixture-root-1'), 'hex'), 'root', TRUE);
+
+INSERT INTO projects (id, project_name, root_path, root_layer_id)
+VALUES (1, 'test_project', '/test_project', 1000001);
+
+ALTER TABLE symbols          ALTER COLUMN layer SET DEFAULT 1000001;
+ALTER TABLE symbol_instances ALTER COLUMN layer SET DEFAULT 1000001;
+ALTER TABLE symbol_refs      ALTER COLUMN layer SET DEFAULT 1000001;
+ALTER TABLE objects          ALTER COLUMN layer SET DEFAULT 1000001;
+
+-- Object 1: source file
+INSERT INTO objects (id, project_id, module_path, filesystem_path, filetype, content_hash)
+VALUES (1, 1, 'ops.c', '/ops.c', 'c', 'hash1');
+
+-- Object 2: sentinel directory
+INSERT INTO objects (id, project_id, module_path, filesystem_path, filetype, content_hash)
+VALUES (2, 1, '/', '/', 'directory', '');
+
+-- Symbols
+INSERT INTO symbols (id, name, project_id, symbol_type, symbol_scope) VALUES
+    (1, '/', 1, 4, NULL),
+    (2, '/ops.c', 1, 2, NULL),
+    (3, 'ops.my_read', 1, 1, NULL),
+    (4, 'ops.my_write', 1, 1, NULL),
+    (5, 'ops.my_fops', 1, 6, NULL),
+    (6, 'ops.register', 1, 1, NULL),
+    (7, 'ops.tables', 1, 6, NULL),
+    (8, 'ops.file_operations.write', 1, 8, NULL),
+    (9, 'ops.counter', 1, 6, NULL);
+
+-- Symbol instances
+INSERT INTO symbol_instances (id, symbol, object_id, offset_range, instance_type) VALUES
+    (100, 1, 2, int4range(0, 0), 4),
+    (101, 1, 1, int4range(0, 2000), 5),
+    (102, 2, 1, int4range(0, 1000), 6),
+    (103, 3, 1, int4range(100, 200), 1),  -- my_read
+    (104, 4, 1, int4range(200, 300), 1),  -- my_write
+    (105, 5, 1, int4range(300, 400), 1),  -- my_fops
+    (106, 6, 1, int4range(400, 500), 1),  -- register
+    (107, 7, 1, int4range(500, 700), 1),  -- tables
+    (108, 8, 1, int4range(550, 600), 1),  -- file_operations.write inside tables
+    (109, 9, 1, int4range(700, 710), 1);  -- counter
+
+INSERT INTO symbol_refs(to_symbol, from_object, from_offset_range, kind) VALUES
+    -- my_fops = { .read = my_read, .write = my_write }
+    (3, 1, int4range(320, 327), 4),
+    (4, 1, int4range(340, 348), 4),
+    -- register calls my_read and takes my_write's address
+    (3, 1, int4range(420, 427), 1),
+    (4, 1, int4range(440, 448), 4),
+    -- register reads my_fops
+    (5, 1, int4range(460, 467), 2),
+    -- tables = { { .write = my_write }, &counter }
+    (4, 1, int4range(560, 568), 4),
+    (9, 1, int4range(620, 627), 4);
+
+ALTER TABLE symbols          ALTER COLUMN layer DROP DEFAULT;
+ALTER TABLE symbol_instances ALTER COLUMN layer DROP DEFAULT;
+ALTER TABLE symbol_refs      ALTER COLUMN layer DROP DEFAULT;
+ALTER TABLE objects          ALTER COLUMN layer DROP DEFAULT;
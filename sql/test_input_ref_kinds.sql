@@ -0,0 +1,65 @@
+SET search_path TO index, public;
+
+-- Root layer for the fixture project.  Persistent inserts below inherit it
+-- via the layer column DEFAULT.
+INSERT INTO layers (id, parent_id, hash, kind, populated)
+OVERRIDING SYSTEM VALUE
+VALUES (1000001, NULL, decode(md5('fixture-root-1'), 'hex'), 'root', TRUE);
+
+INSERT INTO projects (id, project_name, root_path, root_layer_id)
+VALUES (1, 'test_project', '/test_project', 1000001);
+
+ALTER TABLE symbols          ALTER COLUMN layer SET DEFAULT 1000001;
+ALTER TABLE symbol_instances ALTER COLUMN layer SET DEFAULT 1000001;
+ALTER TABLE symbol_refs      ALTER COLUMN layer SET DEFAULT 1000001;
+ALTER TABLE objects          ALTER COLUMN layer SET DEFAULT 1000001;
+
+-- Placeholder content for object 1: one blank line, so every offset the
+-- instances and refs below use resolves to a position.
+INSERT INTO content_store (content_hash, content) VALUES
+    ('hash1', convert_to(repeat(' ', 2000) || E'\n', 'UTF8'));
+
+-- Object 1: source file
+INSERT INTO objects (id, project_id, module_path, filesystem_path, filetype, content_hash)
+VALUES (1, 1, 'kinds.c', '/kinds.c', 'c', 'hash1');
+
+-- Object 2: sentinel directory
+INSERT INTO objects (id, project_id, module_path, filesystem_path, filetype, content_hash)
+VALUES (2, 1, '/', '/', 'directory', '');
+
+-- Symbols
+INSERT INTO symbols (id, name, project_id, symbol_type, symbol_scope) VALUES
+    (1, '/', 1, 4, NULL),
+    (2, '/kinds.c', 1, 2, NULL),
+    (3, 'kinds.get', 1, 1, NULL),
+    (4, 'kinds.set', 1, 1, NULL),
+    (5, 'kinds.where', 1, 1, NULL),
+    (6, 'kinds.legacy', 1, 1, NULL),
+    (7, 'kinds.level', 1, 6, NULL),
+    (8, 'kinds.limit', 1, 6, NULL);
+
+-- Symbol instances
+INSERT INTO symbol_instances (id, symbol, object_id, offset_range, instance_type) VALUES
+    (100, 1, 2, int4range(0, 0), 4),
+    (101, 1, 1, int4range(0, 2000), 5),
+    (102, 2, 1, int4range(0, 1000), 6),
+    (103, 7, 1, int4range(0, 10), 1),     -- level
+    (104, 8, 1, int4range(11, 25), 1),    -- limit
+    (105, 3, 1, int4range(100, 200), 1),  -- get
+    (106, 4, 1, int4range(200, 300), 1),  -- set
+    (107, 5, 1, int4range(300, 400), 1),  -- where
+    (108, 6, 1, int4range(400, 500), 1);  -- legacy
+
+-- References (kind: 1 call, 2 read, 3 write, 4 address_taken)
+INSERT INTO symbol_refs(to_symbol, from_object, from_offset_range, kind) VALUES
+    (7, 1, int4range(150, 155), 2),     -- get reads level
+    (7, 1, int4range(210, 215), 3),     -- set writes level
+    (8, 1, int4range(220, 225), 2),     -- set reads limit
+    (3, 1, int4range(230, 233), 1),     -- set calls get
+    (7, 1, int4range(310, 315), 4),     -- where takes &level
+    (7, 1, int4range(410, 415), NULL);  -- legacy: unclassified
+
+ALTER TABLE symbols          ALTER COLUMN layer DROP DEFAULT;
+ALTER TABLE symbol_instances ALTER COLUMN layer DROP DEFAULT;
+ALTER TABLE symbol_refs      ALTER COLUMN layer DROP DEFAULT;
+ALTER TABLE objects          ALTER COLUMN layer DROP DEFAULT;
@@ -0,0 +1,62 @@
+SET search_path TO index, public;
+
+-- Root layer for the fixture project.  Persistent inserts below inherit it
+-- via the layer column DEFAULT.
+INSERT INTO layers (id, parent_id, hash, kind, populated)
+OVERRIDING SYSTEM VALUE
+VALUES (1000001, NULL, decode(md5('fixture-root-1'), 'hex'), 'root', TRUE);
+
+INSERT INTO projects (id, project_name, root_path, root_layer_id)
+VALUES (1, 'test_project', '/test_project', 1000001);
+
+ALTER TABLE symbols          ALTER COLUMN layer SET DEFAULT 1000001;
+ALTER TABLE symbol_instances ALTER COLUMN layer SET DEFAULT 1000001;
+ALTER TABLE symbol_refs      ALTER COLUMN layer SET DEFAULT 1000001;
+ALTER TABLE objects          ALTER COLUMN layer SET DEFAULT 1000001;
+
+-- Object 1: source file
+INSERT INTO objects (id, project_id, module_path, filesystem_path, filetype, content_hash)
+VALUES (1, 1, 'meta.c', '/meta.c', 'c', 'hash1');
+
+-- Object 2: sentinel directory
+INSERT INTO objects (id, project_id, module_path, filesystem_path, filetype, content_hash)
+VALUES (2, 1, '/', '/', 'directory', '');
+
+-- Symbols
+INSERT INTO symbols (id, name, project_id, symbol_type, symbol_scope) VALUES
+    (1, '/', 1, 4, NULL),
+    (2, '/meta.c', 1, 2, NULL),
+    (3, 'meta.add', 1, 1, NULL),
+    (4, 'meta.name', 1, 1, NULL),
+    (5, 'meta.helper', 1, 1, NULL),
+    (6, 'meta.bare', 1, 1, NULL),
+    (7, 'meta.count', 1, 6, NULL);
+
+-- Symbol instances
+INSERT INTO symbol_instances (id, symbol, object_id, offset_range, instance_type) VALUES
+    (100, 1, 2, int4range(0, 0), 4),
+    (101, 1, 1, int4range(0, 2000), 5),
+    (102, 2, 1, int4range(0, 1000), 6),
+    (103, 3, 1, int4range(100, 200), 1),  -- add
+    (104, 4, 1, int4range(200, 300), 1),  -- name
+    (105, 5, 1, int4range(300, 400), 1),  -- helper
+    (106, 6, 1, int4range(400, 500), 1),  -- bare
+    (107, 7, 1, int4range(0, 10), 1);     -- count
+
+-- add calls helper
+INSERT INTO symbol_refs(to_symbol, from_object, from_offset_range, kind) VALUES
+    (5, 1, int4range(150, 156), 1);
+
+-- Metadata, types already in normalize_type_text form.  bare has none.
+INSERT INTO symbol_metadata
+    (symbol, signature, return_type, param_names, param_types, storage_class, visibility)
+VALUES
+    (3, 'int add(int a, int b)', 'int', '{a,b}', '{int,int}', NULL, NULL),
+    (4, 'const char *name(void)', 'const char*', '{}', '{}', NULL, NULL),
+    (5, 'static int helper(int x)', 'int', '{x}', '{int}', 'static', NULL),
+    (7, 'static int count', NULL, '{}', '{}', 'static', NULL);
+
+ALTER TABLE symbols          ALTER COLUMN layer DROP DEFAULT;
+ALTER TABLE symbol_instances ALTER COLUMN layer DROP DEFAULT;
+ALTER TABLE symbol_refs      ALTER COLUMN layer DROP DEFAULT;
+ALTER TABLE objects          ALTER COLUMN layer DROP DEFAULT;
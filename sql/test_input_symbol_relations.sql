@@ -0,0 +1,61 @@
+SET search_path TO index, public;
+
+-- Root layer for the fixture project.  Persistent inserts below inherit it
+-- via the layer column DEFAULT.
+INSERT INTO layers (id, parent_id, hash, kind, populated)
+OVERRIDING SYSTEM VALUE
+VALUES (1000001, NULL, decode(md5('fixture-root-1'), 'hex'), 'root', TRUE);
+
+INSERT INTO projects (id, project_name, root_path, root_layer_id)
+VALUES (1, 'test_project', '/test_project', 1000001);
+
+ALTER TABLE symbols          ALTER COLUMN layer SET DEFAULT 1000001;
+ALTER TABLE symbol_instances ALTER COLUMN layer SET DEFAULT 1000001;
+ALTER TABLE symbol_refs      ALTER COLUMN layer SET DEFAULT 1000001;
+ALTER TABLE objects          ALTER COLUMN layer SET DEFAULT 1000001;
+
+-- Object 1: source file
+INSERT INTO objects (id, project_id, module_path, filesystem_path, filetype, content_hash)
+VALUES (1, 1, 'shapes.go', '/shapes.go', 'go', 'hash1');
+
+-- Object 2: sentinel directory
+INSERT INTO objects (id, project_id, module_path, filesystem_path, filetype, content_hash)
+VALUES (2, 1, '/', '/', 'directory', '');
+
+-- Symbols
+INSERT INTO symbols (id, name, project_id, symbol_type, symbol_scope) VALUES
+    (1, '/', 1, 4, NULL),
+    (2, '/shapes.go', 1, 2, NULL),
+    (3, 'shapes.Base', 1, 5, NULL),
+    (4, 'shapes.Derived', 1, 5, NULL),
+    (5, 'shapes.Reader', 1, 5, NULL),
+    (6, 'shapes.FileReader', 1, 5, NULL),
+    (7, 'shapes.Unrelated', 1, 5, NULL),
+    (8, 'shapes.use', 1, 1, NULL);
+
+-- Symbol instances
+INSERT INTO symbol_instances (id, symbol, object_id, offset_range, instance_type) VALUES
+    (100, 1, 2, int4range(0, 0), 4),
+    (101, 1, 1, int4range(0, 2000), 5),
+    (102, 2, 1, int4range(0, 1000), 6),
+    (103, 3, 1, int4range(100, 200), 1),  -- Base
+    (104, 4, 1, int4range(200, 300), 1),  -- Derived
+    (105, 5, 1, int4range(300, 400), 1),  -- Reader
+    (106, 6, 1, int4range(400, 500), 1),  -- FileReader
+    (107, 7, 1, int4range(500, 600), 1),  -- Unrelated
+    (108, 8, 1, int4range(600, 700), 1);  -- use
+
+-- use references Derived
+INSERT INTO symbol_refs(to_symbol, from_object, from_offset_range, kind) VALUES
+    (4, 1, int4range(650, 657), 5);
+
+-- Derived embeds Base, Derived and FileReader implement Reader
+INSERT INTO symbol_relations (from_symbol, to_symbol, kind) VALUES
+    (4, 3, 3),
+    (4, 5, 2),
+    (6, 5, 2);
+
+ALTER TABLE symbols          ALTER COLUMN layer DROP DEFAULT;
+ALTER TABLE symbol_instances ALTER COLUMN layer DROP DEFAULT;
+ALTER TABLE symbol_refs      ALTER COLUMN layer DROP DEFAULT;
+ALTER TABLE objects          ALTER COLUMN layer DROP DEFAULT;